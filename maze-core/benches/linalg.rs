@@ -0,0 +1,39 @@
+//! Times the matrix products the renderer makes for every instance it draws, with plain loops and, when built with
+//! `--features nalgebra`, through nalgebra. Run with `cargo bench -p maze-core [--features nalgebra]`
+
+use std::time::{Duration, Instant};
+
+use maze_core::linalg;
+
+const PRODUCTS: usize = 2_000_000;
+
+type Mat4 = [[f32; 4]; 4];
+
+// Average time for one model-view-projection product, chaining each result into the next so none can be skipped
+fn time(name: &str, mul: fn(Mat4, Mat4) -> Mat4) -> Duration {
+    let view_projection = mul(linalg::projection(0.1, 100.0, 1.0, 16.0 / 9.0), linalg::view([0.5, 0.0, 0.0], [1.0; 3], [-2.0, 1.0, -4.0]));
+    let mut total = 0.0;
+    let start = Instant::now();
+    for i in 0..PRODUCTS {
+        let model = linalg::translate([i as f32 % 7.0, i as f32 % 5.0, total % 3.0]);
+        total += mul(view_projection, model)[3][2];
+    }
+    let each = start.elapsed() / PRODUCTS as u32;
+    println!("{:>8}: {:>5}ns per product (checksum {})", name, each.as_nanos(), total);
+    each
+}
+
+fn main() {
+    println!("Multiplying {} matrices", PRODUCTS);
+    #[cfg_attr(not(feature = "nalgebra"), allow(unused_variables))]
+    let loops = time("loops", linalg::mul_loops);
+    #[cfg(feature = "nalgebra")]
+    {
+        let nalgebra = time("nalgebra", linalg::mul_nalgebra);
+        println!("nalgebra takes {:.0}% of the time of the loops", nalgebra.as_secs_f64() * 100.0 / loops.as_secs_f64());
+    }
+    #[cfg(not(feature = "nalgebra"))]
+    {
+        println!("Build with --features nalgebra to compare against nalgebra");
+    }
+}
@@ -0,0 +1,54 @@
+//! Times the searches the ghosts, the bot and maze generation make on a large maze: a distance field from one cell
+//! against a shortest path to every cell in turn, and one search for the nearest of many targets against a route to
+//! each. Run with `cargo bench -p maze-core --bench pathfinding`
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+
+use maze_core::config::Config;
+use maze_core::objects::Inventory;
+use maze_core::world::{Coordinate, World};
+
+const DIMENSIONS: [usize; 4] = [20, 20, 20, 20];
+const TARGETS: usize = 200; // Cells searched for at once, about as much food as a large maze holds
+const RUNS: u32 = 3;
+const EVERY: usize = 1000; // Of the cells, reached one at a time to compare against one distance field
+
+// Average time for one run of the search, printing it along with a checksum so the result can't be skipped
+fn time(name: &str, search: impl Fn() -> usize) -> Duration {
+    let mut checksum = 0;
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        checksum += search();
+    }
+    let each = start.elapsed() / RUNS;
+    println!("{:>22}: {:>8.2}ms per run (checksum {})", name, each.as_secs_f64() * 1000.0, checksum);
+    each
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let world = World::new(&Config { dimensions: DIMENSIONS, ..Default::default() }, &mut rng).expect("Failed to generate maze");
+    let mut cells: Vec<Coordinate> = world.distance_field(world.start).into_keys().collect();
+    cells.sort(); // Maps iterate in any order, which would change the targets from run to run
+    cells.shuffle(&mut rng);
+    println!("Searching a {:?} maze of {} cells", DIMENSIONS, cells.len());
+
+    let field = time("distance field", || world.distance_field(world.start).values().sum());
+    let paths = time("path to every 1000th", || cells.iter().step_by(EVERY).map(|cell| world.bfs(world.start, *cell).len()).sum());
+    println!("One distance field takes {:.1}% of the time of a path to every {}th cell", field.as_secs_f64() * 100.0 / paths.as_secs_f64(), EVERY);
+
+    let targets: HashSet<Coordinate> = cells.iter().take(TARGETS).copied().collect();
+    let (inventory, avoid) = (Inventory::new(), HashSet::new());
+    let nearest = time("nearest of targets", || world.nearest_of(world.start, &targets, &inventory, &avoid).map_or(0, |route| route.len()));
+    let routes = time("route to each target", || targets.iter()
+        .filter_map(|target| world.route_avoiding(world.start, *target, &inventory, &avoid))
+        .map(|route| route.len())
+        .min()
+        .unwrap_or(0));
+    println!("Searching for the nearest takes {:.1}% of the time of a route to each", nearest.as_secs_f64() * 100.0 / routes.as_secs_f64());
+}
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use crate::objects::{Inventory, Objects};
+use crate::world::{Coordinate, World};
+
+const DANGER: usize = 2; // Moves from a ghost the bot keeps out of, wherever there's another way round
+
+/// Computer player that eats the nearest food or picks up the nearest key it can reach until none is left, then heads for the exit
+pub struct Bot;
+
+impl Bot {
+    /// Next step to take from the given cell, or None if there's nowhere left to go
+    pub fn next_move(&self, world: &World, objects: &Objects, inventory: &Inventory, cell: [i32; 4]) -> Option<[i32; 4]> {
+        self.next_move_avoiding(world, objects, inventory, cell, &[])
+    }
+
+    /// Next step like next_move, going the long way round rather than near any of the ghosts' cells. If there's no
+    /// way round, it goes the short way anyway
+    pub fn next_move_avoiding(&self, world: &World, objects: &Objects, inventory: &Inventory, cell: [i32; 4], ghosts: &[[usize; 4]]) -> Option<[i32; 4]> {
+        if !world.contains(cell) {
+            return None; // Already left the maze
+        }
+        let from = (cell[0] as usize, cell[1] as usize, cell[2] as usize, cell[3] as usize);
+        let danger: HashSet<Coordinate> = ghosts.iter().flat_map(|&[x, y, z, w]| world.near((x, y, z, w), DANGER)).collect();
+        let anywhere = HashSet::new();
+        let targets: HashSet<Coordinate> = objects.food().chain(objects.keys().map(|(key, _)| key)).copied().collect();
+        let nearest = |avoid: &HashSet<Coordinate>| world.nearest_of(from, &targets, inventory, avoid);
+        let route = nearest(&danger)
+            .or_else(|| nearest(&anywhere))
+            .or_else(|| world.route_avoiding(from, world.exit, inventory, &danger))
+            .or_else(|| world.route(from, world.exit, inventory))
+            .unwrap_or_default();
+        match route.get(1) {
+            Some (next) => Some (delta(from, *next)),
+            None if world.exit_open && from == world.exit => Some (world.exit_delta),
+            None => None
+        }
+    }
+}
+
+fn delta(from: Coordinate, to: Coordinate) -> [i32; 4] {
+    [to.0 as i32 - from.0 as i32, to.1 as i32 - from.1 as i32, to.2 as i32 - from.2 as i32, to.3 as i32 - from.3 as i32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Loop round two rows, with the food at the far end of the top one and the exit below it
+    const LOOP: &str = "size 7x2x1x1
+w 0 z 0
++---+---+---+---+---+---+---+
+|S                       o  |
++   +---+---+---+---+---+   +
+|                        E   
++---+---+---+---+---+---+---+
+";
+
+    #[test]
+    fn goes_the_long_way_round_a_ghost() {
+        let world = World::parse_level(LOOP).unwrap();
+        let objects = Objects::from_world(&world);
+        let inventory = Inventory::new();
+        assert_eq!(Bot.next_move(&world, &objects, &inventory, [0, 0, 0, 0]), Some ([1, 0, 0, 0]));
+        assert_eq!(Bot.next_move_avoiding(&world, &objects, &inventory, [0, 0, 0, 0], &[[3, 0, 0, 0]]), Some ([0, 1, 0, 0]));
+        // Boxed in on both sides, so straight past
+        assert_eq!(Bot.next_move_avoiding(&world, &objects, &inventory, [0, 0, 0, 0], &[[3, 0, 0, 0], [3, 1, 0, 0]]), Some ([1, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::{MazeError, Result};
+use crate::game::Game;
+use crate::world::World;
+
+/// File listing the campaign's levels, next to the main config file
+pub const LEVELS: &str = "levels.toml";
+
+const DEFAULT_LEVELS: &str = include_str!("../res/levels.toml");
+
+/// Mazes played one after another, each changing some settings from the config file
+pub struct Campaign {
+    levels: Vec<Level>
+}
+
+struct Level {
+    name: String,
+    settings: Vec<(String, String)> // Written the way the older config format would have them, eg. dimensions and 5x5x3x3
+}
+
+#[derive(Deserialize)]
+struct LevelsFile {
+    level: Vec<toml::value::Table>
+}
+
+impl Campaign {
+    /// Read the levels listed next to the config file, first writing out the default campaign if there's no list yet.
+    /// Every level is checked against the config's settings, so a bad one is found before the campaign starts
+    pub fn open(config_path: &str, config: &Config) -> Result<Campaign> {
+        let path = Path::new(config_path).with_file_name(LEVELS).to_string_lossy().into_owned();
+        if !Path::new(&path).exists() {
+            fs::write(&path, DEFAULT_LEVELS).map_err(|e| MazeError::io(&path, e))?;
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| MazeError::io(&path, e))?;
+        let campaign = Campaign::parse(&contents).map_err(|reason| MazeError::resource(&path, reason))?;
+        for level in 0..campaign.len() {
+            campaign.config(config, level).map_err(|reason| MazeError::resource(&path, reason))?;
+        }
+        Ok (campaign)
+    }
+
+    fn parse(contents: &str) -> std::result::Result<Campaign, String> {
+        let file: LevelsFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+        if file.level.is_empty() {
+            return Err ("Expected at least one [[level]]".to_string());
+        }
+        let levels = file.level.into_iter().enumerate().map(|(i, table)| {
+            let mut name = format!("Level {}", i + 1);
+            let mut settings = Vec::new();
+            for (key, value) in table {
+                match (key.as_str(), value) {
+                    ("name", toml::Value::String (value)) => name = value,
+                    (_, toml::Value::String (value)) => settings.push((key, value)),
+                    (_, toml::Value::Array (values)) => settings.push((key, values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("x"))),
+                    (_, value) => settings.push((key, value.to_string()))
+                }
+            }
+            Level { name, settings }
+        }).collect();
+        Ok (Campaign { levels })
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    pub fn name(&self, level: usize) -> &str {
+        &self.levels[level].name
+    }
+
+    /// Settings for the level, counting from 0: the config's, with the level's on top. A level file the level plays is
+    /// read to fit the maze size and food to it, as for one given on the command line
+    pub fn config(&self, config: &Config, level: usize) -> std::result::Result<Config, String> {
+        let mut config = config.clone();
+        let level = &self.levels[level];
+        for (key, value) in &level.settings {
+            config.set(key, value).map_err(|reason| format!("{}: {}", level.name, reason))?;
+        }
+        if let Some (path) = &config.level {
+            World::from_level(path).map_err(|e| format!("{}: {}", level.name, e))?.fit_config(&mut config);
+        }
+        config.check().map_err(|(_, reason)| format!("{}: {}", level.name, reason))?;
+        Ok (config)
+    }
+}
+
+/// How far through the campaign the player has got, saved between levels
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Progress {
+    pub level: usize, // Next level to play, counting from 0
+    pub lives: Option<u32>, // Carried over from the last level, or None to start with the level's own
+    pub score: u32 // Total points from every level finished
+}
+
+/// Progress file kept alongside a config file, eg. config.campaign for config.toml, so each profile has its own
+pub fn progress_path(config: &str) -> String {
+    Path::new(config).with_extension("campaign").to_string_lossy().into_owned()
+}
+
+impl Progress {
+    /// Read saved progress, starting from the first level if there isn't any
+    pub fn load(path: &str) -> Result<Progress> {
+        if !Path::new(path).exists() {
+            return Ok (Progress::default());
+        }
+        let contents = fs::read_to_string(path).map_err(|e| MazeError::io(path, e))?;
+        toml::from_str(&contents).map_err(|e| MazeError::resource(path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let contents = toml::to_string(self).map_err(|e| MazeError::resource(path, e))?;
+        fs::write(path, contents).map_err(|e| MazeError::io(path, e))
+    }
+
+    /// Give a new game of the next level the lives carried over from the last
+    pub fn start(&self, game: &mut Game) {
+        if let Some (lives) = self.lives {
+            game.player.lives = lives;
+        }
+    }
+
+    /// Move on from a level the player has escaped, keeping their lives and adding up their points
+    pub fn finish_level(&mut self, game: &Game, config: &Config) {
+        self.level += 1;
+        self.lives = Some (game.player.lives);
+        self.score += game.player.final_score(config).total();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_change_settings_from_the_config() {
+        let campaign = Campaign::parse("
+[[level]]
+name = \"Small\"
+dimensions = [3, 3, 2, 2]
+food-count = 3
+
+[[level]]
+dimensions = \"4x4x2x2\"
+ghost-move-time = 1.2
+").unwrap();
+        assert_eq!(campaign.len(), 2);
+        assert_eq!(campaign.name(0), "Small");
+        assert_eq!(campaign.name(1), "Level 2");
+        let config = Config::default();
+        let first = campaign.config(&config, 0).unwrap();
+        assert_eq!(first.dimensions, [3, 3, 2, 2]);
+        assert_eq!(first.food_count, 3);
+        assert_eq!(first.lives, config.lives);
+        let second = campaign.config(&config, 1).unwrap();
+        assert_eq!(second.dimensions, [4, 4, 2, 2]);
+        assert_eq!(second.ghost_move_time, 1.2);
+    }
+
+    #[test]
+    fn unplayable_levels_are_refused() {
+        let campaign = Campaign::parse("[[level]]\nname = \"Flat\"\ndimensions = [1, 3, 2, 2]\n").unwrap();
+        assert!(campaign.config(&Config::default(), 0).err().unwrap().starts_with("Flat: "));
+        let campaign = Campaign::parse("[[level]]\nspeed = 3\n").unwrap();
+        assert!(campaign.config(&Config::default(), 0).is_err());
+        assert!(Campaign::parse("").is_err());
+        let campaign = Campaign::parse("[[level]]\nname = \"Lost\"\nlevel = \"no such level.txt\"\n").unwrap();
+        assert!(campaign.config(&Config::default(), 0).err().unwrap().starts_with("Lost: "));
+    }
+
+    #[test]
+    fn the_default_campaign_is_playable() {
+        let campaign = Campaign::parse(DEFAULT_LEVELS).unwrap();
+        for level in 0..campaign.len() {
+            let config = campaign.config(&Config::default(), level).unwrap();
+            Game::from_seed(&config, 1).unwrap();
+        }
+    }
+}
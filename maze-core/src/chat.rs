@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::{debug, info, warn};
+
+/// Seconds each event chat votes for lasts
+pub const EVENT_TIME: f32 = 10.0;
+
+const SERVER: (&str, u16) = ("irc.chat.twitch.tv", 6667);
+const QUEUE: usize = 4; // Events waiting for the game to pick them up, past which more are dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(1); // Longest to wait on chat before checking whether the vote is over
+const RECONNECT_TIME: Duration = Duration::from_secs(10); // Wait before trying chat again after losing it
+
+/// Something chat can vote to throw at the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChatEvent {
+    Rush, // Ghosts move at double speed
+    Reveal, // The level is shown from above
+    Spin // Movement keys turn a quarter turn
+}
+
+impl ChatEvent {
+    pub const ALL: [ChatEvent; 3] = [ChatEvent::Rush, ChatEvent::Reveal, ChatEvent::Spin];
+
+    /// What to type in chat to vote for the event
+    pub fn command(&self) -> &'static str {
+        match self {
+            ChatEvent::Rush => "!rush",
+            ChatEvent::Reveal => "!map",
+            ChatEvent::Spin => "!spin"
+        }
+    }
+
+    // Event voted for by a chat message, if it's a vote at all
+    fn voted(message: &str) -> Option<ChatEvent> {
+        let command = message.split_whitespace().next()?.to_lowercase();
+        ChatEvent::ALL.into_iter().find(|event| event.command() == command)
+    }
+}
+
+// Votes in the current round, counting only the latest from each viewer
+#[derive(Debug, Default)]
+struct Ballot {
+    votes: HashMap<String, ChatEvent>
+}
+
+impl Ballot {
+    fn vote(&mut self, viewer: &str, event: ChatEvent) {
+        self.votes.insert(viewer.to_string(), event);
+    }
+
+    // Event with the most votes, with ties going to the first in ALL, and a fresh start for the next round
+    fn count(&mut self) -> Option<ChatEvent> {
+        let tally = |event: &ChatEvent| self.votes.values().filter(|vote| *vote == event).count();
+        let winner = ChatEvent::ALL.into_iter().filter(|event| tally(event) > 0).rev().max_by_key(|event| tally(event));
+        self.votes.clear();
+        winner
+    }
+}
+
+// Line from the chat server worth acting on
+#[derive(Debug, PartialEq)]
+enum Line<'a> {
+    Ping (&'a str),
+    Message { viewer: &'a str, text: &'a str }
+}
+
+impl<'a> Line<'a> {
+    // Lines look like `PING :tmi.twitch.tv` or `:viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :message`
+    fn parse(line: &'a str) -> Option<Line<'a>> {
+        let line = line.trim_end();
+        if let Some (server) = line.strip_prefix("PING ") {
+            return Some (Line::Ping (server));
+        }
+        let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+        let (_channel, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+        let viewer = prefix.split('!').next()?;
+        Some (Line::Message { viewer, text })
+    }
+}
+
+/// Reads a Twitch channel's chat on a thread of its own, tallying votes and passing on the winner of each round.
+/// Winners wait in a short queue for the game to pick up with `events`, eg. once a frame
+pub struct Chat {
+    events: Receiver<ChatEvent>
+}
+
+impl Chat {
+    /// Start reading the channel's chat anonymously, closing the vote every vote_time seconds
+    pub fn connect(channel: &str, vote_time: f32) -> Chat {
+        let (sender, events) = mpsc::sync_channel(QUEUE);
+        let (channel, vote_time) = (channel.to_string(), Duration::from_secs_f32(vote_time));
+        thread::spawn(move || {
+            let mut ballot = Ballot::default();
+            let mut vote_ends = Instant::now() + vote_time;
+            loop {
+                match read_chat(&channel, vote_time, &mut ballot, &mut vote_ends, &sender) {
+                    Ok (()) => return, // Nobody left to pass events to
+                    Err (e) => warn!(target: "net", "Lost touch with #{}'s chat: {}", channel, e)
+                }
+                thread::sleep(RECONNECT_TIME);
+            }
+        });
+        Chat { events }
+    }
+
+    /// Events chat has voted for since last asked
+    pub fn events(&self) -> impl Iterator<Item = ChatEvent> + '_ {
+        self.events.try_iter()
+    }
+}
+
+// Join the channel and count votes until the game stops listening, or the connection fails
+fn read_chat(channel: &str, vote_time: Duration, ballot: &mut Ballot, vote_ends: &mut Instant, sender: &SyncSender<ChatEvent>) -> io::Result<()> {
+    let mut stream = TcpStream::connect(SERVER)?;
+    stream.set_read_timeout(Some (READ_TIMEOUT))?;
+    // Twitch lets anyone read chat under a justinfan name, without logging in
+    let nick = format!("justinfan{}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_micros());
+    write!(stream, "NICK {}\r\nJOIN #{}\r\n", nick, channel)?;
+    info!(target: "net", "Reading #{}'s chat, where {} vote every {}s", channel,
+        ChatEvent::ALL.map(|event| event.command()).join(", "), vote_time.as_secs());
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    loop {
+        match reader.read_line(&mut line) {
+            Ok (0) => return Err (io::Error::new(io::ErrorKind::UnexpectedEof, "the server hung up")),
+            Ok (_) => {
+                match Line::parse(&line) {
+                    Some (Line::Ping (server)) => write!(stream, "PONG {}\r\n", server)?,
+                    Some (Line::Message { viewer, text }) => if let Some (event) = ChatEvent::voted(text) {
+                        debug!(target: "net", "{} voted {}", viewer, event.command());
+                        ballot.vote(viewer, event);
+                    },
+                    None => {}
+                }
+                line.clear();
+            },
+            Err (e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}, // Part of a line may be kept for next time
+            Err (e) => return Err (e)
+        }
+        if Instant::now() >= *vote_ends {
+            *vote_ends = Instant::now() + vote_time;
+            if let Some (event) = ballot.count() {
+                info!(target: "net", "Chat voted {}", event.command());
+                match sender.try_send(event) {
+                    Ok (()) | Err (TrySendError::Full (_)) => {},
+                    Err (TrySendError::Disconnected (_)) => return Ok (())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_lines_are_read_as_votes() {
+        let line = ":sam!sam@sam.tmi.twitch.tv PRIVMSG #maze :!Rush now please\r\n";
+        assert_eq!(Line::parse(line), Some (Line::Message { viewer: "sam", text: "!Rush now please" }));
+        assert_eq!(ChatEvent::voted("!Rush now please"), Some (ChatEvent::Rush));
+        assert_eq!(ChatEvent::voted("rush"), None);
+        assert_eq!(Line::parse("PING :tmi.twitch.tv\r\n"), Some (Line::Ping (":tmi.twitch.tv")));
+        assert_eq!(Line::parse(":tmi.twitch.tv 001 justinfan1 :Welcome, GLHF!"), None);
+    }
+
+    #[test]
+    fn each_viewer_gets_one_vote() {
+        let mut ballot = Ballot::default();
+        ballot.vote("sam", ChatEvent::Spin);
+        ballot.vote("sam", ChatEvent::Reveal);
+        ballot.vote("alex", ChatEvent::Spin);
+        ballot.vote("kim", ChatEvent::Rush);
+        assert_eq!(ballot.count(), Some (ChatEvent::Rush));
+        assert_eq!(ballot.count(), None);
+        ballot.vote("sam", ChatEvent::Spin);
+        ballot.vote("alex", ChatEvent::Spin);
+        ballot.vote("kim", ChatEvent::Rush);
+        assert_eq!(ballot.count(), Some (ChatEvent::Spin));
+    }
+}
@@ -0,0 +1,1634 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, read_to_string};
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+use log::LevelFilter;
+use serde::{Deserialize, Deserializer};
+use serde::de::Error as _;
+use toml::Value;
+
+use crate::error::{MazeError, Result};
+use crate::world::{Coordinate, World};
+
+/// Which graphics card to render with
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Card {
+    #[default]
+    Discrete,
+    Number (usize), // As listed by --list-gpus
+    Name (String) // Part of the card's name, in any case
+}
+
+/// Window size or fullscreen mode
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Window {
+    Borderless,
+    Exclusive,
+    Size (u32, u32)
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window::Size (640, 480)
+    }
+}
+
+/// Frame rate cap
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum TargetFps {
+    #[default]
+    Unlimited,
+    Fixed (usize)
+}
+
+/// Rendering resolution
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Resolution {
+    #[default]
+    Max,
+    Fixed (u32, u32)
+}
+
+/// Texture filtering mode
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum TextureQuality {
+    Low,
+    Trilinear,
+    Anisotropic (u32)
+}
+
+impl Default for TextureQuality {
+    fn default() -> Self {
+        TextureQuality::Anisotropic (16)
+    }
+}
+
+/// What the HUD clock shows
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum DisplayClock {
+    None,
+    Stopwatch,
+    Timer (u32)
+}
+
+/// How much to log, overall and for individual subsystems (world, render, ghost, input)
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Text")]
+pub struct LogLevels {
+    pub default: LevelFilter,
+    pub targets: Vec<(String, LevelFilter)>
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        // Keep release builds quiet unless asked otherwise
+        LogLevels {
+            default: if cfg!(debug_assertions) { LevelFilter::Info } else { LevelFilter::Warn },
+            targets: Vec::new()
+        }
+    }
+}
+
+impl LogLevels {
+    /// Level for messages from the given subsystem
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.targets.iter().find(|(name, _)| name == target).map_or(self.default, |(_, level)| *level)
+    }
+}
+
+/// Where the player starts
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Start {
+    Fixed ([usize; 4]),
+    Random
+}
+
+/// Where the player reappears after being caught while they have lives left
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Respawn {
+    Start,
+    Checkpoint // Cell of the last food eaten
+}
+
+/// Rules the game is played by
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Mode {
+    Classic, // Escape before the ghost catches you
+    TimeAttack, // No ghost, but the exit has to be reached within a par time
+    Zen, // No ghost and no clock
+    Survival // No way out, with food coming back and the ghost getting faster until it catches you
+}
+
+/// How the exit cell is chosen
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum ExitPlacement {
+    OppositeCorner,
+    MaxEccentricity,
+    RandomFar
+}
+
+/// How the food is scattered through the maze
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum FoodPlacement {
+    Uniform, // Any empty cell alike
+    FarFromStart, // More likely the further a cell is from the start
+    PerSlice, // Dealt out evenly between the w-slices
+    DeadEnds // Cells with only one way in or out first
+}
+
+/// How the player gets around each level
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Movement {
+    Grid, // A cell at a time for each press of a key
+    Free // Gliding wherever the held keys point, stopped by the walls, though still a level or slice at a time
+}
+
+/// Colors the w-slices are told apart by
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Text")]
+pub enum Palette {
+    #[default]
+    Rainbow,
+    Colorblind, // Okabe-Ito colors, which stay distinct with any kind of color blindness
+    HighContrast // Bright, saturated colors far apart in lightness
+}
+
+/// Camera placement relative to the player
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraPreset {
+    pub offset: [f32; 3], // From the player
+    pub fov: f32,
+    pub pitch: f32 // Degrees, 0 looks straight down
+}
+
+/// How each level of the maze is lit, shading from the top level's tint down to the bottom's
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelTheme {
+    pub top: [f32; 3],
+    pub bottom: [f32; 3],
+    pub curve: f32 // How quickly levels take on the bottom tint going down: 1 evenly, below 1 sooner, above 1 later
+}
+
+impl LevelTheme {
+    /// Tint of level z in a maze the given number of levels deep
+    pub fn tint(&self, z: usize, depth: usize) -> [f32; 3] {
+        let down = if depth > 1 { (depth - 1 - z.min(depth - 1)) as f32 / (depth - 1) as f32 } else { 0.0 };
+        let t = down.powf(self.curve);
+        [0, 1, 2].map(|i| self.top[i] + (self.bottom[i] - self.top[i]) * t)
+    }
+}
+
+/// How much of the maze around the player is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewDistance {
+    pub below: usize, // Levels under the player's own
+    pub above: usize, // Levels over the player's own
+    pub slices: usize // W-slices either side of the player's own
+}
+
+impl ViewDistance {
+    /// Lowest and highest levels drawn with the player on the given level, in a maze the given number of levels deep
+    pub fn levels(&self, level: i32, depth: usize) -> (usize, usize) {
+        let top = depth as i32 - 1;
+        ((level - self.below as i32).clamp(0, top) as usize, (level + self.above as i32).clamp(0, top) as usize)
+    }
+
+    /// W-slices drawn with the player in the given one, in a maze with the given number of them
+    pub fn slices(&self, fourth: i32, count: usize) -> impl Iterator<Item = usize> {
+        let reach = self.slices as i32;
+        (fourth - reach..=fourth + reach).filter(move |w| *w >= 0 && *w < count as i32).map(|w| w as usize)
+    }
+
+    /// Whether the cell is drawn with the player in the given cell
+    pub fn contains(&self, player: [i32; 4], (_, _, z, w): Coordinate) -> bool {
+        let (z, w) = (z as i32, w as i32);
+        z >= player[2] - self.below as i32 && z <= player[2] + self.above as i32 && (w - player[3]).abs() <= self.slices as i32
+    }
+}
+
+// The documented config file shipped with the game, which a missing config file is created from
+const DEFAULT_FILE: &str = include_str!("../res/config.toml");
+
+// Most levels drawn above or below the player, and w-slices drawn either side, before frames slow to a crawl
+const MAX_LEVELS: usize = 12;
+const MAX_SLICES: usize = 4;
+const MAX_DOORS: usize = 10; // One for each digit a key is written as in the text map
+
+/// Names of the camera presets, in the order they are stored in the config
+pub const CAMERA_PRESETS: [&str; 4] = ["close", "overview", "shoulder", "first-person"];
+
+/// Names of the HUD widgets that can be dragged around the screen, in the order their places are stored in the config
+pub const HUD_WIDGETS: [&str; 4] = ["controls", "clock", "score", "lives"];
+
+/// A setting that can change while the game is running, when the config file is edited
+#[derive(Clone, Copy)]
+pub enum LiveSetting {
+    UiScale (f32),
+    DisplayControls (bool),
+    DisplayCompass (bool),
+    EditHud (bool),
+    HudLayout ([[f32; 2]; 4]),
+    CameraPresets ([CameraPreset; 4]),
+    FovEffects (f32),
+    CameraShake (f32),
+    Palette (Palette),
+    SliceMarks (bool),
+    SliceOverlay (bool),
+    LevelTheme (LevelTheme),
+    ViewDistance (ViewDistance),
+    TargetFps (TargetFps)
+}
+
+// Each setting's value reads the same way in either format
+impl FromStr for Card {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "discrete" => Ok (Card::Discrete),
+            _ if value.chars().all(|c| c.is_ascii_digit()) => Ok (Card::Number (parse(value, "Expected discrete, an integer or part of a card's name")?)),
+            _ if !value.is_empty() => Ok (Card::Name (value.to_string())),
+            _ => Err ("Expected discrete, an integer or part of a card's name".to_string())
+        }
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "borderless" => Ok (Window::Borderless),
+            "exclusive" => Ok (Window::Exclusive),
+            _ => {
+                let [x, y] = parse_list(value, "Expected borderless, exclusive or a window size of the form 640x480")?;
+                Ok (Window::Size (x, y))
+            }
+        }
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        if value == "max" { return Ok (Resolution::Max); }
+        let [x, y] = parse_list(value, "Expected max or a resolution of the form 640x480")?;
+        Ok (Resolution::Fixed (x, y))
+    }
+}
+
+impl FromStr for TargetFps {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        if value == "unlimited" { Ok (TargetFps::Unlimited) } else { Ok (TargetFps::Fixed (parse(value, "Expected unlimited or an integer")?)) }
+    }
+}
+
+impl FromStr for TextureQuality {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "low" => Ok (TextureQuality::Low),
+            "trilinear" => Ok (TextureQuality::Trilinear),
+            _ => Ok (TextureQuality::Anisotropic (parse(value.trim_end_matches('x'), "Expected low, trilinear or an anisotropy level of the form 16x")?))
+        }
+    }
+}
+
+impl FromStr for DisplayClock {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "none" => Ok (DisplayClock::None),
+            "stopwatch" => Ok (DisplayClock::Stopwatch),
+            _ => Ok (DisplayClock::Timer (parse(value, "Expected none, stopwatch or an integer for timer")?))
+        }
+    }
+}
+
+impl FromStr for LogLevels {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        // Overall level first, then any per-subsystem overrides, eg. warn, ghost=debug
+        let mut parts = value.split(',').map(|s| s.trim());
+        let expected = "Expected off, error, warn, info, debug or trace";
+        let default = parse(parts.next().unwrap_or_default(), expected)?;
+        let targets = parts.map(|part| {
+            let (target, level) = part.split_once('=').ok_or("Expected subsystem log level of the form ghost=debug")?;
+            Ok ((target.trim().to_string(), parse(level.trim(), expected)?))
+        }).collect::<std::result::Result<_, String>>()?;
+        Ok (LogLevels { default, targets })
+    }
+}
+
+impl FromStr for Start {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        if value == "random" { Ok (Start::Random) } else { Ok (Start::Fixed (parse_list(value, "Expected random or a start cell of the form 0x0x0x0")?)) }
+    }
+}
+
+impl FromStr for Respawn {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "start" => Ok (Respawn::Start),
+            "checkpoint" => Ok (Respawn::Checkpoint),
+            _ => Err ("Expected start or checkpoint for respawn".to_string())
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "classic" => Ok (Mode::Classic),
+            "time-attack" => Ok (Mode::TimeAttack),
+            "zen" => Ok (Mode::Zen),
+            "survival" => Ok (Mode::Survival),
+            _ => Err ("Expected classic, time-attack, zen or survival for mode".to_string())
+        }
+    }
+}
+
+impl FromStr for ExitPlacement {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "opposite-corner" => Ok (ExitPlacement::OppositeCorner),
+            "max-eccentricity" => Ok (ExitPlacement::MaxEccentricity),
+            "random-far" => Ok (ExitPlacement::RandomFar),
+            _ => Err ("Expected opposite-corner, max-eccentricity or random-far for exit".to_string())
+        }
+    }
+}
+
+impl FromStr for FoodPlacement {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "uniform" => Ok (FoodPlacement::Uniform),
+            "far-from-start" => Ok (FoodPlacement::FarFromStart),
+            "per-slice" => Ok (FoodPlacement::PerSlice),
+            "dead-ends" => Ok (FoodPlacement::DeadEnds),
+            _ => Err ("Expected uniform, far-from-start, per-slice or dead-ends for food-placement".to_string())
+        }
+    }
+}
+
+impl FromStr for Movement {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "grid" => Ok (Movement::Grid),
+            "free" => Ok (Movement::Free),
+            _ => Err ("Expected grid or free for movement".to_string())
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "rainbow" => Ok (Palette::Rainbow),
+            "colorblind" => Ok (Palette::Colorblind),
+            "high-contrast" => Ok (Palette::HighContrast),
+            _ => Err ("Expected rainbow, colorblind or high-contrast for palette".to_string())
+        }
+    }
+}
+
+// Each setting's value writes out the way it reads in the older format, so it can be saved back
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Card::Discrete => write!(f, "discrete"),
+            Card::Number (n) => write!(f, "{}", n),
+            Card::Name (name) => write!(f, "{}", name)
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Window::Borderless => write!(f, "borderless"),
+            Window::Exclusive => write!(f, "exclusive"),
+            Window::Size (x, y) => write!(f, "{}x{}", x, y)
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Max => write!(f, "max"),
+            Resolution::Fixed (x, y) => write!(f, "{}x{}", x, y)
+        }
+    }
+}
+
+impl fmt::Display for TargetFps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetFps::Unlimited => write!(f, "unlimited"),
+            TargetFps::Fixed (fps) => write!(f, "{}", fps)
+        }
+    }
+}
+
+impl fmt::Display for TextureQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureQuality::Low => write!(f, "low"),
+            TextureQuality::Trilinear => write!(f, "trilinear"),
+            TextureQuality::Anisotropic (level) => write!(f, "{}x", level)
+        }
+    }
+}
+
+impl fmt::Display for DisplayClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayClock::None => write!(f, "none"),
+            DisplayClock::Stopwatch => write!(f, "stopwatch"),
+            DisplayClock::Timer (seconds) => write!(f, "{}", seconds)
+        }
+    }
+}
+
+impl fmt::Display for LogLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        for (target, level) in &self.targets {
+            write!(f, ", {}={}", target, level.to_string().to_lowercase())?;
+        }
+        Ok (())
+    }
+}
+
+impl fmt::Display for Start {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Start::Fixed (cell) => write!(f, "{}", join(cell, "x")),
+            Start::Random => write!(f, "random")
+        }
+    }
+}
+
+impl fmt::Display for Respawn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Respawn::Start => write!(f, "start"),
+            Respawn::Checkpoint => write!(f, "checkpoint")
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Classic => write!(f, "classic"),
+            Mode::TimeAttack => write!(f, "time-attack"),
+            Mode::Zen => write!(f, "zen"),
+            Mode::Survival => write!(f, "survival")
+        }
+    }
+}
+
+impl Mode {
+    /// Whether there's a ghost to escape from
+    pub fn has_ghost(&self) -> bool {
+        matches!(self, Mode::Classic | Mode::Survival)
+    }
+}
+
+impl fmt::Display for ExitPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitPlacement::OppositeCorner => write!(f, "opposite-corner"),
+            ExitPlacement::MaxEccentricity => write!(f, "max-eccentricity"),
+            ExitPlacement::RandomFar => write!(f, "random-far")
+        }
+    }
+}
+
+impl fmt::Display for FoodPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FoodPlacement::Uniform => write!(f, "uniform"),
+            FoodPlacement::FarFromStart => write!(f, "far-from-start"),
+            FoodPlacement::PerSlice => write!(f, "per-slice"),
+            FoodPlacement::DeadEnds => write!(f, "dead-ends")
+        }
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Movement::Grid => write!(f, "grid"),
+            Movement::Free => write!(f, "free")
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Palette::Rainbow => write!(f, "rainbow"),
+            Palette::Colorblind => write!(f, "colorblind"),
+            Palette::HighContrast => write!(f, "high-contrast")
+        }
+    }
+}
+
+impl fmt::Display for CameraPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {:?}, {:?}", join(&self.offset, "x"), self.fov, self.pitch)
+    }
+}
+
+/// A setting's value as text, the way the older format would have written it, eg. [640, 480] as 640x480
+#[derive(Deserialize)]
+#[serde(try_from = "Value")]
+struct Text (String);
+
+impl TryFrom<Value> for Text {
+    type Error = String;
+    fn try_from(value: Value) -> std::result::Result<Self, String> {
+        match value {
+            Value::String (text) => Ok (Text (text)),
+            Value::Integer (_) | Value::Float (_) | Value::Boolean (_) => Ok (Text (value.to_string())),
+            Value::Array (items) => {
+                let items = items.into_iter().map(|item| Text::try_from(item).map(|text| text.0)).collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok (Text (items.join("x")))
+            },
+            _ => Err ("Expected a single value or a list".to_string())
+        }
+    }
+}
+
+// TOML values for keyword settings go through the same parsing as the older format
+macro_rules! from_text {
+    ($($setting:ty),*) => {
+        $(impl TryFrom<Text> for $setting {
+            type Error = String;
+            fn try_from(text: Text) -> std::result::Result<Self, String> {
+                text.0.parse()
+            }
+        })*
+    };
+}
+
+from_text!(Card, Window, Resolution, TargetFps, TextureQuality, DisplayClock, LogLevels, Start, Respawn, Mode, ExitPlacement, FoodPlacement, Movement, Palette);
+
+/// User settings, read from a TOML config file, or one in the older `key: value` format
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub card: Card,
+    #[serde(rename = "resources")]
+    pub resource_path: String,
+    pub window: Window,
+    pub resolution: Resolution,
+    pub target_fps: TargetFps,
+    pub texture_quality: TextureQuality,
+    pub frames_in_flight: u32, // Frames drawn ahead of the one on screen, 2 for double buffering or 3 for triple
+    pub depth_prepass: bool, // Lay down the depth of solid geometry first, so hidden surfaces are never shaded
+    #[serde(deserialize_with = "camera")]
+    pub camera: usize, // Index into CAMERA_PRESETS
+    #[serde(deserialize_with = "camera_presets")]
+    pub camera_presets: [CameraPreset; 4],
+    pub fov_effects: f32, // Strength of the field of view widening while moving and narrowing near the ghost, 0 to 1
+    pub camera_shake: f32, // Strength of the camera shaking with the ghost close by and on losing a life, 0 to 1
+    pub top_tint: [f32; 3], // Color the top level of the maze is lit with
+    pub bottom_tint: [f32; 3], // Color the bottom level is lit with, levels in between shading from one to the other
+    pub theme_curve: f32, // How quickly levels take on the bottom tint going down: 1 evenly, below 1 sooner, above 1 later
+    pub see_through_walls: bool, // Walls fade out where they stand between the camera and the player
+    pub levels_below: usize, // Levels drawn under the player's own
+    pub levels_above: usize, // Levels drawn over the player's own, which can hide the player from a camera looking down
+    pub adjacent_slices: usize, // W-slices drawn either side of the player's own
+    pub decorations: f32, // How thickly cobwebs, pillars and runes are scattered through the maze, from 0 for none up to 4
+    pub ui_scale: f32,
+    pub edit_hud: bool, // Drag the HUD's widgets around with the mouse, rather than clicking the controls to move
+    #[serde(deserialize_with = "hud_layout")]
+    pub hud_layout: [[f32; 2]; 4], // How far each of HUD_WIDGETS has been dragged from its usual place, in half screen heights
+    pub display_controls: bool,
+    pub display_clock: DisplayClock,
+    pub display_compass: bool,
+    pub palette: Palette, // Colors the w-slices, and everything colored after them, are drawn in
+    pub slice_marks: bool, // Floors striped differently in each w-slice, and the player's slice numbered on screen
+    pub slice_overlay: bool, // Faint copies of the food and ghosts in the neighbouring w-slices drawn over the player's
+    pub event_feed_time: f32, // Seconds each line of the event feed stays on screen, or 0 for no feed
+    pub event_feed_size: f32, // Height of the event feed's text, relative to the usual
+    pub event_feed_stdout: bool, // Print each line of the event feed as it happens
+    pub language: String, // Code of the language text is shown in, eg. "fr", or "auto" for the system's
+    pub dimensions: [usize; 4],
+    pub seed: Option<u64>, // Same seed, same maze; a random one each game if not given
+    pub level: Option<String>, // Hand-made maze to play instead of generating one
+    pub race_echo: bool, // Race an echo of the fastest escape from the same maze
+    pub mode: Mode,
+    pub movement: Movement,
+    pub ghost_move_time: f32,
+    pub ghosts: usize, // Working together past one, with the nearest chasing, the next heading the player off and the next guarding the exit
+    pub co_op: bool, // A second player moves a ghost from the same keyboard
+    pub split_screen: bool, // A second player races to the exit on the other half of the window
+    pub food_count: usize,
+    pub food_placement: FoodPlacement,
+    pub doors: usize,
+    pub teleporters: usize, // Pairs of linked pads
+    pub ghost_teleports: bool,
+    pub traps: usize,
+    pub power_ups: usize,
+    pub power_up_time: f32, // Seconds each power-up lasts
+    pub flashlight_time: f32, // Seconds a full battery lasts, or 0 for no flashlight
+    pub batteries: usize,
+    pub fruit_interval: f32, // Seconds between bonus fruit, or 0 for none
+    pub fruit_time: f32, // Seconds each fruit stays before vanishing
+    pub fruit_points: u32,
+    pub combo_time: f32, // Seconds after eating food for the next to count towards a combo, or 0 for no combos
+    pub combo_max: u32, // Highest score multiplier a combo can reach
+    pub shift_interval: f32, // Seconds between walls moving, or 0 for a fixed maze
+    pub lives: u32,
+    pub respawn: Respawn,
+    pub start: Start,
+    pub exit: ExitPlacement,
+    pub min_solution: f32, // Fewest moves from start to exit, as a multiple of the moves from one corner of the maze to the opposite one
+    pub min_w_moves: usize, // Fewest moves between w-slices on the way from start to exit, if there's more than one slice
+    pub log_level: LogLevels,
+    pub player_name: String,
+    pub leaderboard: Option<String>, // Server to send escapes to and fetch the daily challenge's best times from
+    pub twitch_channel: Option<String>, // Channel whose chat votes on events to throw at the player
+    pub chat_vote_time: f32, // Seconds chat has to vote on each event
+    pub best_times: BTreeMap<String, u32>, // Seconds taken to escape, for each size of maze
+    #[serde(skip)]
+    pub legacy_format: bool // Read from a `key: value` file rather than TOML
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            card: Card::Discrete,
+            resource_path: "res/".to_string(),
+            window: Window::Size(1280, 720),
+            resolution: Resolution::Max,
+            target_fps: TargetFps::Fixed(60),
+            texture_quality: TextureQuality::Anisotropic(16),
+            frames_in_flight: 2,
+            depth_prepass: false,
+            camera: 0,
+            camera_presets: [
+                CameraPreset { offset: [0.0, 1.6, 4.0], fov: 90.0, pitch: 30.0 },
+                CameraPreset { offset: [0.0, 4.0, 12.0], fov: 70.0, pitch: 20.0 },
+                CameraPreset { offset: [0.6, 1.8, 1.6], fov: 80.0, pitch: 50.0 },
+                CameraPreset { offset: [0.0, 0.0, 0.6], fov: 100.0, pitch: 85.0 }
+            ],
+            fov_effects: 1.0,
+            camera_shake: 1.0,
+            top_tint: [1.0, 0.97, 0.9],
+            bottom_tint: [0.45, 0.55, 0.85],
+            theme_curve: 1.0,
+            see_through_walls: true,
+            levels_below: 6,
+            levels_above: 0,
+            adjacent_slices: 2,
+            decorations: 1.0,
+            ui_scale: 1.0,
+            edit_hud: false,
+            hud_layout: [[0.0; 2]; 4],
+            display_controls: true,
+            display_clock: DisplayClock::None,
+            display_compass: true,
+            palette: Palette::Rainbow,
+            slice_marks: false,
+            slice_overlay: false,
+            event_feed_time: 0.0,
+            event_feed_size: 1.0,
+            event_feed_stdout: false,
+            language: "auto".to_string(),
+            dimensions: [5, 5, 5, 3],
+            seed: None,
+            level: None,
+            race_echo: false,
+            mode: Mode::Classic,
+            movement: Movement::Grid,
+            ghost_move_time: 1.65,
+            ghosts: 1,
+            co_op: false,
+            split_screen: false,
+            food_count: 10,
+            food_placement: FoodPlacement::Uniform,
+            doors: 0,
+            teleporters: 0,
+            ghost_teleports: false,
+            traps: 0,
+            power_ups: 0,
+            power_up_time: 10.0,
+            flashlight_time: 30.0,
+            batteries: 0,
+            fruit_interval: 0.0,
+            fruit_time: 8.0,
+            fruit_points: 5,
+            combo_time: 3.0,
+            combo_max: 5,
+            shift_interval: 0.0,
+            lives: 3,
+            respawn: Respawn::Start,
+            start: Start::Fixed([0, 0, 0, 0]),
+            exit: ExitPlacement::OppositeCorner,
+            min_solution: 1.5,
+            min_w_moves: 1,
+            log_level: LogLevels::default(),
+            player_name: "Player".to_string(),
+            leaderboard: None,
+            twitch_channel: None,
+            chat_vote_time: 30.0,
+            best_times: BTreeMap::new(),
+            legacy_format: false
+        }
+    }
+}
+
+impl Config {
+    /// Read settings from the file, using defaults for any that are missing
+    pub fn new(file: &str) -> Result<Config> {
+        let contents = read_to_string(file).map_err(|e| MazeError::io(file, e))?;
+        Config::parse(file, &contents)
+    }
+
+    /// Read settings from the contents of a config file in either format. The path is only used in errors
+    pub fn parse(path: &str, contents: &str) -> Result<Config> {
+        let error = |line: usize, reason: String| MazeError::Config {
+            path: path.to_string(),
+            line,
+            text: contents.lines().nth(line.saturating_sub(1)).unwrap_or_default().to_string(),
+            reason
+        };
+        let config = if is_legacy(contents) {
+            Config::parse_legacy(contents).map_err(|(line, reason)| error(line, reason))?
+        } else {
+            toml::from_str(contents).map_err(|e: toml::de::Error| {
+                // toml only knows where syntax errors are, but names the key for bad values, eg. "... for key `window`"
+                let message = e.to_string();
+                let key = message.split_once(" for key `").or_else(|| message.split_once("unknown field `"))
+                    .and_then(|(_, rest)| rest.split('`').next())
+                    .map(|key| key.rsplit('.').next().unwrap_or(key));
+                let line = key.and_then(|key| setting_line(contents, &[key]))
+                    .or_else(|| e.line_col().map(|(line, _)| line + 1))
+                    .unwrap_or(0);
+                let reason = message.split(" for key `").next().unwrap_or_default();
+                error(line, reason.split(" at line ").next().unwrap_or_default().to_string())
+            })?
+        };
+        config.check().map_err(|(keys, reason)| {
+            let keys: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+            error(setting_line(contents, &keys).unwrap_or(0), reason)
+        })?;
+        Ok (config)
+    }
+
+    /// Apply settings from the command line on top of the config file, eg. ("dimensions", "5x5x3x3")
+    pub fn override_with(&mut self, settings: &[(&str, String)]) -> Result<()> {
+        let error = |option: &str, reason: String| MazeError::Argument { option: option.to_string(), reason };
+        for (key, value) in settings {
+            self.set(key, value).map_err(|reason| error(key, reason))?;
+        }
+        if let Some (path) = &self.level {
+            World::from_level(path)?.fit_config(self);
+        }
+        self.check().map_err(|(keys, reason)| {
+            // Blame the option given, rather than whatever it clashed with in the file
+            let key = keys.iter().find(|key| settings.iter().any(|(option, _)| option == key)).unwrap_or(&keys[0]);
+            error(key, reason)
+        })
+    }
+
+    /// Settings that can change live which differ in the other config
+    pub fn live_changes(&self, other: &Config) -> Vec<LiveSetting> {
+        let mut changes = Vec::new();
+        if self.ui_scale != other.ui_scale {
+            changes.push(LiveSetting::UiScale (other.ui_scale));
+        }
+        if self.display_controls != other.display_controls {
+            changes.push(LiveSetting::DisplayControls (other.display_controls));
+        }
+        if self.display_compass != other.display_compass {
+            changes.push(LiveSetting::DisplayCompass (other.display_compass));
+        }
+        if self.edit_hud != other.edit_hud {
+            changes.push(LiveSetting::EditHud (other.edit_hud));
+        }
+        if self.hud_layout != other.hud_layout {
+            changes.push(LiveSetting::HudLayout (other.hud_layout));
+        }
+        if self.palette != other.palette {
+            changes.push(LiveSetting::Palette (other.palette));
+        }
+        if self.slice_marks != other.slice_marks {
+            changes.push(LiveSetting::SliceMarks (other.slice_marks));
+        }
+        if self.slice_overlay != other.slice_overlay {
+            changes.push(LiveSetting::SliceOverlay (other.slice_overlay));
+        }
+        if self.camera_presets != other.camera_presets {
+            changes.push(LiveSetting::CameraPresets (other.camera_presets));
+        }
+        if self.fov_effects != other.fov_effects {
+            changes.push(LiveSetting::FovEffects (other.fov_effects));
+        }
+        if self.camera_shake != other.camera_shake {
+            changes.push(LiveSetting::CameraShake (other.camera_shake));
+        }
+        if self.level_theme() != other.level_theme() {
+            changes.push(LiveSetting::LevelTheme (other.level_theme()));
+        }
+        if self.view_distance() != other.view_distance() {
+            changes.push(LiveSetting::ViewDistance (other.view_distance()));
+        }
+        if self.target_fps != other.target_fps {
+            changes.push(LiveSetting::TargetFps (other.target_fps));
+        }
+        changes
+    }
+
+    /// Names of the settings that differ in the other config but only take effect after restarting
+    pub fn restart_changes(&self, other: &Config) -> Vec<&'static str> {
+        [
+            ("card", self.card != other.card),
+            ("resources", self.resource_path != other.resource_path),
+            ("window", self.window != other.window),
+            ("resolution", self.resolution != other.resolution),
+            ("texture-quality", self.texture_quality != other.texture_quality),
+            ("frames-in-flight", self.frames_in_flight != other.frames_in_flight),
+            ("depth-prepass", self.depth_prepass != other.depth_prepass),
+            ("camera", self.camera != other.camera),
+            ("see-through-walls", self.see_through_walls != other.see_through_walls),
+            ("decorations", self.decorations != other.decorations),
+            ("display-clock", self.display_clock != other.display_clock),
+            ("event-feed-time", self.event_feed_time != other.event_feed_time),
+            ("event-feed-size", self.event_feed_size != other.event_feed_size),
+            ("event-feed-stdout", self.event_feed_stdout != other.event_feed_stdout),
+            ("language", self.language != other.language),
+            ("dimensions", self.dimensions != other.dimensions),
+            ("seed", self.seed != other.seed),
+            ("level", self.level != other.level),
+            ("race-echo", self.race_echo != other.race_echo),
+            ("mode", self.mode != other.mode),
+            ("movement", self.movement != other.movement),
+            ("ghost-move-time", self.ghost_move_time != other.ghost_move_time),
+            ("ghosts", self.ghosts != other.ghosts),
+            ("co-op", self.co_op != other.co_op),
+            ("split-screen", self.split_screen != other.split_screen),
+            ("food-count", self.food_count != other.food_count),
+            ("food-placement", self.food_placement != other.food_placement),
+            ("doors", self.doors != other.doors),
+            ("teleporters", self.teleporters != other.teleporters),
+            ("ghost-teleports", self.ghost_teleports != other.ghost_teleports),
+            ("traps", self.traps != other.traps),
+            ("power-ups", self.power_ups != other.power_ups),
+            ("power-up-time", self.power_up_time != other.power_up_time),
+            ("flashlight-time", self.flashlight_time != other.flashlight_time),
+            ("batteries", self.batteries != other.batteries),
+            ("fruit-interval", self.fruit_interval != other.fruit_interval),
+            ("fruit-time", self.fruit_time != other.fruit_time),
+            ("fruit-points", self.fruit_points != other.fruit_points),
+            ("combo-time", self.combo_time != other.combo_time),
+            ("combo-max", self.combo_max != other.combo_max),
+            ("shift-interval", self.shift_interval != other.shift_interval),
+            ("lives", self.lives != other.lives),
+            ("respawn", self.respawn != other.respawn),
+            ("start", self.start != other.start),
+            ("exit", self.exit != other.exit),
+            ("min-solution", self.min_solution != other.min_solution),
+            ("min-w-moves", self.min_w_moves != other.min_w_moves),
+            ("log-level", self.log_level != other.log_level),
+            ("player-name", self.player_name != other.player_name),
+            ("leaderboard", self.leaderboard != other.leaderboard),
+            ("twitch-channel", self.twitch_channel != other.twitch_channel),
+            ("chat-vote-time", self.chat_vote_time != other.chat_vote_time)
+        ].into_iter().filter(|(_, changed)| *changed).map(|(key, _)| key).collect()
+    }
+
+    /// Take on a setting changed while the game is running
+    pub fn apply(&mut self, change: LiveSetting) {
+        match change {
+            LiveSetting::UiScale (scale) => self.ui_scale = scale,
+            LiveSetting::DisplayControls (display) => self.display_controls = display,
+            LiveSetting::DisplayCompass (display) => self.display_compass = display,
+            LiveSetting::EditHud (edit) => self.edit_hud = edit,
+            LiveSetting::HudLayout (layout) => self.hud_layout = layout,
+            LiveSetting::Palette (palette) => self.palette = palette,
+            LiveSetting::SliceMarks (marks) => self.slice_marks = marks,
+            LiveSetting::SliceOverlay (overlay) => self.slice_overlay = overlay,
+            LiveSetting::CameraPresets (presets) => self.camera_presets = presets,
+            LiveSetting::FovEffects (strength) => self.fov_effects = strength,
+            LiveSetting::CameraShake (strength) => self.camera_shake = strength,
+            LiveSetting::LevelTheme (theme) => {
+                self.top_tint = theme.top;
+                self.bottom_tint = theme.bottom;
+                self.theme_curve = theme.curve;
+            },
+            LiveSetting::ViewDistance (distance) => {
+                self.levels_below = distance.below;
+                self.levels_above = distance.above;
+                self.adjacent_slices = distance.slices;
+            },
+            LiveSetting::TargetFps (fps) => self.target_fps = fps
+        }
+    }
+
+    /// How the levels of the maze are lit, from the tint and curve settings
+    pub fn level_theme(&self) -> LevelTheme {
+        LevelTheme { top: self.top_tint, bottom: self.bottom_tint, curve: self.theme_curve }
+    }
+
+    /// How much of the maze around the player is drawn
+    pub fn view_distance(&self) -> ViewDistance {
+        ViewDistance { below: self.levels_below, above: self.levels_above, slices: self.adjacent_slices }
+    }
+
+    /// Best time escaping a maze of the current size, if there is one yet
+    pub fn best_time(&self) -> Option<u32> {
+        self.best_times.get(&maze_size(&self.dimensions)).copied()
+    }
+
+    /// Every setting apart from the camera presets, HUD layout and best times, written the way the older config format would have it,
+    /// in the order config.toml lists them. Each reads back the same through `set`, and a missing seed is None
+    pub fn settings(&self) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("card", Some (self.card.to_string())),
+            ("resources", Some (self.resource_path.clone())),
+            ("window", Some (self.window.to_string())),
+            ("resolution", Some (self.resolution.to_string())),
+            ("target-fps", Some (self.target_fps.to_string())),
+            ("texture-quality", Some (self.texture_quality.to_string())),
+            ("frames-in-flight", Some (self.frames_in_flight.to_string())),
+            ("depth-prepass", Some (self.depth_prepass.to_string())),
+            ("display-controls", Some (self.display_controls.to_string())),
+            ("display-clock", Some (self.display_clock.to_string())),
+            ("display-compass", Some (self.display_compass.to_string())),
+            ("palette", Some (self.palette.to_string())),
+            ("slice-marks", Some (self.slice_marks.to_string())),
+            ("slice-overlay", Some (self.slice_overlay.to_string())),
+            ("event-feed-time", Some (format!("{:?}", self.event_feed_time))),
+            ("event-feed-size", Some (format!("{:?}", self.event_feed_size))),
+            ("event-feed-stdout", Some (self.event_feed_stdout.to_string())),
+            ("language", Some (self.language.clone())),
+            ("camera", Some (CAMERA_PRESETS[self.camera].to_string())),
+            ("fov-effects", Some (format!("{:?}", self.fov_effects))),
+            ("camera-shake", Some (format!("{:?}", self.camera_shake))),
+            ("top-tint", Some (join(&self.top_tint, "x"))),
+            ("bottom-tint", Some (join(&self.bottom_tint, "x"))),
+            ("theme-curve", Some (format!("{:?}", self.theme_curve))),
+            ("see-through-walls", Some (self.see_through_walls.to_string())),
+            ("levels-below", Some (self.levels_below.to_string())),
+            ("levels-above", Some (self.levels_above.to_string())),
+            ("adjacent-slices", Some (self.adjacent_slices.to_string())),
+            ("decorations", Some (format!("{:?}", self.decorations))),
+            ("ui-scale", Some (format!("{:?}", self.ui_scale))),
+            ("edit-hud", Some (self.edit_hud.to_string())),
+            ("dimensions", Some (join(&self.dimensions, "x"))),
+            ("seed", self.seed.map(|seed| seed.to_string())),
+            ("level", self.level.clone()),
+            ("race-echo", Some (self.race_echo.to_string())),
+            ("mode", Some (self.mode.to_string())),
+            ("movement", Some (self.movement.to_string())),
+            ("ghost-move-time", Some (format!("{:?}", self.ghost_move_time))),
+            ("ghosts", Some (self.ghosts.to_string())),
+            ("co-op", Some (self.co_op.to_string())),
+            ("split-screen", Some (self.split_screen.to_string())),
+            ("food-count", Some (self.food_count.to_string())),
+            ("food-placement", Some (self.food_placement.to_string())),
+            ("doors", Some (self.doors.to_string())),
+            ("teleporters", Some (self.teleporters.to_string())),
+            ("ghost-teleports", Some (self.ghost_teleports.to_string())),
+            ("traps", Some (self.traps.to_string())),
+            ("power-ups", Some (self.power_ups.to_string())),
+            ("power-up-time", Some (format!("{:?}", self.power_up_time))),
+            ("flashlight-time", Some (format!("{:?}", self.flashlight_time))),
+            ("batteries", Some (self.batteries.to_string())),
+            ("fruit-interval", Some (format!("{:?}", self.fruit_interval))),
+            ("fruit-time", Some (format!("{:?}", self.fruit_time))),
+            ("fruit-points", Some (self.fruit_points.to_string())),
+            ("combo-time", Some (format!("{:?}", self.combo_time))),
+            ("combo-max", Some (self.combo_max.to_string())),
+            ("shift-interval", Some (format!("{:?}", self.shift_interval))),
+            ("lives", Some (self.lives.to_string())),
+            ("respawn", Some (self.respawn.to_string())),
+            ("start", Some (self.start.to_string())),
+            ("exit", Some (self.exit.to_string())),
+            ("min-solution", Some (format!("{:?}", self.min_solution))),
+            ("min-w-moves", Some (self.min_w_moves.to_string())),
+            ("log-level", Some (self.log_level.to_string())),
+            ("player-name", Some (self.player_name.clone())),
+            ("leaderboard", self.leaderboard.clone()),
+            ("twitch-channel", self.twitch_channel.clone()),
+            ("chat-vote-time", Some (format!("{:?}", self.chat_vote_time)))
+        ]
+    }
+
+    /// Write the settings to the file, only touching the lines of settings that differ from what it already holds,
+    /// so comments and layout are kept. A missing file is first filled in with the documented defaults from config.toml
+    pub fn save(&self, path: &str) -> Result<()> {
+        let contents = match read_to_string(path) {
+            Ok (contents) => contents,
+            Err (e) if e.kind() == ErrorKind::NotFound => DEFAULT_FILE.to_string(),
+            Err (e) => return Err (MazeError::io(path, e))
+        };
+        let contents = self.merge_into(path, &contents)?;
+        fs::write(path, contents).map_err(|e| MazeError::io(path, e))
+    }
+
+    // Rewrite the contents of a config file, in whichever format it's in, to hold these settings
+    fn merge_into(&self, path: &str, contents: &str) -> Result<String> {
+        let legacy = is_legacy(contents);
+        let separator = if legacy { ":" } else { " =" };
+        let saved = Config::parse(path, contents)?.entries(legacy);
+        let entries = self.entries(legacy);
+        let removed: Vec<_> = saved.iter()
+            .filter(|(table, key, _)| !entries.iter().any(|entry| entry.0 == *table && entry.1 == *key))
+            .map(|(table, key, _)| (*table, key.clone(), None))
+            .collect();
+        let mut lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
+        for (table, key, after) in entries.into_iter().chain(removed) {
+            let before = saved.iter().find(|entry| entry.0 == table && entry.1 == key).and_then(|entry| entry.2.clone());
+            if before == after {
+                continue;
+            }
+            let setting = |value: &str, comment: &str| format!("{}{} {}{}", key, separator, value, comment);
+            match (find_setting(&lines, table, &key, false), after) {
+                (Some (i), Some (value)) => {
+                    let comment = trailing_comment(&lines[i]).to_string();
+                    lines[i] = setting(&value, &comment);
+                },
+                (Some (i), None) => lines[i] = format!("# {}", lines[i]), // Comment out, eg. a seed no longer used
+                (None, Some (value)) => match find_setting(&lines, table, &key, true) {
+                    Some (i) => lines[i] = setting(&value, ""), // Fill in the commented out example
+                    None => {
+                        let i = end_of_table(&mut lines, table);
+                        lines.insert(i, setting(&value, ""));
+                    }
+                },
+                (None, None) => {}
+            }
+        }
+        Ok (lines.join("\n") + "\n")
+    }
+
+    // Each setting as it would appear in a file of either format: the TOML table it's in, its key and its value
+    fn entries(&self, legacy: bool) -> Vec<(Option<&'static str>, String, Option<String>)> {
+        let mut entries: Vec<_> = self.settings().into_iter().map(|(key, value)| {
+            (None, key.to_string(), if legacy { value } else { value.map(|value| toml_value(key, &value)) })
+        }).collect();
+        for (name, preset) in CAMERA_PRESETS.iter().zip(&self.camera_presets) {
+            entries.push(if legacy {
+                (None, format!("camera-{}", name), Some (preset.to_string()))
+            } else {
+                let value = format!("{{ offset = [{}], fov = {:?}, pitch = {:?} }}", join(&preset.offset, ", "), preset.fov, preset.pitch);
+                (Some ("camera-presets"), name.to_string(), Some (value))
+            });
+        }
+        for (name, offset) in HUD_WIDGETS.iter().zip(&self.hud_layout) {
+            entries.push(if legacy {
+                (None, format!("hud-{}", name), Some (join(offset, "x")))
+            } else {
+                (Some ("hud-layout"), name.to_string(), Some (format!("[{}]", join(offset, ", "))))
+            });
+        }
+        for (size, seconds) in &self.best_times {
+            entries.push(if legacy {
+                (None, format!("best-time-{}", size), Some (seconds.to_string()))
+            } else {
+                (Some ("best-times"), size.clone(), Some (seconds.to_string()))
+            });
+        }
+        entries
+    }
+
+    // Older `key: value` format, one setting per line
+    fn parse_legacy(contents: &str) -> std::result::Result<Config, (usize, String)> {
+        let mut config = Config { legacy_format: true, ..Config::default() };
+        for (number, text) in contents.lines().enumerate() {
+            let line = text.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue; // Skip empty/comment line
+            }
+            config.parse_line(line).map_err(|reason| (number + 1, reason))?;
+        }
+        Ok (config)
+    }
+
+    fn parse_line(&mut self, line: &str) -> std::result::Result<(), String> {
+        let (key, value) = line.split_once(':').ok_or("Expected a line of the form `key: value'")?;
+        self.set(key.trim(), value.trim())
+    }
+
+    /// Change one setting, written the way the older config format would have it, eg. dimensions and 5x5x3x3.
+    /// Settings given on the command line are applied this way, on top of the config file
+    pub fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        match key {
+            "card" => self.card = value.parse()?,
+            "resources" => self.resource_path = value.to_string(),
+            "window" => self.window = value.parse()?,
+            "resolution" => self.resolution = value.parse()?,
+            "target-fps" => self.target_fps = value.parse()?,
+            "texture-quality" => self.texture_quality = value.parse()?,
+            "frames-in-flight" => self.frames_in_flight = parse(value, "Expected 2 or 3")?,
+            "depth-prepass" => self.depth_prepass = parse(value, "Expected true or false")?,
+            "fov" => self.camera_presets[0].fov = parse(value, "Expected decimal value")?, // Kept for older config files
+            "camera" => self.camera = camera_index(value)?,
+            "fov-effects" => self.fov_effects = parse(value, "Expected decimal value")?,
+            "camera-shake" => self.camera_shake = parse(value, "Expected decimal value")?,
+            "top-tint" => self.top_tint = parse_list(value, "Expected a color of the form 1.0x0.97x0.9")?,
+            "bottom-tint" => self.bottom_tint = parse_list(value, "Expected a color of the form 0.45x0.55x0.85")?,
+            "theme-curve" => self.theme_curve = parse(value, "Expected decimal value")?,
+            "see-through-walls" => self.see_through_walls = parse(value, "Expected true or false")?,
+            "levels-below" => self.levels_below = parse(value, "Expected integer")?,
+            "levels-above" => self.levels_above = parse(value, "Expected integer")?,
+            "adjacent-slices" => self.adjacent_slices = parse(value, "Expected integer")?,
+            "decorations" => self.decorations = parse(value, "Expected decimal value")?,
+            "ui-scale" => self.ui_scale = parse(value, "Expected decimal value")?,
+            "edit-hud" => self.edit_hud = parse(value, "Expected true or false")?,
+            "display-controls" => self.display_controls = parse(value, "Expected true or false")?,
+            "display-clock" => self.display_clock = value.parse()?,
+            "display-compass" => self.display_compass = parse(value, "Expected true or false")?,
+            "palette" => self.palette = value.parse()?,
+            "slice-marks" => self.slice_marks = parse(value, "Expected true or false")?,
+            "slice-overlay" => self.slice_overlay = parse(value, "Expected true or false")?,
+            "event-feed-time" => self.event_feed_time = parse(value, "Expected decimal value")?,
+            "event-feed-size" => self.event_feed_size = parse(value, "Expected decimal value")?,
+            "event-feed-stdout" => self.event_feed_stdout = parse(value, "Expected true or false")?,
+            "language" => self.language = value.to_string(),
+            "dimensions" => self.dimensions = parse_list(value, "Expected dimensions of the form 5x5x5x3")?,
+            "seed" => self.seed = Some (parse(value, "Expected a whole number for the seed")?),
+            "level" => self.level = Some (value.to_string()),
+            "race-echo" => self.race_echo = parse(value, "Expected true or false")?,
+            "mode" => self.mode = value.parse()?,
+            "movement" => self.movement = value.parse()?,
+            "ghost-move-time" => self.ghost_move_time = parse(value, "Expected decimal value")?,
+            "ghosts" => self.ghosts = parse(value, "Expected integer")?,
+            "co-op" => self.co_op = parse(value, "Expected true or false")?,
+            "split-screen" => self.split_screen = parse(value, "Expected true or false")?,
+            "food-count" => self.food_count = parse(value, "Expected integer")?,
+            "food-placement" => self.food_placement = value.parse()?,
+            "doors" => self.doors = parse(value, "Expected integer")?,
+            "teleporters" => self.teleporters = parse(value, "Expected integer")?,
+            "ghost-teleports" => self.ghost_teleports = parse(value, "Expected true or false")?,
+            "traps" => self.traps = parse(value, "Expected integer")?,
+            "power-ups" => self.power_ups = parse(value, "Expected integer")?,
+            "power-up-time" => self.power_up_time = parse(value, "Expected decimal value")?,
+            "flashlight-time" => self.flashlight_time = parse(value, "Expected decimal value")?,
+            "batteries" => self.batteries = parse(value, "Expected integer")?,
+            "fruit-interval" => self.fruit_interval = parse(value, "Expected decimal value")?,
+            "fruit-time" => self.fruit_time = parse(value, "Expected decimal value")?,
+            "fruit-points" => self.fruit_points = parse(value, "Expected integer")?,
+            "combo-time" => self.combo_time = parse(value, "Expected decimal value")?,
+            "combo-max" => self.combo_max = parse(value, "Expected integer")?,
+            "shift-interval" => self.shift_interval = parse(value, "Expected decimal value")?,
+            "lives" => self.lives = parse(value, "Expected integer")?,
+            "respawn" => self.respawn = value.parse()?,
+            "start" => self.start = value.parse()?,
+            "exit" => self.exit = value.parse()?,
+            "min-solution" => self.min_solution = parse(value, "Expected decimal value")?,
+            "min-w-moves" => self.min_w_moves = parse(value, "Expected integer")?,
+            "log-level" => self.log_level = value.parse()?,
+            "player-name" => self.player_name = value.to_string(),
+            "leaderboard" => self.leaderboard = Some (value.to_string()),
+            "twitch-channel" => self.twitch_channel = Some (value.trim_start_matches('#').to_lowercase()),
+            "chat-vote-time" => self.chat_vote_time = parse(value, "Expected decimal value")?,
+            _ if key.starts_with("best-time-") => {
+                let size: [usize; 4] = parse_list(&key["best-time-".len()..], "Expected best time of a maze size, eg. best-time-5x5x5x3")?;
+                self.best_times.insert(maze_size(&size), parse(value, "Expected a whole number of seconds")?);
+            },
+            _ if key.starts_with("hud-") => {
+                let index = hud_widget(&key["hud-".len()..])?;
+                self.hud_layout[index] = parse_list(value, "Expected how far the widget has moved, of the form 0.1x-0.25")?;
+            },
+            _ if key.starts_with("camera-") => {
+                let index = CAMERA_PRESETS.iter().position(|name| *name == &key["camera-".len()..]).ok_or(format!("Invalid camera preset: {}", key))?;
+                let parts: Vec<&str> = value.split(',').map(|s| s.trim()).collect();
+                if let [offset, fov, pitch] = parts[..] {
+                    self.camera_presets[index] = CameraPreset {
+                        offset: parse_list(offset, "Expected camera offset of the form 0.0x1.6x4.0")?,
+                        fov: parse(fov, "Expected decimal value for camera fov")?,
+                        pitch: parse(pitch, "Expected decimal value for camera pitch")?
+                    };
+                } else {
+                    return Err ("Expected camera preset of the form 0.0x1.6x4.0, 90, 30".to_string());
+                }
+            },
+            _ => return Err (format!("Unknown setting `{}'", key))
+        }
+        Ok (())
+    }
+
+    // Catch settings that read fine but can't make a playable game, along with the keys that could be to blame
+    pub(crate) fn check(&self) -> std::result::Result<(), (Vec<String>, String)> {
+        if self.target_fps == TargetFps::Fixed (0) {
+            return Err ((vec!["target-fps".to_string()], "Expected a target of at least 1 FPS, or unlimited".to_string()));
+        }
+        if !(2..=3).contains(&self.frames_in_flight) {
+            return Err ((vec!["frames-in-flight".to_string()], "Expected 2 for double buffering or 3 for triple".to_string()));
+        }
+        if !(self.min_solution >= 0.0 && self.min_solution.is_finite()) {
+            return Err ((vec!["min-solution".to_string()], "Expected a multiple of at least 0".to_string()));
+        }
+        if self.ghosts == 0 {
+            return Err ((vec!["ghosts".to_string()], "Expected at least 1 ghost".to_string()));
+        }
+        if self.co_op && self.split_screen {
+            let keys = vec!["co-op".to_string(), "split-screen".to_string()];
+            return Err ((keys, "Expected only one of co-op and split-screen, since the second player has one set of keys".to_string()));
+        }
+        if self.lives == 0 {
+            return Err ((vec!["lives".to_string()], "Expected at least 1 life".to_string()));
+        }
+        if self.chat_vote_time <= 0.0 {
+            return Err ((vec!["chat-vote-time".to_string()], "Expected some time for chat to vote".to_string()));
+        }
+        for (name, preset) in CAMERA_PRESETS.iter().zip(&self.camera_presets) {
+            if !(30.0..=120.0).contains(&preset.fov) {
+                let keys = vec![format!("camera-{}", name), name.to_string(), "fov".to_string()];
+                return Err ((keys, format!("Expected {} camera fov between 30 and 120 degrees", name)));
+            }
+        }
+        for (name, offset) in HUD_WIDGETS.iter().zip(&self.hud_layout) {
+            if offset.iter().any(|f| !(-2.0..=2.0).contains(f)) {
+                let keys = vec![format!("hud-{}", name), name.to_string()];
+                return Err ((keys, format!("Expected the {} to move at most 2 either way, to stay on screen", name)));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.fov_effects) {
+            return Err ((vec!["fov-effects".to_string()], "Expected a strength between 0 and 1".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.camera_shake) {
+            return Err ((vec!["camera-shake".to_string()], "Expected a strength between 0 and 1".to_string()));
+        }
+        for (key, tint) in [("top-tint", self.top_tint), ("bottom-tint", self.bottom_tint)] {
+            if tint.iter().any(|channel| !(0.0..=2.0).contains(channel)) {
+                return Err ((vec![key.to_string()], "Expected each channel of the tint between 0 and 2".to_string()));
+            }
+        }
+        if self.theme_curve <= 0.0 {
+            return Err ((vec!["theme-curve".to_string()], "Expected a curve above 0".to_string()));
+        }
+        for (key, levels) in [("levels-below", self.levels_below), ("levels-above", self.levels_above)] {
+            if levels > MAX_LEVELS {
+                return Err ((vec![key.to_string()], format!("Expected at most {} levels, since each one drawn slows every frame", MAX_LEVELS)));
+            }
+        }
+        if self.doors > MAX_DOORS {
+            return Err ((vec!["doors".to_string()], format!("Expected at most {} doors, one for each key from 0 to 9", MAX_DOORS)));
+        }
+        if self.adjacent_slices > MAX_SLICES {
+            return Err ((vec!["adjacent-slices".to_string()], format!("Expected at most {} slices, since each one drawn slows every frame", MAX_SLICES)));
+        }
+        if !(0.0..=60.0).contains(&self.event_feed_time) {
+            return Err ((vec!["event-feed-time".to_string()], "Expected a number of seconds between 0 and 60".to_string()));
+        }
+        if !(0.5..=3.0).contains(&self.event_feed_size) {
+            return Err ((vec!["event-feed-size".to_string()], "Expected a size between 0.5 and 3".to_string()));
+        }
+        if !(0.0..=4.0).contains(&self.decorations) {
+            return Err ((vec!["decorations".to_string()], "Expected a density between 0 and 4".to_string()));
+        }
+        // Only generating a maze needs room both ways along every axis, so a hand-made level can be flat
+        if self.level.is_none() && self.dimensions.iter().any(|&size| size < 2) {
+            return Err ((vec!["dimensions".to_string()], "Expected every dimension to be at least 2".to_string()));
+        }
+        let cells: usize = self.dimensions.iter().product();
+        if self.food_count > cells {
+            let keys = vec!["food-count".to_string(), "dimensions".to_string()];
+            return Err ((keys, format!("Expected at most {} food, one for each cell", cells)));
+        }
+        Ok (())
+    }
+}
+
+// Older config files set `key: value` on each line, where TOML uses `key = value`
+fn is_legacy(contents: &str) -> bool {
+    contents.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| match (line.find(':'), line.find('=')) {
+            (Some (colon), Some (equals)) => colon < equals,
+            (Some (_), None) => true,
+            _ => false
+        })
+}
+
+// Line number setting the first of the keys found, in either format
+fn setting_line(contents: &str, keys: &[&str]) -> Option<usize> {
+    keys.iter().find_map(|key| {
+        contents.lines().position(|line| line.split([':', '=']).next().unwrap_or_default().trim() == *key)
+    }).map(|i| i + 1)
+}
+
+// Settings always written as TOML strings, even if they look like numbers
+const TEXT_SETTINGS: [&str; 4] = ["resources", "log-level", "player-name", "twitch-channel"];
+
+// A setting's value written in TOML rather than the older format, eg. 640x480 as [640, 480]
+fn toml_value(key: &str, value: &str) -> String {
+    let number = |text: &str| text.parse::<f64>().is_ok();
+    let list: Vec<&str> = value.split('x').collect();
+    if !TEXT_SETTINGS.contains(&key) && (value == "true" || value == "false" || number(value)) {
+        value.to_string()
+    } else if list.len() > 1 && list.iter().all(|item| number(item)) {
+        format!("[{}]", list.join(", "))
+    } else {
+        Value::String (value.to_string()).to_string()
+    }
+}
+
+// Line setting the key within the TOML table, or a commented out example of it like `# seed = 1234`
+fn find_setting(lines: &[String], table: Option<&str>, key: &str, commented: bool) -> Option<usize> {
+    let mut current = None;
+    lines.iter().position(|line| {
+        let line = line.trim();
+        if let Some (name) = line.strip_prefix('[') {
+            current = Some (name.trim_end_matches(']').trim());
+            return false;
+        }
+        let line = match (commented, line.strip_prefix('#')) {
+            (true, Some (example)) => example.trim_start(),
+            (false, None) => line,
+            _ => return false
+        };
+        current == table && line.split([':', '=']).next().unwrap_or_default().trim() == key
+    })
+}
+
+// Where to add a setting to the TOML table: after the last one already there, adding the table if need be
+fn end_of_table(lines: &mut Vec<String>, table: Option<&str>) -> usize {
+    let mut current = None;
+    let mut end = None;
+    let mut first_table = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if let Some (name) = line.strip_prefix('[') {
+            current = Some (name.trim_end_matches(']').trim());
+            first_table = first_table.or(Some (i));
+        }
+        if current == table && !line.is_empty() && !line.starts_with('#') {
+            end = Some (i + 1);
+        }
+    }
+    match (end, table) {
+        (Some (end), _) => end,
+        (None, None) => first_table.unwrap_or(lines.len()), // Top level settings go before any table
+        (None, Some (table)) => {
+            lines.push(String::new());
+            lines.push(format!("[{}]", table));
+            lines.len()
+        }
+    }
+}
+
+// Comment at the end of a setting's line, with the space before it, outside of any quoted text
+fn trailing_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[line[..i].trim_end().len()..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+fn camera_index(name: &str) -> std::result::Result<usize, String> {
+    CAMERA_PRESETS.iter().position(|preset| *preset == name).ok_or_else(|| "Expected close, overview, shoulder or first-person for camera".to_string())
+}
+
+// Starting camera preset, by name
+fn camera<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<usize, D::Error> {
+    let Text (name) = Text::deserialize(deserializer)?;
+    camera_index(&name).map_err(D::Error::custom)
+}
+
+// Presets given by name, eg. close = { offset = [0.0, 1.6, 4.0], fov = 90, pitch = 30 }, replacing the defaults
+fn camera_presets<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<[CameraPreset; 4], D::Error> {
+    let mut presets = Config::default().camera_presets;
+    for (name, preset) in HashMap::<String, CameraPreset>::deserialize(deserializer)? {
+        presets[camera_index(&name).map_err(D::Error::custom)?] = preset;
+    }
+    Ok (presets)
+}
+
+fn hud_widget(name: &str) -> std::result::Result<usize, String> {
+    HUD_WIDGETS.iter().position(|widget| *widget == name).ok_or_else(|| "Expected controls, clock, score or lives for a HUD widget".to_string())
+}
+
+// Places of HUD widgets given by name, eg. clock = [0.0, 0.25], leaving the rest where they usually go
+fn hud_layout<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<[[f32; 2]; 4], D::Error> {
+    let mut layout = [[0.0; 2]; 4];
+    for (name, offset) in HashMap::<String, [f32; 2]>::deserialize(deserializer)? {
+        layout[hud_widget(&name).map_err(D::Error::custom)?] = offset;
+    }
+    Ok (layout)
+}
+
+fn parse<T: FromStr>(value: &str, expected: &str) -> std::result::Result<T, String> {
+    value.parse().map_err(|_| expected.to_string())
+}
+
+/// Name a maze size is known by, eg. 5x5x5x3 for best times
+pub fn maze_size(dimensions: &[usize; 4]) -> String {
+    join(dimensions, "x")
+}
+
+// Write out values separated by x, like 640x480, or another separator
+fn join<T: fmt::Debug>(items: &[T], separator: &str) -> String {
+    items.iter().map(|item| format!("{:?}", item)).collect::<Vec<_>>().join(separator)
+}
+
+// Parse a fixed number of values separated by x, like 640x480
+fn parse_list<T: FromStr, const N: usize>(value: &str, expected: &str) -> std::result::Result<[T; N], String> {
+    value.split('x')
+        .map(|s| parse(s.trim(), expected))
+        .collect::<std::result::Result<Vec<T>, String>>()?
+        .try_into()
+        .map_err(|_| expected.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = "
+# Comments work the same
+window = [800, 600]
+texture-quality = \"trilinear\"
+camera = \"overview\"
+display-clock = 90
+dimensions = [4, 4, 2, 2]
+food-count = 6
+start = \"random\"
+log-level = \"warn, ghost=debug\"
+
+[camera-presets]
+shoulder = { offset = [0.5, 1.5, 2.0], fov = 75, pitch = 40 }
+";
+
+    const LEGACY: &str = "
+# Comments work the same
+window: 800x600
+texture-quality: trilinear
+camera: overview
+display-clock: 90
+dimensions: 4x4x2x2
+food-count: 6
+start: random
+log-level: warn, ghost=debug
+camera-shoulder: 0.5x1.5x2.0, 75, 40
+";
+
+    fn line_of(error: MazeError) -> usize {
+        match error {
+            MazeError::Config { line, .. } => line,
+            _ => panic!("Expected a config error, got {}", error)
+        }
+    }
+
+    #[test]
+    fn both_formats_read_the_same() {
+        for (contents, legacy) in [(TOML, false), (LEGACY, true)] {
+            let config = Config::parse("test", contents).unwrap();
+            assert_eq!(config.legacy_format, legacy);
+            assert!(config.window == Window::Size (800, 600));
+            assert!(matches!(config.texture_quality, TextureQuality::Trilinear));
+            assert_eq!(config.camera, 1);
+            assert!(config.display_clock == DisplayClock::Timer (90));
+            assert_eq!(config.dimensions, [4, 4, 2, 2]);
+            assert!(matches!(config.start, Start::Random));
+            assert_eq!(config.log_level.level("ghost"), LevelFilter::Debug);
+            assert_eq!(config.log_level.level("world"), LevelFilter::Warn);
+            assert_eq!(config.camera_presets[2].offset, [0.5, 1.5, 2.0]);
+            assert_eq!(config.camera_presets[0].fov, Config::default().camera_presets[0].fov);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        assert_eq!(line_of(Config::parse("test", "lives = 3\nwindow = \"huge\"\n").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\nwindoww = [640, 480]\n").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "lives: 3\nwindow: huge\n").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "palette = \"sepia\"\n").err().unwrap()), 1);
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        let fov = "lives = 3\n\n[camera-presets]\nclose = { offset = [0.0, 1.0, 1.0], fov = 150, pitch = 30 }\n";
+        assert_eq!(line_of(Config::parse("test", fov).err().unwrap()), 4);
+        assert_eq!(line_of(Config::parse("test", "dimensions = [4, 4, 1, 2]\n").err().unwrap()), 1);
+        assert_eq!(line_of(Config::parse("test", "# Tiny\ndimensions: 2x2x2x2\nfood-count: 17\n").err().unwrap()), 3);
+        assert_eq!(line_of(Config::parse("test", "lives = 0").err().unwrap()), 1);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\ntarget-fps = 0").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\ndecorations = 5.0").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "bottom-tint = [0.5, -0.5, 0.5]").err().unwrap()), 1);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\nadjacent-slices = 9").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\ndoors = 11").err().unwrap()), 2);
+        assert_eq!(line_of(Config::parse("test", "lives = 3\n\n[hud-layout]\nclock = [0.0, 3.0]\n").err().unwrap()), 4);
+    }
+
+    #[test]
+    fn edits_split_into_live_and_restart_changes() {
+        let mut config = Config::parse("test", TOML).unwrap();
+        let edited = Config::parse("test", &format!("ui-scale = 1.5\ntarget-fps = 144\n{}", TOML.replace("[4, 4, 2, 2]", "[6, 6, 2, 2]"))).unwrap();
+        let changes = config.live_changes(&edited);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(config.restart_changes(&edited), ["dimensions"]);
+        for change in changes {
+            config.apply(change);
+        }
+        assert_eq!(config.ui_scale, 1.5);
+        assert!(config.target_fps == TargetFps::Fixed (144));
+        assert!(config.live_changes(&edited).is_empty());
+    }
+
+    #[test]
+    fn levels_shade_from_the_top_tint_down_to_the_bottom() {
+        let theme = LevelTheme { top: [1.0, 1.0, 1.0], bottom: [0.0, 0.5, 1.0], curve: 1.0 };
+        assert_eq!(theme.tint(4, 5), [1.0, 1.0, 1.0]);
+        assert_eq!(theme.tint(0, 5), [0.0, 0.5, 1.0]);
+        assert_eq!(theme.tint(2, 5), [0.5, 0.75, 1.0]);
+        assert_eq!(theme.tint(0, 1), [1.0, 1.0, 1.0]);
+        let late = LevelTheme { curve: 2.0, ..theme };
+        assert_eq!(late.tint(2, 5), [0.75, 0.875, 1.0]);
+    }
+
+    #[test]
+    fn view_distance_keeps_within_the_maze() {
+        let distance = ViewDistance { below: 5, above: 1, slices: 2 };
+        assert_eq!(distance.levels(3, 10), (0, 4));
+        assert_eq!(distance.levels(9, 10), (4, 9));
+        assert_eq!(distance.slices(0, 3).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(distance.slices(4, 6).collect::<Vec<_>>(), [2, 3, 4, 5]);
+        assert!(distance.contains([0, 0, 6, 1], (3, 3, 7, 3)));
+        assert!(!distance.contains([0, 0, 6, 1], (3, 3, 8, 1)));
+        assert!(!distance.contains([0, 0, 6, 1], (3, 3, 0, 1)));
+    }
+
+    #[test]
+    fn saved_settings_read_back_the_same() {
+        let mut config = Config::parse("test", TOML).unwrap();
+        config.seed = Some (42);
+        config.ghost_move_time = 1.2;
+        config.camera_presets[3].pitch = 60.0;
+        config.palette = Palette::HighContrast;
+        config.hud_layout[2] = [-0.5, 0.25];
+        config.player_name = "1337".to_string();
+        config.best_times.insert("4x4x2x2".to_string(), 37);
+        let mut read_back = Config::default();
+        for (key, value) in config.settings() {
+            if let Some (value) = value {
+                read_back.set(key, &value).unwrap();
+            }
+        }
+        assert_eq!(read_back.settings(), config.settings());
+        for contents in [TOML, LEGACY, DEFAULT_FILE] {
+            let saved = Config::parse("test", &config.merge_into("test", contents).unwrap()).unwrap();
+            assert_eq!(saved.settings(), config.settings());
+            assert!(saved.camera_presets == config.camera_presets);
+            assert_eq!(saved.hud_layout, config.hud_layout);
+            assert_eq!(saved.best_time(), Some (37));
+            assert_eq!(saved.legacy_format, contents == LEGACY);
+        }
+    }
+
+    #[test]
+    fn saving_only_touches_changed_settings() {
+        let mut config = Config::parse("test", TOML).unwrap();
+        config.camera = 3;
+        config.food_count = 8;
+        config.lives = 5;
+        let contents = config.merge_into("test", &TOML.replace("food-count = 6", "food-count = 6  # Plenty")).unwrap();
+        assert!(contents.contains("# Comments work the same\nwindow = [800, 600]\n"));
+        assert!(contents.contains("\ncamera = \"first-person\"\n"));
+        assert!(contents.contains("\nfood-count = 8  # Plenty\n"));
+        assert!(contents.find("lives = 5").unwrap() < contents.find("[camera-presets]").unwrap());
+        assert!(contents.contains("shoulder = { offset = [0.5, 1.5, 2.0], fov = 75, pitch = 40 }"));
+
+        // A new config file keeps all the documentation from config.toml
+        let defaults = Config::default().merge_into("config.toml", DEFAULT_FILE).unwrap();
+        assert_eq!(defaults.lines().filter(|line| line.starts_with('#')).count(), DEFAULT_FILE.lines().filter(|line| line.starts_with('#')).count());
+        assert_eq!(Config::parse("config.toml", &defaults).unwrap().settings(), Config::default().settings());
+    }
+}
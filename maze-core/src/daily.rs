@@ -0,0 +1,115 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::config::Config;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Day of a daily challenge, counted in whole days since 1970-01-01 UTC so everyone shares the same day at the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Day (pub u64);
+
+impl Day {
+    pub fn today() -> Day {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Day (since_epoch.as_secs() / SECONDS_PER_DAY)
+    }
+
+    /// Seed for the day's maze, spreading neighbouring days far apart
+    pub fn seed(&self) -> u64 {
+        (self.0 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    /// Settings for the day's challenge. The size of the maze comes from the day, and every other setting that shapes
+    /// the game is put back to its default so it plays the same for everyone. The compass is off, with no hints
+    pub fn config(&self, base: &Config) -> Config {
+        let mut rng = StdRng::seed_from_u64(self.seed());
+        let dimensions = [rng.gen_range(4..=7), rng.gen_range(4..=7), rng.gen_range(2..=4), rng.gen_range(2..=3)];
+        let defaults = Config::default();
+        Config {
+            dimensions,
+            seed: Some (self.seed()),
+            level: None,
+            race_echo: false,
+            mode: defaults.mode,
+            movement: defaults.movement,
+            display_compass: false,
+            food_count: dimensions.iter().product::<usize>() / 10,
+            food_placement: defaults.food_placement,
+            ghost_move_time: defaults.ghost_move_time,
+            ghosts: defaults.ghosts,
+            co_op: defaults.co_op,
+            split_screen: defaults.split_screen,
+            doors: 1,
+            teleporters: 1,
+            ghost_teleports: defaults.ghost_teleports,
+            traps: 2,
+            power_ups: defaults.power_ups,
+            power_up_time: defaults.power_up_time,
+            flashlight_time: defaults.flashlight_time,
+            batteries: defaults.batteries,
+            fruit_interval: defaults.fruit_interval,
+            fruit_time: defaults.fruit_time,
+            fruit_points: defaults.fruit_points,
+            combo_time: defaults.combo_time,
+            combo_max: defaults.combo_max,
+            shift_interval: defaults.shift_interval,
+            lives: defaults.lives,
+            respawn: defaults.respawn,
+            start: defaults.start,
+            exit: defaults.exit,
+            min_solution: defaults.min_solution,
+            min_w_moves: defaults.min_w_moves,
+            twitch_channel: defaults.twitch_channel,
+            ..base.clone()
+        }
+    }
+}
+
+// Written as YYYY-MM-DD, converting days to a date on the proleptic Gregorian calendar
+impl fmt::Display for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Count from 0000-03-01, so leap days fall at the end of each year, in eras of 400 years
+        let days = self.0 + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153; // From March
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn days_are_written_as_dates() {
+        assert_eq!(Day (0).to_string(), "1970-01-01");
+        assert_eq!(Day (59).to_string(), "1970-03-01");
+        assert_eq!(Day (11_016).to_string(), "2000-02-29");
+        assert_eq!(Day (20_742).to_string(), "2026-10-16");
+    }
+
+    #[test]
+    fn everyone_gets_the_same_maze() {
+        let mine = Config { food_count: 1, doors: 4, ghost_move_time: 0.5, ui_scale: 2.0, ..Default::default() };
+        let theirs = Config::default();
+        let (mine, theirs) = (Day (20_742).config(&mine), Day (20_742).config(&theirs));
+        assert_eq!(mine.dimensions, theirs.dimensions);
+        assert_eq!(mine.ui_scale, 2.0);
+        assert!(!mine.display_compass);
+        mine.check().unwrap();
+        let maze = |config: &Config| Game::new(config).unwrap().world.to_string();
+        assert_eq!(maze(&mine), maze(&theirs));
+        assert_ne!(Day (20_742).seed(), Day (20_743).seed());
+    }
+}
@@ -0,0 +1,194 @@
+//! Props scattered through a maze after it's generated, so large mazes aren't one long run of identical walls.
+//!
+//! Props are placed from the layout of each level alone, rolling for each cell by hashing its coordinates rather than
+//! drawing from the maze's random generator. The same maze always comes out decorated the same way, and decorating it
+//! never changes the maze or how a game in it plays out.
+
+use crate::world::{Axis, Coordinate, Wall, World};
+
+const COBWEB_CHANCE: f32 = 0.6; // Of a dead end having a cobweb, at a density of 1
+const LONG_CORRIDOR: usize = 4; // Fewest cells in a straight corridor to line with pillars
+const PILLAR_SPACING: f32 = 2.0; // Cells between each pair of pillars down a corridor, at a density of 1
+const RUNE_RANGE: usize = 3; // Moves from the exit runes glow within
+const RUNE_CHANCE: f32 = 0.7; // Of a cell near the exit having a rune, at a density of 1
+
+/// A prop decorating one level of the maze
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prop {
+    Cobweb { cell: Coordinate, wall: [i32; 2], side: i32 }, // Strung across the top of the wall in direction [x, y], in the corner to its left (-1) or right (1)
+    Pillar { corner: Coordinate }, // Standing where the corners of cells (x - 1, y - 1) and (x, y) meet
+    Rune { cell: Coordinate, turn: f32 } // Glowing on the floor, turned by this many radians
+}
+
+/// Props for the level (w, z), thinned out or packed in by the density: 0 for none, 1 for the usual amount
+pub fn decorate(world: &World, w: usize, z: usize, density: f32) -> Vec<Prop> {
+    if density <= 0.0 {
+        return Vec::new();
+    }
+    let mut props = cobwebs(world, w, z, density);
+    props.extend(pillars(world, w, z, density));
+    props.extend(runes(world, w, z, density));
+    props
+}
+
+// Cobwebs at the back of dead ends, away from their only way in
+fn cobwebs(world: &World, w: usize, z: usize, density: f32) -> Vec<Prop> {
+    let mut props = Vec::new();
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let cell = (x, y, z, w);
+            let near = world.near(cell, 1);
+            if near.len() != 2 || cell == world.exit || roll(world, cell, 1) >= COBWEB_CHANCE * density {
+                continue;
+            }
+            let way_in = near.into_iter().find(|n| *n != cell).unwrap();
+            let wall = match [way_in.0 as i32 - x as i32, way_in.1 as i32 - y as i32] {
+                [0, 0] => [[1, 0], [-1, 0], [0, 1], [0, -1]][(roll(world, cell, 2) * 4.0) as usize % 4], // Way in is up, down or through w
+                [dx, dy] => [-dx, -dy]
+            };
+            let side = if roll(world, cell, 3) < 0.5 { -1 } else { 1 };
+            props.push(Prop::Cobweb { cell, wall, side });
+        }
+    }
+    props
+}
+
+// Pairs of pillars down either side of long, straight corridors
+fn pillars(world: &World, w: usize, z: usize, density: f32) -> Vec<Prop> {
+    let spacing = ((PILLAR_SPACING / density).round() as usize).max(1);
+    let mut props = Vec::new();
+    // Corridors along x, walled in above and below
+    for y in 0..world.height {
+        let walled = |x: usize| world.walls.get(Axis::Y, (x, y, z, w)) == Wall::SolidWall && world.walls.get(Axis::Y, (x, y + 1, z, w)) == Wall::SolidWall;
+        let open = |x: usize| world.walls.get(Axis::X, (x, y, z, w)) != Wall::SolidWall;
+        for (start, end) in runs(world.width, walled, open) {
+            props.extend((start + spacing..=end).step_by(spacing).flat_map(|x| [(x, y, z, w), (x, y + 1, z, w)]).map(|corner| Prop::Pillar { corner }));
+        }
+    }
+    // Corridors along y, walled in either side
+    for x in 0..world.width {
+        let walled = |y: usize| world.walls.get(Axis::X, (x, y, z, w)) == Wall::SolidWall && world.walls.get(Axis::X, (x + 1, y, z, w)) == Wall::SolidWall;
+        let open = |y: usize| world.walls.get(Axis::Y, (x, y, z, w)) != Wall::SolidWall;
+        for (start, end) in runs(world.height, walled, open) {
+            props.extend((start + spacing..=end).step_by(spacing).flat_map(|y| [(x, y, z, w), (x + 1, y, z, w)]).map(|corner| Prop::Pillar { corner }));
+        }
+    }
+    props
+}
+
+// First and last cell of each run of at least LONG_CORRIDOR walled in cells along a row, each open to the one before
+fn runs(length: usize, walled: impl Fn(usize) -> bool, open: impl Fn(usize) -> bool) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for i in 0..=length {
+        let continues = i < length && walled(i) && start.is_some() && open(i);
+        if continues {
+            continue;
+        }
+        if let Some (first) = start.take() {
+            if i - first >= LONG_CORRIDOR {
+                runs.push((first, i - 1));
+            }
+        }
+        if i < length && walled(i) {
+            start = Some (i);
+        }
+    }
+    runs
+}
+
+// Runes on the floor of cells within a few moves of the exit
+fn runes(world: &World, w: usize, z: usize, density: f32) -> Vec<Prop> {
+    let mut cells: Vec<Coordinate> = world.near(world.exit, RUNE_RANGE).into_iter()
+        .filter(|cell| (cell.2, cell.3) == (z, w) && roll(world, *cell, 4) < RUNE_CHANCE * density)
+        .collect();
+    cells.sort_unstable();
+    cells.into_iter().map(|cell| Prop::Rune { cell, turn: roll(world, cell, 5) * std::f32::consts::TAU }).collect()
+}
+
+// Number from 0 up to 1 for the cell, different for each salt but the same every time, unless the cell's metadata
+// carries a decoration seed to mix in
+fn roll(world: &World, (x, y, z, w): Coordinate, salt: u32) -> f32 {
+    let mut hash = salt.wrapping_mul(0x9e37_79b9) ^ world.meta.decoration_seed((x, y, z, w)).unwrap_or(0);
+    for i in [x, y, z, w] {
+        hash = (hash ^ i as u32).wrapping_mul(0x85eb_ca6b);
+        hash ^= hash >> 13;
+    }
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Single level with one corridor six cells long along the top, and a dead end hanging off its left end
+    // +-+-+-+-+-+-+
+    // |           |
+    // + +-+-+-+-+-+
+    // | |
+    // +-+
+    fn corridor() -> World {
+        let mut world = World::empty([6, 2, 1, 1]);
+        for x in 1..6 {
+            world.open_wall((x - 1, 0, 0, 0), (x, 0, 0, 0));
+        }
+        world.open_wall((0, 0, 0, 0), (0, 1, 0, 0));
+        world.exit = (5, 0, 0, 0);
+        world
+    }
+
+    #[test]
+    fn nothing_is_placed_at_zero_density() {
+        assert!(decorate(&corridor(), 0, 0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn decorations_come_out_the_same_every_time() {
+        let world = corridor();
+        assert_eq!(decorate(&world, 0, 0, 1.0), decorate(&world, 0, 0, 1.0));
+    }
+
+    #[test]
+    fn cobwebs_hang_opposite_the_way_into_a_dead_end() {
+        let world = corridor();
+        let webs = cobwebs(&world, 0, 0, 1.0 / COBWEB_CHANCE);
+        assert!(webs.iter().all(|web| matches!(web, Prop::Cobweb { cell: (0, 1, 0, 0), wall: [0, 1], .. })), "{:?}", webs);
+        assert_eq!(webs.len(), 1);
+    }
+
+    #[test]
+    fn pillars_line_long_corridors_in_pairs() {
+        let world = corridor();
+        let pillars = pillars(&world, 0, 0, 1.0);
+        // The top row is walled in from x = 1 to 5, so pillars stand on the boundaries at x = 3 and 5
+        let expected: Vec<Prop> = [(3, 0, 0, 0), (3, 1, 0, 0), (5, 0, 0, 0), (5, 1, 0, 0)].into_iter().map(|corner| Prop::Pillar { corner }).collect();
+        assert_eq!(pillars, expected);
+    }
+
+    #[test]
+    fn runes_only_glow_near_the_exit() {
+        let world = corridor();
+        for prop in runes(&world, 0, 0, 1.0 / RUNE_CHANCE) {
+            match prop {
+                Prop::Rune { cell: (x, y, _, _), .. } => assert!(x >= 5 - RUNE_RANGE && y == 0, "Rune too far from the exit at {:?}", (x, y)),
+                _ => panic!("Expected only runes")
+            }
+        }
+        assert_eq!(runes(&world, 0, 0, 1.0 / RUNE_CHANCE).len(), RUNE_RANGE + 1);
+    }
+
+    #[test]
+    fn a_decoration_seed_rerolls_only_its_cell() {
+        let mut world = corridor();
+        let before = runes(&world, 0, 0, 1.0 / RUNE_CHANCE);
+        world.meta.set_decoration_seed((5, 0, 0, 0), Some (12345));
+        let after = runes(&world, 0, 0, 1.0 / RUNE_CHANCE);
+        assert_eq!(before.len(), after.len());
+        for (old, new) in before.iter().zip(&after) {
+            match (old, new) {
+                (Prop::Rune { cell: (5, 0, 0, 0), turn: a }, Prop::Rune { turn: b, .. }) => assert_ne!(a, b),
+                _ => assert_eq!(old, new)
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::hash_map::HashMap;
+use std::hash::Hash;
+use core::fmt::Debug;
+
+/// Union-find over hashable values. Unions hang the smaller set under the larger, and finds point every value they
+/// pass straight at the top, so a run of operations takes close to constant time each even on the largest mazes
+pub struct DisjointSet<T> {
+    index: HashMap<T, usize>, // Where each value is in the lists below
+    values: Vec<T>, // In the order they were added
+    parents: Vec<usize>, // Of each value, or the value itself at the top of a set
+    sizes: Vec<usize>, // Of each set, kept up to date at its top
+    sets: usize
+}
+
+impl<T: Eq + Hash + Copy + Debug> Default for DisjointSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash + Copy + Debug> DisjointSet<T> {
+    pub fn new() -> DisjointSet<T> {
+        DisjointSet { index: HashMap::new(), values: Vec::new(), parents: Vec::new(), sizes: Vec::new(), sets: 0 }
+    }
+
+    /// Add the value in a set of its own, unless it's already in one
+    pub fn add(&mut self, val: &T) {
+        if self.index.contains_key(val) {
+            return;
+        }
+        self.index.insert(*val, self.values.len());
+        self.parents.push(self.values.len());
+        self.values.push(*val);
+        self.sizes.push(1);
+        self.sets += 1;
+    }
+
+    /// Merge the sets holding a and b, under the top of the larger, or of b's if they're the same size
+    pub fn union(&mut self, a: &T, b: &T) {
+        let a_top = self.root(a);
+        let b_top = self.root(b);
+        if a_top == b_top { // Already in the same set
+            return;
+        }
+        let (smaller, larger) = if self.sizes[a_top] > self.sizes[b_top] { (b_top, a_top) } else { (a_top, b_top) };
+        self.parents[smaller] = larger;
+        self.sizes[larger] += self.sizes[smaller];
+        self.sets -= 1;
+    }
+
+    /// Value at the top of the set holding val, the same for every value in the set
+    pub fn find(&mut self, val: &T) -> T {
+        let top = self.root(val);
+        self.values[top]
+    }
+
+    /// Whether a and b are in the same set
+    pub fn connected(&mut self, a: &T, b: &T) -> bool {
+        self.root(a) == self.root(b)
+    }
+
+    /// Number of values in the set holding val
+    pub fn size(&mut self, val: &T) -> usize {
+        let top = self.root(val);
+        self.sizes[top]
+    }
+
+    /// Number of values added
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Number of separate sets
+    pub fn count(&self) -> usize {
+        self.sets
+    }
+
+    /// Every set, each listing its values in the order they were added, in the order their first values were added
+    pub fn components(&mut self) -> impl Iterator<Item = Vec<T>> {
+        let mut components: Vec<Vec<T>> = Vec::with_capacity(self.sets);
+        let mut positions = HashMap::new(); // Of each set's top in components
+        for i in 0..self.values.len() {
+            let top = self.top(i);
+            let position = *positions.entry(top).or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+            components[position].push(self.values[i]);
+        }
+        components.into_iter()
+    }
+
+    // Index of the top of the set holding val
+    fn root(&mut self, val: &T) -> usize {
+        let i = *self.index.get(val).unwrap_or_else(|| panic!("{:?} not in disjoint set", val));
+        self.top(i)
+    }
+
+    // Index of the top of the set holding the value at index i, pointing everything on the way straight at it
+    fn top(&mut self, i: usize) -> usize {
+        let mut top = i;
+        while self.parents[top] != top {
+            top = self.parents[top];
+        }
+        let mut current = i;
+        while current != top {
+            current = std::mem::replace(&mut self.parents[current], top);
+        }
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    // Random unions over a few values, checked against plainly relabelling every value of one set with the other's label
+    fn check_against_labels(seed: u64, values: usize, unions: usize) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut set = DisjointSet::new();
+        let mut labels: Vec<usize> = (0..values).collect();
+        for i in 0..values {
+            set.add(&i);
+        }
+        for _ in 0..unions {
+            let (a, b) = (rng.gen_range(0..values), rng.gen_range(0..values));
+            set.union(&a, &b);
+            let (from, to) = (labels[a], labels[b]);
+            for label in labels.iter_mut().filter(|label| **label == from) {
+                *label = to;
+            }
+            let (c, d) = (rng.gen_range(0..values), rng.gen_range(0..values));
+            assert_eq!(set.connected(&c, &d), labels[c] == labels[d], "seed {}", seed);
+            assert_eq!(set.size(&c), labels.iter().filter(|label| **label == labels[c]).count(), "seed {}", seed);
+        }
+        let mut distinct = labels.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(set.count(), distinct.len(), "seed {}", seed);
+        for i in 0..values {
+            let top = set.find(&i);
+            assert_eq!(labels[top], labels[i], "seed {}", seed);
+            assert_eq!(set.find(&top), top, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn matches_relabelling_on_random_unions() {
+        for seed in 0..200 {
+            check_against_labels(seed, 1 + seed as usize % 40, 60);
+        }
+    }
+
+    #[test]
+    fn components_partition_the_values_in_order() {
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut set = DisjointSet::new();
+            for i in 0..30 {
+                set.add(&i);
+            }
+            for _ in 0..rng.gen_range(0..40) {
+                set.union(&rng.gen_range(0..30), &rng.gen_range(0..30));
+            }
+            let components: Vec<Vec<i32>> = set.components().collect();
+            assert_eq!(components.len(), set.count());
+            let mut all: Vec<i32> = components.iter().flatten().copied().collect();
+            all.sort_unstable();
+            assert_eq!(all, (0..30).collect::<Vec<_>>());
+            for component in &components {
+                assert!(component.windows(2).all(|pair| pair[0] < pair[1]));
+                assert!(component.iter().all(|value| set.connected(value, &component[0])));
+                assert_eq!(set.size(&component[0]), component.len());
+            }
+            assert!(components.windows(2).all(|pair| pair[0][0] < pair[1][0]));
+            for (i, a) in components.iter().enumerate() {
+                assert!(components[i + 1..].iter().all(|b| !set.connected(&a[0], &b[0])));
+            }
+        }
+    }
+
+    #[test]
+    fn adding_again_leaves_the_set_alone() {
+        let mut set = DisjointSet::new();
+        set.add(&'a');
+        set.add(&'b');
+        set.union(&'a', &'b');
+        set.add(&'a');
+        assert!(set.connected(&'a', &'b'));
+        assert_eq!((set.len(), set.count(), set.size(&'a')), (2, 1, 2));
+    }
+}
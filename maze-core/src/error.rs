@@ -0,0 +1,53 @@
+use std::fmt;
+use std::io;
+
+use thiserror::Error;
+
+/// Anything that stops the game from starting
+#[derive(Debug, Error)]
+pub enum MazeError {
+    /// A file couldn't be opened, read or written
+    #[error("Couldn't access `{path}': {source}")]
+    Io { path: String, source: io::Error },
+    /// A line of the config file couldn't be understood
+    #[error("{path}:{line}: {reason} (in `{text}')")]
+    Config { path: String, line: usize, text: String, reason: String },
+    /// A command line option couldn't be understood
+    #[error("Invalid --{option}: {reason}")]
+    Argument { option: String, reason: String },
+    /// The configured maze can't be generated
+    #[error("Can't generate maze: {0}")]
+    World (String),
+    /// A model, texture or other resource file is malformed
+    #[error("Invalid resource `{path}': {reason}")]
+    Resource { path: String, reason: String },
+    /// A saved game couldn't be read back
+    #[error("Can't load saved game `{path}': {reason}")]
+    Save { path: String, reason: String },
+    /// The file of past runs couldn't be read or written
+    #[error("Invalid stats file `{path}': {reason}")]
+    Stats { path: String, reason: String },
+    /// No usable graphics card, window or Vulkan device
+    #[error("Graphics setup failed: {0}")]
+    Graphics (String),
+    /// The other player in an online race couldn't be reached
+    #[error("Online race failed: {0}")]
+    Network (String)
+}
+
+pub type Result<T> = std::result::Result<T, MazeError>;
+
+impl MazeError {
+    /// Wrap an IO error with the file it came from
+    pub fn io(path: &str, source: io::Error) -> MazeError {
+        MazeError::Io { path: path.to_string(), source }
+    }
+
+    pub fn resource(path: &str, reason: impl fmt::Display) -> MazeError {
+        MazeError::Resource { path: path.to_string(), reason: reason.to_string() }
+    }
+
+    pub fn graphics(reason: impl fmt::Display) -> MazeError {
+        MazeError::Graphics (reason.to_string())
+    }
+}
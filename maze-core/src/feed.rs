@@ -0,0 +1,169 @@
+//! Short lines of text describing what's happening in a game, eg. "Food eaten 4/10" or "Ghost is near".
+//!
+//! The feed watches the game from one frame to the next and notes whatever changed, so it can be shown on screen for
+//! players who can't easily pick it out of the scene, or printed for a stream overlay to follow. Each line is kept as a
+//! [`Message`] for the player's [`Locale`](crate::locale::Locale) to put into words.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::game::Game;
+use crate::linalg;
+use crate::locale::Message;
+use crate::player::GameState;
+
+const GHOST_NEAR: f32 = 3.0; // Cells away the nearest ghost has to come within to be called near
+const GHOST_GONE: f32 = 4.5; // Cells away it has to get again before it can be called near again
+const MAX_LINES: usize = 4; // Lines kept at once, the oldest going first
+
+/// Recent events in a game, each kept for a while after it happens
+pub struct Feed {
+    lines: VecDeque<(Message, Instant)>,
+    seen: Option<Seen> // How the game looked last update, or None before the first
+}
+
+// Parts of the game the feed reports changes in
+#[derive(Clone, Copy, PartialEq)]
+struct Seen {
+    score: u32,
+    lives: u32,
+    cell: [i32; 4],
+    ghost_near: bool,
+    exit_open: bool,
+    game_state: GameState
+}
+
+impl Feed {
+    pub fn new() -> Feed {
+        Feed { lines: VecDeque::new(), seen: None }
+    }
+
+    /// Note whatever has happened since the last update, returning the new lines
+    pub fn update(&mut self, game: &Game, config: &Config, now: Instant) -> Vec<Message> {
+        let player = &game.player;
+        let nearest = game.ghosts.iter()
+            .map(|ghost| linalg::sub(ghost.position(), player.get_position()).iter().map(|x| x * x).sum::<f32>().sqrt())
+            .fold(f32::INFINITY, f32::min);
+        let was_near = self.seen.is_some_and(|seen| seen.ghost_near);
+        let seen = Seen {
+            score: player.score,
+            lives: player.lives,
+            cell: player.cell(),
+            ghost_near: config.mode.has_ghost() && nearest < if was_near { GHOST_GONE } else { GHOST_NEAR },
+            exit_open: game.world.exit_open,
+            game_state: player.game_state
+        };
+        let mut events = Vec::new();
+        if let Some (last) = self.seen.replace(seen) {
+            if seen.score > last.score {
+                events.push(Message::new("food-eaten").with("eaten", seen.score).with("total", config.food_count));
+            }
+            if seen.ghost_near && !last.ghost_near && seen.game_state == GameState::Playing {
+                events.push(Message::new("ghost-near"));
+            }
+            if seen.cell != last.cell && seen.game_state == GameState::Playing {
+                for dw in [-1, 1] {
+                    if game.world.check_move(seen.cell, [0, 0, 0, dw], &player.inventory) {
+                        events.push(Message::new("portal").with("slice", seen.cell[3] + dw + 1));
+                    }
+                }
+            }
+            if seen.exit_open && !last.exit_open {
+                events.push(Message::new("exit-open"));
+            }
+            if seen.lives < last.lives {
+                events.push(if seen.lives == 1 { Message::new("caught-last-life") } else { Message::new("caught").with("lives", seen.lives) });
+            }
+            if seen.game_state != last.game_state {
+                match seen.game_state {
+                    GameState::Won => events.push(Message::new("escaped")),
+                    GameState::Lost => events.push(Message::new("game-over")),
+                    _ => {}
+                }
+            }
+        }
+        for event in &events {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back((event.clone(), now));
+        }
+        events
+    }
+
+    /// Lines from the last given number of seconds, oldest first
+    pub fn lines(&self, now: Instant, seconds: f32) -> impl Iterator<Item = &Message> {
+        let shown = Duration::from_secs_f32(seconds.max(0.0));
+        self.lines.iter().filter(move |(_, at)| now.saturating_duration_since(*at) < shown).map(|(line, _)| line)
+    }
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Feed::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::Locale;
+
+    fn game() -> (Game, Config) {
+        let config = Config { dimensions: [4, 4, 2, 2], food_count: 5, ..Config::default() };
+        (Game::from_seed(&config, 3).unwrap(), config)
+    }
+
+    #[test]
+    fn nothing_is_reported_before_anything_changes() {
+        let (game, config) = game();
+        let mut feed = Feed::new();
+        let now = Instant::now();
+        assert!(feed.update(&game, &config, now).is_empty());
+        assert!(feed.update(&game, &config, now).is_empty());
+        assert_eq!(feed.lines(now, 5.0).count(), 0);
+    }
+
+    #[test]
+    fn changes_are_reported_once() {
+        let (mut game, config) = game();
+        let mut feed = Feed::new();
+        let now = Instant::now();
+        feed.update(&game, &config, now);
+        game.player.score += 1;
+        game.world.exit_open = true;
+        let lines: Vec<String> = feed.update(&game, &config, now).iter().map(|line| Locale::english().text(line)).collect();
+        assert_eq!(lines, ["Food eaten 1/5", "Exit is open"]);
+        assert!(feed.update(&game, &config, now).is_empty());
+    }
+
+    #[test]
+    fn the_ghost_is_only_near_again_after_leaving() {
+        let (mut game, config) = game();
+        let mut feed = Feed::new();
+        let now = Instant::now();
+        let [x, y, z, w] = game.player.get_position();
+        game.ghosts[0].set_position([x + 10.0, y, z, w]);
+        feed.update(&game, &config, now);
+        game.ghosts[0].set_position([x + 1.0, y, z, w]);
+        assert_eq!(feed.update(&game, &config, now), [Message::new("ghost-near")]);
+        // Backing off a little isn't enough to be called near again on the way back
+        game.ghosts[0].set_position([x + 4.0, y, z, w]);
+        feed.update(&game, &config, now);
+        game.ghosts[0].set_position([x + 1.0, y, z, w]);
+        assert!(feed.update(&game, &config, now).is_empty());
+    }
+
+    #[test]
+    fn old_lines_drop_off() {
+        let (mut game, config) = game();
+        let mut feed = Feed::new();
+        let now = Instant::now();
+        feed.update(&game, &config, now);
+        game.player.score += 1;
+        feed.update(&game, &config, now);
+        assert_eq!(feed.lines(now + Duration::from_secs(2), 3.0).map(|line| line.key).collect::<Vec<_>>(), ["food-eaten"]);
+        assert_eq!(feed.lines(now + Duration::from_secs(4), 3.0).count(), 0);
+    }
+}
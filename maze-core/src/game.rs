@@ -0,0 +1,273 @@
+use std::time::{Duration, Instant};
+
+use rand::{random, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::config::{maze_size, Config, Mode};
+use crate::error::{MazeError, Result};
+use crate::ghost::Ghost;
+use crate::linalg;
+use crate::objects::Objects;
+use crate::pack::Coordinator;
+use crate::player::{GameState, Player};
+use crate::save::SavedGame;
+use crate::world::World;
+use crate::world::meta::Flags;
+
+const PAR_CELL_TIME: f32 = 1.5; // Seconds of a time attack's par time for each cell along the shortest route out
+const PAR_FOOD_TIME: u32 = 5; // Extra seconds for each food to detour for
+const SURVIVAL_SPEED_UP_TIME: f32 = 20.0; // Seconds between the ghosts speeding up in survival
+const SURVIVAL_SPEED_UP: f32 = 0.9; // Fraction of its move time each ghost takes after each speed up
+const SURVIVAL_FASTEST_MOVE: f32 = 0.35; // Seconds the ghosts' moves take at the most they can speed up to
+
+/// One run of the game: the maze, everything in it, and the rules tying them together
+pub struct Game {
+    pub world: World,
+    pub player: Player,
+    pub rival: Option<Player>, // Second player racing to the exit in split-screen
+    pub ghosts: Vec<Ghost>,
+    pack: Coordinator,
+    pub objects: Objects,
+    pub seed: u64, // From the config if it has one, so the same seed gives the same maze
+    next_shift: Option<Instant>,
+    next_speed_up: Option<Instant>, // When the ghosts next get faster in survival
+    rng: StdRng
+}
+
+impl Game {
+    /// Generate a fresh maze and place the player, ghosts and food in it
+    pub fn new(config: &Config) -> Result<Game> {
+        Game::from_seed(config, config.seed.unwrap_or_else(random))
+    }
+
+    /// Generate the game from the given seed, whatever the config says. With a level file in the config, the maze is read
+    /// from it instead and the seed only drives the ghost and fruit
+    pub fn from_seed(config: &Config, seed: u64) -> Result<Game> {
+        if let Some (path) = &config.level {
+            return Ok (Game::from_world(config, World::from_level(path)?, seed));
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world = World::new(config, &mut rng)?;
+        let (x, y, z, w) = world.start;
+        let player = Player::new(config, [x as i32, y as i32, z as i32, w as i32]);
+        let ghosts: Vec<Ghost> = (0..config.ghosts).map(|_| Ghost::new(config, &mut rng)).collect();
+        let objects = Objects::new(&mut world, config, &mut rng);
+        let pack = Coordinator::new(ghosts.len());
+        Ok (Game { world, player, rival: None, ghosts, pack, objects, seed, next_shift: None, next_speed_up: None, rng }.with_mode(config))
+    }
+
+    /// Play in a maze that's already been built, with its objects in place, such as a hand-made level
+    pub fn from_world(config: &Config, world: World, seed: u64) -> Game {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (x, y, z, w) = world.start;
+        let player = Player::new(config, [x as i32, y as i32, z as i32, w as i32]);
+        // A ghost at each spawn the level marks, and any more the config asks for at random
+        let ghosts: Vec<Ghost> = (0..config.ghosts.max(world.ghost_spawns.len())).map(|i| match world.ghost_spawns.get(i) {
+            Some (&(x, y, z, w)) => Ghost::at(config, [x, y, z, w]),
+            None => Ghost::new(config, &mut rng)
+        }).collect();
+        let objects = Objects::from_world(&world);
+        let pack = Coordinator::new(ghosts.len());
+        Game { world, player, rival: None, ghosts, pack, objects, seed, next_shift: None, next_speed_up: None, rng }.with_mode(config)
+    }
+
+    // Bring in the second player to race in split-screen, set the clock running down from the par time in a time attack,
+    // and hand a ghost to the second player in co-op
+    fn with_mode(mut self, config: &Config) -> Game {
+        // Everyone stands in the start cell first, and may step out of it before an update would mark it
+        self.world.meta.mark(self.world.start, Flags::VISITED);
+        if config.split_screen {
+            let (x, y, z, w) = self.world.start;
+            self.rival = Some (Player::new(config, [x as i32, y as i32, z as i32, w as i32]));
+        }
+        if config.mode == Mode::TimeAttack {
+            let par_time = self.par_time(config);
+            self.player.set_time_limit(par_time);
+            if let Some (rival) = &mut self.rival {
+                rival.set_time_limit(par_time);
+            }
+        }
+        if config.co_op && config.mode.has_ghost() {
+            self.ghosts[0].set_controlled(true);
+        }
+        self
+    }
+
+    /// Seconds to escape in during a time attack, from the length of the shortest route out and the food to eat on the way
+    pub fn par_time(&self, config: &Config) -> u32 {
+        (self.world.solution.len() as f32 * PAR_CELL_TIME).ceil() as u32 + PAR_FOOD_TIME * config.food_count as u32
+    }
+
+    /// Snapshot of the game as it stands, to carry on from later
+    pub fn snapshot(&self, now: Instant) -> SavedGame {
+        SavedGame {
+            seed: self.seed,
+            dimensions: [self.world.width, self.world.height, self.world.depth, self.world.fourth],
+            next_shift: self.next_shift.map(|next| next.saturating_duration_since(now).as_secs_f32()),
+            world: self.world.snapshot(),
+            player: self.player.snapshot(now),
+            ghosts: self.ghosts.iter().map(Ghost::snapshot).collect(),
+            objects: self.objects.snapshot(now)
+        }
+    }
+
+    /// Carry on from a saved game: the same maze is generated again from its seed, then everything is put back the
+    /// way it was. The other maze settings need to be the same as when it was saved for it to come out the same
+    pub fn restore(config: &Config, saved: &SavedGame, now: Instant) -> Result<Game> {
+        if saved.dimensions != config.dimensions {
+            return Err (MazeError::World (format!("The saved game is in a {} maze, but the settings are for {}", maze_size(&saved.dimensions), maze_size(&config.dimensions))));
+        }
+        let mut game = Game::from_seed(config, saved.seed)?;
+        if saved.ghosts.len() != game.ghosts.len() {
+            return Err (MazeError::World (format!("The saved game has {} ghosts, but the settings are for {}", saved.ghosts.len(), game.ghosts.len())));
+        }
+        game.world.restore(&saved.world).map_err(MazeError::World)?;
+        game.objects.restore(&saved.objects, &mut game.world, now);
+        game.player.restore(&saved.player, now);
+        for (ghost, snapshot) in game.ghosts.iter_mut().zip(&saved.ghosts) {
+            ghost.restore(snapshot, now);
+        }
+        game.next_shift = saved.next_shift.map(|seconds| now + Duration::from_secs_f32(seconds));
+        game.rng = StdRng::seed_from_u64(random()); // Where the seeded random numbers had got to isn't saved
+        Ok (game)
+    }
+
+    /// Start moving the player by delta unless a wall is in the way or they're held by a snare, returning whether they moved
+    pub fn try_move(&mut self, now: Instant, delta: [i32; 4], seconds: f32) -> bool {
+        if self.player.game_state != GameState::Playing || self.player.escaping() || self.player.frozen(now) || !self.world.check_move(self.player.cell(), delta, &self.player.inventory) {
+            return false;
+        }
+        self.player.move_position(now, delta, seconds);
+        true
+    }
+
+    /// Start moving the second player in split-screen by delta, under the same rules as the first
+    pub fn try_move_rival(&mut self, now: Instant, delta: [i32; 4], seconds: f32) -> bool {
+        match &mut self.rival {
+            Some (rival) if rival.game_state == GameState::Playing && !rival.escaping() && !rival.frozen(now) && self.world.check_move(rival.cell(), delta, &rival.inventory) => {
+                rival.move_position(now, delta, seconds);
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// Point the player's free movement the way the held keys do in x and y, while they're playing
+    pub fn steer(&mut self, now: Instant, heading: [f32; 2]) {
+        if self.player.game_state == GameState::Playing && !self.player.escaping() {
+            self.player.steer(now, heading);
+        }
+    }
+
+    /// Point the second player's free movement in split-screen, under the same rules as the first
+    pub fn steer_rival(&mut self, now: Instant, heading: [f32; 2]) {
+        if let Some (rival) = self.rival.as_mut().filter(|rival| rival.game_state == GameState::Playing && !rival.escaping()) {
+            rival.steer(now, heading);
+        }
+    }
+
+    /// Whether anyone is still playing, or on their way out, rather than the game being over for everyone
+    pub fn in_play(&self) -> bool {
+        [Some (&self.player), self.rival.as_ref()].into_iter().flatten().any(|player| in_play(player) || player.escaping())
+    }
+
+    /// Whether a second player is moving a ghost
+    pub fn co_op(&self) -> bool {
+        self.ghosts.iter().any(Ghost::controlled)
+    }
+
+    /// Start moving the second player's ghost by delta, returning whether it moved
+    pub fn try_move_ghost(&mut self, now: Instant, delta: [i32; 4]) -> bool {
+        if self.player.game_state != GameState::Playing {
+            return false;
+        }
+        match self.ghosts.iter_mut().find(|ghost| ghost.controlled()) {
+            Some (ghost) => ghost.try_move(now, delta, &self.player, &self.world),
+            None => false
+        }
+    }
+
+    /// Speed every ghost up until the given time, eg. when chat votes for it
+    pub fn rush_ghosts(&mut self, until: Instant) {
+        self.ghosts.iter_mut().for_each(|ghost| ghost.rush(until));
+    }
+
+    /// Advance the objects, players and ghosts up to the given time, shifting the maze's walls whenever they're due
+    pub fn update(&mut self, now: Instant, config: &Config) {
+        if !self.in_play() {
+            return;
+        }
+        if config.shift_interval > 0.0 {
+            let interval = Duration::from_secs_f32(config.shift_interval);
+            let next_shift = self.next_shift.get_or_insert(now + interval);
+            if now >= *next_shift {
+                *next_shift = now + interval;
+                self.world.shift_walls(&mut self.rng);
+            }
+        }
+        self.objects.update(now, config, &mut self.world, self.player.cell(), &mut self.rng);
+        if in_play(&self.player) || self.player.escaping() {
+            self.player.update(now, config, &mut self.world, &mut self.objects);
+        }
+        if let Some (rival) = &mut self.rival {
+            if in_play(rival) || rival.escaping() {
+                rival.update(now, config, &mut self.world, &mut self.objects);
+            }
+            // First one out wins the race, and the other loses it
+            if escaped(&self.player) && in_play(rival) {
+                rival.game_state = GameState::Lost;
+            } else if escaped(rival) && in_play(&self.player) {
+                self.player.game_state = GameState::Lost;
+            }
+        }
+        if config.mode == Mode::Survival {
+            // Food eaten comes back somewhere else, and the ghosts keep getting faster
+            self.objects.refill_food(&mut self.world, config.food_count, self.player.cell(), &mut self.rng);
+            let interval = Duration::from_secs_f32(SURVIVAL_SPEED_UP_TIME);
+            let next_speed_up = self.next_speed_up.get_or_insert(now + interval);
+            if now >= *next_speed_up {
+                *next_speed_up = now + interval;
+                for ghost in &mut self.ghosts {
+                    ghost.speed_up(SURVIVAL_SPEED_UP, SURVIVAL_FASTEST_MOVE);
+                }
+            }
+        }
+        if config.mode.has_ghost() && (in_play(&self.player) || self.rival.as_ref().is_some_and(in_play)) {
+            // The pack closes in around the first player until they're out of the game, then the second
+            let lead = match &self.rival {
+                Some (rival) if !in_play(&self.player) => rival,
+                _ => &self.player
+            };
+            self.pack.update(&mut self.ghosts, lead, &mut self.world);
+            for i in 0..self.ghosts.len() {
+                // Each ghost goes after whichever player still in the game is closer
+                let ghost = &self.ghosts[i];
+                let closer = |player: &Player| linalg::sub(ghost.position(), player.get_position()).iter().map(|x| x * x).sum::<f32>();
+                let rival_closer = self.rival.as_ref().is_some_and(|rival| {
+                    in_play(rival) && (!in_play(&self.player) || closer(rival) < closer(&self.player))
+                });
+                let chased = match &mut self.rival {
+                    Some (rival) if rival_closer => rival,
+                    _ => &mut self.player
+                };
+                let lives = chased.lives;
+                self.ghosts[i].update(now, chased, &self.world);
+                if chased.lives < lives && chased.game_state == GameState::Respawning {
+                    // Caught, so the whole pack goes back to where it started along with the player
+                    self.ghosts.iter_mut().for_each(|ghost| ghost.respawn(now));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Whether the player is still in the game, or between lives, rather than on their way out
+fn in_play(player: &Player) -> bool {
+    (player.game_state == GameState::Playing || player.game_state == GameState::Respawning) && !player.escaping()
+}
+
+// Whether the player has made it out, or is on their way
+fn escaped(player: &Player) -> bool {
+    player.game_state == GameState::Won || player.escaping()
+}
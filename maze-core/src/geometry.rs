@@ -0,0 +1,77 @@
+//! Visibility tests against the view frustum, for culling what the camera can't see before it's drawn
+
+/// The six planes bounding what a view-projection matrix can see, each as [a, b, c, d] with a unit normal
+/// [a, b, c] facing inwards, so a point p is inside a plane when a*p.x + b*p.y + c*p.z + d >= 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [[f32; 4]; 6] // Left, right, bottom, top, near, far
+}
+
+impl Frustum {
+    /// Extract the planes from a view-projection matrix, as made with linalg::projection, or from a full
+    /// model-view-projection matrix to get them in the model's own space
+    pub fn new(view_projection: [[f32; 4]; 4]) -> Frustum {
+        // A point is visible when each of x, y and z in clip space lies between -w and w, and each of those six
+        // comparisons is a plane made of a row of the matrix added to or taken from its last row
+        let row = |i: usize| view_projection.map(|col| col[i]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let plus = |a: [f32; 4]| [0, 1, 2, 3].map(|i| w[i] + a[i]);
+        let minus = |a: [f32; 4]| [0, 1, 2, 3].map(|i| w[i] - a[i]);
+        let planes = [plus(x), minus(x), plus(y), minus(y), plus(z), minus(z)].map(|plane| {
+            let length = (plane[0] * plane[0] + plane[1] * plane[1] + plane[2] * plane[2]).sqrt();
+            plane.map(|f| f / length)
+        });
+        Frustum { planes }
+    }
+
+    /// Whether any of the sphere might be visible
+    pub fn intersects_sphere(&self, centre: [f32; 3], radius: f32) -> bool {
+        self.planes.iter().all(|plane| distance(plane, centre) >= -radius)
+    }
+
+    /// Whether any of the axis-aligned box between the two corners might be visible. Boxes near the frustum's
+    /// corners can pass without being seen, which only costs drawing something off screen
+    pub fn intersects_aabb(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal is the last to leave through it
+            let corner = [0, 1, 2].map(|i| if plane[i] >= 0.0 { max[i] } else { min[i] });
+            distance(plane, corner) >= 0.0
+        })
+    }
+}
+
+// Signed distance from the plane to the point, positive on the inside
+fn distance(plane: &[f32; 4], point: [f32; 3]) -> f32 {
+    plane[0] * point[0] + plane[1] * point[1] + plane[2] * point[2] + plane[3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg;
+
+    // Looking straight down from 10 above the origin, seeing a 90 degree cone out to 100 away
+    fn overhead() -> Frustum {
+        Frustum::new(linalg::mul(linalg::projection(0.1, 100.0, 1.0, 1.0), linalg::translate([0.0, 0.0, -10.0])))
+    }
+
+    #[test]
+    fn spheres_in_view_intersect() {
+        let frustum = overhead();
+        assert!(frustum.intersects_sphere([0.0, 0.0, 0.0], 0.5));
+        assert!(frustum.intersects_sphere([9.0, 0.0, 0.0], 0.5));
+        assert!(!frustum.intersects_sphere([12.0, 0.0, 0.0], 0.5));
+        assert!(!frustum.intersects_sphere([0.0, 0.0, 11.0], 0.5)); // Behind the camera
+        assert!(!frustum.intersects_sphere([0.0, 0.0, -95.0], 0.5)); // Past the far plane
+        assert!(frustum.intersects_sphere([0.0, 0.0, -95.0], 10.0));
+    }
+
+    #[test]
+    fn boxes_partly_in_view_intersect() {
+        let frustum = overhead();
+        assert!(frustum.intersects_aabb([-1.0, -1.0, 0.0], [1.0, 1.0, 1.0]));
+        assert!(frustum.intersects_aabb([8.0, -1.0, 0.0], [20.0, 1.0, 1.0]));
+        assert!(!frustum.intersects_aabb([15.0, -1.0, 0.0], [20.0, 1.0, 1.0]));
+        assert!(!frustum.intersects_aabb([-1.0, 15.0, 0.0], [1.0, 20.0, 1.0]));
+    }
+}
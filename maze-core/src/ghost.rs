@@ -0,0 +1,573 @@
+use log::{debug, trace};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::objects::{Inventory, PowerUp};
+use crate::player::{GameState, Player};
+use crate::world::{Coordinate, World};
+use crate::config::{Config, Movement};
+use crate::linalg;
+
+const PURSUIT_STEP: f32 = 0.05; // Most seconds closed in at once, so a slow frame can't carry the ghost through a wall
+const HALF_SIZE: f32 = 0.25; // Half the width of the box the ghost bumps into walls with while closing in
+
+/// Where the ghost was in a saved game. Saved between moves, so the ghost carries on from the cell it was moving into
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GhostSnapshot {
+    cell: [usize; 4],
+    spawn: [usize; 4],
+    grace: bool,
+    on_pad: Option<[usize; 4]>,
+    move_time: f32
+}
+
+/// Chases the player through the maze once they start eating
+pub struct Ghost {
+    grace: bool, // Grace period where ghost doesn't move till first food eaten
+    position: [f32; 4],
+    spawn: [usize; 4],
+    reach_dest: Instant,
+    dest_position: [usize; 4],
+    init_position: [usize; 4],
+    start: [f32; 4], // Where the current move set off from, off the middle of a cell after closing in
+    move_time: f32,
+    teleports: bool, // Whether the ghost uses teleporter pads
+    on_pad: Option<[usize; 4]>, // Pad the ghost arrived on by teleporting
+    target: Option<Coordinate>, // Cell to head for instead of the player's, as the pack coordinator hands out
+    controlled: bool, // Moved by a second player in co-op rather than chasing on its own
+    rushed_until: Option<Instant>, // Moving at double speed until then, as chat voted
+    free: bool, // Closes in on exactly where the player is once next to them, as they glide in free movement
+    pursued: Option<Instant>, // When the ghost last closed in, while it's doing so
+    current_move_time: f32 // Incorporates speed penalties for 3rd or 4th dimensional movement
+}
+
+impl Ghost {
+    /// Ghost at a random cell in the far half of the maze
+    pub fn new(config: &Config, rng: &mut impl Rng) -> Ghost {
+        Ghost::at(config, config.dimensions.map(|d| rng.gen_range(d/2..d)))
+    }
+
+    /// Ghost at the given cell
+    pub fn at(config: &Config, spawn: [usize; 4]) -> Ghost {
+        Ghost {
+            grace: true,
+            position: spawn.map(|i| i as f32),
+            spawn,
+            reach_dest: Instant::now(),
+            dest_position: spawn,
+            init_position: spawn,
+            start: spawn.map(|i| i as f32),
+            move_time: config.ghost_move_time,
+            teleports: config.ghost_teleports,
+            on_pad: None,
+            target: None,
+            controlled: false,
+            rushed_until: None,
+            free: config.movement == Movement::Free,
+            pursued: None,
+            current_move_time: config.ghost_move_time
+        }
+    }
+
+    /// Step towards the player, taking a life if they've been caught
+    pub fn update(&mut self, now: Instant, player: &mut Player, world: &World) {
+        if player.game_state != GameState::Playing {
+            return; // Player may have already left the maze this frame
+        }
+        if self.grace {
+            if player.score > 0 {
+                debug!(target: "ghost", "Ghost woke up at {:?}", self.dest_position);
+                self.grace = false;
+            } else {
+                return;
+            }
+        }
+
+        // Did we reach the player?
+        if self.caught(player) {
+            debug!(target: "ghost", "Ghost caught the player at {:?}", player.cell());
+            player.lose_life(now);
+            if player.game_state == GameState::Respawning {
+                self.respawn(now);
+            }
+            return;
+        }
+
+        if now > self.reach_dest {
+            if self.pursued.is_none() {
+                self.position = self.dest_position.map(|i| i as f32);
+            }
+            self.init_position = self.dest_position;
+            if self.on_pad != Some (self.dest_position) {
+                self.on_pad = None;
+                if let Some (target) = world.teleport_target(self.dest_position.map(|i| i as i32)).filter(|_| self.teleports) {
+                    debug!(target: "ghost", "Ghost teleported from {:?} to {:?}", self.dest_position, target);
+                    self.dest_position = target.map(|i| i as usize);
+                    self.position = self.dest_position.map(|i| i as f32);
+                    self.init_position = self.dest_position;
+                    self.on_pad = Some (self.dest_position);
+                    self.pursued = None;
+                }
+            }
+            if self.controlled {
+                return; // Waits for the second player's next move
+            }
+            // Otherwise, use BFS to track player, or whichever cell the ghost was told to head for
+            let ghost_pos = (self.dest_position[0], self.dest_position[1], self.dest_position[2], self.dest_position[3]);
+            let player_pos = (player.cell()[0] as usize, player.cell()[1] as usize, player.cell()[2] as usize, player.cell()[3] as usize);
+            // Next target position
+            let (x, y, z, w) = *world.bfs(ghost_pos, self.target.unwrap_or(player_pos)).get(1).unwrap_or(&ghost_pos);
+            // A gliding player is seldom mid-cell, so once next to them in their level, head straight for them
+            if self.free && self.target.is_none() && (x, y, z, w) == player_pos && (z, w) == (ghost_pos.2, ghost_pos.3) {
+                self.pursue(now, player, world);
+                return;
+            }
+            self.pursued = None;
+            self.set_off(now, [x, y, z, w], player);
+        } else {
+            // Animate movement
+            let progress = 1.0 - (self.reach_dest - now).as_secs_f32() / self.current_move_time; // ranges from 0.0 at start to 1.0 at dest
+            self.position = [0, 1, 2, 3].map(|i| self.start[i] + (self.dest_position[i] as f32 - self.start[i]) * progress);
+        }
+    }
+
+    /// Move by delta for a second player, as long as the ghost has finished its last move and the walls allow it.
+    /// Doors don't stop the ghost, and neither does an open exit, which it can't leave through. Returns whether it moved
+    pub fn try_move(&mut self, now: Instant, delta: [i32; 4], player: &Player, world: &World) -> bool {
+        if !self.controlled || self.grace || now <= self.reach_dest {
+            return false;
+        }
+        let cell = self.dest_position.map(|i| i as i32);
+        let target = [0, 1, 2, 3].map(|i| cell[i] + delta[i]);
+        if !world.check_move(cell, delta, &every_key(world)) || !world.contains(target) {
+            return false;
+        }
+        self.position = self.dest_position.map(|i| i as f32);
+        self.init_position = self.dest_position;
+        self.set_off(now, target.map(|i| i as usize), player);
+        true
+    }
+
+    // Start moving into a neighbouring cell, taking longer to move up, down or between w-slices
+    fn set_off(&mut self, now: Instant, dest: [usize; 4], player: &Player) {
+        self.dest_position = dest;
+        self.start = self.position;
+        trace!(target: "ghost", "Ghost heading from {:?} to {:?}", self.init_position, self.dest_position);
+        self.current_move_time = self.pace(now, player) *
+            if self.dest_position[2] != self.init_position[2] {
+                2.0 // Vertical penalty
+            } else if self.dest_position[3] != self.init_position[3] {
+                5.0 // Fourth penalty
+            } else {
+                1.0
+            };
+        self.reach_dest = now + Duration::from_secs_f32(self.current_move_time);
+    }
+
+    // Close in on exactly where the player is across the level, at the ghost's usual speed, sliding along any walls
+    // between them. The ghost is in the cell it's moving through the whole time
+    fn pursue(&mut self, now: Instant, player: &Player, world: &World) {
+        let mut seconds = self.pursued.map_or(0.0, |pursued| now.saturating_duration_since(pursued).as_secs_f32());
+        self.pursued = Some (now);
+        let speed = 1.0 / self.pace(now, player);
+        let keys = every_key(world);
+        while seconds > 0.0 {
+            let step = seconds.min(PURSUIT_STEP);
+            seconds -= step;
+            let [dx, dy, _, _] = linalg::sub(player.get_position(), self.position);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance == 0.0 {
+                break;
+            }
+            let reach = (speed * step).min(distance);
+            self.position = world.sweep(self.position, [dx / distance * reach, dy / distance * reach], HALF_SIZE, &keys);
+        }
+        self.dest_position = self.position.map(|i| i.round() as usize);
+        self.init_position = self.dest_position;
+    }
+
+    // Seconds a move along x or y takes right now, slower while the player's clock is running and faster while rushed
+    fn pace(&self, now: Instant, player: &Player) -> f32 {
+        let mut pace = self.move_time;
+        if player.has_effect(now, PowerUp::Clock) {
+            pace *= 2.0; // Slowed down by the player's clock
+        }
+        if self.rushed_until.is_some_and(|until| now < until) {
+            pace /= 2.0;
+        }
+        pace
+    }
+
+    /// Hand the ghost over to a second player, or back to chasing on its own
+    pub fn set_controlled(&mut self, controlled: bool) {
+        self.controlled = controlled;
+    }
+
+    pub fn controlled(&self) -> bool {
+        self.controlled
+    }
+
+    /// Move at double speed until the given time, starting from the next move
+    pub fn rush(&mut self, until: Instant) {
+        self.rushed_until = Some (until);
+    }
+
+    /// Current position, between cells while moving
+    pub fn position(&self) -> [f32; 4] {
+        self.position
+    }
+
+    /// Place the ghost directly, outside of normal movement (eg. when following a broadcast game)
+    pub fn set_position(&mut self, position: [f32; 4]) {
+        self.position = position;
+        self.init_position = position.map(|f| f.round() as usize);
+        self.dest_position = self.init_position;
+    }
+
+    /// Return to the spawn cell, eg. after catching a player who has lives left
+    pub fn respawn(&mut self, now: Instant) {
+        self.position = self.spawn.map(|i| i as f32);
+        self.init_position = self.spawn;
+        self.pursued = None;
+        self.dest_position = self.spawn;
+        self.reach_dest = now;
+    }
+
+    /// Everything about the ghost worth keeping in a saved game
+    pub fn snapshot(&self) -> GhostSnapshot {
+        GhostSnapshot { cell: self.dest_position, spawn: self.spawn, grace: self.grace, on_pad: self.on_pad, move_time: self.move_time }
+    }
+
+    /// Carry on from a saved game, setting off from the saved cell
+    pub fn restore(&mut self, snapshot: &GhostSnapshot, now: Instant) {
+        self.spawn = snapshot.spawn;
+        self.grace = snapshot.grace;
+        self.on_pad = snapshot.on_pad;
+        self.move_time = snapshot.move_time;
+        self.position = snapshot.cell.map(|i| i as f32);
+        self.init_position = snapshot.cell;
+        self.pursued = None;
+        self.dest_position = snapshot.cell;
+        self.reach_dest = now;
+    }
+
+    /// Cell the ghost is in, or moving into
+    pub fn cell(&self) -> [usize; 4] {
+        self.dest_position
+    }
+
+    /// Head for the given cell rather than straight for the player, or for the player again with None
+    pub fn set_target(&mut self, target: Option<Coordinate>) {
+        self.target = target;
+    }
+
+    pub fn target(&self) -> Option<Coordinate> {
+        self.target
+    }
+
+    /// Make every move from now on take a fraction of the time, down to a shortest move time
+    pub fn speed_up(&mut self, factor: f32, fastest: f32) {
+        self.move_time = (self.move_time * factor).max(fastest);
+        debug!(target: "ghost", "Ghost sped up to {}s a move", self.move_time);
+    }
+
+    /// Seconds the ghost takes to move one cell along x or y
+    pub fn move_time(&self) -> f32 {
+        self.move_time
+    }
+
+    /// Whether the ghost has woken up and is after the player
+    pub fn chasing(&self) -> bool {
+        !self.grace
+    }
+
+    /// End the grace period, so the ghost starts chasing straight away
+    pub fn wake(&mut self) {
+        self.grace = false;
+    }
+
+    /// Whether the ghost is close enough to the player to catch them
+    pub fn caught(&self, player: &Player) -> bool {
+        let player_dist = linalg::sub(self.position, player.get_position()).map(|i| i * i).iter().fold(0.0, |acc, i| acc + i);
+        player_dist < 0.2
+    }
+}
+
+// Keys to every door, which never stop the ghost
+fn every_key(world: &World) -> Inventory {
+    let mut every_key = Inventory::new();
+    (0..world.doors.len()).for_each(|key| every_key.add_key(key));
+    every_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Objects;
+    use rand::thread_rng;
+    use crate::world::Cell;
+
+    const TICK: f32 = 0.05;
+
+    // Small scripted game, stepped forward in fixed ticks without a GPU
+    struct Sim {
+        config: Config,
+        world: World,
+        player: Player,
+        ghost: Ghost,
+        objects: Objects,
+        now: Instant,
+        ghost_path: Vec<[usize; 4]> // Every cell the ghost has headed towards
+    }
+
+    impl Sim {
+        fn new(mut world: World, player: [i32; 4], ghost: [usize; 4]) -> Sim {
+            let config = Config { food_count: 0, ghost_move_time: 1.0, lives: 1, ..Default::default() };
+            let objects = Objects::new(&mut world, &config, &mut thread_rng());
+            Sim {
+                player: Player::new(&config, player),
+                ghost: Ghost::at(&config, ghost),
+                ghost_path: vec![ghost],
+                now: Instant::now(),
+                config,
+                world,
+                objects
+            }
+        }
+
+        fn with_lives(mut self, lives: u32) -> Sim {
+            self.config.lives = lives;
+            self.player = Player::new(&self.config, self.player.cell());
+            self
+        }
+
+        fn without_grace(mut self) -> Sim {
+            self.ghost.grace = false;
+            self
+        }
+
+        fn gliding(mut self) -> Sim {
+            self.config.movement = Movement::Free;
+            self.player = Player::new(&self.config, self.player.cell());
+            self.ghost.free = true;
+            self
+        }
+
+        fn tick(&mut self, seconds: f32) {
+            for _ in 0..(seconds / TICK).round() as usize {
+                self.now += Duration::from_secs_f32(TICK);
+                self.player.update(self.now, &self.config, &mut self.world, &mut self.objects);
+                self.ghost.update(self.now, &mut self.player, &self.world);
+                if self.ghost_path.last() != Some (&self.ghost.dest_position) {
+                    self.ghost_path.push(self.ghost.dest_position);
+                }
+            }
+        }
+
+        fn step(&mut self, delta: [i32; 4]) {
+            assert!(self.world.check_move(self.player.cell(), delta, &self.player.inventory), "Scripted move {:?} is blocked", delta);
+            self.player.move_position(self.now, delta, 0.0);
+        }
+    }
+
+    // Build a single level from a map like the following, where each cell is a space
+    // and a space between two cells means there's no wall between them
+    // +-+-+
+    // |   |
+    // +-+-+
+    fn layout(map: &[&str]) -> World {
+        let rows: Vec<Vec<char>> = map.iter().map(|row| row.chars().collect()).collect();
+        let (width, height) = ((rows[0].len() - 1) / 2, (rows.len() - 1) / 2);
+        let mut world = World::empty([width, height, 1, 1]);
+        for y in 0..height {
+            for x in 0..width {
+                if x > 0 && rows[2 * y + 1][2 * x] == ' ' {
+                    world.open_wall((x - 1, y, 0, 0), (x, y, 0, 0));
+                }
+                if y > 0 && rows[2 * y][2 * x + 1] == ' ' {
+                    world.open_wall((x, y - 1, 0, 0), (x, y, 0, 0));
+                }
+            }
+        }
+        world
+    }
+
+    fn corridor(length: usize) -> World {
+        let walls = "+-".repeat(length) + "+";
+        let cells = "|".to_string() + &" ".repeat(length * 2 - 1) + "|";
+        layout(&[&walls, &cells, &walls])
+    }
+
+    #[test]
+    fn waits_during_grace_period() {
+        let mut world = corridor(5);
+        world.cells[0][0][0][1] = Cell::Food;
+        let mut sim = Sim::new(world, [0, 0, 0, 0], [4, 0, 0, 0]);
+        sim.tick(3.0);
+        assert_eq!(sim.ghost.position(), [4.0, 0.0, 0.0, 0.0]);
+        assert_eq!(sim.ghost_path, vec![[4, 0, 0, 0]]);
+
+        // Eating the first food wakes the ghost up
+        sim.step([1, 0, 0, 0]);
+        sim.tick(0.1);
+        assert_eq!(sim.player.score, 1);
+        assert_eq!(sim.ghost_path, vec![[4, 0, 0, 0], [3, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn gliding_player_slides_along_walls_to_a_stop() {
+        let mut sim = Sim::new(corridor(5), [0, 0, 0, 0], [4, 0, 0, 0]).gliding();
+        sim.player.steer(sim.now, [1.0, 1.0]);
+        sim.tick(5.0);
+        let stopped = 4.5 - 0.05 - 0.25; // Against the end wall, less the wall's half thickness and the player's half width
+        let [x, y, _, _] = sim.player.get_position();
+        assert!((x - stopped).abs() < 1e-4 && (y - (stopped - 4.0)).abs() < 1e-4, "{:?}", sim.player.get_position());
+        assert_eq!(sim.player.cell(), [4, 0, 0, 0]);
+        assert_eq!(sim.player.replay.moves.last().map(|(_, cell)| *cell), Some ([4, 0, 0, 0]));
+    }
+
+    #[test]
+    fn closes_in_on_a_gliding_player_off_the_middle_of_their_cell() {
+        let mut sim = Sim::new(corridor(5), [4, 0, 0, 0], [0, 0, 0, 0]).gliding();
+        sim.player.steer(sim.now, [1.0, 1.0]);
+        sim.tick(1.0);
+        sim.player.steer(sim.now, [0.0, 0.0]);
+        sim.ghost.wake();
+        sim.tick(6.0);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+        assert!(sim.ghost.position()[1] > 0.0, "{:?}", sim.ghost.position());
+    }
+
+    #[test]
+    fn follows_shortest_path_around_walls() {
+        let world = layout(&[
+            "+-+-+-+",
+            "|     |",
+            "+ +-+ +",
+            "| | | |",
+            "+-+-+-+"
+        ]);
+        let mut sim = Sim::new(world, [2, 1, 0, 0], [0, 1, 0, 0]).without_grace();
+        sim.tick(3.5);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        assert_eq!(sim.ghost_path, vec![[0, 1, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0], [2, 0, 0, 0], [2, 1, 0, 0]]);
+    }
+
+    #[test]
+    fn catches_stationary_player() {
+        let mut sim = Sim::new(corridor(3), [0, 0, 0, 0], [2, 0, 0, 0]).without_grace();
+        sim.tick(1.5);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        sim.tick(1.0);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn catching_takes_a_life_and_respawns_both() {
+        let mut sim = Sim::new(corridor(5), [0, 0, 0, 0], [2, 0, 0, 0]).with_lives(2).without_grace();
+        sim.step([1, 0, 0, 0]);
+        sim.tick(1.5);
+        assert_eq!(sim.player.game_state, GameState::Respawning);
+        assert_eq!(sim.player.lives, 1);
+        assert_eq!(sim.player.cell(), [0, 0, 0, 0]);
+        assert_eq!(sim.ghost.position(), [2.0, 0.0, 0.0, 0.0]);
+
+        // Play resumes after a pause, and the next catch ends the game
+        sim.tick(1.5);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        sim.tick(3.0);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+        assert_eq!(sim.player.lives, 0);
+    }
+
+    #[test]
+    fn clock_slows_ghost_down() {
+        let mut world = corridor(3);
+        world.cells[0][0][0][0] = Cell::PowerUp (PowerUp::Clock);
+        let mut sim = Sim::new(world, [0, 0, 0, 0], [2, 0, 0, 0]).without_grace();
+        sim.tick(0.1);
+        assert!(sim.player.has_effect(sim.now, PowerUp::Clock));
+        assert_eq!(sim.ghost.current_move_time, 2.0);
+        sim.tick(2.9);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        sim.tick(1.0);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn teleporting_ghost_takes_the_shortcut() {
+        let mut world = corridor(6);
+        world.link_teleporters((1, 0, 0, 0), (4, 0, 0, 0));
+        let mut walking = Sim::new(corridor(6), [0, 0, 0, 0], [5, 0, 0, 0]).without_grace();
+        let mut teleporting = Sim::new(world, [0, 0, 0, 0], [5, 0, 0, 0]).without_grace();
+        teleporting.ghost.teleports = true;
+        walking.tick(2.5);
+        teleporting.tick(2.5);
+        assert_eq!(walking.player.game_state, GameState::Playing);
+        assert_eq!(teleporting.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn vertical_moves_are_slower() {
+        let mut world = World::empty([1, 1, 2, 1]);
+        world.open_wall((0, 0, 0, 0), (0, 0, 1, 0));
+        let mut sim = Sim::new(world, [0, 0, 0, 0], [0, 0, 1, 0]).without_grace();
+        sim.tick(0.1);
+        assert_eq!(sim.ghost.current_move_time, 2.0);
+        sim.tick(0.9);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        sim.tick(0.4);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn fourth_dimension_moves_are_slowest() {
+        let mut world = World::empty([1, 1, 1, 2]);
+        world.open_wall((0, 0, 0, 0), (0, 0, 0, 1));
+        let mut sim = Sim::new(world, [0, 0, 0, 0], [0, 0, 0, 1]).without_grace();
+        sim.tick(0.1);
+        assert_eq!(sim.ghost.current_move_time, 5.0);
+        sim.tick(2.4);
+        assert_eq!(sim.player.game_state, GameState::Playing);
+        sim.tick(0.6);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn rushed_ghost_moves_faster() {
+        let mut sim = Sim::new(corridor(3), [0, 0, 0, 0], [2, 0, 0, 0]).without_grace();
+        sim.ghost.rush(sim.now + Duration::from_secs(10));
+        sim.tick(0.1);
+        assert_eq!(sim.ghost.current_move_time, 0.5);
+        sim.tick(1.0);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn second_player_moves_the_ghost_through_walls_only_where_open() {
+        let mut sim = Sim::new(corridor(3), [0, 0, 0, 0], [2, 0, 0, 0]).without_grace();
+        sim.ghost.set_controlled(true);
+        sim.tick(3.0);
+        assert_eq!(sim.ghost_path, vec![[2, 0, 0, 0]]);
+        assert!(!sim.ghost.try_move(sim.now, [0, 1, 0, 0], &sim.player, &sim.world));
+        assert!(!sim.ghost.try_move(sim.now, [1, 0, 0, 0], &sim.player, &sim.world));
+        assert!(sim.ghost.try_move(sim.now, [-1, 0, 0, 0], &sim.player, &sim.world));
+        assert!(!sim.ghost.try_move(sim.now, [-1, 0, 0, 0], &sim.player, &sim.world));
+        sim.tick(1.1);
+        assert_eq!(sim.ghost.position(), [1.0, 0.0, 0.0, 0.0]);
+        assert!(sim.ghost.try_move(sim.now, [-1, 0, 0, 0], &sim.player, &sim.world));
+        sim.tick(1.1);
+        assert_eq!(sim.player.game_state, GameState::Lost);
+    }
+
+    #[test]
+    fn stops_once_game_is_over() {
+        let mut sim = Sim::new(corridor(3), [0, 0, 0, 0], [2, 0, 0, 0]).without_grace();
+        sim.player.game_state = GameState::Won;
+        sim.tick(3.0);
+        assert_eq!(sim.ghost_path, vec![[2, 0, 0, 0]]);
+        assert_eq!(sim.player.game_state, GameState::Won);
+    }
+}
@@ -0,0 +1,210 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(10); // Longest to wait on the server before giving up
+const TOP: usize = 10; // Best times kept from each day's board
+
+/// A finished run as sent to the leaderboard, POSTed as TOML to the server's /runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Submission {
+    pub name: String,
+    pub seed: u64,
+    pub dimensions: [usize; 4],
+    pub seconds: u32,
+    pub replay: String, // Hash of the run's replay, as 16 hex digits, for the server to check the time against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<String> // Date of the daily challenge played
+}
+
+/// One of the best times in a daily challenge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub seconds: u32
+}
+
+// What the server sends back from /daily/DATE: a [[time]] table for each of the day's best times, fastest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Board {
+    #[serde(default)]
+    time: Vec<Entry>
+}
+
+/// Answer to a request made in the background
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Submitted,
+    Daily (String, Vec<Entry>), // Date, and its best times
+    Failed (String)
+}
+
+// Server named by an http:// address, and the path under it the leaderboard lives at
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    path: String
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> std::result::Result<Endpoint, String> {
+        let rest = url.strip_prefix("http://").ok_or("Expected an address starting with http://")?;
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some ((host, port)) => (host, port.parse().map_err(|_| "Expected a port number after the :")?),
+            None => (authority, 80)
+        };
+        if host.is_empty() {
+            return Err ("Expected a server name after http://".to_string());
+        }
+        Ok (Endpoint { host: host.to_string(), port, path: path.trim_end_matches('/').to_string() })
+    }
+
+    // Path on the server of one of the leaderboard's routes
+    fn route(&self, route: &str) -> String {
+        if self.path.is_empty() { format!("/{}", route) } else { format!("/{}/{}", self.path, route) }
+    }
+}
+
+/// Talks to a leaderboard server on threads of its own, so the game never waits on the network.
+/// Replies pile up until they're picked up with `replies`, eg. once a frame
+pub struct Leaderboard {
+    endpoint: Endpoint,
+    sender: Sender<Reply>,
+    replies: Receiver<Reply>
+}
+
+impl Leaderboard {
+    pub fn new(url: &str) -> Result<Leaderboard> {
+        let endpoint = Endpoint::parse(url).map_err(|reason| MazeError::Network (format!("Invalid leaderboard address `{}': {}", url, reason)))?;
+        let (sender, replies) = mpsc::channel();
+        Ok (Leaderboard { endpoint, sender, replies })
+    }
+
+    /// Send a finished run in the background
+    pub fn submit(&self, submission: Submission) {
+        let (endpoint, sender) = (self.endpoint.clone(), self.sender.clone());
+        thread::spawn(move || {
+            let reply = match submit(&endpoint, &submission) {
+                Ok (()) => Reply::Submitted,
+                Err (e) => Reply::Failed (format!("Couldn't send the run: {}", e))
+            };
+            let _ = sender.send(reply);
+        });
+    }
+
+    /// Fetch the best times in the day's challenge in the background
+    pub fn fetch_daily(&self, date: &str) {
+        let (endpoint, sender, date) = (self.endpoint.clone(), self.sender.clone(), date.to_string());
+        thread::spawn(move || {
+            let reply = match daily(&endpoint, &date) {
+                Ok (entries) => Reply::Daily (date, entries),
+                Err (e) => Reply::Failed (format!("Couldn't fetch the best times for {}: {}", date, e))
+            };
+            let _ = sender.send(reply);
+        });
+    }
+
+    /// Best times in the day's challenge, waiting for the answer, eg. to print them from the command line
+    pub fn daily(&self, date: &str) -> Result<Vec<Entry>> {
+        daily(&self.endpoint, date).map_err(MazeError::Network)
+    }
+
+    /// Replies that have come in since last asked, without waiting for any still on their way
+    pub fn replies(&self) -> impl Iterator<Item = Reply> + '_ {
+        self.replies.try_iter()
+    }
+}
+
+fn submit(endpoint: &Endpoint, submission: &Submission) -> std::result::Result<(), String> {
+    let body = toml::to_string(submission).map_err(|e| e.to_string())?;
+    request(endpoint, "POST", &endpoint.route("runs"), &body).map(|_| ())
+}
+
+fn daily(endpoint: &Endpoint, date: &str) -> std::result::Result<Vec<Entry>, String> {
+    let body = request(endpoint, "GET", &endpoint.route(&format!("daily/{}", date)), "")?;
+    let mut board: Board = toml::from_str(&body).map_err(|e| format!("Unexpected answer: {}", e))?;
+    board.time.truncate(TOP);
+    Ok (board.time)
+}
+
+// Make one request and return the body of a successful response. Asking in HTTP/1.0 keeps the answer in one piece
+// rather than chunked, and the connection closes once it's sent
+fn request(endpoint: &Endpoint, method: &str, path: &str, body: &str) -> std::result::Result<String, String> {
+    debug!(target: "net", "{} http://{}:{}{}", method, endpoint.host, endpoint.port, path);
+    let mut stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port)).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some (TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some (TIMEOUT)).map_err(|e| e.to_string())?;
+    // Sent in one write, as the stream isn't buffered
+    let request = format!("{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/toml\r\nContent-Length: {}\r\n\r\n{}",
+        method, path, endpoint.host, body.len(), body);
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+    body_of(&response)
+}
+
+// Body of an HTTP response, or what went wrong if it wasn't a success
+fn body_of(response: &str) -> std::result::Result<String, String> {
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("The server's answer was cut short")?;
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some (status) if status.starts_with('2') => Ok (body.to_string()),
+        _ => Err (format!("The server answered `{}'", status_line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn addresses_split_into_server_and_path() {
+        let endpoint = Endpoint::parse("http://scores.example.com:8080/maze/").unwrap();
+        assert_eq!(endpoint, Endpoint { host: "scores.example.com".to_string(), port: 8080, path: "maze".to_string() });
+        assert_eq!(endpoint.route("runs"), "/maze/runs");
+        assert_eq!(Endpoint::parse("http://localhost").unwrap().route("runs"), "/runs");
+        assert!(Endpoint::parse("https://localhost").is_err());
+        assert!(Endpoint::parse("http://localhost:port").is_err());
+    }
+
+    #[test]
+    fn only_successes_have_a_body() {
+        assert_eq!(body_of("HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nhi"), Ok ("hi".to_string()));
+        assert!(body_of("HTTP/1.0 404 Not Found\r\n\r\n").is_err());
+        assert!(body_of("HTTP/1.0 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn daily_times_come_back_in_the_background() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Read up to the blank line after the headers, however many pieces the request arrives in
+            let mut request = Vec::new();
+            let mut byte = [0; 1];
+            while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                request.push(byte[0]);
+            }
+            let board = "[[time]]\nname = \"Sam\"\nseconds = 42\n";
+            write!(stream, "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{}", board.len(), board).unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        let leaderboard = Leaderboard::new(&format!("http://127.0.0.1:{}/maze", port)).unwrap();
+        leaderboard.fetch_daily("2026-10-16");
+        assert!(server.join().unwrap().starts_with("GET /maze/daily/2026-10-16 HTTP/1.0\r\n"));
+        let reply = leaderboard.replies.recv_timeout(TIMEOUT).unwrap();
+        assert_eq!(reply, Reply::Daily ("2026-10-16".to_string(), vec![Entry { name: "Sam".to_string(), seconds: 42 }]));
+    }
+}
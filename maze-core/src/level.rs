@@ -0,0 +1,358 @@
+//! Hand-made mazes, read from the same text map that `--export-maze` writes.
+//!
+//! A level file starts with a `size` line, then any `teleporter` lines linking pairs of pads, then each level of each
+//! w-slice drawn from above, headed `w W z Z`. Blank lines and lines starting with `#` are skipped.
+//!
+//! ```text
+//! size 3x2x1x2
+//! teleporter 2x0x0x0 0x1x0x1
+//! w 0 z 0
+//! +---+---+---+
+//! |S  |o > T  |
+//! +   +-0-+   +
+//! |0      |G  |
+//! +---+---+---+
+//! ```
+//!
+//! Each cell takes four characters: the wall to its left, what's in it, the way through its floor or ceiling and the
+//! way through to the neighbouring w-slices. Walls between rows take three characters under each cell.
+//!
+//! - Walls: ` ` or `   ` for none, `|` or `---` for a solid wall, and a digit (`-3-` between rows) for a door opened
+//!   by the key with that number. A gap in the outer wall next to `E` is the way out
+//! - Contents: `S` start, `E` exit, `G` ghost spawn, `o` food, a digit for a key, `B` boots, `C` clock, `b` battery,
+//!   `T` teleporter pad, `^` spikes, `&` snare and `v` pit
+//! - Floors and ceilings: `u` for a way up, `d` for a way down, `x` for both
+//! - Slices: `>` for a way through to the next w-slice, `<` for the previous one, `=` for both
+//!
+//! Ways up, down and between slices need marking from both cells they join.
+
+use std::fs;
+
+use crate::config::Config;
+use crate::error::{MazeError, Result};
+use crate::objects::PowerUp;
+use crate::world::{Axis, Cell, Coordinate, Trap, Wall, World};
+
+impl World {
+    /// Read a hand-made maze from a level file
+    pub fn from_level(path: &str) -> Result<World> {
+        let contents = fs::read_to_string(path).map_err(|e| MazeError::io(path, e))?;
+        World::parse_level(&contents).map_err(|(line, reason)| MazeError::Config {
+            path: path.to_string(),
+            line,
+            text: contents.lines().nth(line.saturating_sub(1)).unwrap_or_default().to_string(),
+            reason
+        })
+    }
+
+    /// Read a hand-made maze from the contents of a level file, or the line number and reason it can't be read
+    pub fn parse_level(contents: &str) -> std::result::Result<World, (usize, String)> {
+        let mut lines = contents.lines().enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .peekable();
+
+        let (line, size) = lines.next().ok_or((0, "Expected a size line, eg. size 5x5x3x3".to_string()))?;
+        let dimensions = size.strip_prefix("size ").and_then(parse_cell)
+            .filter(|dimensions| dimensions.iter().all(|&d| d >= 1))
+            .ok_or((line, "Expected a size line, eg. size 5x5x3x3".to_string()))?;
+        let [width, height, depth, fourth] = dimensions;
+        let mut world = World::empty(dimensions);
+        let inside = |[x, y, z, w]: [usize; 4]| x < width && y < height && z < depth && w < fourth;
+
+        let mut teleporters = Vec::new();
+        while let Some ((line, pads)) = lines.next_if(|(_, line)| line.starts_with("teleporter ")) {
+            let pads: Vec<[usize; 4]> = pads["teleporter ".len()..].split_whitespace().filter_map(parse_cell).collect();
+            match pads[..] {
+                [a, b] if inside(a) && inside(b) && a != b => teleporters.push((line, a, b)),
+                _ => return Err ((line, "Expected two different cells inside the maze, eg. teleporter 0x0x0x0 4x4x2x2".to_string()))
+            }
+        }
+
+        // Ways through floors and slices, as marked from each side
+        let mut up = vec![false; width * height * depth * fourth];
+        let mut down = up.clone();
+        let mut next = up.clone();
+        let mut previous = up.clone();
+        let index = |(x, y, z, w): Coordinate| ((w * depth + z) * height + y) * width + x;
+        let mut doors = Vec::new();
+        let mut keys = Vec::new();
+        let (mut start, mut exit) = (None, None);
+        let mut seen = vec![false; depth * fourth];
+
+        while let Some ((line, header)) = lines.next() {
+            let (w, z) = header.strip_prefix("w ").and_then(|rest| rest.split_once(" z "))
+                .and_then(|(w, z)| Some ((w.trim().parse::<usize>().ok()?, z.trim().parse::<usize>().ok()?)))
+                .filter(|&(w, z)| w < fourth && z < depth)
+                .ok_or((line, format!("Expected a level header inside the maze, eg. w 0 z 0, up to w {} z {}", fourth - 1, depth - 1)))?;
+            if std::mem::replace(&mut seen[w * depth + z], true) {
+                return Err ((line, format!("Level w {} z {} was already drawn", w, z)));
+            }
+            for y in 0..=height {
+                // Walls above the row
+                let (line, row) = lines.next().ok_or((line, "Expected the rest of the level".to_string()))?;
+                let row: Vec<char> = row.chars().collect();
+                if row.len() != 4 * width + 1 {
+                    return Err ((line, format!("Expected rows {} characters wide", 4 * width + 1)));
+                }
+                for x in 0..width {
+                    let wall = match &row[4 * x + 1..4 * x + 4] {
+                        [' ', ' ', ' '] => Wall::NoWall,
+                        ['-', '-', '-'] => Wall::SolidWall,
+                        ['-', key, '-'] if key.is_ascii_digit() => Wall::Door (key.to_digit(10).unwrap() as usize),
+                        _ => return Err ((line, "Expected walls between rows of three spaces, --- or a door like -0-".to_string()))
+                    };
+                    if y == 0 || y == height {
+                        world.walls.set(Axis::Y, (x, y, z, w), wall);
+                    } else {
+                        set_wall(&mut world, &mut doors, (x, y - 1, z, w), (x, y, z, w), wall);
+                    }
+                }
+                if y == height {
+                    break;
+                }
+
+                // The row of cells and the walls between them
+                let (line, row) = lines.next().ok_or((line, "Expected the rest of the level".to_string()))?;
+                let row: Vec<char> = row.chars().collect();
+                if row.len() != 4 * width + 1 {
+                    return Err ((line, format!("Expected rows {} characters wide", 4 * width + 1)));
+                }
+                for x in 0..=width {
+                    let wall = match row[4 * x] {
+                        ' ' => Wall::NoWall,
+                        '|' => Wall::SolidWall,
+                        key if key.is_ascii_digit() => Wall::Door (key.to_digit(10).unwrap() as usize),
+                        other => return Err ((line, format!("Expected a space, | or a door number between cells, not `{}'", other)))
+                    };
+                    if x == 0 || x == width {
+                        world.walls.set(Axis::X, (x, y, z, w), wall);
+                    } else {
+                        set_wall(&mut world, &mut doors, (x - 1, y, z, w), (x, y, z, w), wall);
+                    }
+                    if x == width {
+                        break;
+                    }
+                    let cell = (x, y, z, w);
+                    world.cells[w][z][y][x] = match row[4 * x + 1] {
+                        ' ' | '*' => Cell::Empty,
+                        'S' if start.replace(cell).is_some() => return Err ((line, "Expected only one start".to_string())),
+                        'E' if exit.replace(cell).is_some() => return Err ((line, "Expected only one exit".to_string())),
+                        'S' | 'E' => Cell::Empty,
+                        'G' => {
+                            world.ghost_spawns.push(cell);
+                            Cell::Empty
+                        },
+                        'o' => Cell::Food,
+                        key if key.is_ascii_digit() => {
+                            let key = key.to_digit(10).unwrap() as usize;
+                            keys.push(key);
+                            Cell::Key (key)
+                        },
+                        'B' => Cell::PowerUp (PowerUp::Boots),
+                        'C' => Cell::PowerUp (PowerUp::Clock),
+                        'b' => Cell::Battery,
+                        'T' => Cell::Teleporter (usize::MAX), // Linked below
+                        '^' => Cell::Trap (Trap::Spikes),
+                        '&' => Cell::Trap (Trap::Snare),
+                        'v' if z > 0 => Cell::Trap (Trap::Pit),
+                        'v' => return Err ((line, "Expected pits to have a level below to fall into".to_string())),
+                        other => return Err ((line, format!("Expected a cell's contents, not `{}'", other)))
+                    };
+                    let (vertical, slices) = (row[4 * x + 2], row[4 * x + 3]);
+                    if !matches!(vertical, ' ' | 'u' | 'd' | 'x') || !matches!(slices, ' ' | '<' | '>' | '=') {
+                        return Err ((line, "Expected ways up and down of u, d or x, then ways between slices of <, > or =".to_string()));
+                    }
+                    up[index(cell)] = matches!(vertical, 'u' | 'x');
+                    down[index(cell)] = matches!(vertical, 'd' | 'x');
+                    next[index(cell)] = matches!(slices, '>' | '=');
+                    previous[index(cell)] = matches!(slices, '<' | '=');
+                }
+            }
+        }
+        if let Some (missing) = seen.iter().position(|seen| !seen) {
+            return Err ((0, format!("Expected every level to be drawn, but w {} z {} is missing", missing / depth, missing % depth)));
+        }
+
+        // Join levels and slices where both sides agree
+        for w in 0..fourth {
+            for z in 0..depth {
+                for y in 0..height {
+                    for x in 0..width {
+                        let cell = (x, y, z, w);
+                        let joins = [
+                            (up[index(cell)], z + 1 < depth, (x, y, z + 1, w), "up", "down"),
+                            (next[index(cell)], w + 1 < fourth, (x, y, z, w + 1), "into the next slice", "back from the next slice")
+                        ];
+                        for (open, within, other, way, back) in joins {
+                            let marked_back = within && if other.2 != z { down[index(other)] } else { previous[index(other)] };
+                            if open != marked_back || (open && !within) {
+                                return Err ((0, format!("Expected the way {} from {:?} to be marked {} too", way, cell, back)));
+                            }
+                            if open {
+                                world.open_wall(cell, other);
+                            }
+                        }
+                        if (z == 0 && down[index(cell)]) || (w == 0 && previous[index(cell)]) {
+                            return Err ((0, format!("Expected no way out of the maze from {:?} except the exit", cell)));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (line, a, b) in teleporters {
+            let pads = [a, b].map(|[x, y, z, w]| (x, y, z, w));
+            if pads.iter().any(|&(x, y, z, w)| world.cells[w][z][y][x] != Cell::Teleporter (usize::MAX)) {
+                return Err ((line, "Expected both ends to be unlinked teleporter pads, marked T".to_string()));
+            }
+            world.link_teleporters(pads[0], pads[1]);
+        }
+        if let Some (pad) = world.cells.iter().flatten().flatten().flatten().position(|cell| *cell == Cell::Teleporter (usize::MAX)) {
+            return Err ((0, format!("Expected the teleporter pad {:?} to be linked to another", coordinate(pad, dimensions))));
+        }
+
+        // Every door needs its key, numbered from 0
+        doors.sort_unstable();
+        doors.dedup();
+        keys.sort_unstable();
+        if keys != doors || doors.iter().enumerate().any(|(i, key)| i != *key) {
+            return Err ((0, "Expected one key for each door, numbered from 0 up".to_string()));
+        }
+
+        world.start = start.ok_or((0, "Expected a start, marked S".to_string()))?;
+        world.exit = exit.ok_or((0, "Expected an exit, marked E".to_string()))?;
+        let (x, y, z, w) = world.exit;
+        world.exit_delta = [
+            (x == 0 && world.walls.get(Axis::X, (0, y, z, w)) == Wall::NoWall, [-1, 0, 0, 0]),
+            (x == width - 1 && world.walls.get(Axis::X, (width, y, z, w)) == Wall::NoWall, [1, 0, 0, 0]),
+            (y == 0 && world.walls.get(Axis::Y, (x, 0, z, w)) == Wall::NoWall, [0, -1, 0, 0]),
+            (y == height - 1 && world.walls.get(Axis::Y, (x, height, z, w)) == Wall::NoWall, [0, 1, 0, 0])
+        ].into_iter().find(|(open, _)| *open).map(|(_, delta)| delta)
+            .ok_or((0, "Expected a gap in the outer wall next to the exit".to_string()))?;
+
+        // Every door has its key somewhere, so any open way counts
+        if !world.regions().connected(&world.start, &world.exit) {
+            return Err ((0, "Expected the exit to be reachable from the start".to_string()));
+        }
+        world.solution = world.bfs(world.start, world.exit);
+        Ok (world)
+    }
+
+    /// Fit the settings to a hand-made maze: its size, and the food to eat before the exit opens
+    pub fn fit_config(&self, config: &mut Config) {
+        config.dimensions = [self.width, self.height, self.depth, self.fourth];
+        config.food_count = self.cells.iter().flatten().flatten().flatten().filter(|cell| **cell == Cell::Food).count();
+    }
+}
+
+// Wall between neighboring cells of a level, keeping track of door numbers
+fn set_wall(world: &mut World, doors: &mut Vec<usize>, a: Coordinate, b: Coordinate, wall: Wall) {
+    match wall {
+        Wall::SolidWall => {},
+        Wall::NoWall => world.open_wall(a, b),
+        Wall::Door (key) => {
+            world.open_wall(a, b);
+            world.lock_wall(a, b, key);
+            doors.push(key);
+        }
+    }
+}
+
+// Cell or size of the form 1x2x3x4
+fn parse_cell(text: &str) -> Option<[usize; 4]> {
+    let parts: Vec<usize> = text.trim().split('x').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    parts.try_into().ok()
+}
+
+// Coordinate of the cell at the index when iterating through every cell, slice by slice
+fn coordinate(index: usize, [width, height, depth, _]: [usize; 4]) -> Coordinate {
+    (index % width, index / width % height, index / (width * height) % depth, index / (width * height * depth))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Inventory;
+
+    use crate::game::Game;
+
+    // Two slices joined in the middle, with a door on the way
+    const LEVEL: &str = "
+size 3x2x1x2
+teleporter 2x0x0x0 0x1x0x1
+w 0 z 0
++---+---+---+
+|S  |o > T  |
++   +-0-+   +
+|0      |G  |
++---+---+---+
+
+w 1 z 0
++---+---+---+
+|o  |o <    |
++   +   +---+
+|T       E   
++---+---+---+
+";
+
+    #[test]
+    fn levels_read_walls_objects_and_links() {
+        let world = World::parse_level(LEVEL).unwrap();
+        assert_eq!([world.width, world.height, world.depth, world.fourth], [3, 2, 1, 2]);
+        assert_eq!(world.start, (0, 0, 0, 0));
+        assert_eq!(world.exit, (2, 1, 0, 1));
+        assert_eq!(world.exit_delta, [1, 0, 0, 0]);
+        assert_eq!(world.ghost_spawns, vec![(2, 1, 0, 0)]);
+        assert_eq!(world.doors, vec![((1, 0, 0, 0), (1, 1, 0, 0))]);
+        assert_eq!(world.cells[0][0][1][0], Cell::Key (0));
+        assert_eq!(world.teleport_target([2, 0, 0, 0]), Some ([0, 1, 0, 1]));
+        assert!(world.check_move([1, 0, 0, 0], [0, 0, 0, 1], &Inventory::new()));
+        assert!(!world.check_move([1, 0, 0, 0], [0, 1, 0, 0], &Inventory::new()));
+
+        let mut config = Config::default();
+        world.fit_config(&mut config);
+        assert_eq!(config.dimensions, [3, 2, 1, 2]);
+        assert_eq!(config.food_count, 3);
+    }
+
+    #[test]
+    fn levels_play_through_the_command_line() {
+        let path = std::env::temp_dir().join(format!("maze-level-{}.txt", std::process::id()));
+        fs::write(&path, LEVEL).unwrap();
+        let mut config = Config { level: Some (path.to_string_lossy().into_owned()), ..Default::default() };
+        let overridden = config.override_with(&[]);
+        let played = Game::new(&config).is_ok();
+        fs::remove_file(&path).unwrap();
+        overridden.unwrap();
+        assert!(played);
+        assert_eq!(config.dimensions, [3, 2, 1, 2]);
+        assert_eq!(config.food_count, 3);
+    }
+
+    #[test]
+    fn broken_levels_say_where() {
+        assert_eq!(World::parse_level(&LEVEL.replace("w 1 z 0", "w 1 z 1")).err().unwrap().0, 11); // No such level
+        assert_eq!(World::parse_level(&LEVEL.replace("|S  |o", "|S  |x")).err().unwrap().0, 6);
+        let one_sided = LEVEL.replace("o <", "o  ");
+        assert!(World::parse_level(&one_sided).err().unwrap().1.contains("back from the next slice"));
+        let no_key = LEVEL.replace("|0  ", "|   ");
+        assert!(World::parse_level(&no_key).err().unwrap().1.contains("one key for each door"));
+    }
+
+    #[test]
+    fn exported_mazes_read_back_the_same() {
+        let config = Config { dimensions: [4, 3, 2, 2], doors: 2, teleporters: 1, traps: 2, power_ups: 2, batteries: 1, ..Default::default() };
+        let game = Game::from_seed(&config, 7).unwrap();
+        let world = World::parse_level(&game.world.to_string()).unwrap();
+        assert_eq!(world.to_string(), game.world.to_string());
+        assert_eq!(world.solution.len(), game.world.solution.len());
+
+        // And play with the objects where the map put them
+        let mut config = config;
+        world.fit_config(&mut config);
+        let level = Game::from_world(&config, world, 7);
+        assert_eq!(level.objects.food().count(), config.food_count);
+        assert_eq!(level.objects.keys().count(), 2);
+    }
+}
@@ -0,0 +1,39 @@
+//! Game logic for 4D Pacman: maze generation, pathfinding and simulation of the player and ghost.
+//!
+//! Nothing here touches the GPU, so a game can be generated and played out headlessly.
+//! [`World`](world::World) holds the maze itself, while [`Player`](player::Player),
+//! [`Ghost`](ghost::Ghost) and [`Objects`](objects::Objects) are stepped forward by the caller with the current time.
+//! [`Game`](game::Game) bundles all of them into a single run with the movement rules applied,
+//! and [`GameSim`](sim::GameSim) plays one out on a simulated clock. [`Lockstep`](lockstep::Lockstep) steps a game in
+//! fixed ticks from the players' inputs alone, coming out the same on every machine.
+
+pub mod bot;
+pub mod campaign;
+pub mod chat;
+pub mod config;
+pub mod daily;
+pub mod decor;
+pub mod disjoint_set;
+pub mod error;
+pub mod feed;
+pub mod game;
+pub mod geometry;
+pub mod ghost;
+pub mod leaderboard;
+pub mod level;
+pub mod linalg;
+pub mod locale;
+pub mod lockstep;
+pub mod net;
+pub mod objects;
+pub mod pack;
+pub mod player;
+pub mod profile;
+pub mod replay;
+pub mod save;
+pub mod sim;
+pub mod spectate;
+pub mod stats;
+pub mod timing;
+pub mod watcher;
+pub mod world;
@@ -0,0 +1,260 @@
+//! Column-major 4x4 matrix helpers, stored as arrays of columns to match GLSL
+
+use std::cmp::Ordering;
+
+/// Swap rows and columns
+pub fn transpose(mat: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    [0, 1, 2, 3].map(|i| mat.map(|inner| inner[i]))
+}
+
+/// Matrix product a * b
+#[cfg(not(feature = "nalgebra"))]
+pub fn mul(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    mul_loops(a, b)
+}
+
+/// Matrix product a * b
+#[cfg(feature = "nalgebra")]
+pub fn mul(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    mul_nalgebra(a, b)
+}
+
+/// Matrix product a * b using nalgebra, which the compiler can vectorise
+#[cfg(feature = "nalgebra")]
+pub fn mul_nalgebra(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    // Both store matrices as arrays of columns, so they convert straight across
+    (nalgebra_glm::Mat4::from(a) * nalgebra_glm::Mat4::from(b)).into()
+}
+
+/// Matrix product a * b with plain loops
+pub fn mul_loops(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    // Dot rows of a with columns of b
+    // Array of columns
+    let mut prod = [[0.0; 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            // Dot column i of b with row j of a
+            let col = b[i];
+            let row = a.map(|col| col[j]);
+            for k in 0..4 {
+                prod[i][j] += col[k] * row[k];
+            }
+        }
+    }
+    prod
+}
+
+/// Matrix times column vector
+pub fn transform(mat: [[f32; 4]; 4], v: [f32; 4]) -> [f32; 4] {
+    [0, 1, 2, 3].map(|row| (0..4).map(|col| mat[col][row] * v[col]).sum())
+}
+
+/// Inverse matrix, or None if it has none
+pub fn inverse(mat: [[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+    // Gauss-Jordan elimination with partial pivoting. Eliminating columns rather than rows works just as well,
+    // as the inverse of the transpose is the transpose of the inverse
+    let mut mat = mat;
+    let mut inv = identity();
+    for i in 0..4 {
+        let pivot = (i..4).max_by(|&j, &k| mat[j][i].abs().partial_cmp(&mat[k][i].abs()).unwrap_or(Ordering::Equal)).unwrap();
+        if mat[pivot][i] == 0.0 {
+            return None; // Singular
+        }
+        mat.swap(i, pivot);
+        inv.swap(i, pivot);
+        let scale = 1.0 / mat[i][i];
+        mat[i] = mat[i].map(|x| x * scale);
+        inv[i] = inv[i].map(|x| x * scale);
+        for j in (0..4).filter(|&j| j != i) {
+            let factor = mat[j][i];
+            for k in 0..4 {
+                mat[j][k] -= factor * mat[i][k];
+                inv[j][k] -= factor * inv[i][k];
+            }
+        }
+    }
+    Some (inv)
+}
+
+/// Matrix turning normals the same way the model matrix turns the surfaces they belong to: the inverse transpose of its
+/// rotation and scale. Scaling a surface unevenly tilts its normals the opposite way to its points
+pub fn normal_matrix(model: [[f32; 4]; 4]) -> [[f32; 3]; 3] {
+    let inv = inverse(model).unwrap_or_else(identity);
+    [0, 1, 2].map(|col| [inv[0][col], inv[1][col], inv[2][col]])
+}
+
+/// World space point seen at the given point in normalized device coordinates (x and y from -1 to 1, z its depth)
+/// through the view-projection matrix, eg. to find what's under the mouse
+pub fn unproject(ndc: [f32; 3], view_projection: [[f32; 4]; 4]) -> Option<[f32; 3]> {
+    let [x, y, z, w] = transform(inverse(view_projection)?, [ndc[0], ndc[1], ndc[2], 1.0]);
+    Some ([x / w, y / w, z / w])
+}
+
+/// Component-wise sum of two 3D vectors
+pub fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    let mut sum = a;
+    for i in 0..3 {
+        sum[i] += b[i];
+    }
+    sum
+}
+
+/// Component-wise difference of two 4D vectors
+pub fn sub(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    let mut diff = a;
+    for i in 0..4 {
+        diff[i] -= b[i];
+    }
+    diff
+}
+
+/// Translation matrix
+pub fn translate(translation: [f32; 3]) -> [[f32; 4]; 4] {
+    transpose([
+        [1.0, 0.0, 0.0, translation[0]],
+        [0.0, 1.0, 0.0, translation[1]],
+        [0.0, 0.0, 1.0, translation[2]],
+        [0.0, 0.0, 0.0, 1.0]
+    ])
+}
+
+/// Rotation matrix from Euler angles in radians, applied x then y then z
+pub fn rotate(rotation: [f32; 3]) -> [[f32; 4]; 4] {
+    let t = rotation[0];
+    let rot_x = transpose([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, t.cos(), -t.sin(), 0.0],
+        [0.0, t.sin(), t.cos(), 0.0],
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+    let t = rotation[1];
+    let rot_y = transpose([
+        [t.cos(), 0.0, t.sin(), 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [-t.sin(), 0.0, t.cos(), 0.0],
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+    let t = rotation[2];
+    let rot_z = transpose([
+        [t.cos(), -t.sin(), 0.0, 0.0],
+        [t.sin(), t.cos(), 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+    mul(rot_z, mul(rot_y, rot_x))
+}
+
+/// Model matrix: rotate, then scale and translate
+pub fn model(rotation: [f32; 3], scale: [f32; 3], translation: [f32; 3]) -> [[f32; 4]; 4] {
+    let trans_scale = transpose([
+        [scale[0], 0.0, 0.0, translation[0]],
+        [0.0, scale[1], 0.0, translation[1]],
+        [0.0, 0.0, scale[2], translation[2]],
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+    mul(trans_scale, rotate(rotation))
+}
+
+/// View matrix: translate and scale, then rotate
+pub fn view(rotation: [f32; 3], scale: [f32; 3], translation: [f32; 3]) -> [[f32; 4]; 4] {
+    let trans_scale = transpose([
+        [1.0, 0.0, 0.0, translation[0]].map(|x| x * scale[0]),
+        [0.0, 1.0, 0.0, translation[1]].map(|x| x * scale[1]),
+        [0.0, 0.0, 1.0, translation[2]].map(|x| x * scale[2]),
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+    mul(rotate(rotation), trans_scale)
+}
+
+/// Perspective projection with the given focal length (1 / tan(fov / 2))
+pub fn projection(near: f32, far: f32, focal: f32, aspect: f32) -> [[f32; 4]; 4] {
+    transpose([
+        [focal / aspect, 0.0,   0.0,                         0.0],
+        [0.0,            focal, 0.0,                         0.0],
+        [0.0,            0.0,   (near + far) / (near - far), (2.0 * near * far) / (near - far)],
+        [0.0,            0.0,   -1.0,                        0.0]
+    ])
+}
+
+/// Orthographic projection showing half_height units either side of the centre vertically
+pub fn orthographic(near: f32, far: f32, half_height: f32, aspect: f32) -> [[f32; 4]; 4] {
+    transpose([
+        [1.0 / (half_height * aspect), 0.0,               0.0,                0.0],
+        [0.0,                          1.0 / half_height, 0.0,                0.0],
+        [0.0,                          0.0,               2.0 / (near - far), (near + far) / (near - far)],
+        [0.0,                          0.0,               0.0,                1.0]
+    ])
+}
+
+pub fn identity() -> [[f32; 4]; 4] {
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0]
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) {
+        for (col_a, col_b) in a.iter().zip(&b) {
+            for (x, y) in col_a.iter().zip(col_b) {
+                assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        assert_near(inverse(translate([1.0, -2.0, 3.0])).unwrap(), translate([-1.0, 2.0, -3.0]));
+        let m = model([0.3, -1.2, 2.0], [2.0, 0.5, 1.5], [4.0, 5.0, -6.0]);
+        assert_near(mul(m, inverse(m).unwrap()), identity());
+        assert_near(mul(inverse(m).unwrap(), m), identity());
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_multiplies_the_same() {
+        let a = model([0.3, -1.2, 2.0], [2.0, 0.5, 1.5], [4.0, 5.0, -6.0]);
+        let b = projection(0.1, 100.0, 1.2, 1.5);
+        assert_near(mul_nalgebra(a, b), mul_loops(a, b));
+        assert_near(mul_nalgebra(b, a), mul_loops(b, a));
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(inverse(model([0.0; 3], [1.0, 0.0, 1.0], [0.0; 3])), None);
+    }
+
+    #[test]
+    fn normals_scale_inversely() {
+        let expected = [[0.5, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 2.0]];
+        let n = normal_matrix(model([0.0; 3], [2.0, 1.0, 0.5], [7.0, 8.0, 9.0]));
+        for (col, expected_col) in n.iter().zip(&expected) {
+            for (x, y) in col.iter().zip(expected_col) {
+                assert!((x - y).abs() < 1e-5, "{:?}", n);
+            }
+        }
+        // A plain rotation turns normals the same way it turns everything else
+        let r = rotate([0.4, 0.0, 1.1]);
+        let n = normal_matrix(r);
+        for col in 0..3 {
+            for row in 0..3 {
+                assert!((n[col][row] - r[col][row]).abs() < 1e-5, "{:?}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn unproject_finds_the_projected_point() {
+        let vp = mul(projection(0.1, 100.0, 1.0, 16.0 / 9.0), view([0.5, 0.0, 0.0], [1.0; 3], [-1.0, 2.0, -4.0]));
+        let [x, y, z, w] = transform(vp, [1.5, -0.5, 0.25, 1.0]);
+        let point = unproject([x / w, y / w, z / w], vp).unwrap();
+        for (a, b) in point.iter().zip([1.5, -0.5, 0.25]) {
+            assert!((a - b).abs() < 1e-3, "{:?}", point);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Text shown to the player, looked up by key in their language, with English filling in anything left untranslated.
+//!
+//! Each language is a TOML file of keys and strings named after its code, eg. `locale/fr.toml` in the resource
+//! directory. The languages shipped with the game are also built in, and a file in the resource directory takes the
+//! place of the built in one of the same language.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+// Languages shipped with the game, English first as the one everything falls back to
+const BUILT_IN: [(&str, &str); 3] = [
+    ("en", include_str!("../res/locale/en.toml")),
+    ("fr", include_str!("../res/locale/fr.toml")),
+    ("de", include_str!("../res/locale/de.toml"))
+];
+
+/// A piece of text to show, by its key and the values filling in its placeholders
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub key: &'static str,
+    pub args: Vec<(&'static str, String)> // Placeholder name, without braces, and its value
+}
+
+impl Message {
+    pub fn new(key: &'static str) -> Message {
+        Message { key, args: Vec::new() }
+    }
+
+    /// Fill in the placeholder of the given name
+    pub fn with(mut self, name: &'static str, value: impl ToString) -> Message {
+        self.args.push((name, value.to_string()));
+        self
+    }
+}
+
+/// Strings in one language, with English behind them
+pub struct Locale {
+    pub language: String,
+    strings: HashMap<String, String>,
+    english: HashMap<String, String>
+}
+
+impl Locale {
+    /// The built in English text
+    pub fn english() -> Locale {
+        Locale { language: "en".to_string(), strings: HashMap::new(), english: parse("en", BUILT_IN[0].1) }
+    }
+
+    /// Text in the given language, or the system's for "auto", from the resource directory or else built in. Falls
+    /// back to English for a language there's no text for
+    pub fn load(resource_path: &str, language: &str) -> Locale {
+        let language = if language == "auto" { system_language().unwrap_or_else(|| "en".to_string()) } else { language.to_string() };
+        let read = |code: &str| {
+            let file = Path::new(resource_path).join("locale").join(format!("{}.toml", code));
+            fs::read_to_string(file).ok()
+                .or_else(|| BUILT_IN.iter().find(|(built_in, _)| *built_in == code).map(|(_, text)| text.to_string()))
+                .map(|text| parse(code, &text))
+        };
+        let english = read("en").unwrap_or_default();
+        let strings = if language == "en" {
+            HashMap::new()
+        } else {
+            read(&language).unwrap_or_else(|| {
+                warn!(target: "render", "No text in language `{}', using English", language);
+                HashMap::new()
+            })
+        };
+        Locale { language, strings, english }
+    }
+
+    /// Text of the message in this language, with its placeholders filled in. A key missing from every language comes
+    /// out as itself, so it's obvious on screen
+    pub fn text(&self, message: &Message) -> String {
+        let mut text = self.strings.get(message.key).or_else(|| self.english.get(message.key)).cloned().unwrap_or_else(|| message.key.to_string());
+        for (name, value) in &message.args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+// Keys and strings from a language's file, or none if it doesn't read as one
+fn parse(language: &str, text: &str) -> HashMap<String, String> {
+    toml::from_str(text).unwrap_or_else(|e| {
+        warn!(target: "render", "Couldn't read text in language `{}': {}", language, e);
+        HashMap::new()
+    })
+}
+
+/// Language the system is set to, as a code like "fr", from the usual locale environment variables
+pub fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| language_code(&value))
+}
+
+// Language part of a locale like fr_FR.UTF-8, or None for the C and POSIX locales that don't name one
+fn language_code(locale: &str) -> Option<String> {
+    let code = locale.split(['_', '-', '.', '@']).next().unwrap_or("").to_lowercase();
+    if code.is_empty() || code == "c" || code == "posix" { None } else { Some (code) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let text = Locale::english().text(&Message::new("food-eaten").with("eaten", 4).with("total", 10));
+        assert_eq!(text, "Food eaten 4/10");
+    }
+
+    #[test]
+    fn missing_text_falls_back_to_english() {
+        let mut locale = Locale::load("nowhere", "fr");
+        assert_eq!(locale.text(&Message::new("escaped")), "Évadé");
+        locale.strings.remove("escaped");
+        assert_eq!(locale.text(&Message::new("escaped")), "Escaped");
+        assert_eq!(Locale::load("nowhere", "xx").text(&Message::new("escaped")), "Escaped");
+        assert_eq!(locale.text(&Message::new("no-such-key")), "no-such-key");
+    }
+
+    #[test]
+    fn every_built_in_language_has_every_key() {
+        let english = parse("en", BUILT_IN[0].1);
+        for (language, text) in BUILT_IN {
+            let strings = parse(language, text);
+            let mut missing: Vec<&String> = english.keys().filter(|key| !strings.contains_key(*key)).collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} is missing {:?}", language, missing);
+        }
+    }
+
+    #[test]
+    fn system_locales_name_their_language() {
+        assert_eq!(language_code("fr_FR.UTF-8").as_deref(), Some ("fr"));
+        assert_eq!(language_code("de").as_deref(), Some ("de"));
+        assert_eq!(language_code("en-GB").as_deref(), Some ("en"));
+        assert_eq!(language_code("C.UTF-8"), None);
+        assert_eq!(language_code("POSIX"), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::game::Game;
+use crate::net::Connection;
+
+/// Ticks a second in a lockstep game
+pub const TICK_RATE: u32 = 60;
+
+/// What the players asked for during one tick. Moves the rules don't allow are dropped, the same way on every machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inputs {
+    pub player: Option<[i32; 4]>,
+    pub rival: Option<[i32; 4]>, // Second player racing in the same maze
+    pub ghost: Option<[i32; 4]>, // Second player moving a ghost in co-op
+    pub steer: Option<[i32; 2]>, // Way the held keys point in x and y, in free movement
+    pub rival_steer: Option<[i32; 2]>,
+    pub flashlight: bool // Switched on or off
+}
+
+/// Advances a game a tick at a time from the players' inputs alone, so two machines stepping the same seed with the
+/// same inputs see the same ghost paths. The windowed game gathers its inputs between ticks and steps through every
+/// tick that's due each frame. Moves then land on whole ticks, so the replay recorded from them does too, and an
+/// online race hears from the other player on the same ticks.
+///
+/// Every moment the game sees is a whole number of ticks after the epoch, so each duration the rules measure is an
+/// exact number of nanoseconds whatever the real clock says. The float math on top is plain IEEE arithmetic done in a
+/// fixed order, over objects kept in a fixed order, so it rounds the same way everywhere
+pub struct Lockstep {
+    epoch: Instant,
+    tick: u64,
+    move_time: f32 // Seconds each move from one cell to the next takes
+}
+
+impl Lockstep {
+    /// Step the game on from the epoch, which should be no earlier than the game was made. That way any timer it set
+    /// going has already run out by the first tick, and an update at the epoch brings the rest, like the
+    /// flashlight's, onto the tick clock before any inputs
+    pub fn new(game: &mut Game, config: &Config, epoch: Instant, move_time: f32) -> Lockstep {
+        game.update(epoch, config);
+        Lockstep { epoch, tick: 0, move_time }
+    }
+
+    /// Ticks stepped so far
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Game time at the given tick
+    pub fn time(&self, tick: u64) -> Instant {
+        self.epoch + Duration::from_nanos(tick * 1_000_000_000 / TICK_RATE as u64)
+    }
+
+    /// Whether the next tick is due by the given game time
+    pub fn due(&self, now: Instant) -> bool {
+        self.time(self.tick + 1) <= now
+    }
+
+    /// Apply the current tick's inputs, then advance the game to the next tick, trading progress with the other
+    /// player in an online race once it's there
+    pub fn step(&mut self, game: &mut Game, config: &Config, inputs: &Inputs, connection: Option<&mut Connection>) {
+        let now = self.time(self.tick);
+        if let Some (delta) = inputs.player {
+            game.try_move(now, delta, self.move_time);
+        }
+        if let Some (delta) = inputs.rival {
+            game.try_move_rival(now, delta, self.move_time);
+        }
+        if let Some (delta) = inputs.ghost {
+            game.try_move_ghost(now, delta);
+        }
+        if let Some (heading) = inputs.steer {
+            game.steer(now, heading.map(|i| i as f32));
+        }
+        if let Some (heading) = inputs.rival_steer {
+            game.steer_rival(now, heading.map(|i| i as f32));
+        }
+        if inputs.flashlight {
+            game.player.flashlight.toggle();
+        }
+        self.tick += 1;
+        let now = self.time(self.tick);
+        game.update(now, config);
+        if let Some (connection) = connection {
+            connection.update(now, &mut game.player);
+        }
+    }
+}
+
+/// Fingerprint of where everyone is, their scores and the food left, for machines playing the same game to compare
+/// and catch any drift between them. FNV-1a over the exact bits of every position
+pub fn checksum(game: &Game) -> u64 {
+    let mut words = Vec::new();
+    for player in std::iter::once(&game.player).chain(&game.rival) {
+        words.extend(player.get_position().map(f32::to_bits));
+        words.extend([player.score, player.lives]);
+    }
+    for ghost in &game.ghosts {
+        words.extend(ghost.position().map(f32::to_bits));
+    }
+    for &(x, y, z, w) in game.objects.food() {
+        words.extend([x, y, z, w].map(|i| i as u32));
+    }
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Bot;
+
+    #[test]
+    fn same_inputs_play_out_the_same() {
+        let config = Config { dimensions: [5, 5, 2, 2], food_count: 5, ghosts: 2, ghost_move_time: 0.4, shift_interval: 3.0, ..Default::default() };
+        let mut first_game = Game::from_seed(&config, 7).unwrap();
+        let mut first = Lockstep::new(&mut first_game, &config, Instant::now(), 0.3);
+        let mut played = Vec::new();
+        for tick in 0..TICK_RATE as u64 * 20 {
+            let player = if tick % 20 == 0 {
+                Bot.next_move(&first_game.world, &first_game.objects, &first_game.player.inventory, first_game.player.cell())
+            } else {
+                None
+            };
+            let inputs = Inputs { player, ..Default::default() };
+            first.step(&mut first_game, &config, &inputs, None);
+            played.push((inputs, checksum(&first_game)));
+        }
+        // Starting later on the real clock changes nothing
+        std::thread::sleep(Duration::from_millis(5));
+        let mut second_game = Game::from_seed(&config, 7).unwrap();
+        let mut second = Lockstep::new(&mut second_game, &config, Instant::now(), 0.3);
+        for (tick, (inputs, expected)) in played.iter().enumerate() {
+            second.step(&mut second_game, &config, inputs, None);
+            assert_eq!(checksum(&second_game), *expected, "Games drifted apart at tick {}", tick);
+        }
+        assert_eq!(second_game.world.to_string(), first_game.world.to_string());
+    }
+
+    #[test]
+    fn ticks_fall_due_on_game_time() {
+        let config = Config { dimensions: [5, 5, 1, 1], food_count: 2, ..Default::default() };
+        let mut game = Game::from_seed(&config, 3).unwrap();
+        let epoch = Instant::now();
+        let mut lockstep = Lockstep::new(&mut game, &config, epoch, 0.3);
+        assert!(!lockstep.due(epoch));
+        let later = epoch + Duration::from_millis(50);
+        let mut stepped = 0;
+        while lockstep.due(later) {
+            lockstep.step(&mut game, &config, &Inputs::default(), None);
+            stepped += 1;
+        }
+        assert_eq!(stepped, 3);
+        assert!(lockstep.time(lockstep.tick()) <= later);
+    }
+}
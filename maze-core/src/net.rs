@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use log::{debug, info, warn};
+use rand::random;
+
+use crate::config::Config;
+use crate::error::{MazeError, Result};
+use crate::player::{GameState, Player};
+
+/// Port an online race is hosted on unless another is given
+pub const DEFAULT_PORT: u16 = 47_474;
+
+const NET_TICK: f32 = 0.05; // Seconds between updates sent to the other player, however fast frames are drawn
+const INTERPOLATION_DELAY: f32 = 0.1; // Seconds behind the latest update the opponent is drawn, to have two updates to move between
+const SAMPLES: usize = 16; // Updates kept for drawing the opponent between
+const HELLO_INTERVAL: f32 = 0.5; // Seconds between attempts to reach the host while joining
+const JOIN_TIMEOUT: f32 = 10.0; // Seconds to keep trying to reach the host
+const HOST_TIMEOUT: f32 = 300.0; // Seconds to wait for someone to join
+const SILENCE_TIMEOUT: f32 = 5.0; // Seconds without hearing from the opponent before they count as gone
+const MAX_MESSAGE: usize = 2048;
+
+// Settings that shape the maze and the rules, which the host hands to whoever joins so both play the same game
+const SHARED: [&str; 28] = [
+    "dimensions", "mode", "movement", "display-clock", "ghost-move-time", "ghosts", "food-count", "food-placement", "doors",
+    "teleporters", "ghost-teleports", "traps", "power-ups", "power-up-time", "flashlight-time", "batteries", "fruit-interval",
+    "fruit-time", "fruit-points", "combo-time", "combo-max", "shift-interval", "lives", "respawn", "start", "exit",
+    "min-solution", "min-w-moves"
+];
+
+/// How far along the opponent is in the race
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Standing {
+    Racing, Escaped, Out
+}
+
+impl Standing {
+    fn of(player: &Player) -> Standing {
+        match player.game_state {
+            GameState::Won | GameState::Reviewing => Standing::Escaped,
+            _ if player.escaping() => Standing::Escaped,
+            GameState::Lost => Standing::Out,
+            GameState::Playing | GameState::Respawning => Standing::Racing
+        }
+    }
+}
+
+// Everything sent between the two players, as one line of text per datagram, or a few for the settings
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    Hello { name: String }, // Asking to join
+    Welcome { seed: u64, name: String, settings: Vec<(String, String)> }, // The host's answer, with the game to play
+    Update { tick: u32, position: [f32; 4], score: u32, standing: Standing }
+}
+
+impl Message {
+    fn encode(&self) -> String {
+        match self {
+            Message::Hello { name } => format!("hello {}", name),
+            Message::Welcome { seed, name, settings } => {
+                let lines: Vec<String> = settings.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+                format!("welcome {} {}\n{}", seed, name, lines.join("\n"))
+            },
+            Message::Update { tick, position: [x, y, z, w], score, standing } => {
+                let standing = match standing { Standing::Racing => "racing", Standing::Escaped => "escaped", Standing::Out => "out" };
+                format!("update {} {} {} {} {} {} {}", tick, x, y, z, w, score, standing)
+            }
+        }
+    }
+
+    fn decode(text: &str) -> Option<Message> {
+        let mut lines = text.lines();
+        let first = lines.next()?;
+        let (kind, rest) = first.split_once(' ').unwrap_or((first, ""));
+        match kind {
+            "hello" => Some (Message::Hello { name: rest.to_string() }),
+            "welcome" => {
+                let (seed, name) = rest.split_once(' ').unwrap_or((rest, ""));
+                let settings = lines.filter_map(|line| line.split_once('=')).map(|(key, value)| (key.to_string(), value.to_string())).collect();
+                Some (Message::Welcome { seed: seed.parse().ok()?, name: name.to_string(), settings })
+            },
+            "update" => {
+                let parts: Vec<&str> = rest.split_whitespace().collect();
+                if parts.len() != 7 {
+                    return None;
+                }
+                let mut position = [0.0; 4];
+                for i in 0..4 {
+                    position[i] = parts[i + 1].parse().ok()?;
+                }
+                let standing = match parts[6] { "racing" => Standing::Racing, "escaped" => Standing::Escaped, "out" => Standing::Out, _ => return None };
+                Some (Message::Update { tick: parts[0].parse().ok()?, position, score: parts[5].parse().ok()?, standing })
+            },
+            _ => None
+        }
+    }
+}
+
+/// The other player in an online race, as of their latest update, drawn a little behind it so they move smoothly between updates
+pub struct Opponent {
+    pub name: String,
+    pub score: u32,
+    pub standing: Standing,
+    samples: VecDeque<(Instant, [f32; 4])>, // Positions, and when they arrived
+    last_tick: Option<u32>, // Updates can arrive out of order, so older ones than this are dropped
+    heard: Instant
+}
+
+impl Opponent {
+    fn new(name: String, now: Instant) -> Opponent {
+        Opponent { name, score: 0, standing: Standing::Racing, samples: VecDeque::new(), last_tick: None, heard: now }
+    }
+
+    /// Where to draw the opponent, between the two updates either side of a moment just behind now,
+    /// or None before the first update arrives
+    pub fn position(&self, now: Instant) -> Option<[f32; 4]> {
+        let shown = now.checked_sub(Duration::from_secs_f32(INTERPOLATION_DELAY)).unwrap_or(now);
+        let after = self.samples.iter().position(|(at, _)| *at > shown);
+        match after {
+            Some (0) => self.samples.front().map(|(_, position)| *position),
+            Some (i) => {
+                let ((from_at, from), (to_at, to)) = (self.samples[i - 1], self.samples[i]);
+                let t = shown.saturating_duration_since(from_at).as_secs_f32() / to_at.saturating_duration_since(from_at).as_secs_f32().max(f32::EPSILON);
+                Some ([0, 1, 2, 3].map(|i| from[i] + (to[i] - from[i]) * t.clamp(0.0, 1.0)))
+            },
+            None => self.samples.back().map(|(_, position)| *position)
+        }
+    }
+
+    /// Whether nothing has been heard from the opponent for long enough to give up on them
+    pub fn gone(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.heard).as_secs_f32() > SILENCE_TIMEOUT
+    }
+
+    fn hear(&mut self, now: Instant, tick: u32, position: [f32; 4], score: u32, standing: Standing) {
+        self.heard = now;
+        if self.last_tick.is_some_and(|last| tick <= last) {
+            return;
+        }
+        self.last_tick = Some (tick);
+        self.score = score;
+        self.standing = standing;
+        self.samples.push_back((now, position));
+        if self.samples.len() > SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Waits for someone to join an online race
+pub struct Lobby {
+    socket: UdpSocket
+}
+
+impl Lobby {
+    /// Listen for a player to join on the given port, or any free one for 0
+    pub fn open(port: u16) -> Result<Lobby> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|e| network(format!("Couldn't listen on port {}: {}", port, e)))?;
+        Ok (Lobby { socket })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.socket.local_addr().map_or(0, |address| address.port())
+    }
+
+    /// Wait for a player to ask to join, then send them the seed and the settings to play with.
+    /// Without a seed in the config, a random one is chosen and kept in it for both to play
+    pub fn wait(self, config: &mut Config) -> Result<Connection> {
+        if config.level.is_some() {
+            return Err (network("Online races are only in generated mazes, since the other player may not have the level file"));
+        }
+        let seed = *config.seed.get_or_insert_with(random);
+        let started = Instant::now();
+        self.socket.set_read_timeout(Some (Duration::from_secs_f32(HELLO_INTERVAL))).map_err(|e| network(e.to_string()))?;
+        loop {
+            if started.elapsed().as_secs_f32() > HOST_TIMEOUT {
+                return Err (network("Nobody joined in time"));
+            }
+            if let Some ((Message::Hello { name }, peer)) = receive(&self.socket)? {
+                info!(target: "net", "{} joined from {}", name, peer);
+                let settings = config.settings().into_iter()
+                    .filter(|(key, _)| SHARED.contains(key))
+                    .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+                    .collect();
+                let welcome = Message::Welcome { seed, name: config.player_name.clone(), settings };
+                send(&self.socket, peer, &welcome)?;
+                let mut connection = Connection::new(self.socket, peer, name)?;
+                connection.welcome = Some (welcome);
+                return Ok (connection);
+            }
+        }
+    }
+}
+
+/// Link to the other player in an online race, trading updates on a fixed tick
+pub struct Connection {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    tick: u32,
+    next_send: Option<Instant>,
+    opponent: Opponent,
+    welcome: Option<Message> // The host's answer, sent again if the other player is still asking to join
+}
+
+impl Connection {
+    fn new(socket: UdpSocket, peer: SocketAddr, name: String) -> Result<Connection> {
+        socket.set_nonblocking(true).map_err(|e| network(e.to_string()))?;
+        Ok (Connection { socket, peer, tick: 0, next_send: None, opponent: Opponent::new(name, Instant::now()), welcome: None })
+    }
+
+    /// Ask the host at the address to join their race, taking on their settings and the seed of their maze
+    pub fn join(address: &str, config: &mut Config) -> Result<Connection> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| network(e.to_string()))?;
+        let address = if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) };
+        let peer = address.to_socket_addrs().ok().and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| network(format!("Couldn't find {}", address)))?;
+        socket.set_read_timeout(Some (Duration::from_secs_f32(HELLO_INTERVAL))).map_err(|e| network(e.to_string()))?;
+        let started = Instant::now();
+        while started.elapsed().as_secs_f32() < JOIN_TIMEOUT {
+            send(&socket, peer, &Message::Hello { name: config.player_name.clone() })?;
+            if let Some ((Message::Welcome { seed, name, settings }, _)) = receive(&socket)? {
+                let settings: Vec<(&str, String)> = settings.iter().map(|(key, value)| (key.as_str(), value.clone())).collect();
+                config.level = None; // The host's maze is raced, never a level file of this player's
+                config.override_with(&settings)?;
+                config.seed = Some (seed);
+                info!(target: "net", "Joined {}'s race", name);
+                return Connection::new(socket, peer, name);
+            }
+        }
+        Err (network(format!("No answer from {}", address)))
+    }
+
+    pub fn opponent(&self) -> &Opponent {
+        &self.opponent
+    }
+
+    /// Send the player's progress once each tick is due and take in the opponent's, whatever the frame rate.
+    /// Once the opponent has escaped, the race is lost
+    pub fn update(&mut self, now: Instant, player: &mut Player) {
+        let next_send = self.next_send.get_or_insert(now);
+        if now >= *next_send {
+            *next_send = now + Duration::from_secs_f32(NET_TICK);
+            self.tick += 1;
+            let update = Message::Update { tick: self.tick, position: player.get_position(), score: player.score, standing: Standing::of(player) };
+            if let Err (e) = send(&self.socket, self.peer, &update) {
+                debug!(target: "net", "{}", e);
+            }
+        }
+        loop {
+            match receive(&self.socket) {
+                Ok (Some ((message, peer))) if peer == self.peer => match message {
+                    Message::Update { tick, position, score, standing } => self.opponent.hear(now, tick, position, score, standing),
+                    Message::Hello { .. } => {
+                        // Our welcome went missing, so send it again
+                        if let Some (welcome) = &self.welcome {
+                            let _ = send(&self.socket, self.peer, welcome);
+                        }
+                    },
+                    Message::Welcome { .. } => {}
+                },
+                Ok (Some (_)) => {},
+                Ok (None) => break,
+                Err (e) => {
+                    warn!(target: "net", "{}", e);
+                    break;
+                }
+            }
+        }
+        if self.opponent.standing == Standing::Escaped && Standing::of(player) == Standing::Racing {
+            info!(target: "net", "{} escaped first", self.opponent.name);
+            player.game_state = GameState::Lost;
+        }
+    }
+}
+
+fn network(reason: impl Into<String>) -> MazeError {
+    MazeError::Network (reason.into())
+}
+
+fn send(socket: &UdpSocket, peer: SocketAddr, message: &Message) -> Result<()> {
+    socket.send_to(message.encode().as_bytes(), peer).map(|_| ()).map_err(|e| network(format!("Couldn't send to {}: {}", peer, e)))
+}
+
+// Next message waiting, or None once there are none or the wait runs out. Anything unreadable is skipped
+fn receive(socket: &UdpSocket) -> Result<Option<(Message, SocketAddr)>> {
+    let mut buffer = [0; MAX_MESSAGE];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok ((length, peer)) => match std::str::from_utf8(&buffer[..length]).ok().and_then(Message::decode) {
+                Some (message) => return Ok (Some ((message, peer))),
+                None => debug!(target: "net", "Ignored a message from {} that couldn't be read", peer)
+            },
+            Err (e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => return Ok (None),
+            // Windows reports a peer that isn't listening yet as an error on the next receive
+            Err (e) if e.kind() == io::ErrorKind::ConnectionReset => return Ok (None),
+            Err (e) => return Err (network(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn messages_read_back_the_same() {
+        let messages = [
+            Message::Hello { name: "Player Two".to_string() },
+            Message::Welcome { seed: 42, name: "Player".to_string(), settings: vec![("dimensions".to_string(), "5x5x5x3".to_string())] },
+            Message::Update { tick: 7, position: [1.5, 2.0, 0.25, 1.0], score: 3, standing: Standing::Escaped }
+        ];
+        for message in messages {
+            assert_eq!(Message::decode(&message.encode()), Some (message));
+        }
+        assert_eq!(Message::decode("update 1 2 3"), None);
+    }
+
+    #[test]
+    fn opponents_move_smoothly_between_updates() {
+        let start = Instant::now();
+        let mut opponent = Opponent::new("Player Two".to_string(), start);
+        assert_eq!(opponent.position(start), None);
+        opponent.hear(start, 1, [0.0, 0.0, 0.0, 0.0], 0, Standing::Racing);
+        opponent.hear(start + Duration::from_secs_f32(0.2), 2, [2.0, 0.0, 0.0, 0.0], 0, Standing::Racing);
+        opponent.hear(start + Duration::from_secs_f32(0.3), 1, [9.0, 0.0, 0.0, 0.0], 0, Standing::Racing);
+        let halfway = start + Duration::from_secs_f32(0.1 + INTERPOLATION_DELAY);
+        assert!((opponent.position(halfway).unwrap()[0] - 1.0).abs() < 0.01);
+        assert_eq!(opponent.position(start + Duration::from_secs(1)), Some ([2.0, 0.0, 0.0, 0.0]));
+        assert!(opponent.gone(start + Duration::from_secs_f32(SILENCE_TIMEOUT + 1.0)));
+    }
+
+    #[test]
+    fn joining_takes_on_the_hosts_maze() {
+        let lobby = Lobby::open(0).unwrap();
+        let address = format!("127.0.0.1:{}", lobby.port());
+        let mut host = Config { dimensions: [4, 4, 3, 2], food_count: 3, seed: Some (99), ..Default::default() };
+        let joiner = thread::spawn(move || {
+            let mut config = Config { level: Some ("own level.txt".to_string()), ..Default::default() };
+            Connection::join(&address, &mut config).unwrap();
+            config
+        });
+        let connection = lobby.wait(&mut host).unwrap();
+        let config = joiner.join().unwrap();
+        assert_eq!((config.seed, config.level), (Some (99), None));
+        assert_eq!((config.dimensions, config.food_count), (host.dimensions, host.food_count));
+        assert_eq!(connection.opponent().name, config.player_name);
+    }
+}
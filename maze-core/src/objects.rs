@@ -0,0 +1,409 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use log::debug;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Cell, Coordinate, World};
+use crate::config::{Config, FoodPlacement};
+
+/// Seconds eaten food takes to shrink away
+pub const EAT_TIME: f32 = 0.4;
+
+/// Items placed around the maze for the player to collect. Kept in order of their cells, so they're gone through
+/// the same way on every machine
+pub struct Objects {
+    food: BTreeSet<Coordinate>,
+    keys: BTreeMap<Coordinate, usize>, // Cell, and the door it opens
+    power_ups: BTreeMap<Coordinate, PowerUp>,
+    batteries: BTreeSet<Coordinate>,
+    fruit: Option<(Coordinate, Instant)>, // Cell of the bonus fruit, and when it vanishes
+    next_fruit: Option<Instant>,
+    eaten: Vec<(Coordinate, Instant)> // Food eaten lately, and when, while it's still shrinking away
+}
+
+/// What's left lying around the maze in a saved game, with times in seconds from when it was saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ObjectsSnapshot {
+    food: Vec<Coordinate>,
+    keys: Vec<Coordinate>,
+    power_ups: Vec<Coordinate>,
+    batteries: Vec<Coordinate>,
+    fruit: Option<Coordinate>,
+    fruit_left: f32,
+    next_fruit: Option<f32>
+}
+
+/// Pick-ups giving the player a short-lived advantage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerUp {
+    Boots, // Player moves twice as fast
+    Clock // Ghost moves half as fast
+}
+
+/// Keys the player has picked up
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    keys: Vec<usize>
+}
+
+impl Inventory {
+    pub const fn new() -> Inventory {
+        Inventory { keys: Vec::new() }
+    }
+
+    pub fn add_key(&mut self, key: usize) {
+        self.keys.push(key);
+    }
+
+    pub fn has_key(&self, key: usize) -> bool {
+        self.keys.contains(&key)
+    }
+}
+
+impl Objects {
+    /// Hide a key for every door of the world, then scatter the configured amount of food, power-ups and batteries through empty cells
+    pub fn new(world: &mut World, config: &Config, rng: &mut impl Rng) -> Objects {
+        let keys = generate_keys(world, rng);
+        let food = generate_food(world, config, rng);
+        let power_ups = generate_power_ups(world, config, rng);
+        let batteries = generate_batteries(world, config, rng);
+        Objects { food, keys, power_ups, batteries, fruit: None, next_fruit: None, eaten: Vec::new() }
+    }
+
+    /// Pick up the objects already laid out in the world's cells, as a hand-made level has them
+    pub fn from_world(world: &World) -> Objects {
+        let mut objects = Objects { food: BTreeSet::new(), keys: BTreeMap::new(), power_ups: BTreeMap::new(), batteries: BTreeSet::new(), fruit: None, next_fruit: None, eaten: Vec::new() };
+        for (w, slice) in world.cells.iter().enumerate() {
+            for (z, layer) in slice.iter().enumerate() {
+                for (y, row) in layer.iter().enumerate() {
+                    for (x, cell) in row.iter().enumerate() {
+                        match *cell {
+                            Cell::Food => { objects.food.insert((x, y, z, w)); },
+                            Cell::Key (key) => { objects.keys.insert((x, y, z, w), key); },
+                            Cell::PowerUp (power_up) => { objects.power_ups.insert((x, y, z, w), power_up); },
+                            Cell::Battery => { objects.batteries.insert((x, y, z, w)); },
+                            _ => ()
+                        }
+                    }
+                }
+            }
+        }
+        objects
+    }
+
+    /// Bring out a bonus fruit every fruit interval, somewhere along the solution path other than the given cell,
+    /// and take it away again if it isn't eaten in time
+    pub fn update(&mut self, now: Instant, config: &Config, world: &mut World, avoid: [i32; 4], rng: &mut impl Rng) {
+        if config.fruit_interval <= 0.0 {
+            return;
+        }
+        let next_fruit = *self.next_fruit.get_or_insert(now + Duration::from_secs_f32(config.fruit_interval));
+        if let Some (((x, y, z, w), vanish)) = self.fruit {
+            if now >= vanish {
+                debug!(target: "world", "Fruit at {:?} vanished", (x, y, z, w));
+                world.cells[w][z][y][x] = Cell::Empty;
+                self.fruit = None;
+            }
+        }
+        if now >= next_fruit {
+            self.next_fruit = Some (now + Duration::from_secs_f32(config.fruit_interval));
+            if self.fruit.is_some() {
+                return;
+            }
+            let cell = world.solution.iter()
+                .filter(|&&(x, y, z, w)| world.cells[w][z][y][x] == Cell::Empty && [x, y, z, w].map(|i| i as i32) != avoid)
+                .choose(rng);
+            if let Some (&(x, y, z, w)) = cell {
+                debug!(target: "world", "Fruit appeared at {:?}", (x, y, z, w));
+                world.cells[w][z][y][x] = Cell::Fruit;
+                self.fruit = Some (((x, y, z, w), now + Duration::from_secs_f32(config.fruit_time)));
+            }
+        }
+    }
+
+    /// Put food back in random empty cells until there's the given amount again, other than the exit or the given cell
+    pub fn refill_food(&mut self, world: &mut World, count: usize, avoid: [i32; 4], rng: &mut impl Rng) {
+        let (width, height, depth, fourth) = (world.width, world.height, world.depth, world.fourth);
+        while self.food.len() < count {
+            let cells = (0..fourth).flat_map(|w| (0..depth).flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z, w)))));
+            let cell = cells.filter(|&(x, y, z, w)| world.cells[w][z][y][x] == Cell::Empty && (x, y, z, w) != world.exit && [x, y, z, w].map(|i| i as i32) != avoid)
+                .choose(rng);
+            match cell {
+                Some ((x, y, z, w)) => {
+                    debug!(target: "world", "Food came back at {:?}", (x, y, z, w));
+                    world.cells[w][z][y][x] = Cell::Food;
+                    self.food.insert((x, y, z, w));
+                },
+                None => return // Nowhere left to put it
+            }
+        }
+    }
+
+    /// Cells that still have food in them
+    pub fn food(&self) -> impl Iterator<Item = &Coordinate> {
+        self.food.iter()
+    }
+
+    pub fn remove_food(&mut self, pos: Coordinate) {
+        self.food.remove(&pos);
+    }
+
+    /// Take away food the player just ate, leaving it to shrink away over the next EAT_TIME seconds
+    pub fn eat_food(&mut self, pos: Coordinate, now: Instant) {
+        self.remove_food(pos);
+        self.eaten.retain(|(_, at)| now.saturating_duration_since(*at).as_secs_f32() < EAT_TIME);
+        self.eaten.push((pos, now));
+    }
+
+    /// Food eaten in the last EAT_TIME seconds, and how far it's got shrinking away, from 0 to 1
+    pub fn eaten_food(&self, now: Instant) -> impl Iterator<Item = (&Coordinate, f32)> {
+        self.eaten.iter()
+            .map(move |(cell, at)| (cell, now.saturating_duration_since(*at).as_secs_f32() / EAT_TIME))
+            .filter(|(_, progress)| *progress < 1.0)
+    }
+
+    /// Keys still lying around, and the doors they open
+    pub fn keys(&self) -> impl Iterator<Item = (&Coordinate, &usize)> {
+        self.keys.iter()
+    }
+
+    pub fn remove_key(&mut self, pos: Coordinate) {
+        self.keys.remove(&pos);
+    }
+
+    /// Power-ups still waiting to be picked up
+    pub fn power_ups(&self) -> impl Iterator<Item = (&Coordinate, &PowerUp)> {
+        self.power_ups.iter()
+    }
+
+    pub fn remove_power_up(&mut self, pos: Coordinate) {
+        self.power_ups.remove(&pos);
+    }
+
+    /// Batteries still waiting to be picked up
+    pub fn batteries(&self) -> impl Iterator<Item = &Coordinate> {
+        self.batteries.iter()
+    }
+
+    pub fn remove_battery(&mut self, pos: Coordinate) {
+        self.batteries.remove(&pos);
+    }
+
+    /// Cell of the bonus fruit, if one is out
+    pub fn fruit(&self) -> Option<Coordinate> {
+        self.fruit.map(|(cell, _)| cell)
+    }
+
+    pub fn remove_fruit(&mut self) {
+        self.fruit = None;
+    }
+
+    /// Everything still lying around, to save
+    pub fn snapshot(&self, now: Instant) -> ObjectsSnapshot {
+        ObjectsSnapshot {
+            food: self.food.iter().copied().collect(),
+            keys: self.keys.keys().copied().collect(),
+            power_ups: self.power_ups.keys().copied().collect(),
+            batteries: self.batteries.iter().copied().collect(),
+            fruit: self.fruit(),
+            fruit_left: self.fruit.map_or(0.0, |(_, vanish)| vanish.saturating_duration_since(now).as_secs_f32()),
+            next_fruit: self.next_fruit.map(|next| next.saturating_duration_since(now).as_secs_f32())
+        }
+    }
+
+    /// Take away whatever was picked up before the snapshot was saved, from the objects first placed in the same maze
+    pub fn restore(&mut self, snapshot: &ObjectsSnapshot, world: &mut World, now: Instant) {
+        let mut picked_up = Vec::new();
+        picked_up.extend(self.food.iter().filter(|cell| !snapshot.food.contains(cell)));
+        picked_up.extend(self.keys.keys().filter(|cell| !snapshot.keys.contains(cell)));
+        picked_up.extend(self.power_ups.keys().filter(|cell| !snapshot.power_ups.contains(cell)));
+        picked_up.extend(self.batteries.iter().filter(|cell| !snapshot.batteries.contains(cell)));
+        for (x, y, z, w) in picked_up {
+            world.cells[w][z][y][x] = Cell::Empty;
+        }
+        self.food.retain(|cell| snapshot.food.contains(cell));
+        self.keys.retain(|cell, _| snapshot.keys.contains(cell));
+        self.power_ups.retain(|cell, _| snapshot.power_ups.contains(cell));
+        self.batteries.retain(|cell| snapshot.batteries.contains(cell));
+        if let Some ((x, y, z, w)) = snapshot.fruit {
+            world.cells[w][z][y][x] = Cell::Fruit;
+            self.fruit = Some (((x, y, z, w), now + Duration::from_secs_f32(snapshot.fruit_left)));
+        }
+        self.next_fruit = snapshot.next_fruit.map(|seconds| now + Duration::from_secs_f32(seconds));
+    }
+}
+
+// Food goes where the placement picks first, then in random empty cells for whatever it couldn't place
+fn generate_food(world: &mut World, config: &Config, rng: &mut impl Rng) -> BTreeSet<Coordinate> {
+    let picks = match config.food_placement {
+        FoodPlacement::Uniform => Vec::new(),
+        FoodPlacement::FarFromStart => far_from_start(world, config.food_count, rng),
+        FoodPlacement::PerSlice => per_slice(world, config.food_count, rng),
+        FoodPlacement::DeadEnds => dead_ends(world, config.food_count, rng)
+    };
+    let mut food = BTreeSet::new();
+    for (x, y, z, w) in picks {
+        world.cells[w][z][y][x] = Cell::Food;
+        food.insert((x, y, z, w));
+    }
+    while food.len() < config.food_count {
+        let (x, y, z, w) = world.random_empty_cell(rng);
+        world.cells[w][z][y][x] = Cell::Food;
+        food.insert((x, y, z, w));
+    }
+    food
+}
+
+// Empty cells other than the start and exit, in the same order every time so seeded games come out the same
+fn empty_cells(world: &World) -> Vec<Coordinate> {
+    let (width, height, depth, fourth) = (world.width, world.height, world.depth, world.fourth);
+    (0..fourth).flat_map(|w| (0..depth).flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z, w)))))
+        .filter(|&(x, y, z, w)| world.cells[w][z][y][x] == Cell::Empty && (x, y, z, w) != world.start && (x, y, z, w) != world.exit)
+        .collect()
+}
+
+// Up to count cells reachable from the start, each drawn with odds by the square of its distance from the start.
+// Every cell gets a random key that's larger the more it weighs, and the cells with the largest keys are taken
+fn far_from_start(world: &World, count: usize, rng: &mut impl Rng) -> Vec<Coordinate> {
+    let distances = world.distance_field(world.start);
+    let mut keyed: Vec<(f64, Coordinate)> = empty_cells(world).into_iter()
+        .filter_map(|cell| distances.get(&cell).map(|&distance| (cell, (distance * distance) as f64)))
+        .map(|(cell, weight)| (rng.gen::<f64>().powf(1.0 / weight), cell))
+        .collect();
+    keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    keyed.into_iter().take(count).map(|(_, cell)| cell).collect()
+}
+
+// Up to count cells dealt out a slice at a time, going round the slices in a random order, so no slice gets two
+// more than another until it runs out of room
+fn per_slice(world: &World, count: usize, rng: &mut impl Rng) -> Vec<Coordinate> {
+    let mut slices = vec![Vec::new(); world.fourth];
+    for cell in empty_cells(world) {
+        slices[cell.3].push(cell);
+    }
+    for slice in &mut slices {
+        slice.shuffle(rng);
+    }
+    let mut order: Vec<usize> = (0..world.fourth).collect();
+    order.shuffle(rng);
+    let mut picks = Vec::new();
+    while picks.len() < count && slices.iter().any(|slice| !slice.is_empty()) {
+        for &w in &order {
+            if picks.len() < count {
+                picks.extend(slices[w].pop());
+            }
+        }
+    }
+    picks
+}
+
+// Up to count dead ends, in random order
+fn dead_ends(world: &World, count: usize, rng: &mut impl Rng) -> Vec<Coordinate> {
+    let mut cells: Vec<Coordinate> = empty_cells(world).into_iter().filter(|&cell| world.is_dead_end(cell)).collect();
+    cells.shuffle(rng);
+    cells.truncate(count);
+    cells
+}
+
+fn generate_batteries(world: &mut World, config: &Config, rng: &mut impl Rng) -> BTreeSet<Coordinate> {
+    (0..config.batteries).map(|_| {
+        let (x, y, z, w) = world.random_empty_cell(rng);
+        world.cells[w][z][y][x] = Cell::Battery;
+        (x, y, z, w)
+    }).collect()
+}
+
+fn generate_power_ups(world: &mut World, config: &Config, rng: &mut impl Rng) -> BTreeMap<Coordinate, PowerUp> {
+    (0..config.power_ups).map(|_| {
+        let (x, y, z, w) = world.random_empty_cell(rng);
+        let power_up = if rng.gen_bool(0.5) { PowerUp::Boots } else { PowerUp::Clock };
+        world.cells[w][z][y][x] = Cell::PowerUp (power_up);
+        ((x, y, z, w), power_up)
+    }).collect()
+}
+
+// Each key lies somewhere the player can reach using only the keys before it, so no door is ever needed to get its own key
+fn generate_keys(world: &mut World, rng: &mut impl Rng) -> BTreeMap<Coordinate, usize> {
+    let mut inventory = Inventory::new();
+    let mut keys = BTreeMap::new();
+    for key in 0..world.doors.len() {
+        let mut reachable: Vec<Coordinate> = world.reachable(world.start, &inventory).into_iter().collect();
+        reachable.sort(); // Sets iterate in any order, which would spoil seeded games
+        let cell = reachable.into_iter()
+            .filter(|&(x, y, z, w)| (x, y, z, w) != world.start && world.cells[w][z][y][x] == Cell::Empty)
+            .choose(rng)
+            .unwrap_or(world.start); // Nowhere else to put it, so it's picked up straight away
+        let (x, y, z, w) = cell;
+        world.cells[w][z][y][x] = Cell::Key (key);
+        keys.insert(cell, key);
+        inventory.add_key(key);
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const PLACEMENTS: [FoodPlacement; 4] = [FoodPlacement::Uniform, FoodPlacement::FarFromStart, FoodPlacement::PerSlice, FoodPlacement::DeadEnds];
+
+    fn placed(food_placement: FoodPlacement, food_count: usize, seed: u64) -> (World, BTreeSet<Coordinate>) {
+        let config = Config { dimensions: [5, 4, 3, 3], food_count, food_placement, ..Default::default() };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world = World::new(&config, &mut rng).unwrap();
+        let food = generate_food(&mut world, &config, &mut rng);
+        (world, food)
+    }
+
+    #[test]
+    fn every_placement_places_all_the_food() {
+        for placement in PLACEMENTS {
+            for (seed, count) in [(1, 0), (2, 1), (3, 12), (4, 100)] {
+                let (world, food) = placed(placement, count, seed);
+                assert_eq!(food.len(), count, "{} with seed {}", placement, seed);
+                assert!(food.iter().all(|&(x, y, z, w)| world.cells[w][z][y][x] == Cell::Food));
+            }
+        }
+    }
+
+    #[test]
+    fn per_slice_leaves_no_slice_without_food() {
+        for seed in 0..20 {
+            let (_, food) = placed(FoodPlacement::PerSlice, 7, seed);
+            let mut counts = [0; 3];
+            for &(_, _, _, w) in &food {
+                counts[w] += 1;
+            }
+            assert_eq!(counts.iter().max().unwrap() - counts.iter().min().unwrap(), 1, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn dead_ends_come_first() {
+        for seed in 0..20 {
+            let (world, food) = placed(FoodPlacement::DeadEnds, 3, seed);
+            assert!(food.iter().all(|&cell| world.is_dead_end(cell)), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn far_from_start_is_further_than_uniform() {
+        let mean_distance = |placement| {
+            let total: usize = (0..30).map(|seed| {
+                let (world, food) = placed(placement, 5, seed);
+                let distances = world.distance_field(world.start);
+                food.iter().map(|cell| distances[cell]).sum::<usize>()
+            }).sum();
+            total as f32 / 150.0
+        };
+        assert!(mean_distance(FoodPlacement::FarFromStart) > mean_distance(FoodPlacement::Uniform));
+    }
+}
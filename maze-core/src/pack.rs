@@ -0,0 +1,142 @@
+use log::debug;
+
+use crate::ghost::Ghost;
+use crate::player::Player;
+use crate::world::{Coordinate, World};
+
+const AMBUSH_STEPS: usize = 4; // Cells ahead of the player an ambushing ghost heads for
+
+/// What a ghost in a pack is trying to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Chase, // Head straight for the player's cell
+    Ambush, // Head for a few cells ahead of the way the player's been going
+    Guard // Wait at the exit
+}
+
+// Handed out from the nearest ghost to the furthest, starting over once each has been given out
+const ROLES: [Role; 3] = [Role::Chase, Role::Ambush, Role::Guard];
+
+/// Gives a pack of ghosts complementary targets, so they close in from different sides rather than queueing up behind each other
+pub struct Coordinator {
+    roles: Vec<Role>, // For each ghost
+    slice: Option<i32> // Player's w-slice when the roles were last handed out
+}
+
+impl Coordinator {
+    pub fn new(ghosts: usize) -> Coordinator {
+        Coordinator { roles: ROLES.iter().copied().cycle().take(ghosts).collect(), slice: None }
+    }
+
+    /// Role of each ghost, in the same order as the ghosts
+    pub fn roles(&self) -> &[Role] {
+        &self.roles
+    }
+
+    /// Hand out the roles again whenever the player moves to another w-slice, with the nearest ghost chasing,
+    /// then point each ghost at its role's target. Each target is reserved in the world's cell metadata, and a ghost
+    /// whose target another ghost already holds chases instead, so a large pack doesn't pile up on one cell
+    pub fn update(&mut self, ghosts: &mut [Ghost], player: &Player, world: &mut World) {
+        let player_cell = to_coordinate(player.cell());
+        if self.slice != Some (player.cell()[3]) {
+            self.slice = Some (player.cell()[3]);
+            let distances = world.distance_field(player_cell);
+            let mut nearest: Vec<usize> = (0..ghosts.len()).collect();
+            nearest.sort_by_key(|&i| {
+                let [x, y, z, w] = ghosts[i].cell();
+                distances.get(&(x, y, z, w)).copied().unwrap_or(usize::MAX)
+            });
+            for (role, &i) in ROLES.iter().cycle().zip(&nearest) {
+                self.roles[i] = *role;
+            }
+            debug!(target: "ghost", "Player moved to w-slice {}, so the ghosts are now {:?}", player.cell()[3], self.roles);
+        }
+        let ambush = ambush_target(player, world);
+        for i in 0..ghosts.len() {
+            world.meta.release_all(i);
+        }
+        for (i, (ghost, role)) in ghosts.iter_mut().zip(&self.roles).enumerate() {
+            let target = match role {
+                Role::Chase => None,
+                Role::Ambush => Some (ambush),
+                Role::Guard => Some (world.exit)
+            };
+            ghost.set_target(target.filter(|&cell| world.meta.reserve(cell, i)));
+        }
+    }
+}
+
+// Furthest cell up to a few moves ahead of the player, carrying on the way they last moved
+fn ambush_target(player: &Player, world: &World) -> Coordinate {
+    let moves = &player.replay.moves;
+    let mut cell = player.cell();
+    if let [.., (_, from), (_, to)] = moves.as_slice() {
+        let delta = [0, 1, 2, 3].map(|i| to[i] - from[i]);
+        if *to == cell && delta.iter().map(|d| d.abs()).sum::<i32>() == 1 {
+            for _ in 0..AMBUSH_STEPS {
+                if !world.check_move(cell, delta, &player.inventory) || !world.contains([0, 1, 2, 3].map(|i| cell[i] + delta[i])) {
+                    break;
+                }
+                cell = [0, 1, 2, 3].map(|i| cell[i] + delta[i]);
+            }
+        }
+    }
+    to_coordinate(cell)
+}
+
+fn to_coordinate(cell: [i32; 4]) -> Coordinate {
+    (cell[0] as usize, cell[1] as usize, cell[2] as usize, cell[3] as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn corridor(length: usize) -> World {
+        let mut world = World::empty([length, 1, 1, 1]);
+        for x in 1..length {
+            world.open_wall((x - 1, 0, 0, 0), (x, 0, 0, 0));
+        }
+        world.exit = (length - 1, 0, 0, 0);
+        world
+    }
+
+    #[test]
+    fn ghosts_split_up_nearest_first() {
+        let mut world = corridor(8);
+        let config = Config::default();
+        let mut ghosts = vec![Ghost::at(&config, [7, 0, 0, 0]), Ghost::at(&config, [1, 0, 0, 0]), Ghost::at(&config, [5, 0, 0, 0])];
+        let mut player = Player::new(&config, [0, 0, 0, 0]);
+        player.move_position(std::time::Instant::now(), [1, 0, 0, 0], 0.0);
+        let mut pack = Coordinator::new(ghosts.len());
+        pack.update(&mut ghosts, &player, &mut world);
+        assert_eq!(pack.roles(), [Role::Guard, Role::Chase, Role::Ambush]);
+        assert_eq!(ghosts[0].target(), Some ((7, 0, 0, 0)));
+        assert_eq!(ghosts[1].target(), None);
+        assert_eq!(ghosts[2].target(), Some ((5, 0, 0, 0)));
+    }
+
+    #[test]
+    fn a_lone_ghost_chases() {
+        let mut world = corridor(3);
+        let config = Config::default();
+        let mut ghosts = vec![Ghost::at(&config, [2, 0, 0, 0])];
+        let mut pack = Coordinator::new(1);
+        pack.update(&mut ghosts, &Player::new(&config, [0, 0, 0, 0]), &mut world);
+        assert_eq!(pack.roles(), [Role::Chase]);
+        assert_eq!(ghosts[0].target(), None);
+    }
+
+    #[test]
+    fn ghosts_sharing_a_role_dont_share_a_target() {
+        let mut world = corridor(8);
+        let config = Config::default();
+        let mut ghosts: Vec<Ghost> = [7, 6, 5, 4, 3].iter().map(|&x| Ghost::at(&config, [x, 0, 0, 0])).collect();
+        let mut pack = Coordinator::new(ghosts.len());
+        pack.update(&mut ghosts, &Player::new(&config, [0, 0, 0, 0]), &mut world);
+        assert_eq!(pack.roles(), [Role::Ambush, Role::Chase, Role::Guard, Role::Ambush, Role::Chase]);
+        assert_eq!(ghosts.iter().map(Ghost::target).collect::<Vec<_>>(), [Some ((0, 0, 0, 0)), None, Some ((7, 0, 0, 0)), None, None]);
+        assert_eq!(world.meta.reserved_by((0, 0, 0, 0)), Some (0));
+    }
+}
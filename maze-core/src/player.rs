@@ -0,0 +1,598 @@
+use std::time::{Duration, Instant};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::objects::{Inventory, Objects, PowerUp};
+use crate::config::{Config, DisplayClock, Mode, Movement, Respawn};
+use crate::world::{Cell, Trap, World};
+use crate::world::meta::Flags;
+use crate::replay::Replay;
+
+const RESPAWN_TIME: f32 = 1.5; // Seconds before play resumes after losing a life
+const TELEPORT_FLASH_TIME: f32 = 0.6; // Seconds the flash after teleporting takes to fade
+const SNARE_TIME: f32 = 2.0; // Seconds a snare holds the player
+const PAR_TIME: u32 = 15; // Seconds per food the player can take before missing out on a time bonus
+const TIME_BONUS_SECONDS: u32 = 5; // Seconds under par for each point of time bonus
+const NO_HINT_BONUS: u32 = 10; // Points for escaping without the compass
+const ESCAPE_TIME: f32 = 0.8; // Seconds spent flying out through the exit before the game is won
+const ESCAPE_DISTANCE: f32 = 3.0; // Cells flown past the exit in that time
+const GLIDE_SPEED: f32 = 2.5; // Cells a second the player glides at in free movement
+const GLIDE_GRIP: f32 = 12.0; // How quickly gliding speeds up and slows down, as a fraction of the difference each second
+const GLIDE_STEP: f32 = 0.05; // Most seconds glided at once, so a slow frame can't carry the player through a wall
+const HALF_SIZE: f32 = 0.25; // Half the width of the box the player bumps into walls with in free movement
+
+/// Whether the game is still going, and how it ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing, Respawning, Won, Lost, Reviewing
+}
+
+/// Points the player escaped with, and where they came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FinalScore {
+    pub food: u32,
+    pub fruit: u32,
+    pub combo: u32,
+    pub time: u32,
+    pub no_hint: u32
+}
+
+impl FinalScore {
+    pub fn total(&self) -> u32 {
+        self.food + self.fruit + self.combo + self.time + self.no_hint
+    }
+}
+
+/// The player's progress in a saved game, with times in seconds from when it was saved. Saved between moves,
+/// so the player carries on standing in the cell they were moving into
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PlayerSnapshot {
+    cell: [i32; 4],
+    score: u32,
+    bonus: u32,
+    combo: u32,
+    combo_bonus: u32,
+    combo_left: f32,
+    lives: u32,
+    inventory: Inventory,
+    effects: Vec<PowerUp>,
+    effects_left: Vec<f32>,
+    flashlight: bool,
+    battery: f32,
+    on_pad: Option<[i32; 4]>,
+    sprung: Option<[i32; 4]>,
+    frozen_left: f32,
+    respawn_cell: [i32; 4],
+    elapsed: Option<f32>, // Since the first move, if there's been one
+    stopwatch: u32,
+    route: Vec<[i32; 4]>, // Replay of the cells moved through so far, the seconds each was moved into, and the food eaten
+    route_times: Vec<f32>,
+    eaten: Vec<[i32; 4]>
+}
+
+/// Light the player can switch on to see further, running down its battery while lit
+pub struct Flashlight {
+    on: bool,
+    battery: f32, // Charge left, from 0 to 1
+    updated: Instant
+}
+
+impl Flashlight {
+    pub fn new(config: &Config) -> Flashlight {
+        let battery = if config.flashlight_time > 0.0 { 1.0 } else { 0.0 };
+        Flashlight { on: false, battery, updated: Instant::now() }
+    }
+
+    /// Switch on or off. Stays off while the battery is flat
+    pub fn toggle(&mut self) {
+        self.on = !self.on && self.battery > 0.0;
+    }
+
+    pub fn lit(&self) -> bool {
+        self.on
+    }
+
+    pub fn battery(&self) -> f32 {
+        self.battery
+    }
+
+    pub fn recharge(&mut self) {
+        self.battery = 1.0;
+    }
+
+    // Drain the battery for the time it's been lit, switching off once flat
+    fn update(&mut self, now: Instant, flashlight_time: f32) {
+        if self.on {
+            self.battery = (self.battery - now.saturating_duration_since(self.updated).as_secs_f32() / flashlight_time).max(0.0);
+            self.on = self.battery > 0.0;
+        }
+        self.updated = now;
+    }
+}
+
+/// The player's position, score and clock
+pub struct Player {
+    dest_position: [i32; 4],
+    position: [f32; 4],
+    dest_speed: f32,
+    last_update: Instant,
+    reach_dest: Instant,
+    free: bool, // Gliding across each level rather than stepping between cells
+    heading: [f32; 2], // Way the held keys point in x and y, in free movement
+    velocity: [f32; 2],
+    glided: Instant, // When free movement last moved the player
+    pub game_state: GameState,
+    pub score: u32,
+    pub bonus: u32, // Points from fruit, on top of the food eaten
+    pub combo: u32, // Score multiplier for the next food, while the combo lasts
+    pub combo_bonus: u32, // Extra points from food eaten during combos
+    combo_until: Instant,
+    pub lives: u32,
+    pub inventory: Inventory,
+    effects: Vec<(PowerUp, Instant)>, // Active power-ups, and when they wear off
+    pub flashlight: Flashlight,
+    on_pad: Option<[i32; 4]>, // Teleporter pad the player arrived on, which only works again once they've stepped off
+    teleported_at: Option<Instant>,
+    teleport_flash: f32,
+    sprung: Option<[i32; 4]>, // Trap that already went off, which stays harmless until the player leaves its cell
+    frozen_until: Instant,
+    respawn: Respawn,
+    respawn_cell: [i32; 4], // Start, or the last checkpoint reached
+    respawn_until: Instant,
+    start_time: Option<Instant>,
+    escaped_at: Option<Instant>, // When the player walked out through the exit, and started flying off
+    time_limit: Option<u32>, // Seconds to escape in before the game is lost
+    pub stopwatch: u32,
+    pub replay: Replay
+}
+
+impl Player {
+    pub fn new(config: &Config, start: [i32; 4]) -> Player {
+        // Zen has no clock to run out
+        let time_limit = match (config.mode, &config.display_clock) {
+            (Mode::Zen, _) => None,
+            (_, DisplayClock::Timer(duration)) => Some (*duration),
+            _ => None
+        };
+        Player {
+            dest_position: start,
+            position: start.map(|i| i as f32),
+            dest_speed: 0.0,
+            last_update: Instant::now(),
+            reach_dest: Instant::now(),
+            free: config.movement == Movement::Free,
+            heading: [0.0; 2],
+            velocity: [0.0; 2],
+            glided: Instant::now(),
+            game_state: GameState::Playing,
+            score: 0,
+            bonus: 0,
+            combo: 1,
+            combo_bonus: 0,
+            combo_until: Instant::now(),
+            lives: config.lives,
+            inventory: Inventory::new(),
+            effects: Vec::new(),
+            flashlight: Flashlight::new(config),
+            on_pad: None,
+            teleported_at: None,
+            teleport_flash: 0.0,
+            sprung: None,
+            frozen_until: Instant::now(),
+            respawn: config.respawn,
+            respawn_cell: start,
+            respawn_until: Instant::now(),
+            start_time: None,
+            escaped_at: None,
+            time_limit,
+            stopwatch: time_limit.unwrap_or(0),
+            replay: Replay::new(start)
+        }
+    }
+
+    /// Start moving by delta, arriving after the given number of seconds
+    pub fn move_position(&mut self, now: Instant, delta: [i32; 4], seconds: f32) {
+        // Start stopwatch timer
+        if self.start_time.is_none() {
+            self.start_time = Some (now);
+        }
+
+        // Boots halve the time each move takes
+        let seconds = if self.has_effect(now, PowerUp::Boots) { seconds / 2.0 } else { seconds };
+
+        for (dest, d) in self.dest_position.iter_mut().zip(delta) {
+            *dest += d;
+        }
+        self.last_update = now;
+        self.replay.record_move((self.last_update - self.start_time.unwrap()).as_secs_f32(), self.dest_position);
+        self.reach_dest = self.last_update + Duration::from_secs_f32(seconds);
+        if seconds <= 0.1 {
+            self.position = self.dest_position.map(|i| i as f32);
+        } else {
+            let dist = delta.map(|i| i * i).iter().fold(0.0, |acc, x| acc + *x as f32).sqrt();
+            self.dest_speed = dist / seconds;
+        }
+    }
+
+    /// Point free movement the way the held keys do in x and y, or stop with [0, 0]. The clock starts with the first
+    /// push in any direction
+    pub fn steer(&mut self, now: Instant, heading: [f32; 2]) {
+        let length = (heading[0] * heading[0] + heading[1] * heading[1]).sqrt();
+        self.heading = if length > 0.0 { heading.map(|i| i / length) } else { [0.0; 2] };
+        if length > 0.0 && self.start_time.is_none() {
+            self.start_time = Some (now);
+            self.glided = now;
+        }
+    }
+
+    /// Whether the player glides across each level rather than stepping from cell to cell
+    pub fn free(&self) -> bool {
+        self.free
+    }
+
+    /// Current position, between cells while moving
+    pub fn get_position(&self) -> [f32; 4] {
+        self.position
+    }
+
+    /// Place the player directly, outside of normal movement (eg. during a replay)
+    pub fn set_position(&mut self, position: [f32; 4]) {
+        self.position = position;
+        self.dest_position = position.map(|f| f.round() as i32);
+        self.reach_dest = Instant::now();
+    }
+
+    /// Cell the player is in, or moving into
+    pub fn cell(&self) -> [i32; 4] {
+        self.dest_position
+    }
+
+    /// Whether the player is on their way out through the exit, before the game is won
+    pub fn escaping(&self) -> bool {
+        self.escaped_at.is_some() && self.game_state == GameState::Playing
+    }
+
+    /// Seconds since the first move, as the replay records them, or None before moving
+    pub fn run_time(&self, now: Instant) -> Option<f32> {
+        self.start_time.map(|start| now.saturating_duration_since(start).as_secs_f32())
+    }
+
+    /// Seconds the player has to escape in, counting down on the clock, if there's a limit
+    pub fn time_limit(&self) -> Option<u32> {
+        self.time_limit
+    }
+
+    /// Give the player a number of seconds to escape in, counting down from when they first move
+    pub fn set_time_limit(&mut self, seconds: u32) {
+        self.time_limit = Some (seconds);
+        self.stopwatch = seconds;
+    }
+
+    /// Whether the power-up is still in effect
+    pub fn has_effect(&self, now: Instant, power_up: PowerUp) -> bool {
+        self.effects.iter().any(|(effect, until)| *effect == power_up && now < *until)
+    }
+
+    /// Power-ups still in effect, and the seconds left on each
+    pub fn effects(&self, now: Instant) -> impl Iterator<Item = (PowerUp, f32)> + '_ {
+        self.effects.iter().filter(move |(_, until)| now < *until).map(move |(effect, until)| (*effect, (*until - now).as_secs_f32()))
+    }
+
+    /// Tally up the score, with bonuses for a quick escape and for doing without the compass
+    pub fn final_score(&self, config: &Config) -> FinalScore {
+        let won = self.game_state == GameState::Won;
+        let par = PAR_TIME * config.food_count as u32;
+        FinalScore {
+            food: self.score,
+            fruit: self.bonus,
+            combo: self.combo_bonus,
+            time: if won { par.saturating_sub(self.stopwatch) / TIME_BONUS_SECONDS } else { 0 },
+            no_hint: if won && !config.display_compass { NO_HINT_BONUS } else { 0 }
+        }
+    }
+
+    /// Everything about the player worth keeping in a saved game
+    pub fn snapshot(&self, now: Instant) -> PlayerSnapshot {
+        let left = |until: Instant| until.saturating_duration_since(now).as_secs_f32();
+        let (effects, effects_left) = self.effects(now).unzip();
+        PlayerSnapshot {
+            cell: self.dest_position,
+            score: self.score,
+            bonus: self.bonus,
+            combo: self.combo,
+            combo_bonus: self.combo_bonus,
+            combo_left: left(self.combo_until),
+            lives: self.lives,
+            inventory: self.inventory.clone(),
+            effects,
+            effects_left,
+            flashlight: self.flashlight.on,
+            battery: self.flashlight.battery,
+            on_pad: self.on_pad,
+            sprung: self.sprung,
+            frozen_left: left(self.frozen_until),
+            respawn_cell: self.respawn_cell,
+            elapsed: self.run_time(now),
+            stopwatch: self.stopwatch,
+            route: self.replay.moves.iter().map(|(_, cell)| *cell).collect(),
+            route_times: self.replay.moves.iter().map(|(time, _)| *time).collect(),
+            eaten: self.replay.food.clone()
+        }
+    }
+
+    /// Carry on from a saved game, standing still in the saved cell
+    pub fn restore(&mut self, snapshot: &PlayerSnapshot, now: Instant) {
+        let until = |seconds: f32| now + Duration::from_secs_f32(seconds);
+        self.dest_position = snapshot.cell;
+        self.position = snapshot.cell.map(|i| i as f32);
+        self.last_update = now;
+        self.reach_dest = now;
+        self.glided = now;
+        self.game_state = GameState::Playing;
+        self.score = snapshot.score;
+        self.bonus = snapshot.bonus;
+        self.combo = snapshot.combo;
+        self.combo_bonus = snapshot.combo_bonus;
+        self.combo_until = until(snapshot.combo_left);
+        self.lives = snapshot.lives;
+        self.inventory = snapshot.inventory.clone();
+        self.effects = snapshot.effects.iter().zip(&snapshot.effects_left).map(|(effect, seconds)| (*effect, until(*seconds))).collect();
+        self.flashlight.on = snapshot.flashlight;
+        self.flashlight.battery = snapshot.battery;
+        self.flashlight.updated = now;
+        self.on_pad = snapshot.on_pad;
+        self.sprung = snapshot.sprung;
+        self.frozen_until = until(snapshot.frozen_left);
+        self.respawn_cell = snapshot.respawn_cell;
+        self.escaped_at = None;
+        self.start_time = snapshot.elapsed.map(|seconds| now.checked_sub(Duration::from_secs_f32(seconds)).unwrap_or(now));
+        self.stopwatch = snapshot.stopwatch;
+        self.replay.moves = snapshot.route_times.iter().copied().zip(snapshot.route.iter().copied()).collect();
+        self.replay.food = snapshot.eaten.clone();
+    }
+
+    /// Whether a snare is holding the player in place
+    pub fn frozen(&self, now: Instant) -> bool {
+        now < self.frozen_until
+    }
+
+    // Punish the player for walking into a trap
+    fn spring(&mut self, now: Instant, trap: Trap) {
+        debug!(target: "world", "Player walked into {:?} at {:?}", trap, self.dest_position);
+        self.sprung = Some (self.dest_position);
+        match trap {
+            Trap::Spikes => self.lose_life(now),
+            Trap::Snare => self.frozen_until = now + Duration::from_secs_f32(SNARE_TIME),
+            Trap::Pit => {
+                self.dest_position[2] -= 1;
+                self.position = self.dest_position.map(|i| i as f32);
+                self.reach_dest = now;
+                if let Some (start_time) = self.start_time {
+                    self.replay.record_move((now - start_time).as_secs_f32(), self.dest_position);
+                }
+            }
+        }
+    }
+
+    /// Brightness of the flash after teleporting, fading from 1 to 0
+    pub fn teleport_flash(&self) -> f32 {
+        self.teleport_flash
+    }
+
+    // Jump straight to the cell, arriving on its pad
+    fn teleport(&mut self, now: Instant, target: [i32; 4]) {
+        debug!(target: "world", "Player teleported from {:?} to {:?}", self.dest_position, target);
+        self.dest_position = target;
+        self.position = target.map(|i| i as f32);
+        self.reach_dest = now;
+        self.on_pad = Some (target);
+        self.teleported_at = Some (now);
+        if let Some (start_time) = self.start_time {
+            self.replay.record_move((now - start_time).as_secs_f32(), self.dest_position);
+        }
+    }
+
+    // Speed up towards the way the held keys point and glide that way, sliding along any walls in the way. Moves
+    // between levels and slices still go a cell at a time, so those coordinates stay put
+    fn glide(&mut self, now: Instant, world: &World) {
+        let mut seconds = now.saturating_duration_since(self.glided).as_secs_f32();
+        self.glided = now;
+        self.position[2] = self.dest_position[2] as f32;
+        self.position[3] = self.dest_position[3] as f32;
+        if self.frozen(now) || self.start_time.is_none() {
+            self.velocity = [0.0; 2];
+            return;
+        }
+        let speed = if self.has_effect(now, PowerUp::Boots) { GLIDE_SPEED * 2.0 } else { GLIDE_SPEED };
+        while seconds > 0.0 {
+            let step = seconds.min(GLIDE_STEP);
+            seconds -= step;
+            let grip = (GLIDE_GRIP * step).min(1.0);
+            self.velocity = [0, 1].map(|i| self.velocity[i] + (self.heading[i] * speed - self.velocity[i]) * grip);
+            let wanted = self.velocity.map(|v| v * step);
+            let moved = world.sweep(self.position, wanted, HALF_SIZE, &self.inventory);
+            for i in 0..2 {
+                if (moved[i] - self.position[i]).abs() + 1e-6 < wanted[i].abs() {
+                    self.velocity[i] = 0.0; // Ran into a wall
+                }
+            }
+            self.position = moved;
+        }
+        let cell = self.position.map(|i| i.round() as i32);
+        if cell != self.dest_position {
+            self.dest_position = cell;
+            if let Some (start_time) = self.start_time {
+                self.replay.record_move((now - start_time).as_secs_f32(), self.dest_position);
+            }
+        }
+    }
+
+    /// Lose a life after being caught, pausing before play resumes back at the respawn cell.
+    /// Losing the last life loses the game
+    pub fn lose_life(&mut self, now: Instant) {
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives == 0 {
+            self.game_state = GameState::Lost; // Player defeat
+            return;
+        }
+        self.dest_position = self.respawn_cell;
+        self.position = self.respawn_cell.map(|i| i as f32);
+        self.reach_dest = now;
+        if let Some (start_time) = self.start_time {
+            self.replay.record_move((now - start_time).as_secs_f32(), self.dest_position);
+        }
+        self.respawn_until = now + Duration::from_secs_f32(RESPAWN_TIME);
+        self.velocity = [0.0; 2];
+        self.glided = self.respawn_until;
+        self.game_state = GameState::Respawning;
+    }
+
+    /// Advance movement and the clock, eat food, and check for leaving the maze
+    pub fn update(&mut self, now: Instant, config: &Config, world: &mut World, objects: &mut Objects) {
+        // Once out, fly on through the exit portal, speeding up, and win on reaching the far side. The clock has stopped
+        if let Some (escaped_at) = self.escaped_at {
+            let progress = (now.saturating_duration_since(escaped_at).as_secs_f32() / ESCAPE_TIME).min(1.0);
+            let (x, y, z, w) = world.exit;
+            let exit = [x, y, z, w].map(|i| i as f32);
+            self.position = [0, 1, 2, 3].map(|i| exit[i] + world.exit_delta[i] as f32 * ESCAPE_DISTANCE * progress * progress);
+            if progress >= 1.0 {
+                self.game_state = GameState::Won;
+                debug!(target: "world", "Player escaped with {:?}", self.final_score(config));
+            }
+            return;
+        }
+
+        // Update clock
+        if let Some (start_time) = self.start_time {
+            let game_duration = (now - start_time).as_secs_f32().round() as u32;
+            match self.time_limit {
+                Some (timer_duration) => {
+                    if (timer_duration as isize - game_duration as isize) < 0 {
+                        self.game_state = GameState::Lost;
+                        return;
+                    } else {
+                        self.stopwatch = timer_duration - game_duration;
+                    }
+                },
+                None if config.display_clock == DisplayClock::Stopwatch => self.stopwatch = game_duration,
+                None => {}
+            }
+        }
+
+        self.flashlight.update(now, config.flashlight_time);
+
+        // Combos run out if the next food isn't eaten in time
+        if now >= self.combo_until {
+            self.combo = 1;
+        }
+
+        // Wait out the pause after losing a life
+        if self.game_state == GameState::Respawning {
+            let [x, y, z, w] = self.respawn_cell.map(|i| i as usize);
+            world.meta.mark((x, y, z, w), Flags::VISITED); // Put back here, whether or not they stay once play resumes
+            if now < self.respawn_until {
+                return;
+            }
+            self.game_state = GameState::Playing;
+        }
+
+        // Interpolate position. Free movement glides on from the middle of the cell once any single move is done
+        if now > self.reach_dest && self.free && self.glided >= self.reach_dest {
+            self.glide(now, world);
+        } else if now > self.reach_dest {
+            self.position = self.dest_position.map(|i| i as f32);
+            self.glided = now;
+        } else {
+            let delta = [0, 1, 2, 3].map(|i| (self.dest_position[i] as f32 - self.position[i]) * self.dest_speed * (now - self.last_update).as_secs_f32());
+            for (position, d) in self.position.iter_mut().zip(delta) {
+                *position += d;
+            }
+        }
+
+        // Walking out through the exit stops the clock, then the player flies off through the portal
+        if !world.contains(self.cell()) {
+            self.escaped_at = Some (now);
+            self.stopwatch = (now - self.start_time.unwrap()).as_secs_f32().round() as u32;
+            return;
+        }
+
+        // Stepping onto a teleporter pad sends the player to its partner, once the move there is finished
+        if self.on_pad != Some (self.cell()) {
+            self.on_pad = None;
+            if now >= self.reach_dest {
+                if let Some (target) = world.teleport_target(self.cell()) {
+                    self.teleport(now, target);
+                }
+            }
+        }
+        self.teleport_flash = self.teleported_at.map_or(0.0, |at| (1.0 - now.saturating_duration_since(at).as_secs_f32() / TELEPORT_FLASH_TIME).max(0.0));
+
+        // Traps go off once the player has fully stepped in
+        if self.sprung != Some (self.cell()) {
+            self.sprung = None;
+            if let Some (trap) = world.trap(self.cell()) {
+                if now >= self.reach_dest {
+                    self.spring(now, trap);
+                    if self.game_state != GameState::Playing {
+                        return; // Spikes took a life
+                    }
+                }
+            }
+        }
+
+        // Check if something's in player's cell
+        let x = self.cell()[0] as usize;
+        let y = self.cell()[1] as usize;
+        let z = self.cell()[2] as usize;
+        let w = self.cell()[3] as usize;
+        world.meta.mark((x, y, z, w), Flags::VISITED);
+        if let Cell::Key (key) = world.cells[w][z][y][x] {
+            self.inventory.add_key(key);
+            world.cells[w][z][y][x] = Cell::Empty;
+            objects.remove_key((x, y, z, w));
+        }
+        if let Cell::PowerUp (power_up) = world.cells[w][z][y][x] {
+            // Picking up one that's already active starts its timer again
+            self.effects.retain(|(effect, until)| *effect != power_up && now < *until);
+            self.effects.push((power_up, now + Duration::from_secs_f32(config.power_up_time)));
+            world.cells[w][z][y][x] = Cell::Empty;
+            objects.remove_power_up((x, y, z, w));
+        }
+        if world.cells[w][z][y][x] == Cell::Fruit {
+            self.bonus += config.fruit_points;
+            world.cells[w][z][y][x] = Cell::Empty;
+            objects.remove_fruit();
+        }
+        if world.cells[w][z][y][x] == Cell::Battery {
+            self.flashlight.recharge();
+            world.cells[w][z][y][x] = Cell::Empty;
+            objects.remove_battery((x, y, z, w));
+        }
+        if world.cells[w][z][y][x] == Cell::Food {
+            self.score += 1;
+            // Each food eaten in quick succession is worth more than the last
+            if now < self.combo_until {
+                self.combo = (self.combo + 1).min(config.combo_max.max(1));
+            }
+            self.combo_bonus += self.combo - 1;
+            if config.combo_time > 0.0 {
+                self.combo_until = now + Duration::from_secs_f32(config.combo_time);
+            }
+            world.cells[w][z][y][x] = Cell::Empty;
+            objects.eat_food((x, y, z, w), now);
+            self.replay.record_food(self.cell());
+            if self.respawn == Respawn::Checkpoint {
+                let [px, py, pz, pw] = self.respawn_cell.map(|i| i as usize);
+                world.meta.unmark((px, py, pz, pw), Flags::CHECKPOINT);
+                world.meta.mark((x, y, z, w), Flags::CHECKPOINT);
+                self.respawn_cell = self.cell();
+            }
+            // Open the exit once all food is eaten, between both players in a race, unless there's no way out
+            if objects.food().next().is_none() && config.mode != Mode::Survival {
+                world.exit_open = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::{maze_size, Config};
+use crate::error::{MazeError, Result};
+
+/// Directory holding profiles, next to the main config file
+pub const PROFILES: &str = "profiles";
+
+/// Config file of the named profile, eg. profiles/sam.toml, creating it from the main config file's settings
+/// the first time it's used. Each profile keeps its own settings, player name and best times
+pub fn open(config: &str, name: &str) -> Result<String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err (MazeError::Argument { option: "profile".to_string(), reason: "Expected a name of letters, digits, - and _".to_string() });
+    }
+    let directory = Path::new(config).with_file_name(PROFILES);
+    let path = directory.join(format!("{}.toml", name)).to_string_lossy().into_owned();
+    if !Path::new(&path).exists() {
+        fs::create_dir_all(&directory).map_err(|e| MazeError::io(&directory.to_string_lossy(), e))?;
+        let mut settings = Config::new(config)?;
+        settings.player_name = name.to_string();
+        settings.best_times.clear();
+        settings.save(&path)?;
+    }
+    Ok (path)
+}
+
+/// Keep the time if it's the best yet for this size of maze, leaving the rest of the config file alone.
+/// Returns whether it was a new best
+pub fn record_time(path: &str, dimensions: &[usize; 4], seconds: u32) -> Result<bool> {
+    let mut saved = Config::new(path)?;
+    let size = maze_size(dimensions);
+    if saved.best_times.get(&size).is_some_and(|&best| best <= seconds) {
+        return Ok (false);
+    }
+    saved.best_times.insert(size, seconds);
+    saved.save(path)?;
+    Ok (true)
+}
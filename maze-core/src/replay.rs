@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::config::maze_size;
+use crate::error::{MazeError, Result};
+use crate::player::Player;
+use crate::world::{Coordinate, World};
+
+const FLYTHROUGH_SPEED: f32 = 3.0; // Cells per second
+const ECHO_MOVE_TIME: f32 = 0.5; // Seconds an echo takes to slide into each cell, as long as a move without boots
+
+/// Directory holding the fastest escape from each maze, next to the main config file
+pub const REPLAYS: &str = "replays";
+
+/// Recording of a single run: every cell the player moved into, and the food they ate along the way
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "ReplayFile", from = "ReplayFile")]
+pub struct Replay {
+    pub moves: Vec<(f32, [i32; 4])>, // Seconds since start of the run, cell moved into
+    pub food: Vec<[i32; 4]>
+}
+
+impl Replay {
+    pub fn new(start: [i32; 4]) -> Replay {
+        Replay { moves: vec![(0.0, start)], food: Vec::new() }
+    }
+
+    pub fn record_move(&mut self, time: f32, cell: [i32; 4]) {
+        self.moves.push((time, cell));
+    }
+
+    pub fn record_food(&mut self, cell: [i32; 4]) {
+        self.food.push(cell);
+    }
+
+    pub fn path(&self) -> Vec<[i32; 4]> {
+        self.moves.iter().map(|(_, cell)| *cell).collect()
+    }
+
+    /// Fingerprint of every move and the moment it was made, to check a time sent to a leaderboard against its run.
+    /// FNV-1a over the cells and milliseconds, so it comes out the same on every machine and build
+    pub fn hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (time, cell) in &self.moves {
+            let millis = (time * 1000.0).round() as u32;
+            for byte in cell.iter().flat_map(|i| i.to_le_bytes()).chain(millis.to_le_bytes()) {
+                hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    /// Seconds from the start of the run to its last move
+    pub fn duration(&self) -> f32 {
+        self.moves.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    /// Where the player was the given number of seconds into the run, sliding into each cell after moving towards it
+    pub fn position(&self, seconds: f32) -> [f32; 4] {
+        let index = self.moves.iter().rposition(|(time, _)| *time <= seconds).unwrap_or(0);
+        let (time, to) = self.moves[index];
+        if index == 0 {
+            return to.map(|i| i as f32);
+        }
+        let from = self.moves[index - 1].1;
+        let move_time = self.moves.get(index + 1).map_or(ECHO_MOVE_TIME, |(next, _)| (next - time).min(ECHO_MOVE_TIME));
+        let t = if move_time > 0.0 { ((seconds - time) / move_time).min(1.0) } else { 1.0 };
+        [0, 1, 2, 3].map(|i| from[i] as f32 + (to[i] - from[i]) as f32 * t)
+    }
+
+    /// Read back a run written by `record_best`
+    pub fn load(path: &str) -> Result<Replay> {
+        let contents = fs::read_to_string(path).map_err(|e| MazeError::io(path, e))?;
+        toml::from_str(&contents).map_err(|e| MazeError::resource(path, e))
+    }
+
+    /// Keep this run if it's the fastest escape from its maze yet, returning whether it was kept
+    pub fn record_best(&self, path: &str) -> Result<bool> {
+        if Replay::load(path).is_ok_and(|best| best.duration() <= self.duration()) {
+            return Ok (false);
+        }
+        if let Some (directory) = Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|e| MazeError::io(&directory.to_string_lossy(), e))?;
+        }
+        let contents = toml::to_string(self).map_err(|e| MazeError::resource(path, e))?;
+        fs::write(path, contents).map_err(|e| MazeError::io(path, e))?;
+        Ok (true)
+    }
+}
+
+/// Where the fastest escape from the maze made with these dimensions and seed is kept, eg. replays/5x5x3x3-1234.toml
+pub fn best_path(config: &str, dimensions: &[usize; 4], seed: u64) -> String {
+    Path::new(config).with_file_name(REPLAYS).join(format!("{}-{}.toml", maze_size(dimensions), seed)).to_string_lossy().into_owned()
+}
+
+// Replay as written to a file, as TOML arrays can't mix times and cells
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    times: Vec<f32>,
+    cells: Vec<[i32; 4]>,
+    food: Vec<[i32; 4]>
+}
+
+impl From<Replay> for ReplayFile {
+    fn from(replay: Replay) -> ReplayFile {
+        let (times, cells) = replay.moves.into_iter().unzip();
+        ReplayFile { times, cells, food: replay.food }
+    }
+}
+
+impl From<ReplayFile> for Replay {
+    fn from(file: ReplayFile) -> Replay {
+        Replay { moves: file.times.into_iter().zip(file.cells).collect(), food: file.food }
+    }
+}
+
+/// Post-game camera flythrough along the player's recorded path,
+/// alongside the shortest route collecting the same food and leaving through the exit
+pub struct Flythrough {
+    path: Vec<[i32; 4]>,
+    optimal: Vec<[i32; 4]>,
+    start: Instant
+}
+
+impl Flythrough {
+    pub fn new(replay: &Replay, world: &World) -> Flythrough {
+        let path = replay.path();
+        let to_coord = |c: [i32; 4]| -> Coordinate { (c[0] as usize, c[1] as usize, c[2] as usize, c[3] as usize) };
+        let (x, y, z, w) = world.exit;
+        let exit = [x as i32, y as i32, z as i32, w as i32];
+        let mut optimal = replay.food.iter().chain([exit].iter()).fold(vec![path[0]], |mut acc, target| {
+            let from = to_coord(*acc.last().unwrap());
+            let segment = world.bfs(from, to_coord(*target));
+            acc.extend(segment.into_iter().skip(1).map(|(x, y, z, w)| [x as i32, y as i32, z as i32, w as i32]));
+            acc
+        });
+        optimal.push([0, 1, 2, 3].map(|i| exit[i] + world.exit_delta[i]));
+        info!(target: "world", "Replay: took {} moves, shortest route is {} moves", path.len() - 1, optimal.len() - 1);
+        Flythrough {
+            path,
+            optimal,
+            start: Instant::now()
+        }
+    }
+
+    /// Moves the player along the recorded path. Returns false once the flythrough is finished
+    pub fn update(&self, player: &mut Player) -> bool {
+        let progress = (Instant::now() - self.start).as_secs_f32() * FLYTHROUGH_SPEED;
+        let index = progress.floor() as usize;
+        if index + 1 >= self.path.len() {
+            player.set_position(self.path.last().unwrap().map(|i| i as f32));
+            return false;
+        }
+        // Ease in and out of each cell so the camera doesn't move at a jarring constant speed
+        let t = progress.fract();
+        let t = t * t * (3.0 - 2.0 * t);
+        let (from, to) = (self.path[index], self.path[index + 1]);
+        player.set_position([0, 1, 2, 3].map(|i| from[i] as f32 + (to[i] - from[i]) as f32 * t));
+        true
+    }
+
+    /// Cells the player actually visited
+    pub fn path(&self) -> &[[i32; 4]] {
+        &self.path
+    }
+
+    /// Shortest route through the same food to the exit
+    pub fn optimal(&self) -> &[[i32; 4]] {
+        &self.optimal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echoes_slide_between_cells() {
+        let mut replay = Replay::new([0, 0, 0, 0]);
+        replay.record_move(1.0, [1, 0, 0, 0]);
+        replay.record_move(1.25, [1, 1, 0, 0]);
+        assert_eq!(replay.position(0.5), [0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(replay.position(1.125), [0.5, 0.0, 0.0, 0.0]); // Cut short by the next move
+        assert_eq!(replay.position(1.5), [1.0, 0.5, 0.0, 0.0]);
+        assert_eq!(replay.position(5.0), [1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn replays_read_back_the_same() {
+        let mut replay = Replay::new([0, 0, 0, 0]);
+        replay.record_move(0.5, [0, 1, 0, 0]);
+        replay.record_food([0, 1, 0, 0]);
+        let read: Replay = toml::from_str(&toml::to_string(&replay).unwrap()).unwrap();
+        assert_eq!(read, replay);
+    }
+
+    #[test]
+    fn hashes_tell_runs_apart() {
+        let mut replay = Replay::new([0, 0, 0, 0]);
+        replay.record_move(0.5, [0, 1, 0, 0]);
+        let mut slower = Replay::new([0, 0, 0, 0]);
+        slower.record_move(0.6, [0, 1, 0, 0]);
+        assert_eq!(replay.hash(), replay.clone().hash());
+        assert_ne!(replay.hash(), slower.hash());
+    }
+}
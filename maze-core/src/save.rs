@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+use crate::ghost::GhostSnapshot;
+use crate::objects::ObjectsSnapshot;
+use crate::player::PlayerSnapshot;
+use crate::world::WorldSnapshot;
+
+/// A game in progress, saved to carry on with later. Only what has changed since the maze was generated is kept,
+/// with times in seconds from when it was saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SavedGame {
+    pub seed: u64,
+    pub dimensions: [usize; 4],
+    pub next_shift: Option<f32>,
+    pub world: WorldSnapshot,
+    pub player: PlayerSnapshot,
+    pub ghosts: Vec<GhostSnapshot>,
+    pub objects: ObjectsSnapshot
+}
+
+/// Quicksave file kept alongside a config file, eg. config.save for config.toml, so each profile has its own
+pub fn path(config: &str) -> String {
+    Path::new(config).with_extension("save").to_string_lossy().into_owned()
+}
+
+/// Write the saved game as TOML, replacing any saved before
+pub fn write(path: &str, saved: &SavedGame) -> Result<()> {
+    let contents = to_toml(saved).map_err(|e| MazeError::Save { path: path.to_string(), reason: e.to_string() })?;
+    fs::write(path, contents).map_err(|e| MazeError::io(path, e))
+}
+
+/// Read back a saved game written by `write`
+pub fn read(path: &str) -> Result<SavedGame> {
+    let contents = fs::read_to_string(path).map_err(|e| MazeError::io(path, e))?;
+    toml::from_str(&contents).map_err(|e| MazeError::Save { path: path.to_string(), reason: e.to_string() })
+}
+
+fn to_toml(saved: &SavedGame) -> std::result::Result<String, toml::ser::Error> {
+    // Going through a Value puts every table after the plain values, as TOML needs
+    toml::to_string(&toml::Value::try_from(saved)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::config::Config;
+    use crate::game::Game;
+
+    #[test]
+    fn restored_games_save_the_same() {
+        let config = Config { dimensions: [5, 5, 2, 2], food_count: 4, doors: 1, batteries: 1, ..Default::default() };
+        let mut game = Game::from_seed(&config, 42).unwrap();
+        let now = Instant::now();
+        assert!(game.world.shift_walls(&mut StdRng::seed_from_u64(1)));
+        let saved = game.snapshot(now);
+        let read: SavedGame = toml::from_str(&to_toml(&saved).unwrap()).unwrap();
+        assert_eq!(read, saved);
+        let restored = Game::restore(&config, &read, now).unwrap();
+        assert_eq!(restored.snapshot(now), saved);
+        assert_ne!(Game::from_seed(&config, 42).unwrap().snapshot(now).world, saved.world);
+    }
+
+    #[test]
+    fn saves_only_restore_into_the_same_size_of_maze() {
+        let config = Config { dimensions: [4, 4, 2, 2], ..Default::default() };
+        let saved = Game::from_seed(&config, 3).unwrap().snapshot(Instant::now());
+        let bigger = Config { dimensions: [5, 5, 2, 2], ..Default::default() };
+        assert!(Game::restore(&bigger, &saved, Instant::now()).is_err());
+    }
+}
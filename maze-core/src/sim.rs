@@ -0,0 +1,447 @@
+use std::time::{Duration, Instant};
+
+use crate::bot::Bot;
+use crate::config::Config;
+use crate::error::Result;
+use crate::game::Game;
+use crate::ghost::Ghost;
+use crate::player::GameState;
+
+const TICK: f32 = 0.05; // Simulated seconds per update
+
+/// A game stepped forward on a simulated clock instead of in real time, for tests and headless runs
+pub struct GameSim<'a> {
+    pub game: Game,
+    config: &'a Config,
+    start: Instant,
+    now: Instant
+}
+
+impl<'a> GameSim<'a> {
+    /// Generate a fresh game from the config
+    pub fn new(config: &'a Config) -> Result<GameSim<'a>> {
+        Ok (GameSim::from_game(Game::new(config)?, config))
+    }
+
+    /// Simulate an existing game, eg. one with a hand-built world
+    pub fn from_game(game: Game, config: &'a Config) -> GameSim<'a> {
+        let start = Instant::now();
+        GameSim { game, config, start, now: start }
+    }
+
+    /// Simulated seconds since the game started
+    pub fn elapsed(&self) -> f32 {
+        (self.now - self.start).as_secs_f32()
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.game.player.game_state
+    }
+
+    /// Start moving the player by delta, arriving after the given number of seconds. Returns whether the move was allowed
+    pub fn step(&mut self, delta: [i32; 4], seconds: f32) -> bool {
+        self.game.try_move(self.now, delta, seconds)
+    }
+
+    /// Advance the clock by the given number of seconds, updating the game every tick
+    pub fn tick(&mut self, seconds: f32) {
+        for _ in 0..(seconds / TICK).round() as usize {
+            self.now += Duration::from_secs_f32(TICK);
+            self.game.update(self.now, self.config);
+        }
+    }
+
+    /// Let the bot take a step every move_time seconds until the game ends or the time limit runs out, keeping clear of the ghosts
+    pub fn play(&mut self, bot: &Bot, move_time: f32, time_limit: f32) {
+        while self.game.player.game_state == GameState::Playing && self.elapsed() < time_limit {
+            let ghosts: Vec<[usize; 4]> = if self.config.mode.has_ghost() { self.game.ghosts.iter().map(Ghost::cell).collect() } else { Vec::new() };
+            if let Some (delta) = bot.next_move_avoiding(&self.game.world, &self.game.objects, &self.game.player.inventory, self.game.player.cell(), &ghosts) {
+                self.step(delta, move_time);
+            }
+            self.tick(move_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DisplayClock, Mode, Respawn};
+    use crate::objects::{Inventory, PowerUp};
+    use crate::world::{Cell, Coordinate, Trap, World};
+    use crate::world::meta::Flags;
+
+    fn config() -> Config {
+        Config { dimensions: [4, 4, 2, 2], food_count: 6, ..Default::default() }
+    }
+
+    fn cell((x, y, z, w): Coordinate) -> [i32; 4] {
+        [x as i32, y as i32, z as i32, w as i32]
+    }
+
+    // Every step of the path is a single move the rules allow
+    fn assert_walkable(world: &World, path: &[Coordinate]) {
+        for pair in path.windows(2) {
+            let (from, to) = (cell(pair[0]), cell(pair[1]));
+            let delta = [0, 1, 2, 3].map(|i| to[i] - from[i]);
+            assert!(world.check_move(from, delta, &Inventory::new()), "Path steps through a wall from {:?} to {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn solution_path_is_always_valid() {
+        let config = config();
+        for _ in 0..20 {
+            let sim = GameSim::new(&config).unwrap();
+            let world = &sim.game.world;
+            assert_eq!(world.solution.first(), Some (&world.start));
+            assert_eq!(world.solution.last(), Some (&world.exit));
+            assert_walkable(world, &world.solution);
+        }
+    }
+
+    #[test]
+    fn every_food_is_reachable() {
+        let config = config();
+        for _ in 0..20 {
+            let sim = GameSim::new(&config).unwrap();
+            let world = &sim.game.world;
+            assert_eq!(sim.game.objects.food().count(), config.food_count);
+            for food in sim.game.objects.food() {
+                let path = world.bfs(world.start, *food);
+                assert_eq!(path.last(), Some (food));
+                assert_walkable(world, &path);
+            }
+        }
+    }
+
+    #[test]
+    fn ghost_catches_stationary_player() {
+        let config = Config { ghost_move_time: 0.2, lives: 1, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        sim.game.ghosts[0].wake();
+        sim.tick(120.0);
+        assert_eq!(sim.state(), &GameState::Lost);
+        assert!(sim.game.ghosts[0].caught(&sim.game.player));
+    }
+
+    #[test]
+    fn a_pack_of_ghosts_catches_stationary_player() {
+        let config = Config { ghost_move_time: 0.2, ghosts: 3, lives: 1, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        assert_eq!(sim.game.ghosts.len(), 3);
+        sim.game.ghosts.iter_mut().for_each(Ghost::wake);
+        sim.tick(120.0);
+        assert_eq!(sim.state(), &GameState::Lost);
+        assert!(sim.game.ghosts.iter().any(|ghost| ghost.caught(&sim.game.player)));
+    }
+
+    #[test]
+    fn zen_has_no_ghost_or_clock() {
+        let config = Config { ghost_move_time: 0.2, lives: 1, mode: Mode::Zen, display_clock: DisplayClock::Timer (5), ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        sim.game.ghosts[0].wake();
+        let deltas = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]];
+        deltas.iter().find(|delta| sim.step(**delta, 0.5));
+        sim.tick(120.0);
+        assert_eq!(sim.state(), &GameState::Playing);
+        assert_eq!(sim.game.player.time_limit(), None);
+    }
+
+    #[test]
+    fn survival_brings_food_back_and_speeds_the_ghost_up() {
+        let config = Config { mode: Mode::Survival, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        let &(x, y, z, w) = sim.game.objects.food().next().unwrap();
+        sim.game.world.cells[w][z][y][x] = Cell::Empty;
+        sim.game.objects.remove_food((x, y, z, w));
+        sim.tick(0.1);
+        assert_eq!(sim.game.objects.food().count(), config.food_count);
+        sim.tick(21.0);
+        assert!(sim.game.ghosts[0].move_time() < config.ghost_move_time);
+        assert!(!sim.game.world.exit_open);
+    }
+
+    #[test]
+    fn time_attacks_are_lost_past_par() {
+        let config = Config { mode: Mode::TimeAttack, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        let par = sim.game.par_time(&config);
+        assert_eq!(sim.game.player.time_limit(), Some (par));
+        assert!(par as usize > sim.game.world.solution.len());
+        let deltas = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]];
+        deltas.iter().find(|delta| sim.step(**delta, 0.5));
+        sim.tick(par as f32 - 1.0);
+        assert_eq!(sim.state(), &GameState::Playing);
+        sim.tick(2.0);
+        assert_eq!(sim.state(), &GameState::Lost);
+    }
+
+    #[test]
+    fn the_first_one_out_wins_a_race() {
+        let config = Config { mode: Mode::Zen, split_screen: true, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        assert_eq!(sim.game.rival.as_ref().unwrap().cell(), sim.game.player.cell());
+        sim.play(&Bot, 0.5, 600.0);
+        assert_eq!(sim.state(), &GameState::Won);
+        assert_eq!(sim.game.rival.as_ref().unwrap().game_state, GameState::Lost);
+        assert!(!sim.game.in_play());
+    }
+
+    #[test]
+    fn players_fly_out_through_the_exit_before_winning() {
+        let config = Config { mode: Mode::Zen, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        while !sim.game.player.escaping() && sim.elapsed() < 600.0 {
+            if let Some (delta) = Bot.next_move(&sim.game.world, &sim.game.objects, &sim.game.player.inventory, sim.game.player.cell()) {
+                sim.step(delta, 0.0);
+            }
+            sim.tick(TICK);
+        }
+        let stopwatch = sim.game.player.stopwatch;
+        assert_eq!(sim.state(), &GameState::Playing);
+        assert!(sim.game.in_play());
+        assert!(!sim.step(sim.game.world.exit_delta, 0.0));
+        sim.tick(2.0);
+        assert_eq!(sim.state(), &GameState::Won);
+        assert_eq!(sim.game.player.stopwatch, stopwatch);
+        let (x, y, z, w) = sim.game.world.exit;
+        let exit = [x, y, z, w].map(|i| i as f32);
+        assert_ne!(sim.game.player.get_position(), exit);
+    }
+
+    // Dead end the bot never has to walk through, since it holds nothing to pick up and is neither the start nor the exit
+    fn dead_end(sim: &GameSim) -> Option<[usize; 4]> {
+        let world = &sim.game.world;
+        let mut all_keys = Inventory::new();
+        (0..world.doors.len()).for_each(|key| all_keys.add_key(key));
+        let deltas = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]];
+        let cells = (0..world.fourth).flat_map(|w| (0..world.depth).flat_map(move |z| (0..world.height).flat_map(move |y| (0..world.width).map(move |x| (x, y, z, w)))));
+        cells.filter(|&(x, y, z, w)| (x, y, z, w) != world.start && (x, y, z, w) != world.exit && world.cells[w][z][y][x] == Cell::Empty)
+            .find(|c| deltas.iter().filter(|delta| world.check_move(cell(*c), **delta, &all_keys)).count() == 1)
+            .map(|(x, y, z, w)| [x, y, z, w])
+    }
+
+    #[test]
+    fn exit_opens_once_all_food_is_eaten() {
+        // Ghost is parked out of the bot's way, and too slow to ever leave
+        let config = Config { ghost_move_time: 1_000_000.0, doors: 2, ..config() };
+        let mut sim = (0..50).map(|_| GameSim::new(&config).unwrap()).find(|sim| dead_end(sim).is_some()).expect("No maze with a dead end");
+        sim.game.ghosts[0] = Ghost::at(&config, dead_end(&sim).unwrap());
+        let exit = cell(sim.game.world.exit);
+        assert!(!sim.game.world.check_move(exit, sim.game.world.exit_delta, &sim.game.player.inventory));
+        sim.play(&Bot, 0.5, 600.0);
+        assert_eq!(sim.state(), &GameState::Won);
+        assert_eq!(sim.game.player.score, config.food_count as u32);
+        assert_eq!(sim.game.objects.keys().count(), 0);
+        assert!(sim.game.world.exit_open);
+        assert!(sim.game.world.check_move(exit, sim.game.world.exit_delta, &sim.game.player.inventory));
+        assert!(!sim.game.world.contains(sim.game.player.cell()));
+    }
+
+    #[test]
+    fn cells_walked_through_are_marked() {
+        let config = Config { mode: Mode::Zen, respawn: Respawn::Checkpoint, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        let food: Vec<Coordinate> = sim.game.objects.food().copied().collect();
+        sim.play(&Bot, 0.5, 600.0);
+        assert_eq!(sim.state(), &GameState::Won);
+        let meta = &sim.game.world.meta;
+        assert!(meta.has(sim.game.world.start, Flags::VISITED));
+        assert!(food.iter().all(|&cell| meta.has(cell, Flags::VISITED)));
+        assert_eq!(meta.count(Flags::CHECKPOINT), 1);
+        assert!(food.iter().any(|&cell| meta.has(cell, Flags::CHECKPOINT)));
+    }
+
+    #[test]
+    fn walls_block_moves() {
+        let config = config();
+        let mut sim = GameSim::new(&config).unwrap();
+        let blocked = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]]
+            .into_iter()
+            .find(|delta| !sim.game.world.check_move(sim.game.player.cell(), *delta, &sim.game.player.inventory))
+            .unwrap();
+        let before = sim.game.player.cell();
+        assert!(!sim.step(blocked, 0.0));
+        assert_eq!(sim.game.player.cell(), before);
+    }
+
+    #[test]
+    fn boots_halve_move_time() {
+        let config = config();
+        let mut sim = GameSim::new(&config).unwrap();
+        let (x, y, z, w) = sim.game.world.start;
+        sim.game.world.cells[w][z][y][x] = Cell::PowerUp (PowerUp::Boots);
+        sim.tick(TICK);
+        assert_eq!(sim.game.player.effects(sim.now).map(|(effect, _)| effect).collect::<Vec<_>>(), vec![PowerUp::Boots]);
+        let open = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]]
+            .into_iter()
+            .find(|delta| sim.game.world.check_move(sim.game.player.cell(), *delta, &sim.game.player.inventory))
+            .unwrap();
+        assert!(sim.step(open, 1.0));
+        sim.tick(0.6);
+        let dest = [0, 1, 2, 3].map(|i| (cell(sim.game.world.start)[i] + open[i]) as f32);
+        assert_eq!(sim.game.player.get_position(), dest);
+    }
+
+    #[test]
+    fn flashlight_runs_down_until_recharged() {
+        let config = Config { flashlight_time: 2.0, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        sim.game.player.flashlight.toggle();
+        sim.tick(1.0);
+        assert!(sim.game.player.flashlight.lit());
+        assert!((sim.game.player.flashlight.battery() - 0.5).abs() < 0.05);
+        sim.tick(1.5);
+        assert!(!sim.game.player.flashlight.lit());
+        assert_eq!(sim.game.player.flashlight.battery(), 0.0);
+
+        // A flat battery can't be switched back on until a new one is picked up
+        sim.game.player.flashlight.toggle();
+        assert!(!sim.game.player.flashlight.lit());
+        let (x, y, z, w) = sim.game.world.start;
+        sim.game.world.cells[w][z][y][x] = Cell::Battery;
+        sim.tick(TICK);
+        assert_eq!(sim.game.player.flashlight.battery(), 1.0);
+        sim.game.player.flashlight.toggle();
+        assert!(sim.game.player.flashlight.lit());
+    }
+
+    #[test]
+    fn fruit_comes_and_goes_along_the_solution() {
+        let config = Config { fruit_interval: 2.0, fruit_time: 3.0, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        // Food under the player would be eaten on the first tick, so clear it from the start
+        let (x, y, z, w) = sim.game.world.start;
+        sim.game.world.cells[w][z][y][x] = Cell::Empty;
+        sim.game.objects.remove_food(sim.game.world.start);
+        sim.tick(1.0);
+        assert_eq!(sim.game.objects.fruit(), None);
+        sim.tick(1.5);
+        let fruit = sim.game.objects.fruit().expect("Fruit should be out");
+        assert!(sim.game.world.solution.contains(&fruit));
+        let (x, y, z, w) = fruit;
+        assert_eq!(sim.game.world.cells[w][z][y][x], Cell::Fruit);
+        sim.tick(3.0);
+        assert_eq!(sim.game.objects.fruit(), None);
+        assert_eq!(sim.game.world.cells[w][z][y][x], Cell::Empty);
+
+        // Eating fruit scores bonus points, without counting towards the food
+        let (x, y, z, w) = sim.game.world.start;
+        sim.game.world.cells[w][z][y][x] = Cell::Fruit;
+        sim.tick(TICK);
+        assert_eq!(sim.game.player.bonus, config.fruit_points);
+        assert_eq!(sim.game.player.score, 0);
+    }
+
+    #[test]
+    fn teleporters_link_both_ways() {
+        let config = Config { teleporters: 1, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        let (a, b) = sim.game.world.teleporters[0];
+        sim.game.player.set_position(cell(a).map(|i| i as f32));
+        sim.tick(0.1);
+        assert_eq!(sim.game.player.cell(), cell(b));
+
+        // Arriving on a pad doesn't send the player straight back
+        sim.tick(1.0);
+        assert_eq!(sim.game.player.cell(), cell(b));
+
+        // But stepping off and back on again does
+        let off = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]]
+            .into_iter()
+            .find(|delta| sim.game.world.check_move(cell(b), *delta, &sim.game.player.inventory))
+            .unwrap();
+        assert!(sim.step(off, 0.0));
+        sim.tick(0.1);
+        assert!(sim.step(off.map(|i| -i), 0.0));
+        sim.tick(0.1);
+        assert_eq!(sim.game.player.cell(), cell(a));
+    }
+
+    // Put something in a cell next to the start, returning the move into it
+    fn next_to_start(sim: &mut GameSim, contents: Cell) -> [i32; 4] {
+        let start = cell(sim.game.world.start);
+        let delta = [[1, 0, 0, 0], [-1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 0, 0], [0, 0, 1, 0], [0, 0, -1, 0], [0, 0, 0, 1], [0, 0, 0, -1]]
+            .into_iter()
+            .find(|delta| sim.game.world.check_move(start, *delta, &sim.game.player.inventory))
+            .unwrap();
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| (start[i] + delta[i]) as usize);
+        sim.game.world.cells[w][z][y][x] = contents;
+        delta
+    }
+
+    #[test]
+    fn snares_hold_the_player() {
+        let config = config();
+        let mut sim = GameSim::new(&config).unwrap();
+        let delta = next_to_start(&mut sim, Cell::Trap (Trap::Snare));
+        assert!(sim.step(delta, 0.0));
+        sim.tick(TICK);
+        let back = delta.map(|i| -i);
+        assert!(!sim.step(back, 0.0));
+        sim.tick(2.0);
+        assert!(sim.step(back, 0.0));
+        assert_eq!(sim.game.player.cell(), cell(sim.game.world.start));
+    }
+
+    #[test]
+    fn spikes_cost_a_life() {
+        let config = config();
+        let mut sim = GameSim::new(&config).unwrap();
+        let delta = next_to_start(&mut sim, Cell::Trap (Trap::Spikes));
+        assert!(sim.step(delta, 0.0));
+        sim.tick(TICK);
+        assert_eq!(sim.state(), &GameState::Respawning);
+        assert_eq!(sim.game.player.lives, config.lives - 1);
+        assert_eq!(sim.game.player.cell(), cell(sim.game.world.start));
+    }
+
+    #[test]
+    fn pits_drop_to_the_level_below() {
+        let config = config();
+        let mut sim = GameSim::new(&config).unwrap();
+        sim.game.world.cells[0][1][2][3] = Cell::Trap (Trap::Pit);
+        sim.game.player.set_position([3.0, 2.0, 1.0, 0.0]);
+        sim.tick(0.1);
+        assert_eq!(sim.game.player.cell(), [3, 2, 0, 0]);
+    }
+
+    #[test]
+    fn quick_eating_builds_a_combo() {
+        let config = Config { ghost_move_time: 1_000_000.0, combo_time: 3.0, combo_max: 5, ..config() };
+        let mut sim = GameSim::new(&config).unwrap();
+        let (x, y, z, w) = sim.game.world.start;
+        let delta = next_to_start(&mut sim, Cell::Food);
+        let back = delta.map(|i| -i);
+
+        // First food starts the combo off at no extra points
+        assert!(sim.step(delta, 0.0));
+        sim.tick(TICK);
+        assert_eq!((sim.game.player.combo, sim.game.player.combo_bonus), (1, 0));
+
+        // Food eaten straight after is worth double
+        sim.game.world.cells[w][z][y][x] = Cell::Food;
+        assert!(sim.step(back, 0.0));
+        sim.tick(TICK);
+        assert_eq!((sim.game.player.combo, sim.game.player.combo_bonus), (2, 1));
+
+        // Waiting too long breaks the combo
+        sim.tick(config.combo_time + 0.5);
+        assert_eq!(sim.game.player.combo, 1);
+        next_to_start(&mut sim, Cell::Food);
+        assert!(sim.step(delta, 0.0));
+        sim.tick(TICK);
+        assert_eq!((sim.game.player.combo, sim.game.player.combo_bonus), (1, 1));
+        assert_eq!(sim.game.player.score, 3);
+        assert_eq!(sim.game.player.final_score(&config).combo, 1);
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        let config = Config { doors: 2, teleporters: 1, traps: 2, power_ups: 2, batteries: 1, ..config() };
+        let maze = |seed| Game::from_seed(&config, seed).unwrap().world.to_string();
+        assert_eq!(maze(7), maze(7));
+        assert_ne!(maze(7), maze(8));
+    }
+}
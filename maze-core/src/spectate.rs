@@ -0,0 +1,298 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+
+use crate::error::{MazeError, Result};
+use crate::game::Game;
+use crate::player::GameState;
+use crate::world::World;
+
+/// Port a game is broadcast on unless another is given
+pub const DEFAULT_PORT: u16 = 47_475;
+
+const SEND_INTERVAL: f32 = 0.05; // Seconds between positions sent to watchers
+const MAP_INTERVAL: f32 = 1.0; // Seconds between checks for the maze having changed, eg. food eaten or walls shifted
+const CONNECT_TIMEOUT: f32 = 10.0; // Seconds a watcher waits for the first map
+const MAX_UNSENT: usize = 1 << 20; // Bytes kept for a watcher who isn't reading before they're dropped
+const MAX_MAP_LINES: usize = 1 << 20; // Most lines a broadcast maze is believed to take
+
+/// Serves the running game to anyone watching, over plain TCP so a second copy of the game or a web overlay can
+/// follow along. Everything is sent as lines of text:
+///
+/// - `map N` followed by N lines of the maze in the level file format, whenever it changes
+/// - `player X Y Z W SCORE LIVES STATE`, with STATE one of playing, respawning, won, lost or reviewing
+/// - `ghosts X Y Z W X Y Z W ...`, four numbers for each ghost
+pub struct Broadcaster {
+    listener: TcpListener,
+    watchers: Vec<(TcpStream, Vec<u8>)>, // Each with what couldn't be written to them yet
+    map: String, // Maze as last sent
+    next_send: Option<Instant>,
+    next_map: Option<Instant>
+}
+
+impl Broadcaster {
+    /// Start accepting watchers on the given port, or any free one for 0
+    pub fn open(port: u16) -> Result<Broadcaster> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| network(format!("Couldn't broadcast on port {}: {}", port, e)))?;
+        listener.set_nonblocking(true).map_err(|e| network(e.to_string()))?;
+        Ok (Broadcaster { listener, watchers: Vec::new(), map: String::new(), next_send: None, next_map: None })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |address| address.port())
+    }
+
+    /// Let in anyone new, and send the game's state to everyone watching once each update is due.
+    /// Watchers who have gone away are dropped
+    pub fn update(&mut self, now: Instant, game: &Game) {
+        while let Ok ((stream, address)) = self.listener.accept() {
+            // Accepted streams block even though the listener doesn't, and a watcher who stops reading mustn't stall the game
+            if stream.set_nonblocking(true).and_then(|()| stream.set_nodelay(true)).is_err() {
+                continue;
+            }
+            info!(target: "net", "{} started watching", address);
+            let mut watcher = (stream, Vec::new());
+            if self.map.is_empty() || write(&mut watcher, &map_message(&self.map)).is_ok() {
+                self.watchers.push(watcher);
+            }
+        }
+        let next_map = self.next_map.get_or_insert(now);
+        if now >= *next_map {
+            *next_map = now + Duration::from_secs_f32(MAP_INTERVAL);
+            let map = game.world.to_string();
+            if map != self.map {
+                self.map = map;
+                self.send(&map_message(&self.map));
+            }
+        }
+        let next_send = self.next_send.get_or_insert(now);
+        if now >= *next_send {
+            *next_send = now + Duration::from_secs_f32(SEND_INTERVAL);
+            self.send(&positions_message(game));
+        }
+    }
+
+    // Write to every watcher, losing any that can't be written to or have fallen too far behind
+    fn send(&mut self, message: &str) {
+        self.watchers.retain_mut(|watcher| match write(watcher, message) {
+            Ok (()) => true,
+            Err (e) => {
+                info!(target: "net", "A watcher went away: {}", e);
+                false
+            }
+        });
+    }
+}
+
+// Queue the message after anything still waiting for the watcher, and write as much as they'll take now
+fn write((stream, unsent): &mut (TcpStream, Vec<u8>), message: &str) -> io::Result<()> {
+    if unsent.len() + message.len() > MAX_UNSENT {
+        return Err (io::Error::other("Stopped reading the broadcast"));
+    }
+    unsent.extend_from_slice(message.as_bytes());
+    while !unsent.is_empty() {
+        match stream.write(unsent) {
+            Ok (0) => return Err (io::ErrorKind::WriteZero.into()),
+            Ok (length) => drop(unsent.drain(..length)),
+            Err (e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err (e) => return Err (e)
+        }
+    }
+    Ok (())
+}
+
+fn map_message(map: &str) -> String {
+    format!("map {}\n{}", map.lines().count(), map)
+}
+
+fn positions_message(game: &Game) -> String {
+    let numbers = |position: [f32; 4]| position.map(|i| i.to_string()).join(" ");
+    let state = match game.player.game_state {
+        GameState::Playing => "playing",
+        GameState::Respawning => "respawning",
+        GameState::Won => "won",
+        GameState::Lost => "lost",
+        GameState::Reviewing => "reviewing"
+    };
+    let ghosts: Vec<String> = game.ghosts.iter().map(|ghost| numbers(ghost.position())).collect();
+    format!("player {} {} {} {}\nghosts {}\n", numbers(game.player.get_position()), game.player.score, game.player.lives, state, ghosts.join(" "))
+}
+
+/// Follows a game broadcast from elsewhere, keeping up with where everyone is
+pub struct Watcher {
+    stream: TcpStream,
+    received: String, // Text not yet read as whole lines
+    pub player: [f32; 4],
+    pub score: u32,
+    pub lives: u32,
+    pub state: GameState,
+    pub ghosts: Vec<[f32; 4]>
+}
+
+impl Watcher {
+    /// Start watching the game broadcast at the address, waiting for its maze
+    pub fn connect(address: &str) -> Result<(Watcher, World)> {
+        let address = if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) };
+        let stream = TcpStream::connect(&address).map_err(|e| network(format!("Couldn't reach {}: {}", address, e)))?;
+        stream.set_nonblocking(true).map_err(|e| network(e.to_string()))?;
+        let mut watcher = Watcher { stream, received: String::new(), player: [0.0; 4], score: 0, lives: 0, state: GameState::Playing, ghosts: Vec::new() };
+        let started = Instant::now();
+        while started.elapsed().as_secs_f32() < CONNECT_TIMEOUT {
+            if let Some (world) = watcher.update()? {
+                return Ok ((watcher, world));
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        Err (network(format!("No maze came from {}", address)))
+    }
+
+    /// Take in everything sent since the last update, returning the maze if a new one arrived
+    pub fn update(&mut self) -> Result<Option<World>> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok (0) => return Err (network("The broadcast ended")),
+                Ok (length) => self.received.push_str(&String::from_utf8_lossy(&buffer[..length])),
+                Err (e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err (e) => return Err (network(e.to_string()))
+            }
+        }
+        let mut world = None;
+        let lines: Vec<&str> = self.received.split_inclusive('\n').collect();
+        let mut used = 0; // Bytes of whole messages read
+        let mut i = 0;
+        while i < lines.len() && lines[i].ends_with('\n') {
+            let line = lines[i].trim_end();
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some ("map") => {
+                    // The count comes from the other end, so one too big to ever arrive is skipped rather than waited on
+                    let end = parts.next().and_then(|count| count.parse::<usize>().ok())
+                        .filter(|&count| count <= MAX_MAP_LINES)
+                        .and_then(|count| i.checked_add(count));
+                    let Some (end) = end else {
+                        debug!(target: "net", "Skipped a broadcast maze of impossibly many lines: {}", line);
+                        used += lines[i].len();
+                        i += 1;
+                        continue;
+                    };
+                    if end >= lines.len() || !lines[end].ends_with('\n') {
+                        break; // Wait for the rest of the maze
+                    }
+                    let map: String = lines[i + 1..=end].concat();
+                    match World::parse_level(&map) {
+                        Ok (parsed) => world = Some (parsed),
+                        Err ((line, reason)) => debug!(target: "net", "Couldn't read the broadcast maze at line {}: {}", line, reason)
+                    }
+                    used += lines[i..=end].iter().map(|line| line.len()).sum::<usize>();
+                    i = end + 1;
+                    continue;
+                },
+                Some ("player") => {
+                    let numbers: Vec<&str> = parts.collect();
+                    if let [x, y, z, w, score, lives, state] = numbers[..] {
+                        if let (Ok (x), Ok (y), Ok (z), Ok (w)) = (x.parse(), y.parse(), z.parse(), w.parse()) {
+                            self.player = [x, y, z, w];
+                        }
+                        self.score = score.parse().unwrap_or(self.score);
+                        self.lives = lives.parse().unwrap_or(self.lives);
+                        self.state = match state {
+                            "respawning" => GameState::Respawning,
+                            "won" => GameState::Won,
+                            "lost" => GameState::Lost,
+                            "reviewing" => GameState::Reviewing,
+                            _ => GameState::Playing
+                        };
+                    }
+                },
+                Some ("ghosts") => {
+                    let numbers: Vec<f32> = parts.filter_map(|number| number.parse().ok()).collect();
+                    self.ghosts = numbers.chunks_exact(4).map(|ghost| [ghost[0], ghost[1], ghost[2], ghost[3]]).collect();
+                },
+                _ => {}
+            }
+            used += lines[i].len();
+            i += 1;
+        }
+        self.received.drain(..used);
+        Ok (world)
+    }
+}
+
+fn network(reason: impl Into<String>) -> MazeError {
+    MazeError::Network (reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn watchers_see_the_same_maze_and_positions() {
+        let config = Config { dimensions: [3, 3, 2, 2], food_count: 2, ..Default::default() };
+        let game = Game::new(&config).unwrap();
+        let mut broadcaster = Broadcaster::open(0).unwrap();
+        let address = format!("127.0.0.1:{}", broadcaster.port());
+        let watching = std::thread::spawn(move || {
+            let (mut watcher, world) = Watcher::connect(&address).unwrap();
+            let started = Instant::now();
+            while watcher.ghosts.is_empty() && started.elapsed().as_secs_f32() < CONNECT_TIMEOUT {
+                watcher.update().unwrap();
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            (watcher.player, watcher.ghosts.len(), world.to_string())
+        });
+        let started = Instant::now();
+        while !watching.is_finished() && started.elapsed().as_secs_f32() < CONNECT_TIMEOUT {
+            broadcaster.update(Instant::now(), &game);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let (player, ghosts, map) = watching.join().unwrap();
+        assert_eq!(map, game.world.to_string());
+        assert_eq!(player, game.player.get_position());
+        assert_eq!(ghosts, game.ghosts.len());
+    }
+
+    #[test]
+    fn a_watcher_who_stops_reading_is_dropped_without_stalling() {
+        let config = Config { dimensions: [3, 3, 2, 2], food_count: 2, ..Default::default() };
+        let game = Game::new(&config).unwrap();
+        let mut broadcaster = Broadcaster::open(0).unwrap();
+        let _stalled = TcpStream::connect(("127.0.0.1", broadcaster.port())).unwrap();
+        let started = Instant::now();
+        while broadcaster.watchers.is_empty() && started.elapsed().as_secs_f32() < CONNECT_TIMEOUT {
+            broadcaster.update(Instant::now(), &game);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(broadcaster.watchers.len(), 1);
+        let message = "ghosts".to_string() + &" 0".repeat(1 << 16) + "\n";
+        // However much the socket buffers take, the backlog passes the limit well before this much has been sent
+        for _ in 0..256 * MAX_UNSENT / message.len() {
+            broadcaster.send(&message);
+            if broadcaster.watchers.is_empty() {
+                break;
+            }
+        }
+        assert!(broadcaster.watchers.is_empty());
+    }
+
+    #[test]
+    fn a_huge_map_count_is_skipped() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let (mut broadcast, _) = listener.accept().unwrap();
+        broadcast.write_all(format!("map {}\nplayer 1 2 0 0 5 3 won\n", usize::MAX).as_bytes()).unwrap();
+        let mut watcher = Watcher { stream, received: String::new(), player: [0.0; 4], score: 0, lives: 0, state: GameState::Playing, ghosts: Vec::new() };
+        let started = Instant::now();
+        while watcher.state != GameState::Won && started.elapsed().as_secs_f32() < CONNECT_TIMEOUT {
+            assert!(watcher.update().unwrap().is_none());
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(watcher.player, [1.0, 2.0, 0.0, 0.0]);
+        assert_eq!(watcher.score, 5);
+    }
+}
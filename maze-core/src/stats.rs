@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::maze_size;
+use crate::error::{MazeError, Result};
+
+/// How a finished run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Escaped, Caught
+}
+
+/// One finished run, as kept in the stats file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Run {
+    pub dimensions: [usize; 4],
+    pub seed: u64,
+    pub seconds: u32,
+    pub outcome: Outcome,
+    pub moves: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<String> // Date of the daily challenge played, kept apart from the best times
+}
+
+/// Every run finished with one config file or profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(default)]
+    pub runs: Vec<Run>
+}
+
+/// Stats file kept alongside a config file, eg. config.stats for config.toml, so each profile has its own
+pub fn path(config: &str) -> String {
+    Path::new(config).with_extension("stats").to_string_lossy().into_owned()
+}
+
+impl Stats {
+    /// Read the stats file, starting afresh if there isn't one yet
+    pub fn load(path: &str) -> Result<Stats> {
+        if !Path::new(path).exists() {
+            return Ok (Stats::default());
+        }
+        let contents = fs::read_to_string(path).map_err(|e| MazeError::io(path, e))?;
+        toml::from_str(&contents).map_err(|e| MazeError::Stats { path: path.to_string(), reason: e.to_string() })
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let contents = toml::to_string(self).map_err(|e| MazeError::Stats { path: path.to_string(), reason: e.to_string() })?;
+        fs::write(path, contents).map_err(|e| MazeError::io(path, e))
+    }
+
+    /// Add a run to the stats file, returning everything recorded so far
+    pub fn record(path: &str, run: Run) -> Result<Stats> {
+        let mut stats = Stats::load(path)?;
+        stats.runs.push(run);
+        stats.save(path)?;
+        Ok (stats)
+    }
+
+    /// Fastest escape from each size of maze, keyed by its size as written in the config file. Daily challenges don't count
+    pub fn best_times(&self) -> BTreeMap<String, u32> {
+        let mut best = BTreeMap::new();
+        for run in self.runs.iter().filter(|run| run.outcome == Outcome::Escaped && run.daily.is_none()) {
+            let seconds = best.entry(maze_size(&run.dimensions)).or_insert(run.seconds);
+            *seconds = run.seconds.min(*seconds);
+        }
+        best
+    }
+
+    /// Every daily challenge played, keyed by its date, with the fastest escape from it if there was one
+    pub fn daily(&self) -> BTreeMap<String, Option<u32>> {
+        let mut days = BTreeMap::new();
+        for run in &self.runs {
+            if let Some (date) = &run.daily {
+                let best = days.entry(date.clone()).or_insert(None);
+                if run.outcome == Outcome::Escaped {
+                    *best = Some (best.map_or(run.seconds, |seconds: u32| seconds.min(run.seconds)));
+                }
+            }
+        }
+        days
+    }
+
+    /// Number of mazes escaped from
+    pub fn solved(&self) -> usize {
+        self.runs.iter().filter(|run| run.outcome == Outcome::Escaped).count()
+    }
+
+    /// Fraction of runs escaped from rather than caught by the ghost, or 0 before any have been played
+    pub fn win_rate(&self) -> f32 {
+        if self.runs.is_empty() {
+            0.0
+        } else {
+            self.solved() as f32 / self.runs.len() as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(dimensions: [usize; 4], seconds: u32, outcome: Outcome) -> Run {
+        Run { dimensions, seed: 1, seconds, outcome, moves: 20, daily: None }
+    }
+
+    #[test]
+    fn best_times_only_count_escapes() {
+        let stats = Stats { runs: vec![
+            run([5, 5, 3, 3], 90, Outcome::Escaped),
+            run([5, 5, 3, 3], 40, Outcome::Caught),
+            run([5, 5, 3, 3], 60, Outcome::Escaped),
+            run([3, 3, 1, 1], 20, Outcome::Escaped)
+        ] };
+        let best = stats.best_times();
+        assert_eq!(best.get("5x5x3x3"), Some (&60));
+        assert_eq!(best.get("3x3x1x1"), Some (&20));
+        assert_eq!(stats.solved(), 3);
+        assert_eq!(stats.win_rate(), 0.75);
+        assert_eq!(Stats::default().win_rate(), 0.0);
+    }
+
+    #[test]
+    fn daily_challenges_are_kept_apart() {
+        let daily = |date: &str, seconds, outcome| Run { daily: Some (date.to_string()), ..run([5, 5, 3, 3], seconds, outcome) };
+        let stats = Stats { runs: vec![
+            run([5, 5, 3, 3], 90, Outcome::Escaped),
+            daily("2026-10-15", 30, Outcome::Escaped),
+            daily("2026-10-16", 50, Outcome::Caught),
+            daily("2026-10-15", 45, Outcome::Escaped)
+        ] };
+        assert_eq!(stats.best_times().get("5x5x3x3"), Some (&90));
+        let days = stats.daily();
+        assert_eq!(days.get("2026-10-15"), Some (&Some (30)));
+        assert_eq!(days.get("2026-10-16"), Some (&None));
+    }
+
+    #[test]
+    fn stats_read_back_the_same() {
+        let stats = Stats { runs: vec![run([5, 5, 3, 3], 90, Outcome::Escaped), run([3, 3, 1, 1], 20, Outcome::Caught)] };
+        let read: Stats = toml::from_str(&toml::to_string(&stats).unwrap()).unwrap();
+        assert_eq!(read, stats);
+    }
+}
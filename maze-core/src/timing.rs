@@ -0,0 +1,163 @@
+use std::hint;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::config::TargetFps;
+
+// Sleeps can overshoot, so wait out the end of each frame spinning instead
+const SPIN_TIME: Duration = Duration::from_millis(1);
+
+/// Logs how long it lived at debug level once dropped, for timing a block of work
+pub struct Span {
+    target: &'static str,
+    name: &'static str,
+    start: Instant
+}
+
+impl Span {
+    pub fn new(target: &'static str, name: &'static str) -> Span {
+        Span { target, name, start: Instant::now() }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        debug!(target: self.target, "{} took {:.2}ms", self.name, self.start.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+/// Keeps frames to the target rate, sleeping until each one is due
+pub struct FramePacer {
+    interval: Option<Duration>, // None for unlimited
+    next_frame: Instant
+}
+
+impl FramePacer {
+    pub fn new(target: &TargetFps) -> FramePacer {
+        FramePacer { interval: interval(target), next_frame: Instant::now() }
+    }
+
+    /// Change the frame rate cap, eg. after the config file is edited
+    pub fn set_target(&mut self, target: &TargetFps) {
+        self.interval = interval(target);
+        self.next_frame = Instant::now();
+    }
+
+    /// Wait until the next frame is due, returning the time it starts
+    pub fn wait(&mut self) -> Instant {
+        let interval = match self.interval {
+            Some (interval) => interval,
+            None => return Instant::now()
+        };
+        let now = Instant::now();
+        if now + SPIN_TIME < self.next_frame {
+            thread::sleep(self.next_frame - now - SPIN_TIME);
+        }
+        while Instant::now() < self.next_frame {
+            hint::spin_loop();
+        }
+        // Keep to a steady beat from when frames were due, unless a slow frame left us more than a whole frame behind
+        let now = Instant::now();
+        self.next_frame = if now.saturating_duration_since(self.next_frame) > interval { now + interval } else { self.next_frame + interval };
+        now
+    }
+}
+
+fn interval(target: &TargetFps) -> Option<Duration> {
+    match target {
+        TargetFps::Fixed (fps) if *fps > 0 => Some (Duration::from_secs_f64(1.0 / *fps as f64)),
+        _ => None
+    }
+}
+
+/// Time as the game sees it, which stands still while the game is paused and can be slowed down for effect
+#[derive(Default)]
+pub struct GameClock {
+    paused_at: Option<Instant>,
+    offset: Duration, // Total time spent paused, or lost to running slow
+    slow: Option<(Instant, f32)> // Real time the clock last changed speed, and the speed it's running at since, if not full
+}
+
+impl GameClock {
+    pub fn new() -> GameClock {
+        GameClock::default()
+    }
+
+    /// Game time at the given real time
+    pub fn time(&self, now: Instant) -> Instant {
+        let now = self.paused_at.unwrap_or(now);
+        now - self.offset - self.lost(now)
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused_at.is_none() {
+            self.settle(now);
+            self.paused_at = Some (now);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let Some (paused_at) = self.paused_at.take() {
+            self.offset += now - paused_at;
+            if let Some ((since, _)) = &mut self.slow {
+                *since = now;
+            }
+        }
+    }
+
+    /// Run game time at the given fraction of real time from now on, eg. 0.25 for slow motion or 1 for full speed
+    pub fn set_speed(&mut self, now: Instant, speed: f32) {
+        let now = self.paused_at.unwrap_or(now);
+        self.settle(now);
+        self.slow = if speed < 1.0 { Some ((now, speed.max(0.0))) } else { None };
+    }
+
+    /// Fraction of real time the clock is running at
+    pub fn speed(&self) -> f32 {
+        self.slow.map_or(1.0, |(_, speed)| speed)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Catch back up with real time, for a new game made after pausing the last one
+    pub fn reset(&mut self) {
+        *self = GameClock::new();
+    }
+
+    // Game time lost to running slow since the speed last changed
+    fn lost(&self, now: Instant) -> Duration {
+        self.slow.map_or(Duration::ZERO, |(since, speed)| now.saturating_duration_since(since).mul_f32(1.0 - speed))
+    }
+
+    // Fold the time lost to running slow into the offset, to count on from now
+    fn settle(&mut self, now: Instant) {
+        self.offset += self.lost(now);
+        if let Some ((since, _)) = &mut self.slow {
+            *since = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_motion_runs_game_time_slower() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut clock = GameClock::new();
+        clock.set_speed(start, 0.25);
+        assert_eq!(clock.time(start + 2 * second), start + second / 2);
+        // Pausing stops the clock altogether, and resuming carries on slow
+        clock.pause(start + 2 * second);
+        clock.resume(start + 5 * second);
+        assert_eq!(clock.time(start + 6 * second), start + 3 * second / 4);
+        clock.set_speed(start + 6 * second, 1.0);
+        assert_eq!(clock.time(start + 7 * second), start + 7 * second / 4);
+    }
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{info, warn};
+
+use crate::config::{Config, LiveSetting};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the config file from a background thread, passing on any settings that can change live.
+/// Changes to anything else are logged as needing a restart, and left alone
+pub struct ConfigWatcher {
+    changes: Receiver<LiveSetting>
+}
+
+impl ConfigWatcher {
+    /// Start watching the file, starting from the config it was loaded into. Command line overrides are
+    /// applied on top of every reload, so they keep winning over the file
+    pub fn new(path: &str, config: Config, overrides: Vec<(&'static str, String)>) -> ConfigWatcher {
+        let (sender, changes) = channel();
+        let path = path.to_string();
+        thread::spawn(move || {
+            let mut current = config;
+            let mut last = modified(&path);
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let now_modified = modified(&path);
+                if now_modified == last {
+                    continue;
+                }
+                last = now_modified;
+                let reloaded = match Config::new(&path).and_then(|mut c| c.override_with(&overrides).map(|_| c)) {
+                    Ok (reloaded) => reloaded,
+                    Err (e) => {
+                        warn!(target: "input", "Ignoring config change: {}", e);
+                        continue;
+                    }
+                };
+                let restart = current.restart_changes(&reloaded);
+                if !restart.is_empty() {
+                    warn!(target: "input", "Restart to apply changes to {}", restart.join(", "));
+                }
+                for change in current.live_changes(&reloaded) {
+                    current.apply(change);
+                    if sender.send(change).is_err() {
+                        return; // Game has closed
+                    }
+                }
+                info!(target: "input", "Reloaded {}", path);
+            }
+        });
+        ConfigWatcher { changes }
+    }
+
+    /// Settings changed since this was last called, oldest first
+    pub fn changes(&self) -> impl Iterator<Item = LiveSetting> + '_ {
+        self.changes.try_iter()
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
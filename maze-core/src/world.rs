@@ -0,0 +1,1389 @@
+use log::info;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::hash_set::HashSet;
+use std::collections::vec_deque::VecDeque;
+use std::fmt;
+use std::mem;
+
+use crate::disjoint_set::DisjointSet;
+use crate::config::{Config, ExitPlacement, Start};
+use crate::error::{MazeError, Result};
+use crate::objects::{Inventory, PowerUp};
+use crate::timing::Span;
+use meta::CellMeta;
+
+pub mod analysis;
+pub mod meta;
+
+const GENERATION_ATTEMPTS: usize = 20; // Mazes generated looking for one that meets the config's constraints
+const WALL_SWAPS: usize = 100; // Walls swapped on the closest of them, at most, to bring it the rest of the way
+const WALL_HALF_THICKNESS: f32 = 0.05; // How far walls reach either side of halfway between cells, for free movement
+const SWEEP_EPSILON: f32 = 1e-4; // Leeway for a box resting against a wall, so rounding can't carry it through
+
+/// Cell position as (x, y, z, w)
+pub type Coordinate = (usize, usize, usize, usize);
+
+/// Space a wall takes up in its level, as its lowest and highest corners in x and y
+pub type WallBox = [[f32; 2]; 2];
+
+/// Contents of a single cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Food,
+    Key (usize),
+    PowerUp (PowerUp),
+    Battery,
+    Fruit,
+    Teleporter (usize), // Index of the pair this pad belongs to
+    Trap (Trap)
+}
+
+/// Hazards that punish the player for walking into them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Spikes, // Costs a life
+    Snare, // Holds the player in place for a moment
+    Pit // Drops the player to the level below
+}
+
+/// Boundary between two neighboring cells, or between a cell and the outside
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wall {
+    NoWall,
+    SolidWall,
+    Door (usize) // Locked until the player holds the key with this number
+}
+
+/// Direction across which a wall separates two cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+    W
+}
+
+impl Axis {
+    pub const ALL: [Axis; 4] = [Axis::X, Axis::Y, Axis::Z, Axis::W];
+
+    /// Axis of a move by one cell along a single dimension, or None for any other move
+    pub fn of(delta: [i32; 4]) -> Option<Axis> {
+        match delta {
+            [-1 | 1, 0, 0, 0] => Some (Axis::X),
+            [0, -1 | 1, 0, 0] => Some (Axis::Y),
+            [0, 0, -1 | 1, 0] => Some (Axis::Z),
+            [0, 0, 0, -1 | 1] => Some (Axis::W),
+            _ => None
+        }
+    }
+}
+
+/// Every wall of a maze, stored flat for each axis. The wall across an axis at (x, y, z, w) lies between that cell and
+/// the one before it along the axis, so there's one more wall along the axis than there are cells, closing off the far
+/// side of the maze
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WallGrid {
+    dimensions: [usize; 4], // Of the maze in cells
+    walls: [Vec<Wall>; 4] // Across each axis, x changing fastest then y, z and w
+}
+
+impl WallGrid {
+    /// Walls around every cell of a maze with the given dimensions
+    pub fn new(dimensions: [usize; 4]) -> WallGrid {
+        let walls = Axis::ALL.map(|axis| vec![Wall::SolidWall; extent(dimensions, axis).iter().product()]);
+        WallGrid { dimensions, walls }
+    }
+
+    pub fn get(&self, axis: Axis, cell: Coordinate) -> Wall {
+        self.walls[axis as usize][self.index(axis, cell)]
+    }
+
+    pub fn set(&mut self, axis: Axis, cell: Coordinate, wall: Wall) {
+        let i = self.index(axis, cell);
+        self.walls[axis as usize][i] = wall;
+    }
+
+    /// Every wall across the axis in level z of slice w, row by row, with the x and y it's at. Walls across z are
+    /// those under the level's cells
+    pub fn level(&self, axis: Axis, w: usize, z: usize) -> impl Iterator<Item = ([usize; 2], Wall)> + '_ {
+        let [width, height, _, _] = extent(self.dimensions, axis);
+        let start = self.index(axis, (0, 0, z, w));
+        self.walls[axis as usize][start..start + width * height].iter().enumerate()
+            .map(move |(i, wall)| ([i % width, i / width], *wall))
+    }
+
+    // Where the wall is in its axis's list
+    fn index(&self, axis: Axis, (x, y, z, w): Coordinate) -> usize {
+        let [width, height, depth, fourth] = extent(self.dimensions, axis);
+        assert!(x < width && y < height && z < depth && w < fourth,
+            "No {:?} wall at {:?} in a maze of {:?} cells", axis, (x, y, z, w), self.dimensions);
+        ((w * depth + z) * height + y) * width + x
+    }
+}
+
+// Number of walls across the axis along each dimension
+fn extent(dimensions: [usize; 4], axis: Axis) -> [usize; 4] {
+    let mut extent = dimensions;
+    extent[axis as usize] += 1;
+    extent
+}
+
+/// Walls of a saved game, which may have shifted since the maze was generated. One string for each level, slice by
+/// slice, of every wall between cells within the level: those between each cell and the cell to its left, row by row,
+/// then those between each cell and the one above it, with # for a wall and . for a gap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorldSnapshot {
+    exit_open: bool,
+    levels: Vec<String>
+}
+
+/// A 4D maze: the walls between cells, what each cell contains, and where the game starts and ends
+#[derive(Clone)]
+pub struct World {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub fourth: usize,
+
+    // Dimensions: fourth x depth x height x width
+    pub cells: Vec<Vec<Vec<Vec<Cell>>>>,
+    pub walls: WallGrid,
+    pub meta: CellMeta, // Marks gameplay keeps on each cell, like where the player has been
+
+    pub start: Coordinate,
+    pub exit: Coordinate,
+    pub exit_delta: [i32; 4], // Direction to walk from the exit cell to leave the maze
+    pub exit_open: bool,
+    pub solution: Vec<Coordinate>, // Shortest path from start to exit
+    pub doors: Vec<(Coordinate, Coordinate)>, // Cells either side of each door, indexed by key number
+    pub teleporters: Vec<(Coordinate, Coordinate)>, // Linked pads
+    pub ghost_spawns: Vec<Coordinate>, // Where a hand-made level puts the ghost, or empty to place it at random
+
+    neighbors: HashMap<Coordinate, Vec<Coordinate>>,
+    changed_levels: Vec<(usize, usize)> // Levels (w, z) whose walls moved since they were last taken
+}
+
+impl World {
+    /// Generate a random maze from the config, drawing every random choice from the given generator
+    pub fn new(config: &Config, rng: &mut impl Rng) -> Result<World> {
+        let [width, height, depth, fourth] = config.dimensions;
+        if config.dimensions.contains(&0) {
+            return Err (MazeError::World (format!("Every dimension must be at least 1, but got {:?}", config.dimensions)));
+        }
+        let cells: usize = config.dimensions.iter().product();
+        if config.food_count > cells {
+            return Err (MazeError::World (format!("Can't fit {} food in a maze of only {} cells", config.food_count, cells)));
+        }
+        let _span = Span::new("world", "Maze generation");
+        // Generate mazes until one's solution is long enough and moves between slices enough, or settle for the one
+        // that came closest
+        let mut closest: Option<(World, (usize, usize))> = None;
+        for attempt in 1..=GENERATION_ATTEMPTS {
+            let mut world = World::empty(config.dimensions);
+            world.generate_maze(rng);
+            world.start = match config.start {
+                Start::Fixed ([x, y, z, w]) => (x.min(width - 1), y.min(height - 1), z.min(depth - 1), w.min(fourth - 1)),
+                Start::Random => world.random_empty_cell(rng)
+            };
+            world.place_exit(&config.exit, rng);
+            world.solution = world.bfs(world.start, world.exit);
+            let shortfall = world.shortfall(config);
+            if shortfall == (0, 0) && attempt > 1 {
+                info!(target: "world", "Generated {} mazes to find one whose solution is long enough", attempt);
+            }
+            if closest.as_ref().is_none_or(|(_, least)| shortfall < *least) {
+                closest = Some ((world, shortfall));
+            }
+            if shortfall == (0, 0) {
+                break;
+            }
+        }
+        let (mut world, shortfall) = closest.unwrap();
+        if shortfall != (0, 0) {
+            world.swap_walls_until(config, rng);
+            let shortfall = world.shortfall(config);
+            if shortfall != (0, 0) {
+                info!(target: "world", "No maze met the constraints even with walls swapped, so the closest is {} w-moves and {} moves short", shortfall.0, shortfall.1);
+            }
+        }
+        world.place_doors(config.doors);
+        world.place_teleporters(config.teleporters, rng);
+        world.place_traps(config.traps, rng);
+        info!(target: "world", "Generated {:?} maze from {:?} to {:?}, solution is {} moves", config.dimensions, world.start, world.exit, world.solution.len() - 1);
+        Ok (world)
+    }
+
+    // How many moves between slices, then how many moves in all, the solution falls short of the config's minimums
+    fn shortfall(&self, config: &Config) -> (usize, usize) {
+        let w_moves = self.solution.windows(2).filter(|pair| pair[0].3 != pair[1].3).count();
+        self.short_by(config, self.solution.len().saturating_sub(1), w_moves)
+    }
+
+    // How many moves between slices, then how many moves in all, a way from start to exit of the given length would
+    // fall short of the config's minimums
+    fn short_by(&self, config: &Config, moves: usize, w_moves: usize) -> (usize, usize) {
+        let corner_to_corner = self.width + self.height + self.depth + self.fourth - 4;
+        let min_moves = (config.min_solution * corner_to_corner as f32).ceil() as usize;
+        let min_w_moves = if self.fourth > 1 { config.min_w_moves } else { 0 };
+        (min_w_moves.saturating_sub(w_moves), min_moves.saturating_sub(moves))
+    }
+
+    // Bring the solution closer to the config's minimums a wall at a time. Each swap closes a wall on the solution and
+    // opens whichever closed wall between the cells reachable from the start and those reachable from the exit brings
+    // the solution closest, so the exit stays reachable. The maze is braided, so the start may still reach the exit
+    // without the wall closed, and the new solution only shows once it's searched for again; a swap that leaves it
+    // further from the minimums is undone
+    fn swap_walls_until(&mut self, config: &Config, rng: &mut impl Rng) {
+        for _ in 0..WALL_SWAPS {
+            let shortfall = self.shortfall(config);
+            if shortfall == (0, 0) {
+                break;
+            }
+            let i = rng.gen_range(0..self.solution.len() - 1);
+            let (a, b) = (self.solution[i], self.solution[i + 1]);
+            self.close_wall(a, b);
+            let (from_start, from_exit) = (self.walks_from(self.start), self.walks_from(self.exit));
+            // Every closed wall between the halves, with how short a way crossing it would fall. The one just closed
+            // is among them
+            let mut joins: Vec<((usize, usize), Coordinate, Coordinate)> = from_start.iter()
+                .flat_map(|(&c, &walk)| (0..4).flat_map(move |axis| [-1, 1].map(move |step| {
+                    let mut d = to_cell(c);
+                    d[axis] += step;
+                    (c, walk, d)
+                })))
+                .filter(|&(_, _, d)| self.contains(d))
+                .filter_map(|(c, (moves, w_moves), [x, y, z, w])| {
+                    let d = (x as usize, y as usize, z as usize, w as usize);
+                    from_exit.get(&d).map(|&(rest, w_rest)| {
+                        (self.short_by(config, moves + 1 + rest, w_moves + (c.3 != d.3) as usize + w_rest), c, d)
+                    })
+                })
+                .filter(|&(_, c, d)| self.wall_between(c, d) == Wall::SolidWall)
+                .collect();
+            joins.sort(); // Same order whatever order the maps were in, so the seed decides the maze
+            let closest = joins[0].0;
+            let ties = joins.iter().take_while(|(short, _, _)| *short == closest).count();
+            let (_, c, d) = joins[rng.gen_range(0..ties)];
+            self.open_wall(c, d);
+            let solution = self.bfs(self.start, self.exit);
+            let previous = mem::replace(&mut self.solution, solution);
+            if self.shortfall(config) > shortfall {
+                self.close_wall(c, d);
+                self.open_wall(a, b);
+                self.solution = previous;
+            }
+        }
+    }
+
+    // Moves, and moves between slices, along the passages from the cell to every cell it leads to
+    fn walks_from(&self, from: Coordinate) -> HashMap<Coordinate, (usize, usize)> {
+        let mut walks = HashMap::new();
+        walks.insert(from, (0, 0));
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some (cell) = queue.pop_front() {
+            let (moves, w_moves) = walks[&cell];
+            for &n in self.neighbors.get(&cell).into_iter().flatten() {
+                if let Entry::Vacant (walk) = walks.entry(n) {
+                    walk.insert((moves + 1, w_moves + (n.3 != cell.3) as usize));
+                    queue.push_back(n);
+                }
+            }
+        }
+        walks
+    }
+
+    /// A grid with walls around every cell
+    pub fn empty(dimensions: [usize; 4]) -> World {
+        let [width, height, depth, fourth] = dimensions;
+        World {
+            cells: vec![vec![vec![vec![Cell::Empty; width]; height]; depth]; fourth],
+            walls: WallGrid::new(dimensions),
+            meta: CellMeta::new(dimensions),
+            start: (0, 0, 0, 0),
+            exit: (0, 0, 0, 0),
+            exit_delta: [0, 0, 0, 0],
+            exit_open: false,
+            solution: Vec::new(),
+            doors: Vec::new(),
+            teleporters: Vec::new(),
+            ghost_spawns: Vec::new(),
+            neighbors: HashMap::new(),
+            changed_levels: Vec::new(),
+            width,
+            height,
+            depth,
+            fourth
+        }
+    }
+
+    /// Remove the wall between two adjacent cells
+    pub fn open_wall(&mut self, a: Coordinate, b: Coordinate) {
+        self.set_wall(a, b, Wall::NoWall);
+        // Mark them as neighbors for BFS later
+        self.neighbors.entry(a).or_default().push(b);
+        self.neighbors.entry(b).or_default().push(a);
+    }
+
+    /// Put back the wall between two adjacent cells
+    pub fn close_wall(&mut self, a: Coordinate, b: Coordinate) {
+        self.set_wall(a, b, Wall::SolidWall);
+        self.neighbors.entry(a).or_default().retain(|n| *n != b);
+        self.neighbors.entry(b).or_default().retain(|n| *n != a);
+    }
+
+    /// Close one open wall and open a closed one, both within a level. A wall is only closed if its cells
+    /// stay connected without passing any doors, so everything reachable before still is. Returns whether anything moved
+    pub fn shift_walls(&mut self, rng: &mut impl Rng) -> bool {
+        let within_level = |a: &Coordinate, b: &Coordinate| a.2 == b.2 && a.3 == b.3;
+        let mut open: Vec<(Coordinate, Coordinate)> = self.neighbors.iter()
+            .flat_map(|(a, ns)| ns.iter().filter(move |b| a < *b && within_level(a, b)).map(move |b| (*a, *b)))
+            .filter(|&(a, b)| self.wall_between(a, b) == Wall::NoWall) // Leave doors alone
+            .collect();
+        open.sort();
+        open.shuffle(rng);
+        let closed = open.into_iter().find(|&(a, b)| {
+            self.close_wall(a, b);
+            let still_connected = self.route(a, b, &Inventory::new()).is_some();
+            self.open_wall(a, b);
+            still_connected
+        });
+        let (a, b) = match closed {
+            Some (pair) => pair,
+            None => return false // Every open wall is needed to keep the maze connected
+        };
+        self.close_wall(a, b);
+
+        // Any solid wall between two cells of the same level can go
+        let (_, _, z, w) = a;
+        let (width, height) = (self.width, self.height);
+        let candidates: Vec<(Coordinate, Coordinate)> = (0..height).flat_map(move |y| (0..width).flat_map(move |x| {
+                [(x + 1, y), (x, y + 1)].into_iter()
+                    .filter(move |&(nx, ny)| nx < width && ny < height)
+                    .map(move |(nx, ny)| ((x, y, z, w), (nx, ny, z, w)))
+            }))
+            .filter(|&(c, d)| (c, d) != (a, b) && self.wall_between(c, d) == Wall::SolidWall)
+            .collect();
+        if let Some (&(c, d)) = candidates.choose(rng) {
+            self.open_wall(c, d);
+        }
+        info!(target: "world", "Walls shifted in level {} of slice {}, closing {:?} to {:?}", z, w, a, b);
+        self.solution = self.bfs(self.start, self.exit);
+        if !self.changed_levels.contains(&(w, z)) {
+            self.changed_levels.push((w, z));
+        }
+        true
+    }
+
+    /// Walls within each level, to save
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let levels = (0..self.fourth).flat_map(|w| (0..self.depth).map(move |z| (w, z)))
+            .map(|(w, z)| self.level_walls(w, z).map(|(a, b)| if self.wall_between(a, b) == Wall::NoWall { '.' } else { '#' }).collect())
+            .collect();
+        WorldSnapshot { exit_open: self.exit_open, levels }
+    }
+
+    /// Move walls back to where they were in the saved game, in the same maze as it was first generated
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> std::result::Result<(), String> {
+        let levels: Vec<(usize, usize)> = (0..self.fourth).flat_map(|w| (0..self.depth).map(move |z| (w, z))).collect();
+        if snapshot.levels.len() != levels.len() {
+            return Err (format!("Expected walls for {} levels, found {}", levels.len(), snapshot.levels.len()));
+        }
+        for ((w, z), saved) in levels.into_iter().zip(&snapshot.levels) {
+            let pairs: Vec<(Coordinate, Coordinate)> = self.level_walls(w, z).collect();
+            if saved.chars().count() != pairs.len() {
+                return Err (format!("Expected {} walls in level {} of slice {}, found {}", pairs.len(), z, w, saved.chars().count()));
+            }
+            for ((a, b), wall) in pairs.into_iter().zip(saved.chars()) {
+                // Doors never move
+                match (self.wall_between(a, b), wall) {
+                    (Wall::SolidWall, '.') => self.open_wall(a, b),
+                    (Wall::NoWall, '#') => self.close_wall(a, b),
+                    (_, '.' | '#') => {}
+                    (_, other) => return Err (format!("Expected # or . for a wall, found {}", other))
+                }
+            }
+        }
+        self.exit_open = snapshot.exit_open;
+        self.solution = self.bfs(self.start, self.exit);
+        Ok (())
+    }
+
+    // Pairs of neighboring cells within the level: each cell with the one to its left, row by row, then with the one above
+    fn level_walls(&self, w: usize, z: usize) -> impl Iterator<Item = (Coordinate, Coordinate)> {
+        let (width, height) = (self.width, self.height);
+        let across = (0..height).flat_map(move |y| (1..width).map(move |x| ((x - 1, y, z, w), (x, y, z, w))));
+        let down = (1..height).flat_map(move |y| (0..width).map(move |x| ((x, y - 1, z, w), (x, y, z, w))));
+        across.chain(down)
+    }
+
+    /// Levels (w, z) whose walls have moved since this was last called
+    pub fn take_changed_levels(&mut self) -> Vec<(usize, usize)> {
+        std::mem::take(&mut self.changed_levels)
+    }
+
+    /// Put a locked door between two adjacent cells, opened by the key with the given number
+    pub fn lock_wall(&mut self, a: Coordinate, b: Coordinate, key: usize) {
+        self.set_wall(a, b, Wall::Door (key));
+        if key >= self.doors.len() {
+            self.doors.resize(key + 1, (a, b));
+        }
+        self.doors[key] = if a < b { (a, b) } else { (b, a) };
+    }
+
+    // Wall between two adjacent cells
+    fn wall_between(&self, a: Coordinate, b: Coordinate) -> Wall {
+        let (axis, cell) = across(a, b);
+        self.walls.get(axis, cell)
+    }
+
+    fn set_wall(&mut self, a: Coordinate, b: Coordinate, wall: Wall) {
+        let (axis, cell) = across(a, b);
+        self.walls.set(axis, cell, wall);
+    }
+
+    // Lock doors across the solution path, spaced evenly along it. Only walls within a level are used,
+    // so every door is drawn as a wall
+    fn place_doors(&mut self, count: usize) {
+        let candidates: Vec<(Coordinate, Coordinate)> = self.solution.windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .filter(|(a, b)| a.2 == b.2 && a.3 == b.3)
+            .collect();
+        let count = count.min(candidates.len());
+        for key in 0..count {
+            let (a, b) = candidates[(key + 1) * candidates.len() / (count + 1)];
+            self.lock_wall(a, b, key);
+        }
+    }
+
+    // Link pairs of pads far apart from each other, away from the start and exit
+    fn place_teleporters(&mut self, count: usize, rng: &mut impl Rng) {
+        for pair in 0..count {
+            let free = |world: &World, (x, y, z, w): Coordinate| world.cells[w][z][y][x] == Cell::Empty && (x, y, z, w) != world.start && (x, y, z, w) != world.exit;
+            let a = match (0..100).map(|_| self.random_empty_cell(rng)).find(|cell| free(self, *cell)) {
+                Some (cell) => cell,
+                None => return
+            };
+            let b = match self.distance_field(a).into_iter().filter(|(cell, _)| *cell != a && free(self, *cell)).max_by_key(|(cell, d)| (*d, *cell)) {
+                Some ((cell, _)) => cell,
+                None => return
+            };
+            self.link_teleporters(a, b);
+            info!(target: "world", "Teleporter {} links {:?} and {:?}", pair, a, b);
+        }
+    }
+
+    // Scatter traps through empty cells off the solution path, so the player never has to cross one.
+    // Pits only go where there's a level below to fall into
+    fn place_traps(&mut self, count: usize, rng: &mut impl Rng) {
+        let (width, height, depth) = (self.width, self.height, self.depth);
+        let mut candidates: Vec<Coordinate> = (0..self.fourth).flat_map(|w| (0..depth).flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z, w))))).collect();
+        candidates.retain(|&(x, y, z, w)| self.cells[w][z][y][x] == Cell::Empty && (x, y, z, w) != self.start && !self.solution.contains(&(x, y, z, w)));
+        for (x, y, z, w) in candidates.choose_multiple(rng, count).copied().collect::<Vec<_>>() {
+            let traps: &[Trap] = if z > 0 { &[Trap::Spikes, Trap::Snare, Trap::Pit] } else { &[Trap::Spikes, Trap::Snare] };
+            let trap = *traps.choose(rng).unwrap();
+            self.cells[w][z][y][x] = Cell::Trap (trap);
+        }
+    }
+
+    /// Put a pair of pads in the cells, each sending the player to the other
+    pub fn link_teleporters(&mut self, a: Coordinate, b: Coordinate) {
+        let pair = self.teleporters.len();
+        for (x, y, z, w) in [a, b] {
+            self.cells[w][z][y][x] = Cell::Teleporter (pair);
+        }
+        self.teleporters.push((a, b));
+    }
+
+    /// Cell the pad in the given cell sends the player to, if there is one
+    pub fn teleport_target(&self, cell: [i32; 4]) -> Option<[i32; 4]> {
+        if !self.contains(cell) {
+            return None;
+        }
+        let (x, y, z, w) = (cell[0] as usize, cell[1] as usize, cell[2] as usize, cell[3] as usize);
+        match self.cells[w][z][y][x] {
+            Cell::Teleporter (pair) => {
+                let (a, b) = self.teleporters[pair];
+                Some (to_cell(if a == (x, y, z, w) { b } else { a }))
+            },
+            _ => None
+        }
+    }
+
+    /// Trap set in the given cell, if there is one
+    pub fn trap(&self, cell: [i32; 4]) -> Option<Trap> {
+        if !self.contains(cell) {
+            return None;
+        }
+        let (x, y, z, w) = (cell[0] as usize, cell[1] as usize, cell[2] as usize, cell[3] as usize);
+        match self.cells[w][z][y][x] {
+            Cell::Trap (trap) => Some (trap),
+            _ => None
+        }
+    }
+
+    fn generate_maze(&mut self, rng: &mut impl Rng) {
+        // Use randomized kruskal's algorithm
+
+        // Random list of edges
+        #[derive(Debug)]
+        enum MazeEdge {
+            X (Coordinate),
+            Y (Coordinate),
+            Z (Coordinate),
+            W (Coordinate)
+        }
+        let mut edges: Vec<MazeEdge> = Vec::new();
+        for w in 0..self.fourth {
+            for z in 0..self.depth {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        if x != 0 {
+                            edges.push(MazeEdge::X((x, y, z, w)))
+                        }
+                        if y != 0 {
+                            edges.push(MazeEdge::Y((x, y, z, w)))
+                        }
+                        if z != 0 {
+                            edges.push(MazeEdge::Z((x, y, z, w)))
+                        }
+                        if w != 0 {
+                            edges.push(MazeEdge::W((x, y, z, w)))
+                        }
+                    }
+                }
+            }
+        }
+        edges.shuffle(rng);
+
+        // Initialize disjoint set of cells
+        let mut cells = DisjointSet::new();
+        for w in 0..self.fourth {
+            for z in 0..self.depth {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        // Use tuples to hash correctly hopefully
+                        cells.add(&(x, y, z, w));
+                    }
+                }
+            }
+        }
+
+        // Take a random edge and check if the neighbor cells are connected
+        // If not, remove the edge to merge them
+        // Also generate map from each cell to accessible neighbors
+        for edge in edges.iter() {
+            let (cell_a, cell_b) =
+                match edge {
+                    MazeEdge::X ((x, y, z, w)) => ((*x - 1, *y, *z, *w), (*x, *y, *z, *w)),
+                    MazeEdge::Y ((x, y, z, w)) => ((*x, *y - 1, *z, *w), (*x, *y, *z, *w)),
+                    MazeEdge::Z ((x, y, z, w)) => ((*x, *y, *z - 1, *w), (*x, *y, *z, *w)),
+                    MazeEdge::W ((x, y, z, w)) => ((*x, *y, *z, *w - 1), (*x, *y, *z, *w))
+                };
+            let set_a = cells.find(&cell_a);
+            let set_b = cells.find(&cell_b);
+            let within_level = matches!(edge, MazeEdge::X (_) | MazeEdge::Y (_));
+            if set_a != set_b || (within_level && rng.gen_bool(0.3)) {
+                // Remove edge between these cells in world
+                self.open_wall(cell_a, cell_b);
+                // And merge the sets they belong to
+                cells.union(&set_a, &set_b);
+            }
+        }
+        // Results in minimum spanning tree connecting all cells of maze
+    }
+
+    // Choose an exit cell on the outer edge of a level, and open the outer wall next to it
+    fn place_exit(&mut self, placement: &ExitPlacement, rng: &mut impl Rng) {
+        let (x, y, z, w) = self.start;
+        let far = |i: usize, len: usize| if i < len / 2 { len - 1 } else { 0 };
+        let edge_distances = || {
+            self.distance_field(self.start).into_iter().filter(|((x, y, _, _), _)| {
+                *x == 0 || *x == self.width - 1 || *y == 0 || *y == self.height - 1
+            }).collect::<Vec<_>>()
+        };
+        self.exit = match placement {
+            ExitPlacement::OppositeCorner => (far(x, self.width), far(y, self.height), far(z, self.depth), far(w, self.fourth)),
+            ExitPlacement::MaxEccentricity => edge_distances().into_iter().max_by_key(|(cell, d)| (*d, *cell)).unwrap().0,
+            ExitPlacement::RandomFar => {
+                let edges = edge_distances();
+                let max = edges.iter().map(|(_, d)| *d).max().unwrap();
+                let mut far_edges: Vec<_> = edges.into_iter().filter(|(_, d)| *d * 4 >= max * 3).collect();
+                far_edges.sort(); // Same seed, same exit
+                far_edges.choose(rng).unwrap().0
+            }
+        };
+        let (x, y, z, w) = self.exit;
+        self.exit_delta = if x == self.width - 1 {
+            self.walls.set(Axis::X, (x + 1, y, z, w), Wall::NoWall);
+            [1, 0, 0, 0]
+        } else if x == 0 {
+            self.walls.set(Axis::X, (x, y, z, w), Wall::NoWall);
+            [-1, 0, 0, 0]
+        } else if y == self.height - 1 {
+            self.walls.set(Axis::Y, (x, y + 1, z, w), Wall::NoWall);
+            [0, 1, 0, 0]
+        } else {
+            self.walls.set(Axis::Y, (x, y, z, w), Wall::NoWall);
+            [0, -1, 0, 0]
+        };
+    }
+
+    /// Whether the cell lies within the maze
+    pub fn contains(&self, cell: [i32; 4]) -> bool {
+        cell.iter().zip([self.width, self.height, self.depth, self.fourth]).all(|(i, len)| *i >= 0 && (*i as usize) < len)
+    }
+
+    /// Whether moving by delta from the cell leaves the maze through the exit
+    pub fn is_exit(&self, cell: [i32; 4], delta: [i32; 4]) -> bool {
+        let (x, y, z, w) = self.exit;
+        cell == [x as i32, y as i32, z as i32, w as i32] && delta == self.exit_delta
+    }
+
+    /// Rough direction to the exit along whichever axis is furthest off
+    pub fn exit_direction(&self, from: [i32; 4]) -> [i32; 4] {
+        let (x, y, z, w) = self.exit;
+        let target = [x as i32, y as i32, z as i32, w as i32];
+        let diff = [0, 1, 2, 3].map(|i| target[i] + self.exit_delta[i] - from[i]);
+        let axis = (0..4).max_by_key(|i| (diff[*i].abs(), 3 - *i)).unwrap();
+        let mut direction = [0; 4];
+        direction[axis] = diff[axis].signum();
+        direction
+    }
+
+    /// Random cell with nothing in it
+    pub fn random_empty_cell(&self, rng: &mut impl Rng) -> Coordinate {
+        fn gen(world: &World, rng: &mut impl Rng) -> Coordinate {
+            (rng.gen_range(0..world.width), rng.gen_range(0..world.height), rng.gen_range(0..world.depth), rng.gen_range(0..world.fourth))
+        }
+        let (mut x, mut y, mut z, mut w) = gen(self, rng);
+        while self.cells[w][z][y][x] != Cell::Empty {
+            let (nx, ny, nz, nw) = gen(self, rng);
+            x = nx;
+            y = ny;
+            z = nz;
+            w = nw;
+        }
+        (x, y, z, w)
+    }
+
+    /// Cells grouped by which can reach each other through the open walls, as if every door were unlocked
+    pub fn regions(&self) -> DisjointSet<Coordinate> {
+        let mut regions = DisjointSet::new();
+        for w in 0..self.fourth {
+            for z in 0..self.depth {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        regions.add(&(x, y, z, w));
+                    }
+                }
+            }
+        }
+        for (cell, neighbors) in &self.neighbors {
+            for n in neighbors {
+                regions.union(cell, n);
+            }
+        }
+        regions
+    }
+
+    /// Every cell reachable from the given one without passing a door the inventory can't open
+    pub fn reachable(&self, from: Coordinate, inventory: &Inventory) -> HashSet<Coordinate> {
+        let mut visited = HashSet::new();
+        visited.insert(from);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some (cell) = queue.pop_front() {
+            for n in self.neighbors.get(&cell).unwrap_or(&Vec::new()) {
+                if !visited.contains(n) && self.check_move(to_cell(cell), delta(cell, *n), inventory) {
+                    visited.insert(*n);
+                    queue.push_back(*n);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Shortest path between two cells that only passes doors the inventory opens, including both ends,
+    /// or None if the finish can't be reached yet
+    pub fn route(&self, start: Coordinate, finish: Coordinate, inventory: &Inventory) -> Option<Vec<Coordinate>> {
+        self.route_avoiding(start, finish, inventory, &HashSet::new())
+    }
+
+    /// Shortest path like route, that also keeps out of the cells to avoid other than where it starts
+    pub fn route_avoiding(&self, start: Coordinate, finish: Coordinate, inventory: &Inventory, avoid: &HashSet<Coordinate>) -> Option<Vec<Coordinate>> {
+        self.search(start, |cell| cell == finish, inventory, avoid)
+    }
+
+    /// Shortest path like route_avoiding to whichever of the targets is nearest, found in one search however many
+    /// targets there are, or None if none of them can be reached yet
+    pub fn nearest_of(&self, start: Coordinate, targets: &HashSet<Coordinate>, inventory: &Inventory, avoid: &HashSet<Coordinate>) -> Option<Vec<Coordinate>> {
+        self.search(start, |cell| targets.contains(&cell), inventory, avoid)
+    }
+
+    // Breadth-first search for the nearest cell that's a target, keeping out of the cells to avoid and only passing
+    // doors the inventory opens, returning the path there including both ends
+    fn search(&self, start: Coordinate, target: impl Fn(Coordinate) -> bool, inventory: &Inventory, avoid: &HashSet<Coordinate>) -> Option<Vec<Coordinate>> {
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        let mut backtrack: HashMap<Coordinate, Coordinate> = HashMap::new();
+        backtrack.insert(start, start);
+        while let Some (cell) = queue.pop_front() {
+            if target(cell) {
+                let mut route = vec![cell];
+                while *route.last().unwrap() != start {
+                    route.push(backtrack[route.last().unwrap()]);
+                }
+                route.reverse();
+                return Some (route);
+            }
+            for n in self.neighbors.get(&cell).unwrap_or(&Vec::new()) {
+                if !backtrack.contains_key(n) && !avoid.contains(n) && self.check_move(to_cell(cell), delta(cell, *n), inventory) {
+                    backtrack.insert(*n, cell);
+                    queue.push_back(*n);
+                }
+            }
+        }
+        None
+    }
+
+    /// Shortest path between two cells, including both ends
+    pub fn bfs(&self, start: Coordinate, finish: Coordinate) -> Vec<Coordinate> {
+        // Use breadth-first search to find solution
+        let mut queue: VecDeque<Coordinate> = VecDeque::new();
+        queue.push_back(start);
+        let mut visited: HashSet<Coordinate> = HashSet::new();
+        visited.insert(start);
+        let mut backtrack: HashMap<Coordinate, Coordinate> = HashMap::new();
+        while !queue.is_empty() {
+            // Take next cell from queue
+            let cell = queue.pop_front().unwrap();
+
+            // Add unvisited neighbors to the queue
+            for n in self.neighbors.get(&cell).unwrap_or(&Vec::new()) {
+                if !visited.contains(n) {
+                    visited.insert(*n);
+                    queue.push_back(*n);
+                    backtrack.insert(*n, cell);
+                    if *n == finish {
+                        break;
+                    }
+                }
+            }
+        }
+        // Use backtracking information to recover path
+        let mut solution: Vec<Coordinate> = Vec::new();
+        let mut previous = finish;
+        solution.push(finish);
+        while previous != start {
+            previous = *backtrack.get(&previous).expect("Backtracking after BFS failed, impossible");
+            solution.push(previous);
+        }
+        solution.reverse(); // Get finish at the end of the vec
+        solution
+    }
+
+    /// Every cell within the given number of moves of a cell, through doors or not, eg. where a ghost could get to soon
+    pub fn near(&self, from: Coordinate, moves: usize) -> HashSet<Coordinate> {
+        let mut near = HashSet::new();
+        near.insert(from);
+        let mut edge = vec![from];
+        for _ in 0..moves {
+            edge = edge.iter().flat_map(|cell| self.neighbors.get(cell).into_iter().flatten())
+                .filter(|n| near.insert(**n))
+                .copied()
+                .collect();
+        }
+        near
+    }
+
+    /// Cells (x, y) of level (w, z) that could be seen from somewhere in the given cell, looking no more than the
+    /// given number of cells along either axis. A line of sight only ever steps one way along x and one way along y,
+    /// so this floods out into each quarter around the cell without turning back, stopping at solid walls. Doors
+    /// don't block the view, as they may be open, so nothing that can be seen is ever left out
+    pub fn in_sight(&self, w: usize, z: usize, [x, y]: [usize; 2], reach: usize) -> HashSet<[usize; 2]> {
+        let mut seen = HashSet::new();
+        for [sx, sy] in [[-1, -1], [-1, 1], [1, -1], [1, 1]] {
+            let mut edge = vec![[x, y]];
+            let mut quarter = HashSet::new();
+            quarter.insert([x, y]);
+            while let Some ([cx, cy]) = edge.pop() {
+                for [nx, ny] in [[cx as i32 + sx, cy as i32], [cx as i32, cy as i32 + sy]] {
+                    let far = nx.abs_diff(x as i32) as usize > reach || ny.abs_diff(y as i32) as usize > reach;
+                    if far || !self.contains([nx, ny, z as i32, w as i32]) {
+                        continue;
+                    }
+                    let next = [nx as usize, ny as usize];
+                    if self.wall_between((cx, cy, z, w), (next[0], next[1], z, w)) != Wall::SolidWall && quarter.insert(next) {
+                        edge.push(next);
+                    }
+                }
+            }
+            seen.extend(quarter);
+        }
+        seen
+    }
+
+    /// Fewest moves from the given cell to every cell reachable from it through open walls and doors alike
+    pub fn distance_field(&self, from: Coordinate) -> HashMap<Coordinate, usize> {
+        let mut distances = HashMap::new();
+        distances.insert(from, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some (cell) = queue.pop_front() {
+            let distance = distances[&cell];
+            for n in self.neighbors.get(&cell).unwrap_or(&Vec::new()) {
+                if !distances.contains_key(n) {
+                    distances.insert(*n, distance + 1);
+                    queue.push_back(*n);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Whether the cell has only one way in or out
+    pub fn is_dead_end(&self, cell: Coordinate) -> bool {
+        self.neighbors.get(&cell).is_some_and(|neighbors| neighbors.len() == 1)
+    }
+
+    /// Whether the player may move by delta from the current cell, opening any doors with keys from their inventory
+    pub fn check_move(&self, current: [i32; 4], delta: [i32; 4], inventory: &Inventory) -> bool {
+        if !self.contains(current) {
+            return false;
+        }
+        if !self.exit_open && self.is_exit(current, delta) {
+            return false; // Exit stays shut until all food is eaten
+        }
+        let axis = match Axis::of(delta) {
+            Some (axis) => axis,
+            None => return false // Invalid move
+        };
+        // Moving forwards along the axis crosses the wall on the far side of the cell, which belongs to the next cell
+        let mut wall_at = current.map(|i| i as usize);
+        if delta[axis as usize] > 0 {
+            wall_at[axis as usize] += 1;
+        }
+        let [x, y, z, w] = wall_at;
+        match self.walls.get(axis, (x, y, z, w)) {
+            Wall::SolidWall => false,
+            Wall::NoWall => true,
+            Wall::Door (key) => inventory.has_key(key)
+        }
+    }
+
+    /// Where a box reaching half across from its centre to each side ends up after moving by delta in x and y, stopping
+    /// against walls and sliding along any it meets at an angle. The walls are the ones check_move would refuse to cross.
+    /// Moves much over a quarter of a cell can pass through walls, so longer ones need splitting up
+    pub fn sweep(&self, position: [f32; 4], delta: [f32; 2], half: f32, inventory: &Inventory) -> [f32; 4] {
+        let boxes = self.wall_boxes(position.map(|i| i.round() as i32), inventory);
+        let mut moved = position;
+        // One axis after the other, so a move blocked along one still slides along the other
+        for axis in 0..2 {
+            let other = 1 - axis;
+            let mut step = delta[axis];
+            for [low, high] in &boxes {
+                if moved[other] + half <= low[other] + SWEEP_EPSILON || moved[other] - half >= high[other] - SWEEP_EPSILON {
+                    continue; // Not alongside, or only touching at the side
+                }
+                if step > 0.0 && moved[axis] + half <= low[axis] + SWEEP_EPSILON {
+                    step = step.min((low[axis] - moved[axis] - half).max(0.0));
+                } else if step < 0.0 && moved[axis] - half >= high[axis] - SWEEP_EPSILON {
+                    step = step.max((high[axis] - moved[axis] + half).min(0.0));
+                }
+            }
+            moved[axis] += step;
+        }
+        moved
+    }
+
+    /// Walls across x and y in the level around the cell that stop the player, as check_move decides. Each reaches over
+    /// the corners at its ends, so no gap opens where two walls meet
+    pub fn wall_boxes(&self, around: [i32; 4], inventory: &Inventory) -> Vec<WallBox> {
+        let [x, y, z, w] = around;
+        let t = WALL_HALF_THICKNESS;
+        let mut boxes = Vec::new();
+        for a in x - 2..=x + 1 {
+            for b in y - 1..=y + 1 {
+                if self.blocked([a, b, z, w], [1, 0, 0, 0], inventory) {
+                    let (across, along) = (a as f32 + 0.5, b as f32);
+                    boxes.push([[across - t, along - 0.5 - t], [across + t, along + 0.5 + t]]);
+                }
+            }
+        }
+        for a in x - 1..=x + 1 {
+            for b in y - 2..=y + 1 {
+                if self.blocked([a, b, z, w], [0, 1, 0, 0], inventory) {
+                    let (along, across) = (a as f32, b as f32 + 0.5);
+                    boxes.push([[along - 0.5 - t, across - t], [along + 0.5 + t, across + t]]);
+                }
+            }
+        }
+        boxes
+    }
+
+    // Whether the wall between the cell and the next by delta stops the player, from whichever side is in the maze
+    fn blocked(&self, cell: [i32; 4], delta: [i32; 4], inventory: &Inventory) -> bool {
+        let next = [0, 1, 2, 3].map(|i| cell[i] + delta[i]);
+        if self.contains(cell) {
+            !self.check_move(cell, delta, inventory)
+        } else if self.contains(next) {
+            !self.check_move(next, delta.map(|i| -i), inventory)
+        } else {
+            false // Nothing out past the edge of the maze
+        }
+    }
+
+    /// How far along the straight line between two points in the w-slice it first meets a wall or floor, from 0 to 1,
+    /// or None if the way is clear. Cells are centred on their x and y coordinates and stand on their z coordinate, and
+    /// levels above top are left out, as they aren't drawn over the player
+    pub fn first_hit(&self, w: usize, top: usize, from: [f32; 3], to: [f32; 3]) -> Option<f32> {
+        let mut first: Option<f32> = None;
+        for axis in 0..3 {
+            // Walls lie halfway between cells, and floors level with the bottom of each cell
+            let shift = if axis == 2 { 0.0 } else { 0.5 };
+            let (a, b) = (from[axis] + shift, to[axis] + shift);
+            if a == b {
+                continue;
+            }
+            for boundary in (a.min(b).ceil() as i32)..=(a.max(b).floor() as i32) {
+                let t = (boundary as f32 - a) / (b - a);
+                if t <= 0.0 || first.is_some_and(|first| t >= first) {
+                    continue;
+                }
+                let point = [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t);
+                let [x, y, z] = [point[0].round() as i32, point[1].round() as i32, point[2].floor() as i32];
+                let within = |i: i32, len: usize| i >= 0 && (i as usize) < len;
+                let (wall, level) = match axis {
+                    0 if within(boundary, self.width + 1) && within(y, self.height) && within(z, self.depth) =>
+                        (self.walls.get(Axis::X, (boundary as usize, y as usize, z as usize, w)), z),
+                    1 if within(x, self.width) && within(boundary, self.height + 1) && within(z, self.depth) =>
+                        (self.walls.get(Axis::Y, (x as usize, boundary as usize, z as usize, w)), z),
+                    2 if within(x, self.width) && within(y, self.height) && within(boundary, self.depth + 1) =>
+                        (self.walls.get(Axis::Z, (x as usize, y as usize, boundary as usize, w)), boundary),
+                    _ => continue // Outside the maze
+                };
+                if wall != Wall::NoWall && level <= top as i32 {
+                    first = Some (t);
+                }
+            }
+        }
+        first
+    }
+}
+
+/// Text map of every level, slice by slice, which reads back in as a level file. See the level module for the format
+impl fmt::Display for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digit = |n: usize| char::from_digit(n as u32 % 10, 10).unwrap();
+        let cell = |(x, y, z, w): Coordinate| format!("{}x{}x{}x{}", x, y, z, w);
+        writeln!(f, "size {}x{}x{}x{}", self.width, self.height, self.depth, self.fourth)?;
+        for (a, b) in &self.teleporters {
+            writeln!(f, "teleporter {} {}", cell(*a), cell(*b))?;
+        }
+        for w in 0..self.fourth {
+            for z in 0..self.depth {
+                writeln!(f, "w {} z {}", w, z)?;
+                for y in 0..=self.height {
+                    let row: String = (0..self.width).map(|x| match self.walls.get(Axis::Y, (x, y, z, w)) {
+                        Wall::NoWall => "+   ".to_string(),
+                        Wall::SolidWall => "+---".to_string(),
+                        Wall::Door (key) => format!("+-{}-", digit(key))
+                    }).collect();
+                    writeln!(f, "{}+", row)?;
+                    if y == self.height {
+                        break;
+                    }
+                    let mut row = String::new();
+                    for x in 0..=self.width {
+                        row.push(match self.walls.get(Axis::X, (x, y, z, w)) {
+                            Wall::NoWall => ' ',
+                            Wall::SolidWall => '|',
+                            Wall::Door (key) => digit(key)
+                        });
+                        if x == self.width {
+                            break;
+                        }
+                        row.push(match self.cells[w][z][y][x] {
+                            _ if (x, y, z, w) == self.start => 'S',
+                            _ if (x, y, z, w) == self.exit => 'E',
+                            _ if self.ghost_spawns.contains(&(x, y, z, w)) => 'G',
+                            Cell::Empty | Cell::Fruit => ' ',
+                            Cell::Food => 'o',
+                            Cell::Key (key) => digit(key),
+                            Cell::PowerUp (PowerUp::Boots) => 'B',
+                            Cell::PowerUp (PowerUp::Clock) => 'C',
+                            Cell::Battery => 'b',
+                            Cell::Teleporter (_) => 'T',
+                            Cell::Trap (Trap::Spikes) => '^',
+                            Cell::Trap (Trap::Snare) => '&',
+                            Cell::Trap (Trap::Pit) => 'v'
+                        });
+                        row.push(match (self.walls.get(Axis::Z, (x, y, z + 1, w)) != Wall::SolidWall, self.walls.get(Axis::Z, (x, y, z, w)) != Wall::SolidWall) {
+                            (true, true) => 'x',
+                            (true, false) => 'u',
+                            (false, true) => 'd',
+                            (false, false) => ' '
+                        });
+                        row.push(match (self.walls.get(Axis::W, (x, y, z, w + 1)) != Wall::SolidWall, self.walls.get(Axis::W, (x, y, z, w)) != Wall::SolidWall) {
+                            (true, true) => '=',
+                            (true, false) => '>',
+                            (false, true) => '<',
+                            (false, false) => ' '
+                        });
+                    }
+                    writeln!(f, "{}", row)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok (())
+    }
+}
+
+fn to_cell((x, y, z, w): Coordinate) -> [i32; 4] {
+    [x as i32, y as i32, z as i32, w as i32]
+}
+
+// Axis of the wall between two adjacent cells, and the coordinate it's at
+fn across(a: Coordinate, b: Coordinate) -> (Axis, Coordinate) {
+    let (a, b) = if a < b { (a, b) } else { (b, a) };
+    match Axis::of(delta(a, b)) {
+        Some (axis) => (axis, b),
+        None => panic!("Cells {:?} and {:?} aren't adjacent", a, b)
+    }
+}
+
+fn delta(from: Coordinate, to: Coordinate) -> [i32; 4] {
+    let (from, to) = (to_cell(from), to_cell(to));
+    [0, 1, 2, 3].map(|i| to[i] - from[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    const DIMENSIONS: [usize; 4] = [4, 3, 3, 2];
+    const NO_KEYS: Inventory = Inventory::new();
+
+    fn generated() -> World {
+        World::new(&Config { dimensions: DIMENSIONS, ..Default::default() }, &mut thread_rng()).unwrap()
+    }
+
+    fn cell((x, y, z, w): Coordinate) -> [i32; 4] {
+        [x as i32, y as i32, z as i32, w as i32]
+    }
+
+    // Single level shaped like a ring of six cells around a solid middle wall
+    // +-+-+-+
+    // |     |
+    // + +-+ +
+    // |     |
+    // +-+-+-+
+    fn ring() -> World {
+        let mut world = World::empty([3, 2, 1, 1]);
+        world.open_wall((0, 0, 0, 0), (1, 0, 0, 0));
+        world.open_wall((1, 0, 0, 0), (2, 0, 0, 0));
+        world.open_wall((0, 1, 0, 0), (1, 1, 0, 0));
+        world.open_wall((1, 1, 0, 0), (2, 1, 0, 0));
+        world.open_wall((0, 0, 0, 0), (0, 1, 0, 0));
+        world.open_wall((2, 0, 0, 0), (2, 1, 0, 0));
+        world
+    }
+
+    #[test]
+    fn only_cells_a_line_of_sight_could_reach_are_in_sight() {
+        // Corridor along the top, turning down and back on itself at the far end
+        // +-+-+-+-+-+
+        // |         |
+        // +-+-+-+ + +
+        // | | | |   |
+        // +-+-+-+-+-+
+        let mut world = World::empty([5, 2, 1, 1]);
+        for x in 1..5 {
+            world.open_wall((x - 1, 0, 0, 0), (x, 0, 0, 0));
+        }
+        world.open_wall((4, 0, 0, 0), (4, 1, 0, 0));
+        world.open_wall((3, 1, 0, 0), (4, 1, 0, 0));
+        let mut seen: Vec<[usize; 2]> = world.in_sight(0, 0, [0, 0], 5).into_iter().collect();
+        seen.sort_unstable_by_key(|[x, y]| (*y, *x));
+        assert_eq!(seen, [[0, 0], [1, 0], [2, 0], [3, 0], [4, 0], [4, 1]]);
+        assert!(world.in_sight(0, 0, [4, 1], 5).contains(&[0, 0]));
+        assert!(!world.in_sight(0, 0, [0, 0], 3).contains(&[4, 0]));
+    }
+
+    #[test]
+    fn generated_maze_reaches_every_cell() {
+        let world = generated();
+        assert_eq!(world.distance_field(world.start).len(), DIMENSIONS.iter().product::<usize>());
+    }
+
+    #[test]
+    fn wall_grid_keeps_each_axis_apart() {
+        let mut walls = WallGrid::new([2, 3, 1, 1]);
+        walls.set(Axis::X, (2, 1, 0, 0), Wall::NoWall);
+        walls.set(Axis::Y, (1, 3, 0, 0), Wall::Door (4));
+        assert_eq!(walls.get(Axis::X, (2, 1, 0, 0)), Wall::NoWall);
+        assert_eq!(walls.get(Axis::Y, (1, 3, 0, 0)), Wall::Door (4));
+        assert_eq!(walls.get(Axis::Y, (1, 1, 0, 0)), Wall::SolidWall);
+        let open: Vec<[usize; 2]> = walls.level(Axis::X, 0, 0).filter(|(_, wall)| *wall != Wall::SolidWall).map(|(at, _)| at).collect();
+        assert_eq!(open, vec![[2, 1]]);
+        assert_eq!(walls.level(Axis::Y, 0, 0).count(), 8);
+        assert_eq!(walls.level(Axis::Z, 0, 1).count(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wall_grid_rejects_walls_past_the_far_side() {
+        WallGrid::new([2, 2, 2, 2]).get(Axis::Z, (0, 0, 3, 0));
+    }
+
+    #[test]
+    fn regions_split_where_the_walls_do() {
+        let mut world = World::empty([3, 1, 1, 2]);
+        world.open_wall((0, 0, 0, 0), (1, 0, 0, 0));
+        world.open_wall((1, 0, 0, 0), (1, 0, 0, 1));
+        world.open_wall((1, 0, 0, 0), (2, 0, 0, 0));
+        world.lock_wall((1, 0, 0, 0), (2, 0, 0, 0), 0);
+        let mut regions = world.regions();
+        assert_eq!(regions.size(&(2, 0, 0, 0)), 4);
+        assert_eq!(regions.count(), 3);
+        assert_eq!(generated().regions().count(), 1);
+    }
+
+    #[test]
+    fn solutions_are_long_enough_and_cross_slices() {
+        let config = Config { dimensions: [4, 4, 2, 3], exit: ExitPlacement::MaxEccentricity, min_w_moves: 2, ..Default::default() };
+        for seed in 0..40 {
+            let world = World::new(&config, &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(world.shortfall(&config), (0, 0), "seed {}", seed);
+            assert!(world.solution.len() > 14, "seed {}", seed);
+            assert!(world.solution.windows(2).filter(|pair| pair[0].3 != pair[1].3).count() >= 2, "seed {}", seed);
+            // Swapped walls leave the solution up to date and never open a wall twice
+            assert_eq!(world.solution, world.bfs(world.start, world.exit), "seed {}", seed);
+            assert!(world.neighbors.values().all(|ns| ns.iter().collect::<HashSet<_>>().len() == ns.len()), "seed {}", seed);
+        }
+        // A single slice can't ask for moves between slices
+        let flat = Config { dimensions: [4, 4, 2, 1], min_w_moves: 3, ..Default::default() };
+        assert_eq!(World::new(&flat, &mut StdRng::seed_from_u64(0)).unwrap().shortfall(&flat).0, 0);
+    }
+
+    #[test]
+    fn solution_walks_from_start_to_exit() {
+        let world = generated();
+        assert_eq!(world.solution.first(), Some (&world.start));
+        assert_eq!(world.solution.last(), Some (&world.exit));
+        for pair in world.solution.windows(2) {
+            let (from, to) = (cell(pair[0]), cell(pair[1]));
+            let delta = [0, 1, 2, 3].map(|i| to[i] - from[i]);
+            assert!(world.check_move(from, delta, &NO_KEYS), "Solution steps through a wall from {:?} to {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn exit_stays_shut_until_opened() {
+        let mut world = generated();
+        let exit = cell(world.exit);
+        assert!(!world.check_move(exit, world.exit_delta, &NO_KEYS));
+        world.exit_open = true;
+        assert!(world.check_move(exit, world.exit_delta, &NO_KEYS));
+        assert!(!world.contains([0, 1, 2, 3].map(|i| exit[i] + world.exit_delta[i])));
+    }
+
+    #[test]
+    fn check_move_follows_open_walls_in_every_dimension() {
+        let mut world = World::empty([2, 2, 2, 2]);
+        world.open_wall((0, 0, 0, 0), (1, 0, 0, 0));
+        world.open_wall((0, 0, 0, 0), (0, 0, 1, 0));
+        world.open_wall((0, 0, 0, 0), (0, 0, 0, 1));
+        assert!(world.check_move([0, 0, 0, 0], [1, 0, 0, 0], &NO_KEYS));
+        assert!(world.check_move([1, 0, 0, 0], [-1, 0, 0, 0], &NO_KEYS));
+        assert!(world.check_move([0, 0, 0, 0], [0, 0, 1, 0], &NO_KEYS));
+        assert!(world.check_move([0, 0, 1, 0], [0, 0, -1, 0], &NO_KEYS));
+        assert!(world.check_move([0, 0, 0, 0], [0, 0, 0, 1], &NO_KEYS));
+        assert!(world.check_move([0, 0, 0, 1], [0, 0, 0, -1], &NO_KEYS));
+        assert!(!world.check_move([0, 0, 0, 0], [0, 1, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([1, 0, 0, 0], [0, 0, 1, 0], &NO_KEYS));
+    }
+
+    #[test]
+    fn check_move_rejects_boundaries_and_diagonals() {
+        let world = ring();
+        assert!(!world.check_move([0, 0, 0, 0], [-1, 0, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([0, 0, 0, 0], [0, -1, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([0, 0, 0, 0], [0, 0, -1, 0], &NO_KEYS));
+        assert!(!world.check_move([2, 1, 0, 0], [1, 0, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([0, 0, 0, 0], [1, 1, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([0, 0, 0, 0], [2, 0, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([3, 0, 0, 0], [-1, 0, 0, 0], &NO_KEYS));
+    }
+
+    #[test]
+    fn doors_open_with_their_own_key() {
+        let mut world = ring();
+        world.lock_wall((0, 0, 0, 0), (1, 0, 0, 0), 0);
+        assert!(!world.check_move([0, 0, 0, 0], [1, 0, 0, 0], &NO_KEYS));
+        assert!(!world.check_move([1, 0, 0, 0], [-1, 0, 0, 0], &NO_KEYS));
+        let mut inventory = Inventory::new();
+        inventory.add_key(1);
+        assert!(!world.check_move([0, 0, 0, 0], [1, 0, 0, 0], &inventory));
+        inventory.add_key(0);
+        assert!(world.check_move([0, 0, 0, 0], [1, 0, 0, 0], &inventory));
+    }
+
+    #[test]
+    fn sweep_stops_at_walls_and_slides_along_them() {
+        let world = ring();
+        let close = |a: [f32; 4], b: [f32; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5);
+        let stopped = 0.5 - WALL_HALF_THICKNESS - 0.25;
+        let moved = world.sweep([1.0, 0.0, 0.0, 0.0], [0.2, 0.4], 0.25, &NO_KEYS);
+        assert!(close(moved, [1.2, stopped, 0.0, 0.0]), "{:?}", moved);
+        let rested = world.sweep(moved, [0.0, 0.4], 0.25, &NO_KEYS);
+        assert!(close(rested, moved), "{:?}", rested);
+        assert!(close(world.sweep([0.0, 0.0, 0.0, 0.0], [0.0, 0.4], 0.25, &NO_KEYS), [0.0, 0.4, 0.0, 0.0]));
+        assert!(close(world.sweep([0.0, 0.0, 0.0, 0.0], [-0.4, 0.0], 0.25, &NO_KEYS), [-stopped, 0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn sweep_needs_the_key_through_a_door() {
+        let mut world = ring();
+        world.lock_wall((0, 0, 0, 0), (1, 0, 0, 0), 0);
+        let stopped = 0.5 - WALL_HALF_THICKNESS - 0.25;
+        assert!((world.sweep([0.0, 0.0, 0.0, 0.0], [0.4, 0.0], 0.25, &NO_KEYS)[0] - stopped).abs() < 1e-5);
+        let mut inventory = Inventory::new();
+        inventory.add_key(0);
+        assert!((world.sweep([0.0, 0.0, 0.0, 0.0], [0.4, 0.0], 0.25, &inventory)[0] - 0.4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn route_goes_around_locked_doors() {
+        let mut world = ring();
+        world.lock_wall((0, 0, 0, 0), (1, 0, 0, 0), 0);
+        assert_eq!(world.route((0, 0, 0, 0), (1, 0, 0, 0), &NO_KEYS).map(|route| route.len()), Some (6));
+        world.lock_wall((0, 0, 0, 0), (0, 1, 0, 0), 1);
+        assert_eq!(world.route((0, 0, 0, 0), (1, 0, 0, 0), &NO_KEYS), None);
+        assert_eq!(world.reachable((0, 0, 0, 0), &NO_KEYS).len(), 1);
+        assert_eq!(world.reachable((1, 1, 0, 0), &NO_KEYS).len(), 5);
+    }
+
+    #[test]
+    fn nearest_of_heads_for_the_closest_target_it_can_reach() {
+        let mut world = ring();
+        let targets: HashSet<Coordinate> = [(2, 0, 0, 0), (1, 1, 0, 0)].into_iter().collect();
+        let nearest = world.nearest_of((0, 0, 0, 0), &targets, &NO_KEYS, &HashSet::new()).unwrap();
+        assert_eq!((nearest.first(), nearest.len()), (Some (&(0, 0, 0, 0)), 3));
+        // Locking the way along the bottom leaves only the top, where (2, 0) is nearer
+        world.lock_wall((0, 0, 0, 0), (0, 1, 0, 0), 0);
+        assert_eq!(world.nearest_of((0, 0, 0, 0), &targets, &NO_KEYS, &HashSet::new()), Some (vec![(0, 0, 0, 0), (1, 0, 0, 0), (2, 0, 0, 0)]));
+        let avoid: HashSet<Coordinate> = [(1, 0, 0, 0)].into_iter().collect();
+        assert_eq!(world.nearest_of((0, 0, 0, 0), &targets, &NO_KEYS, &avoid), None);
+        assert_eq!(world.nearest_of((0, 0, 0, 0), &HashSet::new(), &NO_KEYS, &HashSet::new()), None);
+    }
+
+    #[test]
+    fn distance_field_counts_moves_around_the_ring() {
+        let world = ring();
+        let distances = world.distance_field((0, 0, 0, 0));
+        assert_eq!(distances.len(), 6);
+        assert_eq!((distances[&(0, 0, 0, 0)], distances[&(2, 0, 0, 0)], distances[&(2, 1, 0, 0)]), (0, 2, 3));
+    }
+
+    #[test]
+    fn doors_lock_the_solution_path() {
+        let world = World::new(&Config { dimensions: DIMENSIONS, doors: 2, ..Default::default() }, &mut thread_rng()).unwrap();
+        assert_eq!(world.doors.len(), 2);
+        for (a, b) in &world.doors {
+            assert!(world.solution.windows(2).any(|pair| (pair[0], pair[1]) == (*a, *b) || (pair[1], pair[0]) == (*a, *b)));
+        }
+    }
+
+    #[test]
+    fn traps_stay_off_the_solution_path() {
+        let world = World::new(&Config { dimensions: DIMENSIONS, traps: 5, ..Default::default() }, &mut thread_rng()).unwrap();
+        let mut traps = 0;
+        for (w, z, y, x) in (0..2).flat_map(|w| (0..3).flat_map(move |z| (0..3).flat_map(move |y| (0..4).map(move |x| (w, z, y, x))))) {
+            if let Cell::Trap (trap) = world.cells[w][z][y][x] {
+                traps += 1;
+                assert!(!world.solution.contains(&(x, y, z, w)));
+                assert!(trap != Trap::Pit || z > 0, "Pit at {:?} has nowhere to drop to", (x, y, z, w));
+            }
+        }
+        assert_eq!(traps, 5);
+    }
+
+    #[test]
+    fn shifting_walls_keeps_every_cell_reachable() {
+        let mut world = World::new(&Config { dimensions: DIMENSIONS, doors: 2, ..Default::default() }, &mut thread_rng()).unwrap();
+        let doors = world.doors.clone();
+        for _ in 0..20 {
+            world.shift_walls(&mut thread_rng());
+            assert_eq!(world.distance_field(world.start).len(), DIMENSIONS.iter().product::<usize>());
+            assert_eq!(world.solution.last(), Some (&world.exit));
+            for (key, (a, b)) in doors.iter().enumerate() {
+                assert_eq!(world.wall_between(*a, *b), Wall::Door (key));
+            }
+        }
+    }
+
+    #[test]
+    fn shifting_a_ring_opens_its_middle() {
+        let mut world = ring();
+        assert!(world.shift_walls(&mut thread_rng()));
+        assert_eq!(world.wall_between((1, 0, 0, 0), (1, 1, 0, 0)), Wall::NoWall);
+        assert_eq!(world.distance_field((0, 0, 0, 0)).len(), 6);
+        assert_eq!(world.take_changed_levels(), vec![(0, 0)]);
+        assert!(world.take_changed_levels().is_empty());
+    }
+
+    #[test]
+    fn corridors_never_shift() {
+        let mut world = World::empty([3, 1, 1, 1]);
+        world.open_wall((0, 0, 0, 0), (1, 0, 0, 0));
+        world.open_wall((1, 0, 0, 0), (2, 0, 0, 0));
+        assert!(!world.shift_walls(&mut thread_rng()));
+        assert!(world.take_changed_levels().is_empty());
+    }
+
+    #[test]
+    fn bfs_takes_the_shorter_way_around() {
+        let world = ring();
+        assert_eq!(world.bfs((0, 0, 0, 0), (2, 0, 0, 0)), vec![(0, 0, 0, 0), (1, 0, 0, 0), (2, 0, 0, 0)]);
+        assert_eq!(world.bfs((0, 1, 0, 0), (2, 0, 0, 0)).len(), 4);
+    }
+
+    #[test]
+    fn bfs_to_own_cell_is_just_that_cell() {
+        let world = ring();
+        assert_eq!(world.bfs((1, 1, 0, 0), (1, 1, 0, 0)), vec![(1, 1, 0, 0)]);
+    }
+
+    #[test]
+    fn bfs_crosses_levels_and_fourth_dimension() {
+        let mut world = World::empty([1, 1, 2, 2]);
+        world.open_wall((0, 0, 0, 0), (0, 0, 1, 0));
+        world.open_wall((0, 0, 1, 0), (0, 0, 1, 1));
+        assert_eq!(world.bfs((0, 0, 0, 0), (0, 0, 1, 1)), vec![(0, 0, 0, 0), (0, 0, 1, 0), (0, 0, 1, 1)]);
+    }
+
+    #[test]
+    fn first_hit_finds_the_nearest_wall() {
+        let world = ring();
+        assert_eq!(world.first_hit(0, 0, [1.0, 0.0, 0.5], [1.0, 1.0, 0.5]), Some (0.5));
+        assert_eq!(world.first_hit(0, 0, [0.0, 0.0, 0.5], [2.0, 0.0, 0.5]), None);
+        assert_eq!(world.first_hit(0, 0, [0.0, 0.0, 0.5], [4.0, 0.0, 0.5]), Some (0.625));
+    }
+
+    #[test]
+    fn first_hit_leaves_out_levels_above_the_top() {
+        let world = World::empty([1, 1, 2, 1]);
+        assert_eq!(world.first_hit(0, 0, [0.0, 0.0, 0.0], [0.0, 0.0, 2.0]), None);
+        assert_eq!(world.first_hit(0, 1, [0.0, 0.0, 0.0], [0.0, 0.0, 2.0]), Some (0.5));
+    }
+}
@@ -0,0 +1,92 @@
+//! Measures of a maze's layout and of how hard its solution is to find, for comparing mazes and picking seeds.
+
+use std::fmt;
+
+use super::{Coordinate, World};
+
+// Weights of the difficulty score
+const WRONG_TURN: usize = 2; // For each way off the solution passed on the way to the exit
+const LEVEL_CHANGE: usize = 1; // Extra for each move up, down or through a portal, which are harder to keep track of
+
+/// Measures of a maze, taken along its shortest solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Analysis {
+    pub solution_length: usize, // Moves from the start to the exit
+    pub dead_ends: usize, // Cells with only one way in or out
+    pub branching: f32, // Average ways on from each cell of the solution, not counting the way back
+    pub axis_moves: [usize; 4], // Moves of the solution along x, y, z and w
+    pub difficulty: usize
+}
+
+impl Analysis {
+    /// Measure the world along its solution. Every move of the solution scores 1 towards the difficulty, every wrong
+    /// turn passed on the way scores WRONG_TURN, and every move between levels or slices LEVEL_CHANGE more
+    pub fn new(world: &World) -> Analysis {
+        let ways = |cell: &Coordinate| world.neighbors.get(cell).map_or(0, |neighbors| neighbors.len());
+        let dead_ends = world.neighbors.values().filter(|neighbors| neighbors.len() == 1).count();
+        let solution = &world.solution;
+        let solution_length = solution.len().saturating_sub(1);
+        let mut axis_moves = [0; 4];
+        for pair in solution.windows(2) {
+            let [(x, y, z, w), (nx, ny, nz, nw)] = [pair[0], pair[1]];
+            let axis = [x != nx, y != ny, z != nz, w != nw].iter().position(|moved| *moved).unwrap_or(0);
+            axis_moves[axis] += 1;
+        }
+        // Ways on from each cell before the exit: the start has no way back, and every other cell came from one
+        let ways_on: Vec<usize> = solution.iter().take(solution_length).enumerate()
+            .map(|(i, cell)| ways(cell).saturating_sub(if i == 0 { 0 } else { 1 }))
+            .collect();
+        let branching = ways_on.iter().sum::<usize>() as f32 / ways_on.len().max(1) as f32;
+        let wrong_turns: usize = ways_on.iter().map(|ways| ways.saturating_sub(1)).sum();
+        let difficulty = solution_length + WRONG_TURN * wrong_turns + LEVEL_CHANGE * (axis_moves[2] + axis_moves[3]);
+        Analysis { solution_length, dead_ends, branching, axis_moves, difficulty }
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [x, y, z, w] = self.axis_moves;
+        write!(f, "difficulty {}: {} moves to the exit ({} along x, {} along y, {} between levels, {} between slices), \
+            {} dead ends, {:.2} ways on from each step",
+            self.difficulty, self.solution_length, x, y, z, w, self.dead_ends, self.branching)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Corridor from (0, 0) to (2, 0), with a dead end off its middle at (1, 1), then up to (2, 0, 1)
+    fn forked() -> World {
+        let mut world = World::empty([3, 2, 2, 1]);
+        world.open_wall((0, 0, 0, 0), (1, 0, 0, 0));
+        world.open_wall((1, 0, 0, 0), (2, 0, 0, 0));
+        world.open_wall((1, 0, 0, 0), (1, 1, 0, 0));
+        world.open_wall((2, 0, 0, 0), (2, 0, 1, 0));
+        world.start = (0, 0, 0, 0);
+        world.exit = (2, 0, 1, 0);
+        world.solution = world.bfs(world.start, world.exit);
+        world
+    }
+
+    #[test]
+    fn counts_moves_turns_and_dead_ends() {
+        let analysis = Analysis::new(&forked());
+        assert_eq!(analysis.solution_length, 3);
+        assert_eq!(analysis.axis_moves, [2, 0, 1, 0]);
+        // (0, 0, 0), the fork's dead end and the top of the climb
+        assert_eq!(analysis.dead_ends, 3);
+        // One way on from the start, two at the fork, one at the foot of the climb
+        assert!((analysis.branching - 4.0 / 3.0).abs() < 1e-6);
+        assert_eq!(analysis.difficulty, 3 + WRONG_TURN + LEVEL_CHANGE);
+    }
+
+    #[test]
+    fn a_single_cell_has_nothing_to_solve() {
+        let mut world = World::empty([1, 1, 1, 1]);
+        world.solution = vec![(0, 0, 0, 0)];
+        let analysis = Analysis::new(&world);
+        assert_eq!((analysis.solution_length, analysis.dead_ends, analysis.difficulty), (0, 0, 0));
+        assert_eq!(analysis.branching, 0.0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Marks and extra data that gameplay systems keep for each cell, beside what the cell holds.
+//!
+//! Flags are stored flat, one byte per cell, so checking them on every move or every step of a search costs no more
+//! than looking up a wall. Data that only a few cells ever carry, like which ghost has claimed a cell, is kept in a map
+//! of just those cells.
+
+use std::collections::hash_map::HashMap;
+use std::ops::{BitOr, BitOrAssign};
+
+use super::Coordinate;
+
+/// A set of yes-or-no marks on a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flags(u8);
+
+impl Flags {
+    pub const NONE: Flags = Flags(0);
+    pub const VISITED: Flags = Flags(1); // The player has stood in the cell
+    pub const CHECKPOINT: Flags = Flags(1 << 1); // The player comes back to life here after being caught
+    pub const RESERVED: Flags = Flags(1 << 2); // A ghost has claimed the cell, see CellMeta::reserve
+
+    /// Whether every mark in other is also in these
+    pub const fn contains(self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+    fn bitor(self, other: Flags) -> Flags {
+        Flags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Flags {
+    fn bitor_assign(&mut self, other: Flags) {
+        self.0 |= other.0;
+    }
+}
+
+// Data carried by the few cells that have any
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Components {
+    decoration_seed: Option<u32>,
+    reserved_by: Option<usize> // Ghost holding the cell
+}
+
+/// Flags for every cell of a maze, and components for the cells that have them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellMeta {
+    dimensions: [usize; 4], // Of the maze in cells
+    flags: Vec<Flags>, // Of each cell, x changing fastest then y, z and w
+    components: HashMap<Coordinate, Components>
+}
+
+impl CellMeta {
+    /// No marks and no components on any cell of a maze with the given dimensions
+    pub fn new(dimensions: [usize; 4]) -> CellMeta {
+        CellMeta { dimensions, flags: vec![Flags::NONE; dimensions.iter().product()], components: HashMap::new() }
+    }
+
+    pub fn flags(&self, cell: Coordinate) -> Flags {
+        self.flags[self.index(cell)]
+    }
+
+    /// Whether the cell has every one of the flags
+    pub fn has(&self, cell: Coordinate, flags: Flags) -> bool {
+        self.flags(cell).contains(flags)
+    }
+
+    pub fn mark(&mut self, cell: Coordinate, flags: Flags) {
+        let i = self.index(cell);
+        self.flags[i] |= flags;
+    }
+
+    pub fn unmark(&mut self, cell: Coordinate, flags: Flags) {
+        let i = self.index(cell);
+        self.flags[i].0 &= !flags.0;
+    }
+
+    /// Take the flags off every cell
+    pub fn clear(&mut self, flags: Flags) {
+        for cell_flags in &mut self.flags {
+            cell_flags.0 &= !flags.0;
+        }
+    }
+
+    /// Number of cells with every one of the flags
+    pub fn count(&self, flags: Flags) -> usize {
+        self.flags.iter().filter(|cell_flags| cell_flags.contains(flags)).count()
+    }
+
+    /// Number mixed into the rolls that decide the cell's props, to decorate it differently from how its position alone would
+    pub fn decoration_seed(&self, cell: Coordinate) -> Option<u32> {
+        self.components.get(&cell).and_then(|components| components.decoration_seed)
+    }
+
+    pub fn set_decoration_seed(&mut self, cell: Coordinate, seed: Option<u32>) {
+        self.index(cell); // Refuses cells outside the maze, as the flags do
+        self.components.entry(cell).or_default().decoration_seed = seed;
+        self.tidy(cell);
+    }
+
+    /// Ghost holding the cell, if any
+    pub fn reserved_by(&self, cell: Coordinate) -> Option<usize> {
+        if !self.has(cell, Flags::RESERVED) {
+            return None; // Saves looking in the map for the many cells nobody holds
+        }
+        self.components.get(&cell).and_then(|components| components.reserved_by)
+    }
+
+    /// Claim the cell for the ghost, returning false without claiming it if another ghost already holds it
+    pub fn reserve(&mut self, cell: Coordinate, ghost: usize) -> bool {
+        match self.reserved_by(cell) {
+            Some (holder) if holder != ghost => false,
+            _ => {
+                self.mark(cell, Flags::RESERVED);
+                self.components.entry(cell).or_default().reserved_by = Some (ghost);
+                true
+            }
+        }
+    }
+
+    /// Give up the ghost's claim on the cell, if it holds it
+    pub fn release(&mut self, cell: Coordinate, ghost: usize) {
+        if self.reserved_by(cell) == Some (ghost) {
+            self.unmark(cell, Flags::RESERVED);
+            self.components.entry(cell).or_default().reserved_by = None;
+            self.tidy(cell);
+        }
+    }
+
+    /// Give up every claim the ghost holds
+    pub fn release_all(&mut self, ghost: usize) {
+        let held: Vec<Coordinate> = self.components.iter()
+            .filter(|(_, components)| components.reserved_by == Some (ghost))
+            .map(|(cell, _)| *cell)
+            .collect();
+        for cell in held {
+            self.release(cell, ghost);
+        }
+    }
+
+    // Drop the cell from the map once it carries nothing, so the map only ever holds the few cells that do
+    fn tidy(&mut self, cell: Coordinate) {
+        if self.components.get(&cell) == Some (&Components::default()) {
+            self.components.remove(&cell);
+        }
+    }
+
+    // Where the cell is in the list of flags
+    fn index(&self, (x, y, z, w): Coordinate) -> usize {
+        let [width, height, depth, fourth] = self.dimensions;
+        assert!(x < width && y < height && z < depth && w < fourth,
+            "No cell at {:?} in a maze of {:?} cells", (x, y, z, w), self.dimensions);
+        ((w * depth + z) * height + y) * width + x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_stay_on_their_own_cells() {
+        let mut meta = CellMeta::new([3, 2, 2, 2]);
+        meta.mark((2, 1, 0, 1), Flags::VISITED);
+        meta.mark((2, 1, 0, 1), Flags::CHECKPOINT);
+        meta.mark((0, 0, 1, 0), Flags::VISITED);
+        assert!(meta.has((2, 1, 0, 1), Flags::VISITED | Flags::CHECKPOINT));
+        assert!(!meta.has((0, 0, 1, 0), Flags::VISITED | Flags::CHECKPOINT));
+        assert!(meta.flags((1, 1, 0, 1)).is_empty());
+        assert_eq!(meta.count(Flags::VISITED), 2);
+        meta.unmark((2, 1, 0, 1), Flags::VISITED);
+        assert_eq!(meta.flags((2, 1, 0, 1)), Flags::CHECKPOINT);
+        meta.clear(Flags::VISITED | Flags::CHECKPOINT);
+        assert_eq!((meta.count(Flags::VISITED), meta.count(Flags::CHECKPOINT)), (0, 0));
+    }
+
+    #[test]
+    fn ghosts_cant_take_each_others_cells() {
+        let mut meta = CellMeta::new([4, 4, 1, 1]);
+        assert!(meta.reserve((1, 1, 0, 0), 0));
+        assert!(meta.reserve((1, 1, 0, 0), 0));
+        assert!(!meta.reserve((1, 1, 0, 0), 1));
+        assert!(meta.reserve((2, 1, 0, 0), 0));
+        meta.release((1, 1, 0, 0), 1);
+        assert_eq!(meta.reserved_by((1, 1, 0, 0)), Some (0));
+        meta.release_all(0);
+        assert_eq!(meta.count(Flags::RESERVED), 0);
+        assert!(meta.reserve((1, 1, 0, 0), 1));
+        assert_eq!(meta.reserved_by((1, 1, 0, 0)), Some (1));
+    }
+
+    #[test]
+    fn components_are_only_kept_while_set() {
+        let mut meta = CellMeta::new([2, 2, 2, 2]);
+        meta.set_decoration_seed((1, 0, 1, 0), Some (7));
+        meta.reserve((1, 0, 1, 0), 3);
+        meta.release((1, 0, 1, 0), 3);
+        assert_eq!(meta.decoration_seed((1, 0, 1, 0)), Some (7));
+        meta.set_decoration_seed((1, 0, 1, 0), None);
+        assert!(meta.components.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn cells_outside_the_maze_are_refused() {
+        CellMeta::new([2, 2, 2, 2]).mark((0, 2, 0, 0), Flags::VISITED);
+    }
+}
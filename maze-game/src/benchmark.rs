@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use maze_core::world::World;
+use maze_render::profiler::{GpuProfiler, STAGES};
+
+/// Seconds the camera flies for before the results are printed
+pub const LENGTH: f32 = 30.0;
+const ORBIT_TIME: f32 = 15.0; // Seconds spent circling the maze from above, before sweeping through the player's level
+const ORBIT_HEIGHT: f32 = 0.6; // Of the orbit's radius, above the top level
+const EYE_HEIGHT: f32 = 0.5; // Above the floor, for the sweep through the level
+
+/// Frame times over a run of --benchmark, which draws the same seeded maze from the same camera path every run while
+/// the bot plays
+pub struct Benchmark {
+    start: Instant,
+    frame_times: Vec<f32> // Seconds each frame took, in order
+}
+
+impl Benchmark {
+    pub fn new(start: Instant) -> Benchmark {
+        Benchmark { start, frame_times: Vec::new() }
+    }
+
+    /// Note how long the last frame took, returning whether the run is over
+    pub fn record(&mut self, now: Instant, frame_time: f32) -> bool {
+        self.frame_times.push(frame_time);
+        self.seconds(now) >= LENGTH
+    }
+
+    pub fn seconds(&self, now: Instant) -> f32 {
+        now.saturating_duration_since(self.start).as_secs_f32()
+    }
+
+    /// Results as a JSON object: the average frame time, the average of the slowest 1% of frames, and the average
+    /// GPU time of each part of a frame if it was timed, all in milliseconds
+    pub fn report(&self, seed: u64, dimensions: [usize; 4], profiler: Option<&GpuProfiler>) -> String {
+        // The first frame waits on every upload, so it isn't counted
+        let mut times: Vec<f32> = self.frame_times.iter().skip(1).copied().collect();
+        let average = times.iter().sum::<f32>() / times.len().max(1) as f32;
+        times.sort_unstable_by(|a, b| b.total_cmp(a));
+        let slowest = &times[..(times.len() / 100).max(1).min(times.len())];
+        let one_percent = slowest.iter().sum::<f32>() / slowest.len().max(1) as f32;
+        let gpu: Vec<String> = profiler.into_iter()
+            .flat_map(|profiler| STAGES.iter().zip(profiler.averages()))
+            .map(|(stage, milliseconds)| format!("\"{}\": {:.3}", stage, milliseconds))
+            .collect();
+        let [x, y, z, w] = dimensions;
+        format!("{{\"seed\": {}, \"dimensions\": [{}, {}, {}, {}], \"frames\": {}, \"average-frame-ms\": {:.3}, \"one-percent-low-ms\": {:.3}, \"gpu-ms\": {{{}}}}}",
+            seed, x, y, z, w, times.len(), average * 1000.0, one_percent * 1000.0, gpu.join(", "))
+    }
+}
+
+/// Where the camera is the given number of seconds into the run, and its yaw and pitch. It circles the whole maze from
+/// above, then sweeps diagonally through the given level at eye height
+pub fn camera_path(world: &World, level: f32, seconds: f32) -> ([f32; 3], f32, f32) {
+    let centre = [(world.width as f32 - 1.0) / 2.0, (world.height as f32 - 1.0) / 2.0, world.depth as f32 / 2.0];
+    if seconds < ORBIT_TIME {
+        let radius = world.width.max(world.height) as f32 * 0.8 + 2.0;
+        let angle = seconds / ORBIT_TIME * std::f32::consts::TAU;
+        let position = [centre[0] + radius * angle.cos(), centre[1] + radius * angle.sin(), world.depth as f32 + radius * ORBIT_HEIGHT];
+        let (yaw, pitch) = looking(position, centre);
+        return (position, yaw, pitch);
+    }
+    // Ease from one corner of the level to the other, looking the way it's going
+    let t = ((seconds - ORBIT_TIME) / (LENGTH - ORBIT_TIME)).min(1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    let [far_x, far_y] = [world.width as f32 - 1.0, world.height as f32 - 1.0];
+    let position = [far_x * t, far_y * t, level + EYE_HEIGHT];
+    let (yaw, pitch) = looking(position, [far_x + 1.0, far_y + 1.0, level + EYE_HEIGHT]);
+    (position, yaw, pitch)
+}
+
+// Yaw and pitch of a camera at the position looking at the target, in the free camera's angles
+fn looking(from: [f32; 3], to: [f32; 3]) -> (f32, f32) {
+    let [dx, dy, dz] = [0, 1, 2].map(|i| to[i] - from[i]);
+    let length = (dx * dx + dy * dy + dz * dz).sqrt().max(f32::EPSILON);
+    ((-dx).atan2(-dy), (-dz / length).clamp(-1.0, 1.0).acos())
+}
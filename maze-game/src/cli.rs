@@ -0,0 +1,129 @@
+use clap::Parser;
+
+/// Escape a 4D maze before the ghost catches you
+#[derive(Parser)]
+#[clap(name = "maze", version)]
+pub struct Args {
+    /// Config file to read settings from
+    #[clap(long, value_name = "PATH", default_value = "config.toml", value_parser)]
+    pub config: String,
+
+    /// Keep separate settings and best times under this name, in profiles/NAME.toml next to the config file
+    #[clap(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Size of the maze along each axis, eg. 5x5x3x3
+    #[clap(long, value_name = "XxYxZxW", value_parser)]
+    pub dimensions: Option<String>,
+
+    /// Generate the same maze every time from this seed
+    #[clap(long, value_parser)]
+    pub seed: Option<u64>,
+
+    /// Rules to play by: classic, time-attack, zen or survival
+    #[clap(long, value_name = "MODE")]
+    pub mode: Option<String>,
+
+    /// Let a second player move a ghost from the same keyboard
+    #[clap(long)]
+    pub co_op: bool,
+
+    /// Race a second player to the exit on the other half of the window
+    #[clap(long, conflicts_with = "co-op")]
+    pub split_screen: bool,
+
+    /// Host an online race, waiting for someone to join on the port [default: 47474]
+    #[clap(long, value_name = "PORT", conflicts_with_all = &["join", "split-screen", "co-op", "daily", "campaign", "continue", "level"])]
+    pub host: Option<Option<u16>>,
+
+    /// Join the online race hosted at the address, eg. 192.168.1.20 or 192.168.1.20:47474, playing by the host's settings
+    #[clap(long, value_name = "ADDRESS", conflicts_with_all = &["split-screen", "co-op", "daily", "campaign", "continue", "level"])]
+    pub join: Option<String>,
+
+    /// Let others watch the game live with --watch, serving it on the port [default: 47475]
+    #[clap(long, value_name = "PORT")]
+    pub broadcast: Option<Option<u16>>,
+
+    /// Watch the game broadcast at the address, eg. 192.168.1.20 or 192.168.1.20:47475, starting from a view from above
+    #[clap(long, value_name = "ADDRESS", conflicts_with_all = &["broadcast", "host", "join", "split-screen", "co-op", "daily", "campaign", "continue", "level"])]
+    pub watch: Option<String>,
+
+    /// Play a hand-made maze from a text map like the ones --export-maze writes
+    #[clap(long, value_name = "PATH")]
+    pub level: Option<String>,
+
+    /// Play the campaign of mazes listed in levels.toml next to the config file, carrying on from the last level reached
+    #[clap(long)]
+    pub campaign: bool,
+
+    /// Play today's daily challenge: the same maze for everyone, with no compass or quickloading
+    #[clap(long, conflicts_with_all = &["campaign", "continue", "level"])]
+    pub daily: bool,
+
+    /// Carry on from the quicksave of the last game, made with F6
+    #[clap(long)]
+    pub r#continue: bool,
+
+    /// Fill the screen with a borderless window
+    #[clap(long, action)]
+    pub fullscreen: bool,
+
+    /// Simulate games with a computer player instead of opening a window [default: 100 games]
+    #[clap(long, value_name = "GAMES", value_parser)]
+    pub headless: Option<Option<usize>>,
+
+    /// Write a text map of the generated maze to the file and print how hard it is, then quit
+    #[clap(long, value_name = "PATH", value_parser)]
+    pub export_maze: Option<String>,
+
+    /// Print best times, mazes escaped and win rate from past runs, then quit
+    #[clap(long)]
+    pub stats: bool,
+
+    /// Print the best times in today's daily challenge from the leaderboard server in the config file, then quit
+    #[clap(long)]
+    pub leaderboard: bool,
+
+    /// Draw the same seeded maze along the same camera path every run while the bot plays, then print frame times and
+    /// GPU timings as JSON and quit. With --headless, time generating and playing the mazes without drawing them instead
+    #[clap(long, conflicts_with_all = &["host", "join", "watch", "split-screen", "campaign", "continue", "daily"], action)]
+    pub benchmark: bool,
+
+    /// Time the GPU's work on each part of every frame and count what its draws cost, logging the averages as the game
+    /// runs and printing them when it closes
+    #[clap(long)]
+    pub gpu_timing: bool,
+
+    /// List the graphics cards that can be chosen with the card setting, then quit
+    #[clap(long, action)]
+    pub list_gpus: bool
+}
+
+impl Args {
+    /// Settings given on the command line, to apply on top of the config file
+    pub fn overrides(&self) -> Vec<(&'static str, String)> {
+        let mut settings = Vec::new();
+        if let Some (dimensions) = &self.dimensions {
+            settings.push(("dimensions", dimensions.clone()));
+        }
+        if let Some (seed) = self.seed {
+            settings.push(("seed", seed.to_string()));
+        }
+        if let Some (mode) = &self.mode {
+            settings.push(("mode", mode.clone()));
+        }
+        if self.co_op {
+            settings.push(("co-op", "true".to_string()));
+        }
+        if self.split_screen {
+            settings.push(("split-screen", "true".to_string()));
+        }
+        if let Some (level) = &self.level {
+            settings.push(("level", level.clone()));
+        }
+        if self.fullscreen {
+            settings.push(("window", "borderless".to_string()));
+        }
+        settings
+    }
+}
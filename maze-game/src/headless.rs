@@ -0,0 +1,81 @@
+use std::fs;
+use std::time::{Duration, Instant};
+
+use maze_core::bot::Bot;
+use maze_core::config::Config;
+use maze_core::error::MazeError;
+use maze_core::game::Game;
+use maze_core::player::GameState;
+use maze_core::sim::GameSim;
+use maze_core::world::analysis::Analysis;
+
+const MOVE_TIME: f32 = 0.5; // Same pace as holding down a movement key
+const TIME_LIMIT: f32 = 600.0; // Give up on games the bot can't finish
+const BENCHMARK_GAMES: u64 = 20; // Played from consecutive seeds, so every run does the same work
+
+/// Games to simulate when --headless doesn't say how many
+pub const DEFAULT_GAMES: usize = 100;
+
+// Play games with the bot instead of a window, and print how they went
+pub fn run(config: &Config, games: usize) -> Result<(), MazeError> {
+    println!("Simulating {} games of {:?} without rendering", games, config.dimensions);
+    let (mut generation_time, mut solution_length, mut game_time) = (Duration::ZERO, 0, 0.0);
+    let (mut won, mut caught, mut lost) = (0, 0, 0);
+    for _ in 0..games {
+        let generation_start = Instant::now();
+        let mut sim = GameSim::new(config)?;
+        generation_time += generation_start.elapsed();
+        solution_length += sim.game.world.solution.len();
+
+        // Step a simulated clock rather than waiting in real time
+        sim.play(&Bot, MOVE_TIME, TIME_LIMIT);
+        game_time += sim.elapsed();
+
+        let game = &sim.game;
+        match game.player.game_state {
+            GameState::Won => won += 1,
+            GameState::Lost if game.ghosts.iter().any(|ghost| ghost.caught(&game.player)) => caught += 1,
+            _ => lost += 1
+        }
+    }
+
+    let games = games.max(1);
+    println!("Average generation time: {:.2}ms", generation_time.as_secs_f64() * 1000.0 / games as f64);
+    println!("Average solution length: {:.1} cells", solution_length as f32 / games as f32);
+    println!("Average game length: {:.1}s", game_time / games as f32);
+    println!("Won: {}, caught by ghost: {}, out of time: {}", won, caught, lost);
+    println!("Ghost catch rate: {:.1}%", caught as f32 * 100.0 / games as f32);
+    Ok (())
+}
+
+// Time generating and playing the same games every run, starting from the configured seed or 0
+pub fn benchmark(config: &Config) -> Result<(), MazeError> {
+    let first = config.seed.unwrap_or(0);
+    println!("Benchmarking {} games of {:?} from seed {}", BENCHMARK_GAMES, config.dimensions, first);
+    let (mut generation_time, mut slowest_generation, mut play_time) = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+    for seed in first..first + BENCHMARK_GAMES {
+        let generation_start = Instant::now();
+        let game = Game::from_seed(config, seed)?;
+        let generation = generation_start.elapsed();
+        generation_time += generation;
+        slowest_generation = slowest_generation.max(generation);
+
+        let play_start = Instant::now();
+        GameSim::from_game(game, config).play(&Bot, MOVE_TIME, TIME_LIMIT);
+        play_time += play_start.elapsed();
+    }
+
+    let games = BENCHMARK_GAMES as f64;
+    println!("Average generation time: {:.2}ms, slowest {:.2}ms", generation_time.as_secs_f64() * 1000.0 / games, slowest_generation.as_secs_f64() * 1000.0);
+    println!("Average time to simulate a game: {:.2}ms", play_time.as_secs_f64() * 1000.0 / games);
+    Ok (())
+}
+
+// Write a text map of a freshly generated maze, with everything placed in it
+pub fn export_maze(config: &Config, path: &str) -> Result<(), MazeError> {
+    let game = Game::new(config)?;
+    fs::write(path, game.world.to_string()).map_err(|e| MazeError::io(path, e))?;
+    println!("Wrote {:?} maze to {}", config.dimensions, path);
+    println!("{}", Analysis::new(&game.world));
+    Ok (())
+}
@@ -0,0 +1,1378 @@
+use std::vec;
+use std::time::{Duration, Instant};
+use std::process;
+use std::path::Path;
+
+use clap::Parser;
+use log::{error, info, warn};
+use winit::event::{DeviceEvent, Event, KeyEvent, MouseButton, WindowEvent, ElementState};
+use winit::event_loop::EventLoop;
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+use winit::window::{CursorGrabMode, Fullscreen, Icon, Window};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use vulkano::{Validated, VulkanError};
+use vulkano::swapchain;
+use vulkano::command_buffer::SubpassContents;
+use vulkano::query::QueryPipelineStatisticFlags;
+use vulkano::sync;
+use vulkano::sync::GpuFuture;
+use vulkano::format::ClearValue;
+
+use maze_core::bot::Bot;
+use maze_core::campaign::{self, Campaign, Progress};
+use maze_core::chat::{self, Chat, ChatEvent};
+use maze_core::config::{self, Config, LogLevels, Mode, TargetFps, ViewDistance};
+use maze_core::daily::Day;
+use maze_core::error::MazeError;
+use maze_core::feed::Feed;
+use maze_core::game::Game;
+use maze_core::ghost::Ghost;
+use maze_core::leaderboard::{Entry, Leaderboard, Reply, Submission};
+use maze_core::linalg;
+use maze_core::locale::{Locale, Message};
+use maze_core::lockstep::{Inputs, Lockstep};
+use maze_core::net::{self, Connection, Lobby};
+use maze_core::player::GameState;
+use maze_core::profile;
+use maze_core::replay::{self, Flythrough, Replay};
+use maze_core::save;
+use maze_core::spectate::{self, Broadcaster, Watcher};
+use maze_core::stats::{self, Outcome, Run, Stats};
+use maze_core::timing::{FramePacer, GameClock, Span};
+use maze_core::watcher::ConfigWatcher;
+use maze_core::world::World;
+use maze_core::world::analysis::Analysis;
+use maze_render::View;
+use maze_render::assets::Assets;
+use maze_render::camera::Camera;
+use maze_render::gfx::{self, FramesInFlight, Recorder};
+use maze_render::ghost::GhostRenderer;
+use maze_render::objects::ObjectsRenderer;
+use maze_render::overlay::SliceOverlayRenderer;
+use maze_render::palette::Palette;
+use maze_render::parameters::{DEATH_SPEED, DEATH_TIME, DEATH_ZOOM, FOV_NARROW, FOV_WIDEN, GHOST_SHAKE, HIT_SHAKE};
+use maze_render::particles::ParticleSystem;
+use maze_render::profiler::{self, GpuProfiler};
+use maze_render::pipeline::{self, Vertex};
+use maze_render::player::{EchoRenderer, PlayerRenderer};
+use maze_render::replay::RouteRenderer;
+use maze_render::scene::{Renderable, Scene};
+use maze_render::sky;
+use maze_render::texture;
+use maze_render::ui::{Click, UserInterface};
+use maze_render::world::WorldRenderer;
+
+mod benchmark;
+mod cli;
+mod headless;
+
+const NAME: &str = "4D Pacman v0.2";
+
+fn main() {
+    // Report anything that stops the game from starting, rather than panicking
+    if let Err (e) = run() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+// Fullscreen mode for the window setting, or None for a normal window. Exclusive fullscreen uses the video mode
+// matching the configured resolution if there is one, otherwise the largest and fastest the monitor offers
+fn fullscreen(window: &Window, setting: &config::Window, resolution: &config::Resolution) -> Option<Fullscreen> {
+    let monitor = window.current_monitor().or_else(|| window.primary_monitor());
+    match setting {
+        config::Window::Borderless => Some (Fullscreen::Borderless(monitor)),
+        config::Window::Exclusive => {
+            let modes = monitor.as_ref().map(|monitor| monitor.video_modes().collect::<Vec<_>>()).unwrap_or_default();
+            let mode = modes.iter()
+                .filter(|mode| matches!(resolution, config::Resolution::Fixed (x, y) if mode.size() == PhysicalSize::new(*x, *y)))
+                .max_by_key(|mode| mode.refresh_rate_millihertz())
+                .or_else(|| modes.iter().max_by_key(|mode| (mode.size().width * mode.size().height, mode.refresh_rate_millihertz())));
+            match mode {
+                Some (mode) => Some (Fullscreen::Exclusive(mode.clone())),
+                None => {
+                    warn!(target: "render", "No video modes to choose from for exclusive fullscreen, using borderless instead");
+                    Some (Fullscreen::Borderless(monitor))
+                }
+            }
+        },
+        config::Window::Size (..) => None
+    }
+}
+
+// Window title naming the maze being played, so it can be shared and played again
+fn title(game: &Game, config: &Config, locale: &Locale, paused: bool) -> String {
+    let message = Message::new(if paused { "title-paused" } else { "title" })
+        .with("name", NAME)
+        .with("size", config::maze_size(&config.dimensions))
+        .with("seed", game.seed);
+    locale.text(&message)
+}
+
+const MOVE_TIME: f32 = 0.5; // Seconds each move from one cell to the next takes
+const ATTRACT_IDLE: f32 = 60.0; // Seconds without a key pressed before the bot starts playing
+const ATTRACT_RESTART: f32 = 5.0; // Seconds the bot's finished game stays up before it starts another
+
+// Keys the second player moves with, as the ghost in co-op or racing in split-screen, and the move each makes
+const SECOND_KEYS: [(KeyCode, [i32; 4]); 8] = [
+    (KeyCode::KeyI, [0, -1, 0, 0]),
+    (KeyCode::KeyK, [0, 1, 0, 0]),
+    (KeyCode::KeyJ, [-1, 0, 0, 0]),
+    (KeyCode::KeyL, [1, 0, 0, 0]),
+    (KeyCode::KeyY, [0, 0, 1, 0]),
+    (KeyCode::KeyH, [0, 0, -1, 0]),
+    (KeyCode::KeyU, [0, 0, 0, -1]),
+    (KeyCode::KeyO, [0, 0, 0, 1])
+];
+
+// Where the mouse is over the first player's view, in the normalized device coordinates the UI is laid out in, or None
+// if it's over the second player's view in a split-screen race
+fn cursor_ndc(position: PhysicalPosition<f64>, window: PhysicalSize<u32>, split_screen: bool) -> Option<[f32; 2]> {
+    let screens = if split_screen { 2.0 } else { 1.0 };
+    let [x, y] = [position.x / window.width as f64 * screens, position.y / window.height as f64];
+    if x > 1.0 { None } else { Some ([2.0 * x as f32 - 1.0, 2.0 * y as f32 - 1.0]) }
+}
+
+// Where each player's view goes in the window: all of it, or side by side halves in a split-screen race
+fn screen_viewports(dimensions: [u32; 2], split_screen: bool) -> Vec<gfx::Viewport> {
+    gfx::viewports(dimensions, if split_screen { 2 } else { 1 })
+}
+
+// How much of the maze is drawn around the player the camera follows: every level of their w-slice and no other
+// slice in the dollhouse view, otherwise as configured
+fn view_distance(config: &Config, camera: &Camera, world: &World) -> ViewDistance {
+    if camera.is_dollhouse() {
+        ViewDistance { above: world.depth, below: world.depth, slices: 0 }
+    } else {
+        config.view_distance()
+    }
+}
+
+// Size of each player's view, for the camera's shape and the UI's layout
+fn screen_size(resolution: [u32; 2], split_screen: bool) -> [u32; 2] {
+    if split_screen { [resolution[0] / 2, resolution[1]] } else { resolution }
+}
+
+// Field of view widening while the player moves and narrowing as the ghost closes in, for the camera to ease towards
+fn fov_target(config: &Config, preset: &config::CameraPreset, game: &Game) -> Option<f32> {
+    if config.fov_effects == 0.0 || game.player.game_state != GameState::Playing {
+        return None;
+    }
+    let moving = game.player.get_position() != game.player.cell().map(|i| i as f32);
+    let [narrow, range] = FOV_NARROW;
+    let shift = if moving { FOV_WIDEN } else { 0.0 } - narrow * (1.0 - ghost_distance(game, config) / range).max(0.0);
+    Some ((preset.fov + shift * config.fov_effects).clamp(30.0, 120.0))
+}
+
+// Point for the camera to follow and field of view while zooming in on the ghost that caught the player, the given
+// fraction of the way through
+fn death_zoom(config: &Config, preset: &config::CameraPreset, game: &Game, progress: f32) -> ([f32; 4], Option<f32>) {
+    let player = game.player.get_position();
+    let distance = |position: [f32; 4]| linalg::sub(position, player).iter().map(|x| x * x).sum::<f32>();
+    let ghost = game.ghosts.iter().map(|ghost| ghost.position()).min_by(|a, b| distance(*a).total_cmp(&distance(*b))).unwrap_or(player);
+    let t = progress.clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    let [drift, narrow] = DEATH_ZOOM;
+    let target = [0, 1, 2, 3].map(|i| player[i] + (ghost[i] - player[i]) * drift * t);
+    let fov = if config.fov_effects == 0.0 { None } else { Some ((preset.fov - narrow * t * config.fov_effects).clamp(30.0, 120.0)) };
+    (target, fov)
+}
+
+// Straight line distance in cells between the player and the nearest ghost, across all four dimensions, or infinite with no ghost
+fn ghost_distance(game: &Game, config: &Config) -> f32 {
+    if !config.mode.has_ghost() {
+        return f32::INFINITY;
+    }
+    game.ghosts.iter()
+        .map(|ghost| linalg::sub(ghost.position(), game.player.get_position()).iter().map(|x| x * x).sum::<f32>().sqrt())
+        .fold(f32::INFINITY, f32::min)
+}
+
+// Fastest earlier escape from the same maze, to race against if that's turned on
+fn load_echo(config: &Config, config_path: &str, game: &Game) -> Option<Replay> {
+    let path = replay::best_path(config_path, &config.dimensions, game.seed);
+    if !config.race_echo || !Path::new(&path).exists() {
+        return None;
+    }
+    Replay::load(&path).map_err(|e| warn!("Couldn't load the echo to race: {}", e)).ok()
+}
+
+// Summary of past runs for --stats
+fn print_stats(stats: &Stats) {
+    println!("{} of {} mazes escaped ({:.0}% against the ghost)", stats.solved(), stats.runs.len(), stats.win_rate() * 100.0);
+    for (size, seconds) in stats.best_times() {
+        println!("{:>12}  best {}:{:02}", size, seconds / 60, seconds % 60);
+    }
+    for (date, best) in stats.daily() {
+        match best {
+            Some (seconds) => println!("{:>12}  daily {}:{:02}", date, seconds / 60, seconds % 60),
+            None => println!("{:>12}  daily, not escaped", date)
+        }
+    }
+}
+
+// Best times in a day's challenge, from the leaderboard server
+fn print_leaderboard(date: &str, entries: &[Entry]) {
+    println!("Best times in the daily challenge for {}:", date);
+    if entries.is_empty() {
+        println!("    Nobody has escaped yet");
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        println!("{:>4}. {:<20} {}:{:02}", i + 1, entry.name, entry.seconds / 60, entry.seconds % 60);
+    }
+}
+
+// Print log messages at the configured levels, which RUST_LOG can override
+fn init_logging(levels: &LogLevels) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(levels.default);
+    for (target, level) in &levels.targets {
+        builder.filter_module(target, *level);
+    }
+    builder.parse_default_env().init();
+}
+
+fn run() -> Result<(), MazeError> {
+    // Load user config file with any command line overrides, and check whether to skip rendering entirely
+    let args = cli::Args::parse();
+    let overrides = args.overrides();
+    let created = !Path::new(&args.config).exists();
+    if created {
+        Config::default().save(&args.config)?;
+    }
+    let config_path = match &args.profile {
+        Some (name) => profile::open(&args.config, name)?,
+        None => args.config.clone()
+    };
+    let save_path = save::path(&config_path);
+    let stats_path = stats::path(&config_path);
+    // Shared by every profile, since it only depends on the card
+    let pipeline_cache_path = gfx::pipeline_cache_path(&args.config);
+    let mut config = Config::new(&config_path)?;
+    config.override_with(&overrides)?;
+    init_logging(&config.log_level);
+    if created {
+        info!("Wrote the default settings to {}", args.config);
+    }
+    let daily = if args.daily { Some (Day::today()) } else { None };
+    if let Some (day) = daily {
+        config = day.config(&config);
+        info!("Daily challenge for {}, a {} maze", day, config::maze_size(&config.dimensions));
+    }
+    if config.legacy_format {
+        warn!("{} uses the older `key: value` format, which still works, but new settings are documented in config.toml", config_path);
+    }
+    if let Some (path) = &args.export_maze {
+        return headless::export_maze(&config, path);
+    }
+    if args.stats {
+        print_stats(&Stats::load(&stats_path)?);
+        return Ok (());
+    }
+    let leaderboard = config.leaderboard.as_deref().map(Leaderboard::new).transpose()?;
+    if args.leaderboard {
+        let leaderboard = leaderboard.as_ref().ok_or_else(|| MazeError::Argument {
+            option: "leaderboard".to_string(),
+            reason: format!("Set leaderboard in {} to the server's address first", config_path)
+        })?;
+        let date = Day::today().to_string();
+        print_leaderboard(&date, &leaderboard.daily(&date)?);
+        return Ok (());
+    }
+    if let (Some (day), Some (leaderboard)) = (daily, &leaderboard) {
+        leaderboard.fetch_daily(&day.to_string());
+    }
+    if args.benchmark && args.headless.is_some() {
+        return headless::benchmark(&config);
+    }
+    if args.benchmark {
+        // Every run draws the same maze as fast as it can
+        config.seed = Some (config.seed.unwrap_or(0));
+        config.target_fps = TargetFps::Unlimited;
+    }
+    if let Some (games) = args.headless {
+        return headless::run(&config, games.unwrap_or(headless::DEFAULT_GAMES));
+    }
+
+    // An online race is agreed before the window opens, so both games start from the same maze at about the same time
+    let mut connection = if let Some (port) = args.host {
+        let lobby = Lobby::open(port.unwrap_or(net::DEFAULT_PORT))?;
+        println!("Waiting for someone to join with --join on port {}", lobby.port());
+        Some (lobby.wait(&mut config)?)
+    } else if let Some (address) = &args.join {
+        Some (Connection::join(address, &mut config)?)
+    } else {
+        None
+    };
+    if let Some (connection) = &connection {
+        // The other player is across the network rather than at this keyboard
+        config.split_screen = false;
+        config.co_op = false;
+        info!("Racing {} online in a {} maze", connection.opponent().name, config::maze_size(&config.dimensions));
+    }
+    let racing = config.split_screen || connection.is_some();
+    let mut opponent_gone = false;
+
+    // Watching starts from the broadcast game's maze, and follows it from then on instead of playing
+    let (mut watching, watched_world) = match &args.watch {
+        Some (address) => {
+            let (watcher, world) = Watcher::connect(address)?;
+            world.fit_config(&mut config);
+            config.split_screen = false;
+            config.co_op = false;
+            info!(target: "net", "Watching the game broadcast at {}", address);
+            (Some (watcher), Some (world))
+        },
+        None => (None, None)
+    };
+    let spectating = watching.is_some();
+    let own_run = !racing && !spectating; // Whether the run goes in the stats and best times, and to the leaderboard
+    let mut broadcaster = args.broadcast.map(|port| Broadcaster::open(port.unwrap_or(spectate::DEFAULT_PORT))).transpose()?;
+    if let Some (broadcaster) = &broadcaster {
+        println!("Broadcasting the game for anyone who runs with --watch on port {}", broadcaster.port());
+    }
+    let chat = config.twitch_channel.as_deref().filter(|_| !spectating).map(|channel| Chat::connect(channel, config.chat_vote_time));
+
+    // Create vulkan instance
+    let event_loop = EventLoop::new().map_err(|e| MazeError::graphics(format!("Couldn't start the event loop: {}", e)))?;
+    let instance = gfx::instance(&event_loop, NAME)?;
+
+    // for layer in instance::layers_list().unwrap() {
+    //     println!("Layer: {}", layer.name())
+    // }
+
+    if args.list_gpus {
+        for card in gfx::list_cards(&instance)? {
+            println!("{}", card);
+        }
+        return Ok (());
+    }
+    info!(target: "render", "Card list: {:?}", gfx::list_cards(&instance)?);
+    let card = gfx::choose_card(&instance, &config.card)?;
+    info!(target: "render", "Using card {}", card.properties().device_name);
+
+    // Create logical device
+    let (device, draw_queue) = gfx::device(card.clone(), args.gpu_timing || args.benchmark)?;
+
+    // Create window
+    let (window, surface) = {
+        let (icon_width, icon_height, icon) = texture::window_icon()?;
+        let mut builder = Window::default_attributes();
+        if let config::Window::Size (width, height) = config.window {
+            builder = builder.with_inner_size(PhysicalSize { width, height });
+        }
+        builder = builder
+            .with_resizable(false)
+            .with_title(NAME)
+            .with_window_icon(Icon::from_rgba(icon, icon_width, icon_height).ok());
+        gfx::window(&event_loop, instance.clone(), builder)?
+    };
+    // Fullscreen needs the window to know which monitor it's on
+    window.set_fullscreen(fullscreen(&window, &config.window, &config.resolution));
+
+    // Create swapchain
+    let (surface_caps, format) = gfx::surface_capabilities(&card, &surface)?;
+    let (res_x, res_y) = match config.resolution {
+        config::Resolution::Fixed (x, y) => (
+            x.clamp(surface_caps.min_image_extent[0], surface_caps.max_image_extent[0]),
+            y.clamp(surface_caps.min_image_extent[1], surface_caps.max_image_extent[1])),
+        config::Resolution::Max => (surface_caps.max_image_extent[0], surface_caps.max_image_extent[1])
+    };
+    let mut resolution = [res_x, res_y];
+    let buffers = config.frames_in_flight.clamp(surface_caps.min_image_count, surface_caps.max_image_count.unwrap_or(u32::MAX));
+    let transform = surface_caps.current_transform;
+    let (mut swapchain, images) = gfx::swapchain(device.clone(), surface.clone(), buffers, format, resolution, transform)?;
+
+    let (samples, sample_count) = gfx::max_samples(&device);
+
+    // Compile shader pipeline, starting from the pipelines cached by the last run
+    let cache = gfx::load_pipeline_cache(device.clone(), &pipeline_cache_path);
+    let pipeline = pipeline::compile_shaders::<Vertex>(device.clone(), cache, &swapchain, samples, config.depth_prepass);
+
+    let mut init_futures = Vec::new();
+
+    // Load models, and UI textures into one atlas
+    let span = Span::new("render", "Loading assets");
+    let mut assets = Assets::new(draw_queue.clone(), &config.resource_path);
+    init_futures.push(assets.load_models(&["wall", "floor", "corner", "ceiling", "portal", "cobweb", "pillar", "rune"])?);
+    GhostRenderer::load_animations(&mut assets)?;
+    init_futures.push(assets.load_textures(&["controls", "controls_dim", "digits", "icons", "font", "win", "lose"])?);
+    drop(span);
+
+    // In a campaign, each level's settings go on top of the config file's
+    let mut base_config = config.clone();
+    let campaign = if args.campaign { Some (Campaign::open(&config_path, &config)?) } else { None };
+    let progress_path = campaign::progress_path(&config_path);
+    let mut progress = Progress::default();
+    if let Some (campaign) = &campaign {
+        progress = Progress::load(&progress_path)?;
+        if progress.level >= campaign.len() {
+            progress = Progress::default();
+        }
+        match campaign.config(&base_config, progress.level) {
+            Ok (level) => {
+                config = level;
+                info!("Campaign level {} of {}: {}", progress.level + 1, campaign.len(), campaign.name(progress.level));
+            },
+            Err (e) => warn!("Couldn't start campaign level {}, playing the config file's settings: {}", progress.level + 1, e)
+        }
+    }
+
+    let locale = Locale::load(&config.resource_path, &config.language);
+
+    // Initialize game elements
+    let mut game = if let Some (world) = watched_world {
+        Game::from_world(&config, world, 0)
+    } else if args.r#continue {
+        Game::restore(&config, &save::read(&save_path)?, Instant::now())?
+    } else {
+        let mut game = Game::new(&config)?;
+        progress.start(&mut game);
+        game
+    };
+    window.set_title(&title(&game, &config, &locale, false));
+    let mut camera = Camera::new(screen_size(resolution, config.split_screen), &config.camera_presets[config.camera]);
+    camera.follow(game.player.get_position(), &game.world);
+    camera.set_overhead(spectating);
+    // The second player's half of the window in a split-screen race follows them with a camera of their own
+    let mut rival_camera = game.rival.as_ref().map(|rival| {
+        let mut rival_camera = Camera::new(screen_size(resolution, true), &config.camera_presets[config.camera]);
+        rival_camera.follow(rival.get_position(), &game.world);
+        rival_camera
+    });
+    let (mut world_renderer, world_init_future) = WorldRenderer::new(&game.world, config.decorations, draw_queue.clone());
+    let (player_renderer, player_init_future) = PlayerRenderer::new(draw_queue.clone());
+    let (echo_renderer, echo_init_future) = EchoRenderer::new(draw_queue.clone());
+    let mut echo = load_echo(&config, &config_path, &game);
+    let ghost_renderer = GhostRenderer::new(device.clone(), [1.0, 1.0, 1.0]);
+    let mut objects_renderer = ObjectsRenderer::new(draw_queue.clone(), &game.world, &game.objects);
+    let mut rival_objects_renderer = game.rival.as_ref().map(|_| ObjectsRenderer::new(draw_queue.clone(), &game.world, &game.objects));
+    let route_renderer = RouteRenderer::new(device.clone());
+    let overlay_renderer = SliceOverlayRenderer::new(device.clone());
+    let mut particles = ParticleSystem::new(device.clone());
+    let mut profiler = if args.gpu_timing || args.benchmark {
+        let profiler = GpuProfiler::new(device.clone(), &draw_queue);
+        if profiler.is_none() {
+            warn!(target: "render", "{} can't time its work", device.physical_device().properties().device_name);
+        }
+        profiler
+    } else {
+        None
+    };
+    let mut feed = Feed::new();
+    let mut ui = UserInterface::new(draw_queue.clone(), &pipeline, &assets, screen_size(resolution, config.split_screen), window.scale_factor(), &config);
+    // Every pipeline has been built by now, so the next run can skip building them
+    gfx::save_pipeline_cache(&device, &pipeline.cache, &pipeline_cache_path);
+    init_futures.push(world_init_future);
+    init_futures.push(player_init_future);
+    init_futures.push(echo_init_future);
+
+    let init_future = init_futures.into_iter().fold(sync::now(device.clone()).boxed(), |acc, future| {
+        acc.join(future).boxed()
+    }).then_signal_fence_and_flush().map_err(|e| MazeError::graphics(format!("Couldn't upload the game's resources: {}", e)))?;
+
+    println!("---------------------------");
+    println!("{0}", NAME);
+    println!("Playing as {}, with settings from {}", config.player_name, config_path);
+    println!("WASD or Arrow Keys to move horizontally");
+    println!("SPACE to move up, LeftControl to move down");
+    println!("Q and E to move through left and right portals");
+    println!("C to switch between camera views, B for a flat view of the whole level from above");
+    println!("V to zoom out to every level of your w-slice, stacked apart to plan the way up and down");
+    println!("With co-op on, a second player moves a ghost with IJKL, Y and H to go up and down, and U and O for the portals");
+    println!("With split-screen on, a second player races you to the exit on the right half of the window with the same keys");
+    println!("Run with --host to race someone online, who runs with --join and your address, in the same maze");
+    println!("Run with --broadcast to let others watch your game live, and with --watch and your address to watch it");
+    println!("Set twitch-channel in the config file to let chat vote to speed up the ghosts, show the level from above or turn your movement keys");
+    println!("Leave the keys alone for a minute before moving and the bot plays, until any key is pressed");
+    println!("Alt+Enter to switch between a window and fullscreen");
+    println!("F5 to pause and fly the camera through walls with WASD, SPACE, LeftControl and the mouse");
+    println!("F6 to quicksave the game, F9 to load it back, or run with --continue to carry on from it");
+    println!("F to switch the flashlight on and off, and pick up batteries to recharge it");
+    println!("Eat all the things to open the exit, then escape to win");
+    println!("Set mode in the config file to time-attack to race a par time with no ghost, zen to explore with no ghost or clock, or survival to score all you can before the ghost catches you");
+    println!("With --daily, play the same maze as everyone else today, with no compass or quicksaves");
+    println!("Set leaderboard in the config file to send your escapes to a server and see the best times in the daily challenge, or run with --leaderboard for them");
+    println!("With --campaign, press R after escaping to go on to the next level in levels.toml");
+    println!("Turn on race-echo in the config file to race an echo of your fastest escape from the same maze");
+    println!("After winning, press G to review your route against the shortest one");
+    println!("Once the game is over, press T for your best time and how many mazes you've escaped, or run with --stats for all of them");
+    println!("Edit the provided config.toml file to change settings, or run with --help for command line options");
+    println!("The UI, camera and frame rate settings update as soon as the file is saved, others need a restart");
+
+    // Initialize framebuffers
+    let mut viewports = screen_viewports(swapchain.image_extent(), config.split_screen);
+    let mut framebuffers = gfx::framebuffers(device.clone(), pipeline.render_pass.clone(), &images, sample_count);
+
+    let mut previous_frame_end = Some (init_future.boxed()); // Uploads the next frame waits on
+    let mut frames = FramesInFlight::new(config.frames_in_flight as usize);
+    let mut pacer = FramePacer::new(&config.target_fps);
+    let mut recreate_swapchain = false;
+    let mut scene = Scene::new(device.clone(), &pipeline, config.see_through_walls);
+    let start_time = Instant::now();
+    let mut last_frame = start_time;
+
+    // Up, down, left, right, ascend, descend, fourth dec, fourth inc
+    let mut keys = [ElementState::Released; 8];
+    let mut second_keys = [ElementState::Released; SECOND_KEYS.len()];
+    let mut flashlight_key = ElementState::Released;
+    let mut flythrough: Option<Flythrough> = None;
+    let mut camera_preset = config.camera;
+    let mut modifiers = ModifiersState::empty();
+    let mut clock = GameClock::new();
+    // Keypresses wait in the inputs for the next tick of the lockstep, which moves the game on
+    let mut lockstep = Lockstep::new(&mut game, &config, clock.time(Instant::now()), MOVE_TIME);
+    let mut inputs = Inputs::default();
+    let mut revealed_until: Option<Instant> = None; // When the overhead view chat voted for ends
+    let mut spun_until: Option<Instant> = None; // When the movement keys chat turned go back to normal
+    let mut next_game: Option<Game> = None; // Restarted or loaded game to swap in before the next frame
+    let can_attract = own_run && campaign.is_none() && daily.is_none();
+    let mut last_input = Instant::now();
+    let mut attract = args.benchmark; // Whether the bot is playing until someone presses a key
+    let mut benchmark = args.benchmark.then(|| benchmark::Benchmark::new(Instant::now()));
+    let mut attract_ended: Option<Instant> = None; // When the bot's last game ended, to start another after a while
+    let mut dying: Option<Instant> = None; // Real time the player was caught for the last time, while the camera zooms in on the ghost
+    let mut bot_played = false; // Whether the bot made any of this game's moves, which keeps it out of the stats
+    let watcher = ConfigWatcher::new(&config_path, base_config.clone(), overrides);
+    // Whatever stopped the game partway through, returned once the event loop has exited
+    let mut failure = None;
+
+    gfx::run(event_loop, |event, event_loop| match event {
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested, ..
+        } => {
+            // Remember the camera view for next time, leaving the rest of the file and any command line overrides alone
+            if camera_preset != config.camera {
+                let saved = Config::new(&config_path).and_then(|mut saved| {
+                    saved.camera = camera_preset;
+                    saved.save(&config_path)
+                });
+                if let Err (e) = saved {
+                    warn!("Couldn't save the camera view: {}", e);
+                }
+            }
+            if let Some (profiler) = &profiler {
+                println!("{}", profiler.report());
+            }
+            event_loop.exit();
+        }
+        Event::WindowEvent {
+            event: WindowEvent::Focused (focused), ..
+        } => {
+            // Stop the clock while the player is away, so the ghost can't catch them, unless racing online where the
+            // other player's game carries on regardless
+            if !focused && game.in_play() && !clock.paused() && connection.is_none() {
+                clock.pause(Instant::now());
+                info!(target: "input", "Paused");
+            } else if focused && clock.paused() && !camera.is_free() {
+                clock.resume(Instant::now());
+                info!(target: "input", "Resumed");
+            }
+            // Let go of the cursor while away, and take it back on returning to a free camera
+            let grabbed = focused && camera.is_free();
+            if let Err (e) = window.set_cursor_grab(if grabbed { CursorGrabMode::Confined } else { CursorGrabMode::None }) {
+                warn!(target: "input", "Couldn't {} the cursor: {}", if grabbed { "grab" } else { "release" }, e);
+            }
+            window.set_cursor_visible(!grabbed);
+            window.set_title(&title(&game, &config, &locale, clock.paused()));
+        }
+        Event::WindowEvent {
+            event: WindowEvent::Resized(_), ..
+        } => {
+            recreate_swapchain = true;
+        }
+        Event::WindowEvent {
+            event: WindowEvent::ScaleFactorChanged { scale_factor, .. }, ..
+        } => {
+            // Moved to a display with a different DPI
+            info!(target: "render", "Display scale factor: {}", scale_factor);
+            ui = UserInterface::new(draw_queue.clone(), &pipeline, &assets, screen_size(resolution, config.split_screen), scale_factor, &config);
+            recreate_swapchain = true;
+        }
+        Event::WindowEvent {
+            event: WindowEvent::ModifiersChanged (state), ..
+        } => {
+            modifiers = state.state();
+        }
+        Event::WindowEvent {
+            event: WindowEvent::CursorMoved { position, .. }, ..
+        } => {
+            ui.point(cursor_ndc(position, window.inner_size(), config.split_screen));
+        }
+        Event::WindowEvent {
+            event: WindowEvent::CursorLeft { .. }, ..
+        } => {
+            ui.point(None);
+        }
+        Event::WindowEvent {
+            event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. }, ..
+        } => {
+            // Clicking a control moves the player the same way as its key
+            let clicked = ui.click(state == ElementState::Pressed, &game.world, &config);
+            last_input = Instant::now();
+            if let Some (Click::Place (widget, offset)) = clicked {
+                // Keep the widget where it was dropped, leaving the rest of the file and any command line overrides alone
+                config.hud_layout[widget] = offset;
+                base_config.hud_layout[widget] = offset;
+                let saved = Config::new(&config_path).and_then(|mut saved| {
+                    saved.hud_layout[widget] = offset;
+                    saved.save(&config_path)
+                });
+                if let Err (e) = saved {
+                    warn!("Couldn't save the HUD layout: {}", e);
+                }
+                return;
+            }
+            if attract || spectating || camera.is_free() || game.player.game_state != GameState::Playing {
+                return;
+            }
+            if let Some (Click::Move (delta)) = clicked {
+                let spun = spun_until.is_some_and(|until| clock.time(Instant::now()) < until);
+                inputs.player = Some (if spun { [-delta[1], delta[0], delta[2], delta[3]] } else { delta });
+            }
+        }
+        Event::WindowEvent {
+            event: WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key: PhysicalKey::Code (keycode),
+                    state, ..
+                }, ..
+            }, ..
+        } => {
+            last_input = Instant::now();
+            if attract {
+                // Any key takes over from the bot, mid-game or not
+                attract = false;
+                info!(target: "input", "Attract mode off");
+                return;
+            }
+            if keycode == KeyCode::Enter && state == ElementState::Pressed && modifiers.alt_key() {
+                // Toggle between a window and fullscreen, in the configured fullscreen mode if there is one
+                if window.fullscreen().is_some() {
+                    window.set_fullscreen(None);
+                    let _ = window.request_inner_size(match config.window {
+                        config::Window::Size (width, height) => PhysicalSize { width, height },
+                        _ => PhysicalSize { width: 1280, height: 720 }
+                    });
+                } else {
+                    let mode = if config.window == config::Window::Exclusive { config::Window::Exclusive } else { config::Window::Borderless };
+                    window.set_fullscreen(fullscreen(&window, &mode, &config.resolution));
+                }
+                info!(target: "input", "Fullscreen {}", if window.fullscreen().is_some() { "on" } else { "off" });
+                recreate_swapchain = true;
+                return;
+            }
+            if keycode == KeyCode::KeyC && state == ElementState::Pressed {
+                // Cycle camera presets
+                camera_preset = (camera_preset + 1) % config.camera_presets.len();
+                camera.transition_to(&config.camera_presets[camera_preset]);
+                info!(target: "input", "Camera: {}", config::CAMERA_PRESETS[camera_preset]);
+                return;
+            }
+            if keycode == KeyCode::F5 && state == ElementState::Pressed {
+                // Detach the camera to inspect the maze, stopping the clock while it's away from the player outside online races
+                let free = !camera.is_free();
+                camera.set_free(free);
+                if let Err (e) = window.set_cursor_grab(if free { CursorGrabMode::Confined } else { CursorGrabMode::None }) {
+                    warn!(target: "input", "Couldn't grab the cursor: {}", e);
+                }
+                window.set_cursor_visible(!free);
+                if free {
+                    if !clock.paused() && connection.is_none() {
+                        clock.pause(Instant::now());
+                    }
+                } else {
+                    camera.transition_to(&config.camera_presets[camera_preset]);
+                    clock.resume(Instant::now());
+                }
+                window.set_title(&title(&game, &config, &locale, clock.paused()));
+                info!(target: "input", "Free camera {}", if free { "on" } else { "off" });
+                return;
+            }
+            if camera.is_free() {
+                // Movement keys fly the camera instead of the player
+                match keycode {
+                    KeyCode::KeyW | KeyCode::ArrowUp => keys[0] = state,
+                    KeyCode::KeyS | KeyCode::ArrowDown => keys[1] = state,
+                    KeyCode::KeyA | KeyCode::ArrowLeft => keys[2] = state,
+                    KeyCode::KeyD | KeyCode::ArrowRight => keys[3] = state,
+                    KeyCode::Space => keys[4] = state,
+                    KeyCode::ControlLeft => keys[5] = state,
+                    _ => {}
+                }
+                return;
+            }
+            if game.co_op() || game.rival.is_some() {
+                if let Some (i) = SECOND_KEYS.iter().position(|(key, _)| *key == keycode) {
+                    if state == ElementState::Pressed && second_keys[i] == ElementState::Released {
+                        if game.co_op() {
+                            inputs.ghost = Some (SECOND_KEYS[i].1);
+                        } else if !game.player.free() || SECOND_KEYS[i].1[2..] != [0, 0] {
+                            inputs.rival = Some (SECOND_KEYS[i].1);
+                        }
+                    }
+                    second_keys[i] = state;
+                    return;
+                }
+            }
+            if keycode == KeyCode::KeyB && state == ElementState::Pressed {
+                // Show the player's whole level at once from straight above
+                let overhead = !camera.is_overhead();
+                camera.set_overhead(overhead);
+                if !overhead {
+                    camera.transition_to(&config.camera_presets[camera_preset]);
+                }
+                info!(target: "input", "Overhead view {}", if overhead { "on" } else { "off" });
+                return;
+            }
+            if keycode == KeyCode::KeyV && state == ElementState::Pressed {
+                // Zoom out to every level of the player's w-slice, stacked apart to plan the way up and down
+                let dollhouse = !camera.is_dollhouse();
+                camera.set_dollhouse(dollhouse);
+                if !dollhouse {
+                    camera.transition_to(&config.camera_presets[camera_preset]);
+                }
+                info!(target: "input", "Dollhouse view {}", if dollhouse { "on" } else { "off" });
+                return;
+            }
+            if spectating {
+                return; // Only the camera is ours to move while watching someone else play
+            }
+            if matches!(keycode, KeyCode::F6 | KeyCode::F9) && state == ElementState::Pressed && daily.is_some() {
+                info!(target: "input", "No quicksaves in the daily challenge");
+                return;
+            }
+            if matches!(keycode, KeyCode::F6 | KeyCode::F9) && state == ElementState::Pressed && racing {
+                info!(target: "input", "No quicksaves in a race");
+                return;
+            }
+            if keycode == KeyCode::KeyR && state == ElementState::Pressed && connection.is_some() && !game.in_play() {
+                info!(target: "input", "Online races can't be restarted, so host or join another to play again");
+                return;
+            }
+            if keycode == KeyCode::F6 && state == ElementState::Pressed && game.player.game_state == GameState::Playing {
+                match save::write(&save_path, &game.snapshot(clock.time(Instant::now()))) {
+                    Ok (()) => info!(target: "input", "Saved the game to {}", save_path),
+                    Err (e) => warn!(target: "input", "{}", e)
+                }
+                return;
+            }
+            let replacement = if keycode == KeyCode::F9 && state == ElementState::Pressed {
+                // Carry on from the quicksave, on a clock caught back up with real time
+                match save::read(&save_path).and_then(|saved| Game::restore(&config, &saved, Instant::now())) {
+                    Ok (loaded) => {
+                        info!(target: "input", "Loaded the game from {}", save_path);
+                        Some (loaded)
+                    },
+                    Err (e) => {
+                        warn!(target: "input", "{}", e);
+                        None
+                    }
+                }
+            } else if keycode == KeyCode::KeyR && state == ElementState::Pressed && !game.in_play() {
+                // Worked out before anything changes, so a level that can't be played now leaves this game running
+                let restart = if let Some (campaign) = &campaign {
+                    // On to the next level after escaping, or back to the first after losing every life
+                    let mut next = progress.clone();
+                    if matches!(game.player.game_state, GameState::Won | GameState::Reviewing) {
+                        next.finish_level(&game, &config);
+                    } else {
+                        info!("Campaign over with {} points", next.score);
+                        next = Progress::default();
+                    }
+                    if next.level == campaign.len() {
+                        info!("Campaign complete with {} points", next.score);
+                        next = Progress::default();
+                    }
+                    campaign.config(&base_config, next.level).map(|level| (level, next))
+                } else {
+                    info!(target: "input", "Restarting");
+                    Ok ((config.clone(), progress.clone()))
+                };
+                match restart.and_then(|(level, next)| Ok ((Game::new(&level).map_err(|e| e.to_string())?, level, next))) {
+                    Ok ((mut restarted, level, next)) => {
+                        if let Some (campaign) = &campaign {
+                            if let Err (e) = next.save(&progress_path) {
+                                warn!("Couldn't save the campaign progress: {}", e);
+                            }
+                            info!("Campaign level {} of {}: {}", next.level + 1, campaign.len(), campaign.name(next.level));
+                        }
+                        config = level;
+                        progress = next;
+                        progress.start(&mut restarted);
+                        Some (restarted)
+                    },
+                    Err (e) => {
+                        warn!(target: "input", "Couldn't start the next game, carrying on with this one: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            if replacement.is_some() {
+                next_game = replacement; // Swapped in before the next frame
+                return;
+            }
+            if game.player.game_state == GameState::Respawning {
+                return; // Can't move until play resumes
+            }
+            if game.player.game_state != GameState::Playing {
+                if keycode == KeyCode::KeyT && state == ElementState::Pressed && game.player.game_state != GameState::Reviewing {
+                    // Switch between the final score and past runs
+                    let stats = if ui.showing_stats() { None } else {
+                        Stats::load(&stats_path).map_err(|e| warn!("Couldn't read past runs: {}", e)).ok()
+                    };
+                    ui.show_stats(stats.as_ref(), &config::maze_size(&config.dimensions));
+                }
+                if keycode == KeyCode::KeyG && state == ElementState::Pressed && game.player.game_state == GameState::Won {
+                    // Review the winning route
+                    flythrough = Some (Flythrough::new(&game.player.replay, &game.world));
+                    game.player.game_state = GameState::Reviewing;
+                }
+                return;
+            }
+            let now = clock.time(Instant::now());
+            let spun = spun_until.is_some_and(|until| now < until);
+            let turn = |delta: [i32; 4]| if spun { [-delta[1], delta[0], delta[2], delta[3]] } else { delta };
+            let free = game.player.free(); // Held keys steer across the level every frame instead of stepping here
+            match keycode {
+                KeyCode::KeyW | KeyCode::ArrowUp => {
+                    if state == ElementState::Pressed && keys[0] == ElementState::Released && !free {
+                        inputs.player = Some (turn([0, -1, 0, 0]));
+                    }
+                    keys[0] = state;
+                },
+                KeyCode::KeyS | KeyCode::ArrowDown => {
+                    if state == ElementState::Pressed && keys[1] == ElementState::Released && !free {
+                        inputs.player = Some (turn([0, 1, 0, 0]));
+                    }
+                    keys[1] = state
+                },
+                KeyCode::KeyA | KeyCode::ArrowLeft => {
+                    if state == ElementState::Pressed && keys[2] == ElementState::Released && !free {
+                        inputs.player = Some (turn([-1, 0, 0, 0]));
+                    }
+                    keys[2] = state
+                },
+                KeyCode::KeyD | KeyCode::ArrowRight => {
+                    if state == ElementState::Pressed && keys[3] == ElementState::Released && !free {
+                        inputs.player = Some (turn([1, 0, 0, 0]));
+                    }
+                    keys[3] = state
+                },
+                KeyCode::Space => {
+                    if state == ElementState::Pressed && keys[4] == ElementState::Released {
+                        inputs.player = Some (turn([0, 0, 1, 0]));
+                    }
+                    keys[4] = state
+                },
+                KeyCode::ControlLeft => {
+                    if state == ElementState::Pressed && keys[5] == ElementState::Released {
+                        inputs.player = Some (turn([0, 0, -1, 0]));
+                    }
+                    keys[5] = state
+                },
+                KeyCode::KeyQ => {
+                    if state == ElementState::Pressed && keys[6] == ElementState::Released {
+                        inputs.player = Some (turn([0, 0, 0, -1]));
+                    }
+                    keys[6] = state
+                },
+                KeyCode::KeyE => {
+                    if state == ElementState::Pressed && keys[7] == ElementState::Released {
+                        inputs.player = Some (turn([0, 0, 0, 1]));
+                    }
+                    keys[7] = state
+                },
+                KeyCode::KeyF => {
+                    if state == ElementState::Pressed && flashlight_key == ElementState::Released {
+                        inputs.flashlight = !inputs.flashlight;
+                        info!(target: "input", "Flashlight {}", if game.player.flashlight.lit() != inputs.flashlight { "on" } else { "off" });
+                    }
+                    flashlight_key = state
+                }
+                _ => {}
+            }
+        }
+        Event::DeviceEvent {
+            event: DeviceEvent::MouseMotion { delta: (dx, dy) }, ..
+        } if camera.is_free() => {
+            camera.look([dx, dy]);
+        }
+        Event::AboutToWait => {
+            if let Some (replacement) = next_game.take() {
+                // Reset game state
+                game = replacement;
+                feed = Feed::new();
+                clock.reset();
+                lockstep = Lockstep::new(&mut game, &config, clock.time(Instant::now()), MOVE_TIME);
+                inputs = Inputs::default();
+                bot_played = attract;
+                attract_ended = None;
+                dying = None;
+                window.set_title(&title(&game, &config, &locale, false));
+                flythrough = None;
+                ui.show_stats(None, "");
+                ui.show_analysis(None);
+                echo = load_echo(&config, &config_path, &game);
+                let (new_world_renderer, world_init_future) = WorldRenderer::new(&game.world, config.decorations, draw_queue.clone());
+                world_renderer = new_world_renderer;
+                objects_renderer = ObjectsRenderer::new(draw_queue.clone(), &game.world, &game.objects);
+                rival_objects_renderer = game.rival.as_ref().map(|_| ObjectsRenderer::new(draw_queue.clone(), &game.world, &game.objects));
+                previous_frame_end = Some (previous_frame_end.take().unwrap().join(world_init_future).boxed());
+            }
+
+            // Pick up any settings edited in the config file since the last frame
+            for change in watcher.changes() {
+                if daily.is_some() && matches!(change, config::LiveSetting::DisplayCompass (_)) {
+                    continue; // No hints in the daily challenge
+                }
+                config.apply(change);
+                base_config.apply(change);
+                match change {
+                    config::LiveSetting::UiScale (_) => ui = UserInterface::new(draw_queue.clone(), &pipeline, &assets, screen_size(resolution, config.split_screen), window.scale_factor(), &config),
+                    config::LiveSetting::CameraPresets (_) => camera.transition_to(&config.camera_presets[camera_preset]),
+                    config::LiveSetting::TargetFps (_) => pacer.set_target(&config.target_fps),
+                    _ => {}
+                }
+            }
+
+            // Answers from the leaderboard server, which come in on their own threads
+            if let Some (leaderboard) = &leaderboard {
+                for reply in leaderboard.replies() {
+                    match reply {
+                        Reply::Submitted => {
+                            info!(target: "net", "Sent the escape to the leaderboard");
+                            if let Some (day) = daily {
+                                leaderboard.fetch_daily(&day.to_string());
+                            }
+                        },
+                        Reply::Daily (date, entries) => print_leaderboard(&date, &entries),
+                        Reply::Failed (reason) => warn!(target: "net", "{}", reason)
+                    }
+                }
+            }
+
+            let frame_start = pacer.wait();
+            let frame_time = (frame_start - last_frame).as_secs_f32();
+            last_frame = frame_start;
+            let now = clock.time(frame_start);
+
+            // Events voted for in chat, which only land while there's a game to play
+            if let Some (chat) = &chat {
+                for event in chat.events() {
+                    if game.player.game_state != GameState::Playing {
+                        continue;
+                    }
+                    let until = now + Duration::from_secs_f32(chat::EVENT_TIME);
+                    match event {
+                        ChatEvent::Rush => game.rush_ghosts(until),
+                        ChatEvent::Reveal => if !camera.is_overhead() {
+                            camera.set_overhead(true);
+                            revealed_until = Some (until);
+                        },
+                        ChatEvent::Spin => spun_until = Some (until)
+                    }
+                }
+            }
+            if revealed_until.is_some_and(|until| now >= until) {
+                revealed_until = None;
+                if camera.is_overhead() {
+                    camera.set_overhead(false);
+                    camera.transition_to(&config.camera_presets[camera_preset]);
+                }
+            }
+
+            previous_frame_end.as_mut().unwrap().cleanup_finished();
+            frames.wait();
+
+            if recreate_swapchain {
+                let dimensions: [u32; 2] = window.inner_size().into();
+                if dimensions == [0, 0] {
+                    return; // Minimized; don't recreate swapchain at all
+                }
+                viewports = screen_viewports(dimensions, config.split_screen);
+                let (new_swapchain, new_images) =
+                    match gfx::recreate_swapchain(&swapchain, dimensions) {
+                        Ok (r) => r,
+                        Err(e) => {
+                            // Left set to try again next frame
+                            error!(target: "render", "Failed to recreate swapchain, skipping the frame: {}", e);
+                            return;
+                        }
+                    };
+                swapchain = new_swapchain;
+                framebuffers = gfx::framebuffers(device.clone(), pipeline.render_pass.clone(), &new_images, sample_count);
+                recreate_swapchain = false;
+                if dimensions != resolution {
+                    // Window changed shape, eg. going fullscreen
+                    resolution = dimensions;
+                    camera.resize(screen_size(resolution, config.split_screen));
+                    if let Some (rival_camera) = &mut rival_camera {
+                        rival_camera.resize(screen_size(resolution, true));
+                    }
+                    ui = UserInterface::new(draw_queue.clone(), &pipeline, &assets, screen_size(resolution, config.split_screen), window.scale_factor(), &config);
+                }
+            }
+
+            let (image_num, suboptimal, acquire_future) =
+                match swapchain::acquire_next_image(swapchain.clone(), None).map_err(Validated::unwrap) {
+                    Ok(r) => r,
+                    Err(VulkanError::OutOfDate) => {
+                        recreate_swapchain = true;
+                        return;
+                    }
+                    Err(e) => {
+                        error!(target: "render", "Failed to acquire next framebuffer, skipping the frame: {}", e);
+                        return;
+                    }
+                };
+            if suboptimal {
+                recreate_swapchain = true;
+            }
+
+            let clear_values = vec![Some ([0.0, 0.0, 0.0, 1.0].into()), None, Some (ClearValue::Depth(1.0))];
+
+            // Left alone before the first move, the bot plays to show how it's done, and keeps starting new games
+            // until someone presses a key
+            if can_attract && !attract && game.player.game_state == GameState::Playing && game.player.run_time(now).is_none()
+                && frame_start.saturating_duration_since(last_input).as_secs_f32() > ATTRACT_IDLE {
+                attract = true;
+                bot_played = true;
+                info!(target: "input", "Attract mode on, press any key to take over");
+            }
+            if attract && game.player.game_state == GameState::Playing && game.player.get_position() == game.player.cell().map(|i| i as f32) {
+                let ghosts: Vec<[usize; 4]> = if config.mode.has_ghost() { game.ghosts.iter().map(Ghost::cell).collect() } else { Vec::new() };
+                if let Some (delta) = Bot.next_move_avoiding(&game.world, &game.objects, &game.player.inventory, game.player.cell(), &ghosts) {
+                    inputs.player = Some (delta);
+                }
+            }
+            // Free movement glides the way the held keys point, turned while chat has them spun like single moves are
+            if game.player.free() && !attract {
+                let spun = spun_until.is_some_and(|until| now < until);
+                let turn = |[x, y]: [i32; 2]| if spun { [-y, x] } else { [x, y] };
+                let held = |i: usize| if keys[i] == ElementState::Pressed && !camera.is_free() { 1 } else { 0 };
+                inputs.steer = Some (turn([held(3) - held(2), held(1) - held(0)]));
+                if game.rival.is_some() && !game.co_op() {
+                    inputs.rival_steer = Some (SECOND_KEYS.iter().zip(&second_keys)
+                        .filter(|(_, state)| **state == ElementState::Pressed)
+                        .fold([0, 0], |[x, y], ((_, delta), _)| [x + delta[0], y + delta[1]]));
+                }
+            }
+            if attract && !game.in_play() && next_game.is_none() {
+                let ended = *attract_ended.get_or_insert(frame_start);
+                if frame_start.saturating_duration_since(ended).as_secs_f32() > ATTRACT_RESTART {
+                    match Game::new(&config) {
+                        Ok (restarted) => next_game = Some (restarted),
+                        Err (e) => {
+                            // Such as the level file going missing, tried again after another wait
+                            warn!("Couldn't start another game: {}", e);
+                            attract_ended = Some (frame_start);
+                        }
+                    }
+                }
+            }
+
+            // Update game state
+            let was_playing = game.player.game_state == GameState::Playing;
+            let was_racing = game.in_play();
+            let lives = game.player.lives;
+            let rival_lives = game.rival.as_ref().map_or(0, |rival| rival.lives);
+            let mut broadcast_over = false;
+            if let Some (watcher) = &mut watching {
+                // Everyone moves as the broadcast says, rather than by the rules here
+                match watcher.update() {
+                    Ok (Some (world)) => {
+                        // The maze changed, eg. food was eaten or walls shifted, so start again from the new one
+                        world.fit_config(&mut config);
+                        game = Game::from_world(&config, world, 0);
+                        let (new_world_renderer, world_init_future) = WorldRenderer::new(&game.world, config.decorations, draw_queue.clone());
+                        world_renderer = new_world_renderer;
+                        objects_renderer = ObjectsRenderer::new(draw_queue.clone(), &game.world, &game.objects);
+                        previous_frame_end = Some (previous_frame_end.take().unwrap().join(world_init_future).boxed());
+                    },
+                    Ok (None) => {},
+                    Err (e) => {
+                        warn!(target: "net", "Stopped watching: {}", e);
+                        broadcast_over = true;
+                    }
+                }
+                game.player.set_position(watcher.player);
+                game.player.score = watcher.score;
+                game.player.lives = watcher.lives;
+                // The route being reviewed isn't sent, so there's only the escape to show
+                game.player.game_state = if watcher.state == GameState::Reviewing { GameState::Won } else { watcher.state };
+                game.ghosts.truncate(watcher.ghosts.len());
+                while game.ghosts.len() < watcher.ghosts.len() {
+                    game.ghosts.push(Ghost::at(&config, [0; 4]));
+                }
+                for (ghost, position) in game.ghosts.iter_mut().zip(&watcher.ghosts) {
+                    ghost.set_position(*position);
+                }
+            } else if !spectating {
+                while lockstep.due(now) {
+                    lockstep.step(&mut game, &config, &inputs, connection.as_mut());
+                    inputs = Inputs::default();
+                }
+            }
+            if broadcast_over {
+                watching = None; // Left showing the game as it was last seen
+            }
+            if let Some (broadcaster) = &mut broadcaster {
+                broadcaster.update(frame_start, &game);
+            }
+            if let Some (connection) = &connection {
+                let gone = connection.opponent().gone(now);
+                if gone && !opponent_gone {
+                    warn!(target: "net", "Lost touch with {}", connection.opponent().name);
+                }
+                opponent_gone = gone;
+            }
+            for line in feed.update(&game, &config, now) {
+                if config.event_feed_stdout {
+                    println!("{}", locale.text(&line));
+                }
+            }
+            // Caught for the last time, so play it out in slow motion before the defeat screen
+            if was_playing && game.player.game_state == GameState::Lost && game.player.lives < lives && !spectating {
+                dying = Some (frame_start);
+                clock.set_speed(frame_start, DEATH_SPEED);
+            }
+            if dying.is_some_and(|caught| frame_start.saturating_duration_since(caught).as_secs_f32() >= DEATH_TIME) {
+                dying = None;
+                clock.set_speed(frame_start, 1.0);
+            }
+            if game.player.lives < lives {
+                camera.shake(HIT_SHAKE * config.camera_shake);
+            } else if game.player.game_state == GameState::Playing && ghost_distance(&game, &config) <= 1.0 {
+                camera.shake(GHOST_SHAKE * config.camera_shake);
+            }
+            if let (Some (rival), Some (rival_camera)) = (&game.rival, &mut rival_camera) {
+                if rival.lives < rival_lives {
+                    rival_camera.shake(HIT_SHAKE * config.camera_shake);
+                }
+                if was_racing && !game.in_play() {
+                    match (&game.player.game_state, &rival.game_state) {
+                        (GameState::Won, _) => info!("{} won the race in {}s", config.player_name, game.player.stopwatch),
+                        (_, GameState::Won) => info!("The second player won the race in {}s", rival.stopwatch),
+                        _ => info!("Nobody escaped, so nobody won the race")
+                    }
+                }
+            }
+            if was_playing && game.player.game_state == GameState::Won && daily.is_none() && own_run && !bot_played && config.mode != Mode::Zen {
+                match profile::record_time(&config_path, &config.dimensions, game.player.stopwatch) {
+                    Ok (true) => info!("{} escaped in {}s, a new best for {}", config.player_name, game.player.stopwatch, config::maze_size(&config.dimensions)),
+                    Ok (false) => info!("{} escaped in {}s", config.player_name, game.player.stopwatch),
+                    Err (e) => warn!("Couldn't save the best time: {}", e)
+                }
+            }
+            if was_playing && game.player.game_state == GameState::Won && own_run && !bot_played && config.mode == Mode::Classic && config.level.is_none() {
+                if let Some (leaderboard) = &leaderboard {
+                    leaderboard.submit(Submission {
+                        name: config.player_name.clone(),
+                        seed: game.seed,
+                        dimensions: config.dimensions,
+                        seconds: game.player.stopwatch,
+                        replay: format!("{:016x}", game.player.replay.hash()),
+                        daily: daily.map(|day| day.to_string())
+                    });
+                }
+            }
+            if was_playing && game.player.game_state == GameState::Won && config.race_echo && !spectating && !bot_played {
+                match game.player.replay.record_best(&replay::best_path(&config_path, &config.dimensions, game.seed)) {
+                    Ok (true) => info!("Fastest escape from this maze yet, kept to race against"),
+                    Ok (false) => {},
+                    Err (e) => warn!("Couldn't save the echo to race: {}", e)
+                }
+            }
+            if was_playing && game.player.game_state == GameState::Won {
+                ui.show_analysis(Some (&Analysis::new(&game.world)));
+            }
+            if was_playing && !spectating && (game.player.game_state == GameState::Won || game.player.game_state == GameState::Lost) {
+                if let Some (campaign) = &campaign {
+                    let points = if game.player.game_state == GameState::Won { game.player.final_score(&config).total() } else { 0 };
+                    ui.show_campaign(Some ((progress.level + 1, campaign.len(), progress.score + points)));
+                }
+                if config.mode == Mode::Survival {
+                    info!("{} survived {}s with {} points", config.player_name, game.player.run_time(now).unwrap_or_default().round(), game.player.final_score(&config).total());
+                }
+                // Losing a race isn't being caught, so races are left out of the past runs, as are the bot's
+                if !racing && !bot_played {
+                    let outcome = if game.player.game_state == GameState::Won { Outcome::Escaped } else { Outcome::Caught };
+                    let run = Run { dimensions: config.dimensions, seed: game.seed, seconds: game.player.stopwatch, outcome, moves: game.player.replay.moves.len(), daily: daily.map(|day| day.to_string()) };
+                    if let Err (e) = Stats::record(&stats_path, run) {
+                        warn!("Couldn't record the run: {}", e);
+                    }
+                }
+            }
+            let changed_levels = game.world.take_changed_levels();
+            if !changed_levels.is_empty() {
+                // Walls moved, so rebuild those levels before drawing them
+                let upload = world_renderer.update(&game.world, &changed_levels, draw_queue.clone());
+                previous_frame_end = Some (previous_frame_end.take().unwrap().join(upload).boxed());
+            }
+            // Load the maze around each player as they move through it, building what's missing in the background
+            let players: Vec<[i32; 4]> = [game.player.cell()].into_iter().chain(game.rival.as_ref().map(|rival| rival.cell())).collect();
+            let streamed = ViewDistance { slices: config.view_distance().slices, ..view_distance(&config, &camera, &game.world) };
+            let upload = world_renderer.stream(&players, &streamed, draw_queue.clone());
+            previous_frame_end = Some (previous_frame_end.take().unwrap().join(upload).boxed());
+            if game.player.game_state == GameState::Reviewing && !flythrough.as_ref().is_some_and(|f| f.update(&mut game.player)) {
+                game.player.game_state = GameState::Won;
+                flythrough = None;
+            }
+            if let Some (benchmark) = &mut benchmark {
+                if benchmark.record(frame_start, frame_time) {
+                    let seed = config.seed.expect("Benchmarks always have a seed");
+                    println!("{}", benchmark.report(seed, config.dimensions, profiler.as_ref()));
+                    event_loop.exit();
+                    return;
+                }
+                let (position, yaw, pitch) = benchmark::camera_path(&game.world, game.player.get_position()[2], benchmark.seconds(frame_start));
+                camera.set_free(true);
+                camera.place(position, yaw, pitch);
+            } else if camera.is_free() {
+                let held = |i: usize| if keys[i] == ElementState::Pressed { 1.0 } else { 0.0 };
+                camera.fly([held(3) - held(2), held(0) - held(1), held(4) - held(5)], frame_time);
+            }
+            if let Some (caught) = dying {
+                let progress = frame_start.saturating_duration_since(caught).as_secs_f32() / DEATH_TIME;
+                let (target, fov) = death_zoom(&config, &config.camera_presets[camera_preset], &game, progress);
+                camera.set_fov_target(fov);
+                camera.follow(target, &game.world);
+            } else {
+                camera.set_fov_target(fov_target(&config, &config.camera_presets[camera_preset], &game));
+                camera.follow(game.player.get_position(), &game.world);
+            }
+            objects_renderer.update(&game.world, &game.objects, &game.player, view_distance(&config, &camera, &game.world), camera.is_dollhouse());
+            if let (Some (rival), Some (rival_camera), Some (rival_objects_renderer)) = (&game.rival, &mut rival_camera, &mut rival_objects_renderer) {
+                rival_camera.follow(rival.get_position(), &game.world);
+                rival_objects_renderer.update(&game.world, &game.objects, rival, view_distance(&config, rival_camera, &game.world), rival_camera.is_dollhouse());
+            }
+            particles.update(&game, &config, now, frame_time * clock.speed());
+
+            // Record the frame's commands, which failing to do is as fatal as any other graphics error
+            let command_buffer = (|| -> Result<_, MazeError> {
+                let mut builder = gfx::Commands::primary(&draw_queue).map_err(MazeError::graphics)?;
+                if let Some (profiler) = &mut profiler {
+                    profiler.begin(&mut builder);
+                }
+                particles.step(frame_time * clock.speed(), &mut builder, &pipeline).map_err(MazeError::graphics)?;
+                if let Some (profiler) = &mut profiler {
+                    profiler.mark(&mut builder, profiler::PARTICLES);
+                }
+
+                // Each player's view of the maze, side by side in a split-screen race with the other player drawn in both.
+                // Online, the opponent is drawn where their updates say they are
+                let opponent = connection.as_ref().and_then(|connection| connection.opponent().position(now));
+                let mut screens = vec![(&game.player, game.rival.as_ref().map(|rival| rival.get_position()).or(opponent), &camera, &objects_renderer)];
+                if let (Some (rival), Some (rival_camera), Some (rival_objects_renderer)) = (&game.rival, &rival_camera, &rival_objects_renderer) {
+                    screens.push((rival, Some (game.player.get_position()), rival_camera, rival_objects_renderer));
+                }
+                if let Some (profiler) = &mut profiler {
+                    profiler.start_counting(&mut builder);
+                }
+                builder
+                    .begin_render_pass(
+                        framebuffers[image_num as usize].clone(),
+                        SubpassContents::SecondaryCommandBuffers,
+                        clear_values
+                    ).map_err(MazeError::graphics)?;
+                let statistics = profiler.as_ref().map_or(QueryPipelineStatisticFlags::empty(), GpuProfiler::inherited);
+                // The event feed follows the first player only
+                let shown_feed: Vec<String> = feed.lines(now, config.event_feed_time).map(|line| locale.text(line)).collect();
+                let shown_feed: Vec<&str> = shown_feed.iter().map(String::as_str).collect();
+                for (i, ((player, rival, camera, objects_renderer), viewport)) in screens.into_iter().zip(&viewports).enumerate() {
+                    let feed_lines: &[&str] = if i == 0 { &shown_feed } else { &[] };
+                    let view = View { world: &game.world, player, rival, ghosts: &game.ghosts, objects: &game.objects, camera, theme: config.level_theme(), palette: Palette::new(&config), distance: view_distance(&config, camera, &game.world), now, time: (now - start_time).as_secs_f32() };
+                    // Everything inside the render pass is recorded into secondary command buffers, the scene's on other threads
+                    let recorder = Recorder::new(draw_queue.clone(), &pipeline.render_pass, viewport.clone(), statistics);
+                    let mut commands = recorder.begin();
+                    sky::render(&view, &mut commands, &pipeline);
+
+                    // The first player's defeat waits for the zoom in on the ghost to finish
+                    let zooming = i == 0 && dying.is_some();
+                    if (player.game_state == GameState::Won || player.game_state == GameState::Lost) && !zooming {
+                        // Game over; only render UI
+                        ui.render(now, player, &game.world, &config, feed_lines, &mut commands);
+                        if let Some (profiler) = &mut profiler {
+                            profiler.mark(&mut commands, profiler::UI);
+                        }
+                        builder.execute_commands(commands.build().map_err(MazeError::graphics)?).map_err(MazeError::graphics)?;
+                        continue;
+                    }
+                    builder.execute_commands(commands.build().map_err(MazeError::graphics)?).map_err(MazeError::graphics)?;
+                    world_renderer.draws(&view, &assets, &mut scene);
+                    player_renderer.draws(&view, &assets, &mut scene);
+                    let ghosts_drawn = config.mode.has_ghost() && (player.game_state == GameState::Playing || player.game_state == GameState::Respawning || zooming);
+                    if ghosts_drawn {
+                        ghost_renderer.draws(&view, &assets, &mut scene);
+                    }
+                    objects_renderer.draws(&view, &assets, &mut scene);
+                    for batch in scene.submit(&view, &recorder, &pipeline) {
+                        builder.execute_commands(batch).map_err(MazeError::graphics)?;
+                    }
+                    let mut commands = recorder.begin();
+                    world_renderer.render_portals(&view, &assets, &mut commands, &pipeline);
+                    particles.render(&view, &mut commands, &pipeline);
+                    if config.slice_overlay {
+                        overlay_renderer.render(&view, &assets, ghosts_drawn, &mut commands, &pipeline);
+                    }
+                    // Only the first player races an echo and reviews their route
+                    if let (Some (echo), Some (seconds), 0) = (&echo, player.run_time(now), i) {
+                        if player.game_state == GameState::Playing || player.game_state == GameState::Respawning {
+                            echo_renderer.render(echo, seconds, &view, &mut commands, &pipeline);
+                        }
+                    }
+                    if let (Some (flythrough), 0) = (&flythrough, i) {
+                        route_renderer.render(flythrough, &view, &mut commands, &pipeline);
+                    }
+                    if let Some (profiler) = &mut profiler {
+                        profiler.mark(&mut commands, profiler::WORLD);
+                    }
+                    if !zooming {
+                        ui.render(now, player, &game.world, &config, feed_lines, &mut commands);
+                    }
+                    if let Some (profiler) = &mut profiler {
+                        profiler.mark(&mut commands, profiler::UI);
+                    }
+                    builder.execute_commands(commands.build().map_err(MazeError::graphics)?).map_err(MazeError::graphics)?;
+                }
+                builder.end_render_pass().map_err(MazeError::graphics)?;
+                if let Some (profiler) = &mut profiler {
+                    profiler.stop_counting(&mut builder);
+                }
+                builder.build().map_err(MazeError::graphics)
+            })();
+            let command_buffer = match command_buffer {
+                Ok (command_buffer) => command_buffer,
+                Err (e) => {
+                    failure = Some (e);
+                    event_loop.exit();
+                    return;
+                }
+            };
+
+            // Uploads since the last frame go along with this one, which follows the frames still in flight
+            let uploads = previous_frame_end.take().unwrap();
+            match frames.submit(draw_queue.clone(), swapchain.clone(), image_num, acquire_future, command_buffer, uploads) {
+                Ok(()) => {}
+                Err(Validated::Error(VulkanError::OutOfDate)) => {
+                    recreate_swapchain = true;
+                }
+                Err(Validated::ValidationError(e)) => {
+                    // The commands themselves are wrong, which drawing the next frame won't fix
+                    failure = Some (MazeError::graphics(e));
+                    event_loop.exit();
+                    return;
+                }
+                Err(e) => {
+                    error!(target: "render", "Failed to flush future: {:?}", e);
+                }
+            }
+            previous_frame_end = Some(sync::now(device.clone()).boxed());
+        }
+        _ => ()
+    })?;
+    failure.map_or(Ok (()), Err)
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::warn;
+use vulkano::device::Queue;
+use vulkano::sync::{self, GpuFuture};
+
+use maze_core::error::MazeError;
+
+use crate::model::{Animation, Model};
+use crate::texture::TextureAtlas;
+
+// Default resources built into the binary, used whenever a file is missing from the resource directory
+const EMBEDDED: [(&str, &[u8]); 23] = [
+    ("wall.obj", include_bytes!("../../res/wall.obj")),
+    ("floor.obj", include_bytes!("../../res/floor.obj")),
+    ("corner.obj", include_bytes!("../../res/corner.obj")),
+    ("ceiling.obj", include_bytes!("../../res/ceiling.obj")),
+    ("portal.obj", include_bytes!("../../res/portal.obj")),
+    ("cobweb.obj", include_bytes!("../../res/cobweb.obj")),
+    ("pillar.obj", include_bytes!("../../res/pillar.obj")),
+    ("rune.obj", include_bytes!("../../res/rune.obj")),
+    ("ghost_idle_0.obj", include_bytes!("../../res/ghost_idle_0.obj")),
+    ("ghost_idle_1.obj", include_bytes!("../../res/ghost_idle_1.obj")),
+    ("ghost_idle_2.obj", include_bytes!("../../res/ghost_idle_2.obj")),
+    ("ghost_idle_3.obj", include_bytes!("../../res/ghost_idle_3.obj")),
+    ("ghost_chase_0.obj", include_bytes!("../../res/ghost_chase_0.obj")),
+    ("ghost_chase_1.obj", include_bytes!("../../res/ghost_chase_1.obj")),
+    ("ghost_chase_2.obj", include_bytes!("../../res/ghost_chase_2.obj")),
+    ("ghost_chase_3.obj", include_bytes!("../../res/ghost_chase_3.obj")),
+    ("controls.png", include_bytes!("../../res/controls.png")),
+    ("controls_dim.png", include_bytes!("../../res/controls_dim.png")),
+    ("digits.png", include_bytes!("../../res/digits.png")),
+    ("icons.png", include_bytes!("../../res/icons.png")),
+    ("font.png", include_bytes!("../../res/font.png")),
+    ("win.png", include_bytes!("../../res/win.png")),
+    ("lose.png", include_bytes!("../../res/lose.png"))
+];
+
+/// Models and textures from the resource directory, each loaded once and then looked up by name
+pub struct Assets {
+    queue: Arc<Queue>,
+    resource_path: PathBuf,
+    models: HashMap<String, Model>,
+    animations: HashMap<String, Animation>,
+    atlas: Option<TextureAtlas>
+}
+
+impl Assets {
+    pub fn new(queue: Arc<Queue>, resource_path: &str) -> Assets {
+        Assets {
+            queue,
+            resource_path: PathBuf::from(resource_path),
+            models: HashMap::new(),
+            animations: HashMap::new(),
+            atlas: None
+        }
+    }
+
+    /// Where a resource file lives on disk
+    pub fn resolve(&self, file: &str) -> PathBuf {
+        self.resource_path.join(file)
+    }
+
+    /// Contents of a resource file, or of the built-in copy if it's missing from disk
+    pub fn read(&self, file: &str) -> Result<Vec<u8>, MazeError> {
+        let path = self.resolve(file);
+        match fs::read(&path) {
+            Ok (bytes) => Ok (bytes),
+            Err (e) => match EMBEDDED.iter().find(|(name, _)| *name == file) {
+                Some ((_, bytes)) => {
+                    warn!(target: "render", "Using built-in {} since {} couldn't be read", file, path.display());
+                    Ok (bytes.to_vec())
+                },
+                None => Err (MazeError::io(&path.to_string_lossy(), e))
+            }
+        }
+    }
+
+    /// Load the named .obj models that haven't been loaded yet
+    pub fn load_models(&mut self, names: &[&str]) -> Result<Box<dyn GpuFuture>, MazeError> {
+        let mut future = sync::now(self.queue.device().clone()).boxed();
+        for name in names {
+            if self.models.contains_key(*name) {
+                continue;
+            }
+            let file = format!("{}.obj", name);
+            let (model, upload) = Model::new(self.queue.clone(), &file, &self.read(&file)?)?;
+            self.models.insert(name.to_string(), model);
+            future = future.join(upload).boxed();
+        }
+        Ok (future)
+    }
+
+    pub fn model(&self, name: &str) -> &Model {
+        self.models.get(name).unwrap_or_else(|| panic!("Model `{}' was never loaded", name))
+    }
+
+    /// Load an animation from its keyframes, named like `ghost_idle_0.obj`, `ghost_idle_1.obj` and so on, moving on
+    /// to the next keyframe every frame_time seconds
+    pub fn load_animation(&mut self, name: &str, keyframes: usize, frame_time: f32) -> Result<(), MazeError> {
+        let frames = (0..keyframes).map(|i| {
+            let file = format!("{}_{}.obj", name, i);
+            self.read(&file).map(|source| (file, source))
+        }).collect::<Result<Vec<_>, _>>()?;
+        self.animations.insert(name.to_string(), Animation::new(name, &frames, frame_time)?);
+        Ok (())
+    }
+
+    pub fn animation(&self, name: &str) -> &Animation {
+        self.animations.get(name).unwrap_or_else(|| panic!("Animation `{}' was never loaded", name))
+    }
+
+    /// Pack the named .png images into the texture atlas, replacing whatever it held before
+    pub fn load_textures(&mut self, names: &[&str]) -> Result<Box<dyn GpuFuture>, MazeError> {
+        let (atlas, future) = TextureAtlas::new(self.queue.clone(), self, names)?;
+        self.atlas = Some (atlas);
+        Ok (future)
+    }
+
+    pub fn atlas(&self) -> &TextureAtlas {
+        self.atlas.as_ref().expect("Textures were never loaded")
+    }
+}
@@ -0,0 +1,256 @@
+use std::time::Instant;
+
+use maze_core::config::CameraPreset;
+use maze_core::linalg;
+use maze_core::world::World;
+
+const TRANSITION_TIME: f32 = 0.4; // Seconds to blend between presets
+const FLY_SPEED: f32 = 4.0; // Cells per second when flying freely
+const LOOK_SPEED: f32 = 0.003; // Radians per pixel of mouse movement
+const WALL_MARGIN: f32 = 0.2; // Distance kept from a wall between the camera and its target, past the near plane
+const FOV_EASING: f32 = 4.0; // Rate the field of view closes the gap to its target, per second
+const SHAKE_DECAY: f32 = 1.5; // Shake lost per second
+const SHAKE_FREQUENCY: f32 = 25.0; // Radians per second of the fastest wobble
+const SHAKE_ANGLE: f32 = 0.03; // Radians the view tilts by at full shake
+const SHAKE_DISTANCE: f32 = 0.08; // Cells the camera moves by at full shake
+const OVERHEAD_HEIGHT: f32 = 10.0; // Levels above the player to look down from in the overhead view
+const PULL_BACK_SPEED: f32 = 3.0; // Fraction of the offset per second to ease back out once a wall is out of the way
+const DOLLHOUSE_PITCH: f32 = 55.0; // Degrees from straight down the dollhouse view looks at the levels from
+const DOLLHOUSE_EASING: f32 = 3.0; // Rate the camera closes the gap to where the dollhouse view looks from, per second
+
+/// Space left between levels stacked apart in the dollhouse view, as a fraction of a level's height
+pub const DOLLHOUSE_GAP: f32 = 1.5;
+
+pub struct Camera {
+    position: [f32; 3],
+    scale: [f32; 3],
+    rotation: [f32; 3],
+    aspect_ratio: f32,
+    fov: f32, // Of the preset, blending along with the rest of it
+    shown_fov: f32, // Actually drawn, easing towards the target
+    fov_target: Option<f32>, // Overrides the preset's field of view
+    offset: [f32; 3], // From the target being followed
+    transition: Option<(CameraPreset, CameraPreset, Instant)>, // From, to, start time
+    free: bool, // Flying on its own instead of following a target
+    reach: f32, // Fraction of the offset the camera sits at, less than 1 when pulled in front of a wall
+    followed: Instant, // When the camera last followed its target
+    shake: f32, // From 0 for still to 1 for the hardest shake
+    created: Instant, // Start of the shake's wobbles
+    overhead: Option<f32>, // Half the height of the orthographic view looking straight down, if that's how the level is shown
+    dollhouse: bool // Looking at every level of the target's w-slice from a distance, stacked apart
+}
+
+impl Camera {
+    pub fn new(resolution: [u32; 2], preset: &CameraPreset) -> Camera {
+        Camera {
+            position: [0.0, 0.0, 0.0],
+            scale: [1.0, 1.0, 1.0],
+            rotation: [preset.pitch.to_radians(), 0.0, 0.0],
+            aspect_ratio: {
+                let [x, y] = resolution;
+                x as f32 / y as f32
+            },
+            fov: preset.fov,
+            shown_fov: preset.fov,
+            fov_target: None,
+            offset: preset.offset,
+            transition: None,
+            free: false,
+            reach: 1.0,
+            followed: Instant::now(),
+            shake: 0.0,
+            created: Instant::now(),
+            overhead: None,
+            dollhouse: false
+        }
+    }
+
+    /// Match the shape of a resized window
+    pub fn resize(&mut self, resolution: [u32; 2]) {
+        let [x, y] = resolution;
+        self.aspect_ratio = x as f32 / y as f32;
+    }
+
+    // Smoothly move from wherever the camera currently is to the given preset
+    pub fn transition_to(&mut self, preset: &CameraPreset) {
+        let current = CameraPreset { offset: self.offset, fov: self.fov, pitch: self.rotation[0].to_degrees() };
+        self.transition = Some ((current, *preset, Instant::now()));
+    }
+
+    /// Field of view for the camera to ease towards, or None to keep to the preset's
+    pub fn set_fov_target(&mut self, fov: Option<f32>) {
+        self.fov_target = fov;
+    }
+
+    /// Shake the camera at least this hard, from 0 to 1, dying down over the next moments
+    pub fn shake(&mut self, amount: f32) {
+        self.shake = self.shake.max(amount.min(1.0));
+    }
+
+    /// Detach the camera to fly around on its own, or reattach it to its target, facing the way the preset does
+    pub fn set_free(&mut self, free: bool) {
+        self.free = free;
+        if free {
+            self.overhead = None;
+            self.dollhouse = false;
+        } else {
+            self.rotation[2] = 0.0;
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.free
+    }
+
+    /// Switch between following the target in perspective and an orthographic view of its whole level from above
+    pub fn set_overhead(&mut self, overhead: bool) {
+        self.overhead = if overhead { Some (1.0) } else { None };
+        if overhead {
+            self.dollhouse = false;
+        }
+    }
+
+    pub fn is_overhead(&self) -> bool {
+        self.overhead.is_some()
+    }
+
+    /// Switch between following the target and zooming out to see every level of its w-slice, stacked DOLLHOUSE_GAP
+    /// apart so the routes between them can be planned
+    pub fn set_dollhouse(&mut self, dollhouse: bool) {
+        self.dollhouse = dollhouse;
+        if dollhouse {
+            self.overhead = None;
+        }
+    }
+
+    pub fn is_dollhouse(&self) -> bool {
+        self.dollhouse
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        self.position
+    }
+
+    /// Fly freely through walls, moving [right, forward, up] relative to where the camera faces, each between -1 and 1
+    pub fn fly(&mut self, movement: [f32; 3], seconds: f32) {
+        let [pitch, _, yaw] = self.rotation;
+        let right = [yaw.cos(), -yaw.sin(), 0.0];
+        let forward = [-yaw.sin() * pitch.sin(), -yaw.cos() * pitch.sin(), -pitch.cos()];
+        let up = [0.0, 0.0, 1.0];
+        let distance = FLY_SPEED * seconds;
+        for i in 0..3 {
+            self.position[i] += (right[i] * movement[0] + forward[i] * movement[1] + up[i] * movement[2]) * distance;
+        }
+    }
+
+    /// Put the free camera at the position, turned by the yaw about the vertical and pitched from looking straight down
+    pub fn place(&mut self, position: [f32; 3], yaw: f32, pitch: f32) {
+        self.position = position;
+        self.rotation[0] = pitch.clamp(0.0, std::f32::consts::PI);
+        self.rotation[2] = yaw;
+    }
+
+    /// Turn the free camera by a mouse movement in pixels, keeping it between looking straight down and straight up
+    pub fn look(&mut self, delta: [f64; 2]) {
+        self.rotation[2] -= delta[0] as f32 * LOOK_SPEED;
+        self.rotation[0] = (self.rotation[0] - delta[1] as f32 * LOOK_SPEED).clamp(0.0, std::f32::consts::PI);
+    }
+
+    // Place the camera at the given target (xyzw), offset by the current preset, but in front of any wall in the way
+    // so it never sees through into the void. A free camera stays where it is
+    pub fn follow(&mut self, target: [f32; 4], world: &World) {
+        let now = Instant::now();
+        let seconds = (now - self.followed).as_secs_f32();
+        self.followed = now;
+        self.shake = (self.shake - SHAKE_DECAY * seconds).max(0.0);
+        let target_fov = self.fov_target.unwrap_or(self.fov);
+        self.shown_fov += (target_fov - self.shown_fov) * (1.0 - (-FOV_EASING * seconds).exp());
+        if self.free {
+            return;
+        }
+        if let Some (half_height) = &mut self.overhead {
+            // Centre the target's level in the view, with half a cell to spare around the outer walls
+            let [width, height] = [world.width as f32, world.height as f32];
+            *half_height = (height / 2.0).max(width / 2.0 / self.aspect_ratio) + 0.5;
+            self.position = [(width - 1.0) / 2.0, (height - 1.0) / 2.0, target[2].round() + OVERHEAD_HEIGHT];
+            return;
+        }
+        if self.dollhouse {
+            // Ease out to where the whole stack of levels fits in view, looking at its middle from the side and above
+            let [width, height] = [world.width as f32, world.height as f32];
+            let top = (world.depth - 1) as f32 * (1.0 + DOLLHOUSE_GAP) + 1.0;
+            let size = width.max(height).max(top);
+            let distance = size / 2.0 / (self.shown_fov / 2.0).to_radians().tan() / self.aspect_ratio.min(1.0) + size / 2.0;
+            let pitch = DOLLHOUSE_PITCH.to_radians();
+            let wanted = [(width - 1.0) / 2.0, (height - 1.0) / 2.0 + distance * pitch.sin(), top / 2.0 + distance * pitch.cos()];
+            let ease = 1.0 - (-DOLLHOUSE_EASING * seconds).exp();
+            self.position = [0, 1, 2].map(|i| self.position[i] + (wanted[i] - self.position[i]) * ease);
+            self.rotation[0] += (pitch - self.rotation[0]) * ease;
+            return;
+        }
+        if let Some ((from, to, start)) = self.transition {
+            let t = ((Instant::now() - start).as_secs_f32() / TRANSITION_TIME).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+            let lerp = |a: f32, b: f32| a + (b - a) * t;
+            self.offset = [0, 1, 2].map(|i| lerp(from.offset[i], to.offset[i]));
+            self.fov = lerp(from.fov, to.fov);
+            self.rotation[0] = lerp(from.pitch, to.pitch).to_radians();
+            if t >= 1.0 {
+                self.transition = None;
+            }
+        }
+        let [w, level] = [target[3], target[2]].map(|i| i.round().max(0.0) as usize);
+        let target = [target[0], target[1], target[2]];
+        let wanted = linalg::add(target, self.offset);
+        let length = self.offset.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let clear = match world.first_hit(w, level, target, wanted) {
+            Some (t) => (t - WALL_MARGIN / length).max(0.0),
+            None => 1.0
+        };
+        // Jump in front of a wall straight away, but ease back out so the view doesn't flicker along a row of walls
+        self.reach = clear.min(self.reach + PULL_BACK_SPEED * seconds);
+        self.position = linalg::add(target, self.offset.map(|x| x * self.reach));
+    }
+
+    pub fn view(&self) -> [[f32; 4]; 4] {
+        let (tilt, nudge) = self.jitter();
+        let position = linalg::add(self.position, nudge);
+        linalg::mul(linalg::rotate(tilt), linalg::mul(self.orientation(), linalg::view([0.0; 3], self.scale, position.map(|x| -x))))
+    }
+
+    // Wobble from the current shake, as a small tilt of the view and a nudge of the camera's position. Squaring the
+    // shake lets it tail off gently rather than stopping dead
+    fn jitter(&self) -> ([f32; 3], [f32; 3]) {
+        let t = (Instant::now() - self.created).as_secs_f32();
+        let strength = self.shake * self.shake;
+        let wave = |i: f32| (t * SHAKE_FREQUENCY / (1.0 + 0.37 * i) + 1.7 * i).sin() * strength;
+        ([0.0, 1.0, 2.0].map(|i| wave(i) * SHAKE_ANGLE), [3.0, 4.0, 5.0].map(|i| wave(i) * SHAKE_DISTANCE))
+    }
+
+    // Turn about the vertical axis by the yaw first, so pitching afterwards never rolls the view
+    fn orientation(&self) -> [[f32; 4]; 4] {
+        let [pitch, _, yaw] = if self.overhead.is_some() { [0.0; 3] } else { self.rotation };
+        linalg::mul(linalg::rotate([pitch, 0.0, 0.0]), linalg::rotate([0.0, 0.0, yaw]))
+    }
+
+    // Maps a screen position (in normalized device coordinates, at depth -1) to the world space direction of the view ray through it
+    pub fn ray(&self) -> [[f32; 4]; 4] {
+        // Every ray of an orthographic view points the same way, straight ahead
+        let focal = if self.overhead.is_some() { f32::INFINITY } else { 1.0 / (self.shown_fov / 2.0).to_radians().tan() };
+        let unproject = linalg::transpose([
+            [self.aspect_ratio / focal, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / focal, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]);
+        // Inverse of a rotation is its transpose
+        linalg::mul(linalg::transpose(self.orientation()), unproject)
+    }
+
+    pub fn projection(&self) -> [[f32; 4]; 4] {
+        match self.overhead {
+            Some (half_height) => linalg::orthographic(0.1, 100.0, half_height, self.aspect_ratio),
+            None => linalg::projection(0.1, 100.0, 1.0 / (self.shown_fov / 2.0).to_radians().tan(), self.aspect_ratio)
+        }
+    }
+}
@@ -0,0 +1,937 @@
+//! Creation of the Vulkan objects the renderers share: swapchains, framebuffers, pipelines, buffers and descriptor sets.
+//!
+//! Everything that depends on the exact vulkano and winit APIs lives here, so moving to a newer release only means
+//! updating this module.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use log::{info, warn};
+
+use vulkano::{Validated, ValidationError, VulkanError, VulkanLibrary};
+use vulkano::buffer::{BufferContents, BufferCreateInfo, BufferUsage, BufferWriteGuard, Subbuffer};
+use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, BufferImageCopy, CommandBufferInheritanceInfo, CommandBufferUsage,
+    CopyBufferInfo, CopyBufferToImageInfo, PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract, RenderPassBeginInfo,
+    SecondaryAutoCommandBuffer, SubpassBeginInfo, SubpassContents, SubpassEndInfo};
+use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
+use vulkano::descriptor_set::{DescriptorSet, DescriptorSetsCollection, WriteDescriptorSet};
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::descriptor_set::layout::DescriptorSetLayout;
+use vulkano::device::{Device, DeviceCreateInfo, DeviceExtensions, DeviceFeatures, Queue, QueueCreateInfo, QueueFlags};
+use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
+use vulkano::format::{ClearValue, CompressionType, Format, FormatFeatures};
+use vulkano::image::view::ImageView;
+use vulkano::image::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::image::{self, Image, ImageCreateInfo, ImageSubresourceLayers, ImageType, ImageUsage, SampleCount};
+use vulkano::instance::{Instance, InstanceCreateFlags, InstanceCreateInfo};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryAllocatorError, MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::pipeline::{ComputePipeline, DynamicState, GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout, PipelineShaderStageCreateInfo};
+use vulkano::pipeline::cache::{PipelineCache, PipelineCacheCreateInfo};
+use vulkano::pipeline::compute::ComputePipelineCreateInfo;
+use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
+use vulkano::pipeline::graphics::color_blend::{AttachmentBlend, ColorBlendAttachmentState, ColorBlendState, ColorComponents};
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
+use vulkano::pipeline::graphics::input_assembly::{InputAssemblyState, PrimitiveTopology};
+use vulkano::pipeline::graphics::multisample::MultisampleState;
+use vulkano::pipeline::graphics::rasterization::RasterizationState;
+use vulkano::pipeline::graphics::vertex_input::{Vertex, VertexBufferDescription, VertexDefinition};
+use vulkano::pipeline::graphics::viewport::ViewportState;
+use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
+use vulkano::query::{QueryControlFlags, QueryPipelineStatisticFlags, QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType};
+use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
+use vulkano::shader::{EntryPoint, ShaderModule};
+use vulkano::swapchain::{Surface, SurfaceCapabilities, SurfaceInfo, SurfaceTransform, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
+    SwapchainPresentInfo};
+use vulkano::sync::{GpuFuture, HostAccessError, PipelineStage};
+use vulkano::sync::future::FenceSignalFuture;
+use winit::application::ApplicationHandler;
+use winit::event::{DeviceEvent, DeviceId, Event, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::window::{Window, WindowAttributes, WindowId};
+
+use maze_core::config::Card;
+use maze_core::error::MazeError;
+
+pub use vulkano::image::sampler::SamplerMipmapMode as MipmapMode;
+pub use vulkano::pipeline::graphics::viewport::Viewport;
+
+pub type Framebuffers = Vec<Arc<Framebuffer>>;
+
+/// Swapchain along with the images it presents, in the order it numbers them
+pub type Presenter = (Arc<Swapchain>, Vec<Arc<Image>>);
+
+const ARENA_SIZE: u64 = 64 * 1024; // Bytes in each buffer a pool hands out pieces of, before it starts another
+const PIPELINE_CACHE_MAGIC: &[u8] = b"mazepipe"; // Start of every pipeline cache file, before the card and driver
+
+/// How a graphics pipeline treats depth, primitives and blending
+#[derive(Clone, Copy)]
+pub struct PipelineOptions {
+    pub depth: bool, // Test and write depth
+    pub lines: bool, // Draw a line list rather than a triangle list
+    pub alpha_blending: bool,
+    pub color: bool, // Write color, or only depth, for a depth pre-pass
+    pub prepassed: bool // Depth is already written by a pre-pass, so only test it, letting equal depths through
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        PipelineOptions { depth: true, lines: false, alpha_blending: false, color: true, prepassed: false }
+    }
+}
+
+// Where the device's memory, command buffers and descriptor sets come from, made once and shared by every renderer
+struct Allocators {
+    device: Arc<Device>,
+    memory: Arc<StandardMemoryAllocator>,
+    commands: Arc<StandardCommandBufferAllocator>,
+    descriptor_sets: Arc<StandardDescriptorSetAllocator>
+}
+
+static ALLOCATORS: OnceLock<Allocators> = OnceLock::new();
+
+// Allocators for the device, made the first time anything is allocated. The game only ever opens one device
+fn allocators(device: &Arc<Device>) -> &'static Allocators {
+    let allocators = ALLOCATORS.get_or_init(|| Allocators {
+        device: device.clone(),
+        memory: Arc::new(StandardMemoryAllocator::new_default(device.clone())),
+        commands: Arc::new(StandardCommandBufferAllocator::new(device.clone(), Default::default())),
+        descriptor_sets: Arc::new(StandardDescriptorSetAllocator::new(device.clone(), Default::default()))
+    });
+    assert!(Arc::ptr_eq(&allocators.device, device), "Only one Vulkan device can be used at a time");
+    allocators
+}
+
+/// Vulkan instance able to draw to windows of the event loop
+pub fn instance(event_loop: &EventLoop<()>, name: &str) -> Result<Arc<Instance>, MazeError> {
+    let library = VulkanLibrary::new().map_err(|e| MazeError::graphics(format!("Couldn't load Vulkan: {}", e)))?;
+    let enabled_extensions = Surface::required_extensions(event_loop).map_err(MazeError::graphics)?;
+    Instance::new(library, InstanceCreateInfo {
+        flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+        application_name: Some (name.to_string()),
+        application_version: vulkano::Version::V1_2,
+        enabled_extensions,
+        ..Default::default()
+    }).map_err(|e| MazeError::graphics(format!("Couldn't create a Vulkan instance: {}", e)))
+}
+
+// Every graphics card, in the order Vulkan lists them
+fn cards(instance: &Arc<Instance>) -> Result<Vec<Arc<PhysicalDevice>>, MazeError> {
+    Ok (instance.enumerate_physical_devices().map_err(|e| MazeError::graphics(format!("Couldn't list graphics cards: {}", e)))?.collect())
+}
+
+/// Graphics card chosen in the config: the first discrete card able to draw to a window, else any card that can,
+/// or a particular card by its number or part of its name
+pub fn choose_card(instance: &Arc<Instance>, choice: &Card) -> Result<Arc<PhysicalDevice>, MazeError> {
+    let cards = cards(instance)?;
+    let card = match choice {
+        Card::Discrete => {
+            let usable = || cards.iter().filter(|card| can_present(card));
+            return usable().find(|card| card.properties().device_type == PhysicalDeviceType::DiscreteGpu).or(usable().next()).cloned()
+                .ok_or_else(|| MazeError::graphics(if cards.is_empty() { "No Vulkan graphics card found" } else { "No graphics card can draw to a window" }));
+        },
+        Card::Number (n) => cards.get(*n).cloned().ok_or_else(|| MazeError::graphics(format!("Graphics card number {} doesn't exist, there are only {}", n, cards.len())))?,
+        Card::Name (name) => cards.iter().find(|card| card.properties().device_name.to_lowercase().contains(&name.to_lowercase())).cloned()
+            .ok_or_else(|| MazeError::graphics(format!("No graphics card named like `{}'", name)))?
+    };
+    if !can_present(&card) {
+        return Err (MazeError::graphics(format!("{} can't draw to a window, it has no swapchain support", card.properties().device_name)));
+    }
+    Ok (card)
+}
+
+/// Description of each graphics card, numbered the way the card setting counts them
+pub fn list_cards(instance: &Arc<Instance>) -> Result<Vec<String>, MazeError> {
+    Ok (cards(instance)?.iter().enumerate().map(|(i, card)| {
+        let properties = card.properties();
+        let usable = if can_present(card) { "" } else { ", can't draw to a window" };
+        format!("{}: {} ({:?}, Vulkan {}{})", i, properties.device_name, properties.device_type, properties.api_version, usable)
+    }).collect())
+}
+
+// Whether the card can draw graphics and present them to a window
+fn can_present(card: &PhysicalDevice) -> bool {
+    card.supported_extensions().khr_swapchain && card.queue_family_properties().iter().any(|q| q.queue_flags.intersects(QueueFlags::GRAPHICS))
+}
+
+/// Logical device on the card, with the features the renderers use where the card has them, and its one queue. Pipeline
+/// statistics can be counted on it if asked for and the card can count them, in secondary command buffers too
+pub fn device(card: Arc<PhysicalDevice>, statistics: bool) -> Result<(Arc<Device>, Arc<Queue>), MazeError> {
+    let name = card.properties().device_name.clone();
+    let enabled_features = DeviceFeatures {
+        robust_buffer_access: true,
+        sampler_anisotropy: card.supported_features().sampler_anisotropy,
+        texture_compression_bc: card.supported_features().texture_compression_bc,
+        pipeline_statistics_query: statistics && card.supported_features().pipeline_statistics_query,
+        inherited_queries: statistics && card.supported_features().inherited_queries,
+        .. DeviceFeatures::empty()
+    };
+    let enabled_extensions = DeviceExtensions {
+        khr_swapchain: true,
+        .. DeviceExtensions::empty()
+    };
+    let draw_family = card.queue_family_properties().iter().position(|q| q.queue_flags.intersects(QueueFlags::GRAPHICS))
+        .ok_or_else(|| MazeError::graphics(format!("{} can't draw graphics", name)))?;
+    let (device, mut queues) = Device::new(card, DeviceCreateInfo {
+        queue_create_infos: vec![QueueCreateInfo { queue_family_index: draw_family as u32, ..Default::default() }],
+        enabled_extensions,
+        enabled_features,
+        ..Default::default()
+    }).map_err(|e| MazeError::graphics(format!("Couldn't use {}: {}", name, e)))?;
+    Ok ((device, queues.next().unwrap()))
+}
+
+/// Window made from the attributes, and the surface the swapchain presents to it through
+pub fn window(event_loop: &EventLoop<()>, instance: Arc<Instance>, attributes: WindowAttributes) -> Result<(Arc<Window>, Arc<Surface>), MazeError> {
+    // winit would rather windows were made once the event loop is running, but everything is set up before it starts
+    #[allow(deprecated)]
+    let window = Arc::new(event_loop.create_window(attributes).map_err(|e| MazeError::graphics(format!("Couldn't create window: {}", e)))?);
+    let surface = Surface::from_window(instance, window.clone()).map_err(|e| MazeError::graphics(format!("Couldn't create window: {}", e)))?;
+    Ok ((window, surface))
+}
+
+/// What the card can present to the window, and the first color format it can present in
+pub fn surface_capabilities(card: &PhysicalDevice, surface: &Surface) -> Result<(SurfaceCapabilities, Format), MazeError> {
+    let query = |e| MazeError::graphics(format!("Couldn't query window surface: {}", e));
+    let capabilities = card.surface_capabilities(surface, SurfaceInfo::default()).map_err(query)?;
+    let (format, _color_space) = *card.surface_formats(surface, SurfaceInfo::default()).map_err(query)?.first()
+        .ok_or_else(|| MazeError::graphics("Window surface supports no formats"))?;
+    Ok ((capabilities, format))
+}
+
+/// Most samples per pixel the device can render color with, as a count and as a setting
+pub fn max_samples(device: &Device) -> (u32, SampleCount) {
+    let sample_count = device.physical_device().properties().framebuffer_color_sample_counts.max_count();
+    (sample_count as u32, sample_count)
+}
+
+/// Viewports side by side across an image of the given size, splitting its width evenly between this many screens
+pub fn viewports(dimensions: [u32; 2], screens: u32) -> Vec<Viewport> {
+    let width = dimensions[0] as f32 / screens as f32;
+    (0..screens).map(|i| Viewport {
+        offset: [i as f32 * width, 0.0],
+        extent: [width, dimensions[1] as f32],
+        depth_range: 0.0..=1.0
+    }).collect()
+}
+
+/// Run the event loop until the handler exits it, passing it each window and device event, then
+/// `Event::AboutToWait` once the events so far are handled, which is when to draw a frame
+pub fn run<F>(event_loop: EventLoop<()>, handler: F) -> Result<(), MazeError>
+        where F: FnMut(Event<()>, &ActiveEventLoop) {
+    struct Handler<F> (F);
+
+    impl<F: FnMut(Event<()>, &ActiveEventLoop)> ApplicationHandler for Handler<F> {
+        fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
+
+        fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+            (self.0)(Event::WindowEvent { window_id, event }, event_loop);
+        }
+
+        fn device_event(&mut self, event_loop: &ActiveEventLoop, device_id: DeviceId, event: DeviceEvent) {
+            (self.0)(Event::DeviceEvent { device_id, event }, event_loop);
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            (self.0)(Event::AboutToWait, event_loop);
+        }
+    }
+
+    // Keep drawing frames rather than waiting for input
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run_app(&mut Handler (handler)).map_err(|e| MazeError::graphics(format!("Event loop failed: {}", e)))
+}
+
+/// Swapchain presenting color images of the given size and format to the window
+pub fn swapchain(device: Arc<Device>, surface: Arc<Surface>, buffers: u32, format: Format, dimensions: [u32; 2], transform: SurfaceTransform)
+        -> Result<Presenter, MazeError> {
+    Swapchain::new(device, surface, SwapchainCreateInfo {
+        min_image_count: buffers,
+        image_format: format,
+        image_extent: dimensions,
+        image_usage: ImageUsage::COLOR_ATTACHMENT,
+        pre_transform: transform,
+        ..Default::default()
+    }).map_err(|e| MazeError::graphics(format!("Couldn't create swapchain: {}", e)))
+}
+
+/// Same swapchain at a new size, eg. after the window was resized
+pub fn recreate_swapchain(swapchain: &Arc<Swapchain>, dimensions: [u32; 2]) -> Result<Presenter, Validated<VulkanError>> {
+    swapchain.recreate(SwapchainCreateInfo { image_extent: dimensions, ..swapchain.create_info() })
+}
+
+/// Render pass drawing multisampled color and depth, resolved into a single-sampled image of the format
+pub fn render_pass(device: Arc<Device>, format: Format, samples: u32) -> Arc<RenderPass> {
+    vulkano::single_pass_renderpass!(
+        device,
+        attachments: {
+            msaa_image: {
+                format: format,
+                samples: samples,
+                load_op: Clear,
+                store_op: DontCare,
+            },
+            color_image: {
+                format: format,
+                samples: 1,
+                load_op: DontCare,
+                store_op: Store,
+            },
+            depth_image: {
+                format: Format::D16_UNORM,
+                samples: samples,
+                load_op: Clear,
+                store_op: DontCare,
+            }
+        },
+        pass: {
+            color: [msaa_image],
+            color_resolve: [color_image],
+            depth_stencil: {depth_image}
+        }
+    ).expect("Failed to create render pass")
+}
+
+// Signalled once a frame's work is done, shared so the next frame can wait on it too
+type FrameFence = Arc<FenceSignalFuture<Box<dyn GpuFuture>>>;
+
+/// Frames submitted to the GPU that may not be finished yet, up to a fixed number of them. The CPU records the next
+/// frame while the GPU works through the ones before, and only waits once it's that many frames ahead
+pub struct FramesInFlight {
+    fences: Vec<Option<FrameFence>>, // Signalled once each frame's work is done, by slot
+    next: usize // Slot of the frame being recorded
+}
+
+impl FramesInFlight {
+    pub fn new(count: usize) -> FramesInFlight {
+        FramesInFlight { fences: (0..count.max(1)).map(|_| None).collect(), next: 0 }
+    }
+
+    /// Wait for the frame submitted that many frames ago to finish, before recording another in its place
+    pub fn wait(&mut self) {
+        for fence in self.fences.iter_mut().flatten() {
+            fence.cleanup_finished();
+        }
+        if let Some (fence) = &self.fences[self.next] {
+            fence.wait(None).expect("Failed to wait for a frame");
+        }
+    }
+
+    /// Run the frame's commands after the one before them, the acquired image and whatever else is given, eg. uploads
+    /// the frame draws from, then present the image. Commands that can't be run at all fail as a validation error
+    pub fn submit(&mut self, queue: Arc<Queue>, swapchain: Arc<Swapchain>, image: u32, acquire: SwapchainAcquireFuture,
+            commands: Arc<PrimaryAutoCommandBuffer>, after: Box<dyn GpuFuture>) -> Result<(), Validated<VulkanError>> {
+        let last = (self.next + self.fences.len() - 1) % self.fences.len();
+        let after = match self.fences[last].clone() {
+            Some (fence) => after.join(fence).boxed(),
+            None => after
+        };
+        let future = after
+            .join(acquire)
+            .then_execute(queue.clone(), commands)
+            .map_err(|e| Box::new(ValidationError { problem: e.to_string().into(), ..Default::default() }))?
+            .then_swapchain_present(queue, SwapchainPresentInfo::swapchain_image_index(swapchain, image))
+            .boxed()
+            .then_signal_fence_and_flush();
+        match future {
+            Ok (fence) => {
+                // Frames are only ever drawn and waited on from the event loop's thread
+                #[allow(clippy::arc_with_non_send_sync)]
+                let fence = Arc::new(fence);
+                self.fences[self.next] = Some (fence);
+                self.next = (self.next + 1) % self.fences.len();
+                Ok (())
+            }
+            Err (e) => {
+                // Nothing was submitted, so the next frame takes this slot instead
+                self.fences[self.next] = None;
+                Err (e)
+            }
+        }
+    }
+}
+
+/// One framebuffer per swapchain image, rendering multisampled with depth and resolving into the image
+pub fn framebuffers(device: Arc<Device>, render_pass: Arc<RenderPass>, images: &[Arc<Image>], sample_count: SampleCount) -> Framebuffers {
+    let memory = allocators(&device).memory.clone();
+    let extent = images[0].extent();
+    let attachment = |format: Format, usage: ImageUsage| {
+        let image = Image::new(memory.clone(), ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format,
+            extent,
+            usage: usage | ImageUsage::TRANSIENT_ATTACHMENT,
+            samples: sample_count,
+            ..Default::default()
+        }, AllocationCreateInfo::default()).expect("Failed to create attachment");
+        ImageView::new_default(image).unwrap()
+    };
+    let dview = attachment(Format::D16_UNORM, ImageUsage::DEPTH_STENCIL_ATTACHMENT);
+    images
+        .iter()
+        .map(|image| {
+            let view = ImageView::new_default(image.clone()).unwrap();
+            let mview = attachment(image.format(), ImageUsage::COLOR_ATTACHMENT);
+            Framebuffer::new(render_pass.clone(), FramebufferCreateInfo {
+                attachments: vec![mview, view, dview.clone()],
+                ..Default::default()
+            }).unwrap()
+        }).collect()
+}
+
+/// Vertex types a graphics pipeline reads, one buffer binding each, in the order they're bound
+#[derive(Default)]
+pub struct VertexInput {
+    buffers: Vec<VertexBufferDescription>
+}
+
+impl VertexInput {
+    pub fn new() -> VertexInput {
+        VertexInput::default()
+    }
+
+    /// Next binding steps through a vertex of this type per vertex
+    pub fn vertex<T: Vertex>(mut self) -> VertexInput {
+        self.buffers.push(T::per_vertex());
+        self
+    }
+
+    /// Next binding steps through a vertex of this type per instance
+    pub fn instance<T: Vertex>(mut self) -> VertexInput {
+        self.buffers.push(T::per_instance());
+        self
+    }
+}
+
+/// Shader module generated by `vulkano_shaders::shader!`, loaded onto a device by [`load_shader!`]
+pub struct Shader (Arc<ShaderModule>);
+
+impl Shader {
+    pub fn new(module: Arc<ShaderModule>) -> Shader {
+        Shader (module)
+    }
+
+    pub fn main_entry_point(&self) -> EntryPoint {
+        self.0.entry_point("main").expect("Shader has no main function")
+    }
+}
+
+/// Graphics pipeline drawing into the first subpass of the render pass, with a dynamic viewport
+pub fn graphics_pipeline(device: Arc<Device>, render_pass: Arc<RenderPass>, cache: Arc<PipelineCache>, vertex_input: VertexInput,
+        vertex_shader: EntryPoint, fragment_shader: EntryPoint, options: PipelineOptions) -> Arc<GraphicsPipeline> {
+    let vertex_input_state = vertex_input.buffers.definition(&vertex_shader).expect("Vertex buffers don't match the vertex shader");
+    let stages = [PipelineShaderStageCreateInfo::new(vertex_shader), PipelineShaderStageCreateInfo::new(fragment_shader)];
+    let layout = pipeline_layout(device.clone(), &stages);
+    let subpass = Subpass::from(render_pass, 0).unwrap();
+    let blend = ColorBlendAttachmentState {
+        blend: options.alpha_blending.then(AttachmentBlend::alpha),
+        color_write_mask: if options.color { ColorComponents::all() } else { ColorComponents::empty() },
+        ..Default::default()
+    };
+    let depth = match options.prepassed {
+        false => DepthState::simple(),
+        true => DepthState { write_enable: false, compare_op: CompareOp::LessOrEqual }
+    };
+    GraphicsPipeline::new(device, Some (cache), GraphicsPipelineCreateInfo {
+        stages: stages.into_iter().collect(),
+        vertex_input_state: Some (vertex_input_state),
+        input_assembly_state: Some (InputAssemblyState {
+            topology: if options.lines { PrimitiveTopology::LineList } else { PrimitiveTopology::TriangleList },
+            ..Default::default()
+        }),
+        viewport_state: Some (ViewportState::default()),
+        rasterization_state: Some (RasterizationState::default()),
+        multisample_state: Some (MultisampleState { rasterization_samples: subpass.num_samples().unwrap_or(SampleCount::Sample1), ..Default::default() }),
+        depth_stencil_state: options.depth.then(|| DepthStencilState { depth: Some (depth), ..Default::default() }),
+        color_blend_state: Some (ColorBlendState::with_attachment_states(subpass.num_color_attachments(), blend)),
+        dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+        subpass: Some (subpass.into()),
+        ..GraphicsPipelineCreateInfo::layout(layout)
+    }).expect("Failed to build graphics pipeline")
+}
+
+/// Compute pipeline running the shader's entry point
+pub fn compute_pipeline(device: Arc<Device>, cache: Arc<PipelineCache>, shader: EntryPoint) -> Arc<ComputePipeline> {
+    let stage = PipelineShaderStageCreateInfo::new(shader);
+    let layout = pipeline_layout(device.clone(), std::slice::from_ref(&stage));
+    ComputePipeline::new(device, Some (cache), ComputePipelineCreateInfo::stage_layout(stage, layout)).expect("Failed to build compute pipeline")
+}
+
+/// Where the pipeline cache is kept, beside the settings file
+pub fn pipeline_cache_path(config: &str) -> String {
+    Path::new(config).with_extension("pipelines").to_string_lossy().into_owned()
+}
+
+/// Pipeline cache filled from the file, if it was written for this card and driver, or an empty one otherwise
+pub fn load_pipeline_cache(device: Arc<Device>, path: &str) -> Arc<PipelineCache> {
+    let header = pipeline_cache_header(&device);
+    let data = match fs::read(path) {
+        Ok (data) => data,
+        Err (e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err (e) => {
+            warn!(target: "render", "Couldn't read the pipeline cache {}: {}", path, e);
+            Vec::new()
+        }
+    };
+    if data.len() > header.len() && data.starts_with(&header) {
+        let initial_data = data[header.len()..].to_vec();
+        // Safe, since the data was written by save_pipeline_cache for this same card and driver
+        if let Ok (cache) = unsafe { PipelineCache::new(device.clone(), PipelineCacheCreateInfo { initial_data, ..Default::default() }) } {
+            info!(target: "render", "Loaded {} bytes of cached pipelines", data.len() - header.len());
+            return cache;
+        }
+    } else if !data.is_empty() {
+        info!(target: "render", "Pipeline cache {} was written for another card or driver, so pipelines are built afresh", path);
+    }
+    // Safe, since an empty cache holds no data for the driver to trust
+    unsafe { PipelineCache::new(device, PipelineCacheCreateInfo::default()) }.expect("Failed to create pipeline cache")
+}
+
+/// Write the cache to the file, after a header naming the card and driver it's only good for
+pub fn save_pipeline_cache(device: &Device, cache: &PipelineCache, path: &str) {
+    let mut contents = pipeline_cache_header(device);
+    match cache.get_data() {
+        Ok (data) => contents.extend(data),
+        Err (e) => {
+            warn!(target: "render", "Couldn't read back the pipeline cache: {}", e);
+            return;
+        }
+    }
+    if let Err (e) = fs::write(path, contents) {
+        warn!(target: "render", "Couldn't write the pipeline cache {}: {}", path, e);
+    }
+}
+
+// Bytes identifying the card and driver a pipeline cache was written for. Vulkan checks its own header too, but some
+// drivers crash on data from another driver rather than rejecting it
+fn pipeline_cache_header(device: &Device) -> Vec<u8> {
+    let properties = device.physical_device().properties();
+    let mut header = PIPELINE_CACHE_MAGIC.to_vec();
+    header.extend(&properties.pipeline_cache_uuid);
+    for id in [properties.vendor_id, properties.device_id, properties.driver_version] {
+        header.extend(id.to_le_bytes());
+    }
+    header
+}
+
+// Layout with the descriptor sets and push constants the shader stages use between them
+fn pipeline_layout(device: Arc<Device>, stages: &[PipelineShaderStageCreateInfo]) -> Arc<PipelineLayout> {
+    let create_info = PipelineDescriptorSetLayoutCreateInfo::from_stages(stages)
+        .into_pipeline_layout_create_info(device.clone())
+        .expect("Shader stages don't agree on their descriptor sets");
+    PipelineLayout::new(device, create_info).expect("Failed to create pipeline layout")
+}
+
+/// Buffer the renderers draw from or write to. Vulkan buffers can't be empty, so an empty one still holds a single
+/// unused element, and only its length says it's empty
+pub struct Buffer<T> {
+    buffer: Subbuffer<[T]>,
+    len: u64
+}
+
+impl<T> Clone for Buffer<T> {
+    fn clone(&self) -> Self {
+        Buffer { buffer: self.buffer.clone(), len: self.len }
+    }
+}
+
+impl<T: BufferContents> Buffer<T> {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Contents for the CPU to overwrite, which fails while the GPU may still be reading them
+    pub fn write(&self) -> Result<BufferWriteGuard<'_, [T]>, HostAccessError> {
+        self.buffer.write()
+    }
+}
+
+// Buffer for the data, at least one element long, in memory the CPU writes through
+fn host_buffer<T, I>(device: &Arc<Device>, data: I, usage: BufferUsage, prefer: MemoryTypeFilter) -> Buffer<T>
+        where T: BufferContents, I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator {
+    let data = data.into_iter();
+    let len = data.len() as u64;
+    let buffer = vulkano::buffer::Buffer::new_slice::<T>(allocators(device).memory.clone(), BufferCreateInfo { usage, ..Default::default() },
+        AllocationCreateInfo { memory_type_filter: prefer | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE, ..Default::default() }, len.max(1))
+        .expect("Failed to create buffer");
+    for (slot, value) in buffer.write().unwrap().iter_mut().zip(data) {
+        *slot = value;
+    }
+    Buffer { buffer, len }
+}
+
+/// Buffer the GPU only reads, filled once from the data; the returned future signals when the upload is done
+pub fn immutable_buffer<T, I>(queue: Arc<Queue>, data: I, usage: BufferUsage) -> (Buffer<T>, Box<dyn GpuFuture>)
+        where T: BufferContents, I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator {
+    let allocators = allocators(queue.device());
+    let staging = host_buffer(queue.device(), data, BufferUsage::TRANSFER_SRC, MemoryTypeFilter::PREFER_HOST);
+    let buffer = vulkano::buffer::Buffer::new_slice::<T>(allocators.memory.clone(), BufferCreateInfo { usage: usage | BufferUsage::TRANSFER_DST, ..Default::default() },
+        AllocationCreateInfo { memory_type_filter: MemoryTypeFilter::PREFER_DEVICE, ..Default::default() }, staging.buffer.len())
+        .expect("Failed to create buffer");
+    let mut builder = AutoCommandBufferBuilder::primary(allocators.commands.clone(), queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit).unwrap();
+    builder.copy_buffer(CopyBufferInfo::buffers(staging.buffer, buffer.clone())).unwrap();
+    let future = builder.build().unwrap().execute(queue).unwrap();
+    (Buffer { buffer, len: staging.len }, future.boxed())
+}
+
+/// Buffers in the GPU's own memory, filled from each list of data by copying it through the staging pool, all in one
+/// submission; the returned future signals when every copy is done. The buffers are always new ones, so a frame still
+/// in flight keeps drawing from the old buffers they replace, which live on until it's done with them
+pub fn device_local_buffers<T: BufferContents>(queue: Arc<Queue>, staging: &BufferPool<T>, data: Vec<Vec<T>>, usage: BufferUsage) -> (Vec<Buffer<T>>, Box<dyn GpuFuture>) {
+    let allocators = allocators(queue.device());
+    let mut builder = AutoCommandBufferBuilder::primary(allocators.commands.clone(), queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit).unwrap();
+    let buffers = data.into_iter().map(|data| {
+        let chunk = staging.chunk(data).expect("Failed to stage buffer");
+        let buffer = vulkano::buffer::Buffer::new_slice::<T>(allocators.memory.clone(), BufferCreateInfo { usage: usage | BufferUsage::TRANSFER_DST, ..Default::default() },
+            AllocationCreateInfo { memory_type_filter: MemoryTypeFilter::PREFER_DEVICE, ..Default::default() }, chunk.buffer.len())
+            .expect("Failed to create buffer");
+        if !chunk.is_empty() {
+            builder.copy_buffer(CopyBufferInfo::buffers(chunk.buffer, buffer.clone())).unwrap();
+        }
+        Buffer { buffer, len: chunk.len }
+    }).collect();
+    let future = builder.build().unwrap().execute(queue).unwrap();
+    (buffers, future.boxed())
+}
+
+/// Buffer the CPU rewrites in place, eg. whenever food is eaten
+pub fn cpu_buffer<T, I>(device: Arc<Device>, data: I, usage: BufferUsage) -> Buffer<T>
+        where T: BufferContents, I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator {
+    host_buffer(&device, data, usage, MemoryTypeFilter::PREFER_DEVICE)
+}
+
+/// Ring of buffers for data that changes every frame, handing out a fresh piece of one each time
+pub struct BufferPool<T> {
+    allocator: SubbufferAllocator,
+    contents: PhantomData<fn() -> T>
+}
+
+impl<T: BufferContents> BufferPool<T> {
+    /// Buffer holding the value
+    pub fn next(&self, data: T) -> Result<Subbuffer<T>, MemoryAllocatorError> {
+        let buffer = self.allocator.allocate_sized()?;
+        *buffer.write().unwrap() = data;
+        Ok (buffer)
+    }
+
+    /// Buffer holding the values
+    pub fn chunk<I>(&self, data: I) -> Result<Buffer<T>, MemoryAllocatorError>
+            where I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator {
+        let data = data.into_iter();
+        let len = data.len() as u64;
+        let buffer = self.allocator.allocate_slice(len.max(1))?;
+        for (slot, value) in buffer.write().unwrap().iter_mut().zip(data) {
+            *slot = value;
+        }
+        Ok (Buffer { buffer, len })
+    }
+}
+
+/// Ring of buffers for data that changes every frame
+pub fn buffer_pool<T>(device: Arc<Device>, usage: BufferUsage) -> BufferPool<T> {
+    let allocator = SubbufferAllocator::new(allocators(&device).memory.clone(), SubbufferAllocatorCreateInfo {
+        arena_size: ARENA_SIZE,
+        buffer_usage: usage,
+        memory_type_filter: MemoryTypeFilter::PREFER_DEVICE | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+        ..Default::default()
+    });
+    BufferPool { allocator, contents: PhantomData }
+}
+
+/// Allocates descriptor sets holding a single uniform buffer, for the first set of the pipeline's layout
+pub struct UniformSets {
+    allocator: Arc<StandardDescriptorSetAllocator>,
+    layout: Arc<DescriptorSetLayout>
+}
+
+impl UniformSets {
+    pub fn new(pipeline: &GraphicsPipeline) -> UniformSets {
+        UniformSets { allocator: allocators(pipeline.device()).descriptor_sets.clone(), layout: pipeline.layout().set_layouts()[0].clone() }
+    }
+
+    pub fn next<T: ?Sized>(&mut self, buffer: Subbuffer<T>) -> Arc<DescriptorSet> {
+        DescriptorSet::new(self.allocator.clone(), self.layout.clone(), [WriteDescriptorSet::buffer(0, buffer)], []).unwrap()
+    }
+}
+
+/// Descriptor set sampling the image, for the first set of the pipeline's layout
+pub fn texture_set(pipeline: &GraphicsPipeline, image: Arc<ImageView>, sampler: Arc<Sampler>) -> Arc<DescriptorSet> {
+    DescriptorSet::new(allocators(pipeline.device()).descriptor_sets.clone(), pipeline.layout().set_layouts()[0].clone(),
+        [WriteDescriptorSet::image_view_sampler(0, image, sampler)], []).unwrap()
+}
+
+/// Sampled 2D array texture, each level holding every layer's pixels in order, largest level first; the returned
+/// future signals when the upload is done
+pub fn array_texture(queue: Arc<Queue>, format: Format, dimensions: [u32; 2], layers: u32, levels: Vec<Vec<u8>>) -> (Arc<Image>, Box<dyn GpuFuture>) {
+    let allocators = allocators(queue.device());
+    let image = Image::new(allocators.memory.clone(), ImageCreateInfo {
+        image_type: ImageType::Dim2d,
+        format,
+        extent: [dimensions[0], dimensions[1], 1],
+        array_layers: layers,
+        mip_levels: levels.len() as u32,
+        usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+        ..Default::default()
+    }, AllocationCreateInfo::default()).expect("Failed to create texture");
+
+    let mut builder = AutoCommandBufferBuilder::primary(allocators.commands.clone(), queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit).unwrap();
+    for (level, pixels) in levels.into_iter().enumerate() {
+        let buffer = host_buffer(queue.device(), pixels, BufferUsage::TRANSFER_SRC, MemoryTypeFilter::PREFER_HOST).buffer;
+        let region = BufferImageCopy {
+            image_subresource: ImageSubresourceLayers { mip_level: level as u32, ..image.subresource_layers() },
+            image_extent: image::mip_level_extent(image.extent(), level as u32).unwrap(),
+            ..Default::default()
+        };
+        builder.copy_buffer_to_image(CopyBufferToImageInfo {
+            regions: [region].into(),
+            ..CopyBufferToImageInfo::buffer_image(buffer, image.clone())
+        }).unwrap();
+    }
+    let future = builder.build().unwrap().execute(queue).unwrap();
+    (image, future.boxed())
+}
+
+/// View of the whole image, for sampling
+pub fn image_view(image: Arc<Image>) -> Arc<ImageView> {
+    ImageView::new_default(image).unwrap()
+}
+
+/// Whether textures in the format can be sampled on this device. Only BCn compression is enabled on it
+pub fn sampleable(device: &Arc<Device>, format: Format) -> bool {
+    let compression = match format.compression() {
+        None => true,
+        Some (CompressionType::BC) => device.enabled_features().texture_compression_bc,
+        Some (_) => false
+    };
+    compression && device.physical_device().format_properties(format)
+        .is_ok_and(|properties| properties.optimal_tiling_features.intersects(FormatFeatures::SAMPLED_IMAGE))
+}
+
+/// Linearly filtered sampler clamped to the edges, with anisotropic filtering unless the anisotropy is 1
+pub fn sampler(device: Arc<Device>, mipmap_mode: MipmapMode, max_lod: f32, anisotropy: f32) -> Arc<Sampler> {
+    Sampler::new(device, SamplerCreateInfo {
+        mag_filter: Filter::Linear,
+        min_filter: Filter::Linear,
+        mipmap_mode,
+        address_mode: [SamplerAddressMode::ClampToEdge; 3],
+        anisotropy: (anisotropy > 1.0).then_some(anisotropy),
+        lod: 0.0..=max_lod,
+        ..Default::default()
+    }).expect("Failed to create texture sampler")
+}
+
+/// Vertex buffers bound together, each a [`Buffer`]
+pub trait VertexBuffers {
+    fn into_bytes(self) -> Vec<Subbuffer<[u8]>>;
+}
+
+impl<T> VertexBuffers for Buffer<T> {
+    fn into_bytes(self) -> Vec<Subbuffer<[u8]>> {
+        vec![self.buffer.into_bytes()]
+    }
+}
+
+impl<A, B> VertexBuffers for (Buffer<A>, Buffer<B>) {
+    fn into_bytes(self) -> Vec<Subbuffer<[u8]>> {
+        vec![self.0.buffer.into_bytes(), self.1.buffer.into_bytes()]
+    }
+}
+
+/// Commands for a frame or part of one. The frame's own are recorded into a primary command buffer, and everything drawn
+/// in the render pass into secondary ones it executes in turn, which can be recorded on any thread. Binding and pushing
+/// only fail when the renderers pass something the pipeline doesn't take, so they panic rather than return errors
+pub struct Commands<L = SecondaryAutoCommandBuffer> {
+    builder: AutoCommandBufferBuilder<L>
+}
+
+/// Commands for a whole frame, which run the compute work and the render pass
+pub type FrameCommands = Commands<PrimaryAutoCommandBuffer>;
+
+impl FrameCommands {
+    pub fn primary(queue: &Arc<Queue>) -> Result<FrameCommands, Validated<VulkanError>> {
+        let allocator = allocators(queue.device()).commands.clone();
+        let builder = AutoCommandBufferBuilder::primary(allocator, queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit)?;
+        Ok (Commands { builder })
+    }
+
+    pub fn begin_render_pass(&mut self, framebuffer: Arc<Framebuffer>, contents: SubpassContents, clear_values: Vec<Option<ClearValue>>)
+            -> Result<&mut Self, Box<ValidationError>> {
+        self.builder.begin_render_pass(
+            RenderPassBeginInfo { clear_values, ..RenderPassBeginInfo::framebuffer(framebuffer) },
+            SubpassBeginInfo { contents, ..Default::default() })?;
+        Ok (self)
+    }
+
+    pub fn end_render_pass(&mut self) -> Result<&mut Self, Box<ValidationError>> {
+        self.builder.end_render_pass(SubpassEndInfo::default())?;
+        Ok (self)
+    }
+
+    /// Run the secondary command buffer inside the render pass
+    pub fn execute_commands(&mut self, commands: Arc<SecondaryAutoCommandBuffer>) -> Result<&mut Self, Box<ValidationError>> {
+        self.builder.execute_commands(commands)?;
+        Ok (self)
+    }
+
+    pub fn bind_pipeline_compute(&mut self, pipeline: Arc<ComputePipeline>) -> &mut Self {
+        self.builder.bind_pipeline_compute(pipeline).expect("Failed to bind pipeline");
+        self
+    }
+
+    /// Run the bound compute pipeline over the given number of work groups
+    pub fn dispatch(&mut self, group_counts: [u32; 3]) -> Result<&mut Self, Box<ValidationError>> {
+        // Safe, since the compute shaders check every invocation against the buffer lengths before touching them
+        unsafe { self.builder.dispatch(group_counts)?; }
+        Ok (self)
+    }
+
+    /// Reset the queries, outside any render pass, so they can be written again
+    pub fn reset_queries(&mut self, pool: Arc<QueryPool>, queries: Range<u32>) -> &mut Self {
+        // Safe, since the queries are only read back once the frame that last wrote them is done
+        unsafe { self.builder.reset_query_pool(pool, queries).expect("Failed to reset queries"); }
+        self
+    }
+
+    /// Start counting into the query, including in any secondary command buffers executed before it ends
+    pub fn begin_query(&mut self, pool: Arc<QueryPool>, query: u32) -> &mut Self {
+        // Safe, since every query is reset before it's begun
+        unsafe { self.builder.begin_query(pool, query, QueryControlFlags::empty()).expect("Failed to begin query"); }
+        self
+    }
+
+    pub fn end_query(&mut self, pool: Arc<QueryPool>, query: u32) -> &mut Self {
+        self.builder.end_query(pool, query).expect("Failed to end query");
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<PrimaryAutoCommandBuffer>, Validated<VulkanError>> {
+        self.builder.build()
+    }
+}
+
+impl Commands {
+    pub fn build(self) -> Result<Arc<SecondaryAutoCommandBuffer>, Validated<VulkanError>> {
+        self.builder.build()
+    }
+}
+
+impl<L> Commands<L> {
+    pub fn set_viewport(&mut self, first_viewport: u32, viewports: impl IntoIterator<Item = Viewport>) -> &mut Self {
+        self.builder.set_viewport(first_viewport, viewports.into_iter().collect()).expect("Failed to set viewport");
+        self
+    }
+
+    pub fn bind_pipeline_graphics(&mut self, pipeline: Arc<GraphicsPipeline>) -> &mut Self {
+        self.builder.bind_pipeline_graphics(pipeline).expect("Failed to bind pipeline");
+        self
+    }
+
+    pub fn bind_descriptor_sets(&mut self, bind_point: PipelineBindPoint, layout: Arc<PipelineLayout>, first_set: u32,
+            descriptor_sets: impl DescriptorSetsCollection) -> &mut Self {
+        self.builder.bind_descriptor_sets(bind_point, layout, first_set, descriptor_sets).expect("Failed to bind descriptor sets");
+        self
+    }
+
+    pub fn push_constants<Pc: BufferContents>(&mut self, layout: Arc<PipelineLayout>, offset: u32, push_constants: Pc) -> &mut Self {
+        self.builder.push_constants(layout, offset, push_constants).expect("Failed to push constants");
+        self
+    }
+
+    pub fn bind_vertex_buffers(&mut self, first_binding: u32, vertex_buffers: impl VertexBuffers) -> &mut Self {
+        self.builder.bind_vertex_buffers(first_binding, vertex_buffers.into_bytes()).expect("Failed to bind vertex buffers");
+        self
+    }
+
+    /// Draw with the bound pipeline and buffers; drawing no vertices or instances does nothing
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32)
+            -> Result<&mut Self, Box<ValidationError>> {
+        if vertex_count > 0 && instance_count > 0 {
+            // Safe, since the shaders only read the buffers and images that are bound, and never out of bounds
+            unsafe { self.builder.draw(vertex_count, instance_count, first_vertex, first_instance)?; }
+        }
+        Ok (self)
+    }
+
+    /// Write the time into the query once every command recorded before has finished
+    pub fn write_timestamp(&mut self, pool: Arc<QueryPool>, query: u32) -> &mut Self {
+        // Safe, since every query is reset before it's written
+        unsafe { self.builder.write_timestamp(pool, query, PipelineStage::BottomOfPipe).expect("Failed to write timestamp"); }
+        self
+    }
+}
+
+/// Starts secondary command buffers drawing into one viewport of the render pass's first subpass. Clones can start
+/// them on other threads at the same time
+#[derive(Clone)]
+pub struct Recorder {
+    queue: Arc<Queue>,
+    subpass: Subpass,
+    viewport: Viewport,
+    statistics: QueryPipelineStatisticFlags // Counted by the query running over the render pass, if any
+}
+
+impl Recorder {
+    pub fn new(queue: Arc<Queue>, render_pass: &Arc<RenderPass>, viewport: Viewport, statistics: QueryPipelineStatisticFlags) -> Recorder {
+        Recorder { queue, subpass: Subpass::from(render_pass.clone(), 0).unwrap(), viewport, statistics }
+    }
+
+    pub fn begin(&self) -> Commands {
+        let allocator = allocators(self.queue.device()).commands.clone();
+        let builder = AutoCommandBufferBuilder::secondary(allocator, self.queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit,
+            CommandBufferInheritanceInfo {
+                render_pass: Some (self.subpass.clone().into()),
+                pipeline_statistics: self.statistics,
+                ..Default::default()
+            }).expect("Failed to create command buffer");
+        let mut commands = Commands { builder };
+        commands.set_viewport(0, [self.viewport.clone()]);
+        commands
+    }
+}
+
+/// Descriptor set binding the buffers, in order, to the first set of the compute pipeline's layout
+pub fn storage_set<T>(pipeline: &ComputePipeline, buffers: impl IntoIterator<Item = Buffer<T>>) -> Arc<DescriptorSet> {
+    let writes = buffers.into_iter().enumerate().map(|(binding, buffer)| WriteDescriptorSet::buffer(binding as u32, buffer.buffer));
+    DescriptorSet::new(allocators(pipeline.device()).descriptor_sets.clone(), pipeline.layout().set_layouts()[0].clone(), writes, []).unwrap()
+}
+
+/// Bits of a timestamp written on the queue that the card fills in, or None if it can't write timestamps there
+pub fn timestamp_bits(queue: &Queue) -> Option<u32> {
+    queue.device().physical_device().queue_family_properties()[queue.queue_family_index() as usize].timestamp_valid_bits
+}
+
+/// Pool of queries that each hold a timestamp
+pub fn timestamp_queries(device: Arc<Device>, count: u32) -> Arc<QueryPool> {
+    QueryPool::new(device, QueryPoolCreateInfo { query_count: count, ..QueryPoolCreateInfo::query_type(QueryType::Timestamp) })
+        .expect("Failed to create query pool")
+}
+
+/// Pool of one query counting the pipeline statistics, which the device must have been created to count
+pub fn statistics_query(device: Arc<Device>, statistics: QueryPipelineStatisticFlags) -> Arc<QueryPool> {
+    QueryPool::new(device, QueryPoolCreateInfo {
+        query_count: 1,
+        pipeline_statistics: statistics,
+        ..QueryPoolCreateInfo::query_type(QueryType::PipelineStatistics)
+    }).expect("Failed to create query pool")
+}
+
+/// Read the results of the queries without waiting, returning whether the GPU was done with them
+pub fn query_results(pool: &QueryPool, queries: Range<u32>, results: &mut [u64]) -> bool {
+    pool.get_results(queries, results, QueryResultFlags::empty()).unwrap_or(false)
+}
+
+/// Load the shader generated by `vulkano_shaders::shader!` in the given module
+macro_rules! load_shader {
+    ($module:ident, $device:expr, $name:expr) => {
+        $crate::gfx::Shader::new($module::load($device).unwrap_or_else(|e| panic!("Failed to load {} shader: {}", $name, e)))
+    };
+}
+pub(crate) use load_shader;
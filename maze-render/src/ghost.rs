@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Device;
+
+use maze_core::error::MazeError;
+use maze_core::ghost::Ghost;
+use maze_core::linalg;
+
+use crate::View;
+use crate::assets::Assets;
+use crate::gfx::{self, BufferPool};
+use crate::pipeline::{InstanceModel, Vertex};
+use crate::scene::{self, Draw, Renderable, Scene};
+
+// Animations the ghosts play before they wake and while they chase, with their keyframes and seconds between them
+const ANIMATIONS: [(&str, usize, f32); 2] = [("ghost_idle", 4, 0.25), ("ghost_chase", 4, 0.1)];
+
+/// Draws the ghosts, idling until they wake and chasing after that, bobbing up and down as they float around.
+/// Each frame's pose is worked out from the animations' keyframes and uploaded fresh
+pub struct GhostRenderer {
+    color: [f32; 3],
+    vertex_buffer_pool: BufferPool<Vertex>,
+    instance_buffer_pool: BufferPool<InstanceModel>
+}
+
+impl GhostRenderer {
+    pub fn new(device: Arc<Device>, color: [f32; 3]) -> GhostRenderer {
+        GhostRenderer {
+            color,
+            vertex_buffer_pool: gfx::buffer_pool(device.clone(), BufferUsage::VERTEX_BUFFER),
+            instance_buffer_pool: gfx::buffer_pool(device, BufferUsage::VERTEX_BUFFER)
+        }
+    }
+
+    /// Load the animations the ghosts are drawn with
+    pub fn load_animations(assets: &mut Assets) -> Result<(), MazeError> {
+        for (name, keyframes, frame_time) in ANIMATIONS {
+            assets.load_animation(name, keyframes, frame_time)?;
+        }
+        Ok (())
+    }
+}
+
+impl Renderable for GhostRenderer {
+    fn draws(&self, view: &View, assets: &Assets, scene: &mut Scene) {
+        // The light glows from just above whichever ghost is nearest the player
+        let light = linalg::add(world_position(view, view.nearest_ghost()), [0.0, 0.0, 1.0]);
+        let lights = scene::lights(view, view.player.cell()[3].max(0) as usize, view.player.get_position()[0..3].try_into().unwrap(), light);
+        for ((name, _, _), chasing) in ANIMATIONS.iter().zip([false, true]) {
+            let positions: Vec<[f32; 3]> = view.ghosts.iter().filter(|ghost| ghost.chasing() == chasing).map(|ghost| world_position(view, ghost)).collect();
+            if positions.is_empty() {
+                continue;
+            }
+            let instances = positions.iter().map(|&[x, y, z]| InstanceModel::new(linalg::translate([x, y, z + view.lift(z)])));
+            scene.add(Draw {
+                mesh: self.vertex_buffer_pool.chunk(assets.animation(name).pose(view.time)).unwrap(),
+                instances: self.instance_buffer_pool.chunk(instances).unwrap(),
+                instance_count: positions.len() as u32,
+                color: self.color,
+                transform: linalg::identity(),
+                lights: Some (lights)
+            });
+        }
+    }
+}
+
+/// Name of the animation the ghost is drawn with, depending on whether it's chasing the player
+pub fn animation(ghost: &Ghost) -> &'static str {
+    ANIMATIONS[ghost.chasing() as usize].0
+}
+
+/// Where a ghost is drawn relative to the player's w-slice, including its bobbing
+pub fn world_position(view: &View, ghost: &Ghost) -> [f32; 3] {
+    let position = ghost.position();
+    let x = position[0] + (position[3] - view.between()) * ((view.world.width + 1) as f32);
+    let z = position[2] + (view.time * 3.0).sin() / 4.0;
+    [x, position[1], z]
+}
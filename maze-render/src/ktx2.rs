@@ -0,0 +1,227 @@
+use std::fs::read;
+
+use basis_universal::{TranscodeParameters, Transcoder, TranscoderTextureFormat};
+use vulkano::format::Format;
+
+const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const HEADER_LEN: usize = 12 + 9 * 4 + 4 * 4 + 2 * 8;
+const LEVEL_INDEX_ENTRY_LEN: usize = 3 * 8;
+
+const SUPERCOMPRESSION_NONE: u32 = 0;
+const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+const MODEL_ETC1S: u8 = 163; // Data format descriptor color models
+const MODEL_UASTC: u8 = 166;
+const TRANSFER_SRGB: u8 = 2;
+const UASTC_RGBA: u8 = 3; // UASTC channel ids with alpha
+const UASTC_RRRG: u8 = 5;
+const ETC1S_GLOBAL_HEADER_LEN: usize = 2 * 2 + 4 * 4;
+const ETC1S_IMAGE_DESC_LEN: usize = 5 * 4;
+
+// Layout of a .basis file, which the transcoder reads
+const BASIS_HEADER_LEN: usize = 77;
+const BASIS_SLICE_DESC_LEN: usize = 23;
+const BASIS_VERSION: u32 = 0x13;
+const BASIS_FLAG_ETC1S: u32 = 1;
+const BASIS_FLAG_ALPHA: u32 = 4;
+const BASIS_FLAG_SRGB: u32 = 16;
+
+/// How a KTX2 file's levels are encoded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    /// Ready to upload in this format
+    Raw (Format),
+    /// Basis Universal, which has to be transcoded into a format the device can sample first
+    Basis { uastc: bool, alpha: bool, srgb: bool }
+}
+
+// A 2D KTX2 texture, uploaded as-is or transcoded from Basis Universal
+pub struct Ktx2 {
+    pub encoding: Encoding,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>, // Largest level first, left empty for Basis Universal textures
+    basis: Vec<u8> // Basis Universal levels rebuilt as a .basis file, the only form the transcoder takes
+}
+
+impl Ktx2 {
+    pub fn load(file: &str) -> Result<Ktx2, String> {
+        let bytes = read(file).map_err(|e| format!("Couldn't read {}: {}", file, e))?;
+        Ktx2::parse(&bytes).map_err(|e| format!("{}: {}", file, e))
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Ktx2, String> {
+        if bytes.len() < HEADER_LEN || bytes[0..12] != IDENTIFIER {
+            return Err("not a KTX2 file".to_string());
+        }
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()) as usize;
+        let range = |offset: usize, length: usize| offset.checked_add(length).and_then(|end| bytes.get(offset..end));
+
+        let vk_format = u32_at(12);
+        let (width, height, depth) = (u32_at(20), u32_at(24), u32_at(28));
+        let (layer_count, face_count, level_count) = (u32_at(32), u32_at(36), u32_at(40));
+        let supercompression = u32_at(44);
+        if width == 0 || height == 0 || depth > 1 || layer_count > 1 || face_count != 1 {
+            return Err("only single 2D images are supported".to_string());
+        }
+        // A level count of 0 asks for mips to be generated, which isn't supported, so only the base level is loaded
+        let level_count = level_count.max(1);
+        if level_count > 32 - width.max(height).leading_zeros() {
+            return Err(format!("{} mip levels is more than a {}x{} image has", level_count, width, height));
+        }
+
+        let levels = (0..level_count as usize).map(|level| {
+            let entry = HEADER_LEN + level * LEVEL_INDEX_ENTRY_LEN;
+            range(entry, LEVEL_INDEX_ENTRY_LEN).ok_or("truncated level index")?;
+            range(u64_at(entry), u64_at(entry + 8)).ok_or("truncated level data")
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        if vk_format != 0 {
+            if supercompression != SUPERCOMPRESSION_NONE {
+                return Err(format!("supercompression scheme {} isn't supported", supercompression));
+            }
+            let format = Format::try_from(ash::vk::Format::from_raw(vk_format as i32))
+                .map_err(|_| format!("unknown vkFormat {}", vk_format))?;
+            let levels = levels.into_iter().map(|level| level.to_vec()).collect();
+            return Ok(Ktx2 { encoding: Encoding::Raw (format), width, height, levels, basis: Vec::new() });
+        }
+
+        // Basis Universal, told apart by the data format descriptor's color model
+        let (dfd_offset, dfd_length) = (u32_at(48) as usize, u32_at(52) as usize);
+        let dfd = range(dfd_offset, dfd_length).filter(|dfd| dfd.len() >= 44).ok_or("truncated data format descriptor")?;
+        let (model, srgb, channel) = (dfd[12], dfd[14] == TRANSFER_SRGB, dfd[31] & 15);
+        let (uastc, alpha, global) = match (model, supercompression) {
+            (MODEL_UASTC, SUPERCOMPRESSION_NONE) => (true, channel == UASTC_RGBA || channel == UASTC_RRRG, None),
+            (MODEL_ETC1S, SUPERCOMPRESSION_BASIS_LZ) => {
+                // Two samples, for the color and alpha slices, make the descriptor 60 bytes long rather than 44
+                let global = range(u64_at(64), u64_at(72)).ok_or("truncated supercompression global data")?;
+                (false, dfd_length == 60, Some (global))
+            },
+            (MODEL_UASTC | MODEL_ETC1S, _) => return Err(format!("supercompression scheme {} isn't supported", supercompression)),
+            _ => return Err(format!("unknown color model {} without a vkFormat", model))
+        };
+        let basis = to_basis(width, height, &levels, global, alpha, srgb)?;
+        Ok(Ktx2 { encoding: Encoding::Basis { uastc, alpha, srgb }, width, height, levels: Vec::new(), basis })
+    }
+
+    /// Every level in the given format, transcoding Basis Universal textures into it
+    pub fn transcode(&self, format: Format) -> Result<Vec<Vec<u8>>, String> {
+        let target = match (self.encoding, format) {
+            (Encoding::Raw (raw), _) if raw == format => return Ok(self.levels.clone()),
+            (Encoding::Raw (raw), _) => return Err(format!("{:?} can't be converted to {:?}", raw, format)),
+            (_, Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK) => TranscoderTextureFormat::BC7_RGBA,
+            (_, Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK) => TranscoderTextureFormat::BC3_RGBA,
+            (_, Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGB_SRGB_BLOCK) => TranscoderTextureFormat::BC1_RGB,
+            (_, Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB) => TranscoderTextureFormat::RGBA32,
+            _ => return Err(format!("Basis Universal can't be transcoded to {:?}", format))
+        };
+        let mut transcoder = Transcoder::new();
+        transcoder.prepare_transcoding(&self.basis).map_err(|_| "corrupt Basis Universal data".to_string())?;
+        let level_count = transcoder.image_level_count(&self.basis, 0);
+        (0..level_count).map(|level_index| {
+            let parameters = TranscodeParameters { image_index: 0, level_index, ..Default::default() };
+            transcoder.transcode_image_level(&self.basis, target, parameters)
+                .map_err(|e| format!("couldn't transcode level {} to {:?}: {:?}", level_index, format, e))
+        }).collect()
+    }
+}
+
+// Rebuild the levels as a .basis file: a header, a description of each slice, ETC1S's codebooks and Huffman tables
+// from the global data, then the slices themselves. ETC1S keeps any alpha in a second slice after each level's color
+fn to_basis(width: u32, height: u32, levels: &[&[u8]], global: Option<&[u8]>, alpha: bool, srgb: bool) -> Result<Vec<u8>, String> {
+    // Each slice as its level, flags and data
+    let mut slices: Vec<(usize, u32, &[u8])> = Vec::new();
+    let mut codebooks: [&[u8]; 3] = [&[], &[], &[]]; // Endpoints, selectors and tables
+    let mut counts = (0, 0);
+    match global {
+        Some (global) => {
+            let field = |offset: usize, length: usize| global.get(offset..offset + length)
+                .map(|bytes| bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as usize))
+                .ok_or("truncated ETC1S global data");
+            counts = (field(0, 2)?, field(2, 2)?);
+            let lengths = [field(4, 4)?, field(8, 4)?, field(12, 4)?];
+            let mut offset = ETC1S_GLOBAL_HEADER_LEN + levels.len() * ETC1S_IMAGE_DESC_LEN;
+            for (codebook, length) in codebooks.iter_mut().zip(lengths) {
+                *codebook = offset.checked_add(length).and_then(|end| global.get(offset..end)).ok_or("truncated ETC1S codebooks")?;
+                offset += length;
+            }
+            for (level, data) in levels.iter().enumerate() {
+                let desc = ETC1S_GLOBAL_HEADER_LEN + level * ETC1S_IMAGE_DESC_LEN;
+                let slice = |offset: usize, length: usize| -> Result<&[u8], String> {
+                    let (offset, length) = (field(desc + offset, 4)?, field(desc + length, 4)?);
+                    offset.checked_add(length).and_then(|end| data.get(offset..end)).ok_or("truncated ETC1S slice".to_string())
+                };
+                slices.push((level, 0, slice(4, 8)?));
+                if alpha {
+                    slices.push((level, 1, slice(12, 16)?));
+                }
+            }
+        },
+        // UASTC keeps alpha in the same blocks, flagged on each slice
+        None => slices.extend(levels.iter().enumerate().map(|(level, data)| (level, alpha as u32, *data)))
+    }
+
+    let codebooks_offset = BASIS_HEADER_LEN + slices.len() * BASIS_SLICE_DESC_LEN;
+    let mut file = vec![0; codebooks_offset];
+    for codebook in codebooks {
+        file.extend_from_slice(codebook);
+    }
+    let mut descs = Vec::new();
+    for (level, flags, data) in &slices {
+        let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+        let desc: [(usize, usize); 10] = [
+            (0, 3), (*level, 1), (*flags as usize, 1), (level_width as usize, 2), (level_height as usize, 2),
+            (level_width.div_ceil(4) as usize, 2), (level_height.div_ceil(4) as usize, 2), (file.len(), 4), (data.len(), 4),
+            (crc16(data) as usize, 2)
+        ];
+        descs.extend(desc);
+        file.extend_from_slice(data);
+    }
+    let mut at = BASIS_HEADER_LEN;
+    for (value, size) in descs {
+        put(&mut file, &mut at, value, size)?;
+    }
+
+    let (etc1s, codebook_lengths) = (global.is_some(), codebooks.map(|codebook| codebook.len()));
+    let flags = if etc1s { BASIS_FLAG_ETC1S } else { 0 } | if alpha { BASIS_FLAG_ALPHA } else { 0 }
+        | if srgb { BASIS_FLAG_SRGB } else { 0 };
+    let offset_if = |length: usize, offset: usize| if length > 0 { offset } else { 0 };
+    let data_crc = crc16(&file[BASIS_HEADER_LEN..]) as usize;
+    let header: [(usize, usize); 26] = [
+        (u16::from_le_bytes(*b"sB") as usize, 2), (BASIS_VERSION as usize, 2), (BASIS_HEADER_LEN, 2), (0, 2),
+        (file.len() - BASIS_HEADER_LEN, 4), (data_crc, 2), (slices.len(), 3), (1, 3), (!etc1s as usize, 1), (flags as usize, 2),
+        (0, 1), (0, 3), (0, 4), (0, 4), (0, 4),
+        (counts.0, 2), (offset_if(codebook_lengths[0], codebooks_offset), 4), (codebook_lengths[0], 3),
+        (counts.1, 2), (offset_if(codebook_lengths[1], codebooks_offset + codebook_lengths[0]), 4), (codebook_lengths[1], 3),
+        (offset_if(codebook_lengths[2], codebooks_offset + codebook_lengths[0] + codebook_lengths[1]), 4), (codebook_lengths[2], 4),
+        (BASIS_HEADER_LEN, 4), (0, 4), (0, 4)
+    ];
+    let mut at = 0;
+    for (value, size) in header {
+        put(&mut file, &mut at, value, size)?;
+    }
+    // The header's own checksum covers everything after it
+    let header_crc = crc16(&file[8..BASIS_HEADER_LEN]) as usize;
+    put(&mut file, &mut 6, header_crc, 2)?;
+    Ok(file)
+}
+
+// Write a little-endian value of the given number of bytes, moving past it
+fn put(file: &mut [u8], at: &mut usize, value: usize, size: usize) -> Result<(), String> {
+    if size < 8 && value >> (size * 8) != 0 {
+        return Err("too large for a .basis file".to_string());
+    }
+    file[*at..*at + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    *at += size;
+    Ok(())
+}
+
+// The CRC-16 .basis files check their header and data against
+fn crc16(bytes: &[u8]) -> u16 {
+    let crc = bytes.iter().fold(!0u16, |crc, &byte| {
+        let q = byte as u16 ^ (crc >> 8);
+        let k = (q >> 4) ^ q;
+        (crc << 8) ^ k ^ (k << 5) ^ (k << 12)
+    });
+    !crc
+}
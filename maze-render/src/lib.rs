@@ -0,0 +1,96 @@
+//! Vulkan renderer for 4D Pacman, drawing the game state simulated by `maze-core`.
+//!
+//! - [`pipeline::compile_shaders`] builds every pipeline the game uses, and [`gfx`] creates the Vulkan objects.
+//! - Each part of the game has a renderer holding its own GPU buffers, eg. [`world::WorldRenderer`] or
+//!   [`ghost::GhostRenderer`], and all of them draw a frame from the same [`View`].
+//! - Anything drawn with the main pipeline implements [`scene::Renderable`] and queues its draws into a
+//!   [`scene::Scene`], which records them all at once on worker threads, into secondary command buffers the frame
+//!   executes in order.
+//! - [`assets::Assets`] loads models and textures.
+//! - [`particles::ParticleSystem`] steps its particles on the GPU each frame before the render pass, then draws them
+//!   over the scene, as [`overlay::SliceOverlayRenderer`] draws the neighbouring w-slices' food and ghosts.
+//! - [`profiler::GpuProfiler`] optionally times the GPU's work on each part of a frame.
+
+use std::cmp::Ordering;
+use std::time::Instant;
+
+use maze_core::config::{LevelTheme, ViewDistance};
+use maze_core::ghost::Ghost;
+use maze_core::objects::Objects;
+use maze_core::player::Player;
+use maze_core::world::World;
+
+use camera::Camera;
+use palette::Palette;
+
+pub mod assets;
+pub mod camera;
+pub mod ghost;
+pub mod gfx;
+pub mod ktx2;
+pub mod model;
+pub mod objects;
+pub mod overlay;
+pub mod palette;
+pub mod parameters;
+pub mod particles;
+pub mod pipeline;
+pub mod player;
+pub mod profiler;
+pub mod replay;
+pub mod scene;
+pub mod sky;
+pub mod texture;
+pub mod ui;
+pub mod world;
+
+/// Game state a frame is drawn from, and the camera it is seen through
+pub struct View<'a> {
+    pub world: &'a World,
+    pub player: &'a Player, // Whoever the camera follows
+    pub rival: Option<[f32; 4]>, // Where the other player in a race is, drawn alongside
+    pub ghosts: &'a [Ghost],
+    pub objects: &'a Objects,
+    pub camera: &'a Camera,
+    pub theme: LevelTheme, // How each level is tinted
+    pub palette: Palette, // Colors the w-slices are drawn in
+    pub distance: ViewDistance, // How much of the maze around the player is drawn
+    pub now: Instant, // When the frame is drawn, on the game's clock
+    pub time: f32 // Seconds since the game started, for animations
+}
+
+impl View<'_> {
+    /// Combined view and projection matrix of the camera
+    pub fn view_projection(&self) -> [[f32; 4]; 4] {
+        maze_core::linalg::mul(self.camera.projection(), self.camera.view())
+    }
+
+    /// W coordinate the view is centred on, easing in and out as the player moves through a portal
+    /// rather than sliding the slices past at a constant speed
+    pub fn between(&self) -> f32 {
+        let w = self.player.get_position()[3];
+        let t = w.fract();
+        w.floor() + t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Ghost closest to the player, which the lighting glows from
+    pub fn nearest_ghost(&self) -> &Ghost {
+        let distance = |ghost: &Ghost| maze_core::linalg::sub(ghost.position(), self.player.get_position()).iter().map(|x| x * x).sum::<f32>();
+        self.ghosts.iter().min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal)).expect("Every game has at least one ghost")
+    }
+
+    /// How far into a move between w-slices the player is, from 0 at either end up to 1 halfway
+    pub fn portal_fade(&self) -> f32 {
+        (self.player.get_position()[3].fract() * std::f32::consts::PI).sin()
+    }
+
+    /// Height added to anything at height z, stacking the levels apart in the dollhouse view, and none otherwise
+    pub fn lift(&self, z: f32) -> f32 {
+        if self.camera.is_dollhouse() { lift(z) } else { 0.0 }
+    }
+}
+
+/// Height the dollhouse view adds to anything at height z, a level's height at a time from the bottom level up
+pub fn lift(z: f32) -> f32 {
+    z * camera::DOLLHOUSE_GAP
+}
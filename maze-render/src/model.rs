@@ -0,0 +1,114 @@
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use log::info;
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Queue;
+use vulkano::sync::GpuFuture;
+
+use maze_core::error::MazeError;
+
+use crate::gfx;
+use crate::pipeline::Vertex;
+
+pub struct Model {
+    pub file: String,
+    pub vertices: gfx::Buffer<Vertex>
+}
+
+impl Model {
+    // Parse a Wavefront .obj file and upload its triangles
+    pub fn new(queue: Arc<Queue>, filename: &str, source: &[u8]) -> Result<(Model, Box<dyn GpuFuture>), MazeError> {
+        let vertices = parse(filename, source)?;
+        info!(target: "render", "Loaded model {}", filename);
+        let (vertices, future) = gfx::immutable_buffer(queue, vertices, BufferUsage::VERTEX_BUFFER);
+        Ok ((Model {
+            file: filename.split('.').next().unwrap().split('/').next_back().unwrap().to_string(),
+            vertices
+        }, future))
+    }
+}
+
+/// Keyframes of a model, each its own .obj file with the same triangles in the same order, played on a loop.
+/// Kept on the CPU, where the pose between two keyframes is worked out each frame
+pub struct Animation {
+    pub name: String,
+    keyframes: Vec<Vec<Vertex>>,
+    frame_time: f32 // Seconds from one keyframe to the next
+}
+
+impl Animation {
+    /// Read each keyframe's .obj file, which all have to have as many triangles as the first
+    pub fn new(name: &str, frames: &[(String, Vec<u8>)], frame_time: f32) -> Result<Animation, MazeError> {
+        let keyframes = frames.iter().map(|(file, source)| parse(file, source)).collect::<Result<Vec<_>, _>>()?;
+        if keyframes.is_empty() {
+            return Err (MazeError::resource(name, "An animation needs at least one keyframe"));
+        }
+        if let Some (((file, _), _)) = frames.iter().zip(&keyframes).find(|(_, keyframe)| keyframe.len() != keyframes[0].len()) {
+            return Err (MazeError::resource(file, format!("Keyframe has a different number of triangles to {}", frames[0].0)));
+        }
+        info!(target: "render", "Loaded animation {} with {} keyframes", name, keyframes.len());
+        Ok (Animation { name: name.to_string(), keyframes, frame_time })
+    }
+
+    /// Pose the given number of seconds into the loop, blended between the keyframes either side
+    pub fn pose(&self, time: f32) -> Vec<Vertex> {
+        let count = self.keyframes.len();
+        let frame = (time / self.frame_time).rem_euclid(count as f32);
+        let (from, to) = (&self.keyframes[frame as usize % count], &self.keyframes[(frame as usize + 1) % count]);
+        let t = frame.fract();
+        let lerp = |a: [f32; 3], b: [f32; 3]| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+        from.iter().zip(to).map(|(a, b)| {
+            let normal = lerp(a.normal, b.normal);
+            let length = normal.iter().map(|f| f * f).sum::<f32>().sqrt().max(f32::EPSILON);
+            Vertex {
+                position: lerp(a.position, b.position),
+                normal: normal.map(|f| f / length),
+                .. *a
+            }
+        }).collect()
+    }
+}
+
+// Triangles of a Wavefront .obj file
+fn parse(filename: &str, source: &[u8]) -> Result<Vec<Vertex>, MazeError> {
+    let mut vertices = Vec::new();
+    let reader = BufReader::new(source);
+    let mut v: Vec<[f32; 3]> = Vec::new();
+    let mut vn: Vec<[f32; 3]> = Vec::new();
+    for (number, res) in reader.lines().enumerate() {
+        let lin = res.map_err(|e| MazeError::io(filename, e))?;
+        let invalid = |reason: &str| MazeError::resource(filename, format!("line {}: {}", number + 1, reason));
+        match lin.get(..2).unwrap_or_default() {
+            "v " => v.push(parse_vector(&lin[2..]).ok_or_else(|| invalid("Expected three floats for vertex"))?),
+            "vn" => vn.push(parse_vector(&lin[3..]).ok_or_else(|| invalid("Expected three floats for normal"))?),
+            "f " => {
+                let face = lin[2..]
+                    .split_ascii_whitespace()
+                    .map(|v| v.split('/').map(|f| f.parse::<usize>().unwrap_or_default())
+                    .collect::<Vec<usize>>())
+                    .collect::<Vec<Vec<usize>>>();
+                if face.len() < 3 {
+                    return Err (invalid("Expected a triangle"));
+                }
+                for corner in &face[..3] {
+                    // Subtract 1 since .OBJ is 1-indexed
+                    let position = corner.first().and_then(|i| v.get(i.wrapping_sub(1))).ok_or_else(|| invalid("Face refers to a missing vertex"))?;
+                    let normal = corner.get(2).and_then(|i| vn.get(i.wrapping_sub(1))).ok_or_else(|| invalid("Face refers to a missing normal"))?;
+                    vertices.push(Vertex {
+                        position: *position,
+                        color: [ 0.0, 0.4, 0.8 ], // TODO uv's
+                        normal: *normal
+                    })
+                }
+            },
+            _ => ()
+        }
+    }
+    Ok (vertices)
+}
+
+fn parse_vector(text: &str) -> Option<[f32; 3]> {
+    let values = text.split_ascii_whitespace().map(|f| f.parse::<f32>().ok()).collect::<Option<Vec<f32>>>()?;
+    values.get(..3)?.try_into().ok()
+}
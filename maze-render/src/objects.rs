@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Queue;
+
+use maze_core::config::ViewDistance;
+use maze_core::linalg;
+use maze_core::objects::{Objects, PowerUp};
+use maze_core::player::Player;
+use maze_core::world::World;
+
+use crate::View;
+use crate::assets::Assets;
+use crate::gfx::{self, BufferPool};
+use crate::pipeline::InstanceModel;
+use crate::scene::{Draw, Renderable, Scene};
+use crate::parameters::{EXIT_COLOR, EXIT_SHUT_GLOW, KEY_COLORS};
+use crate::world::world_transform;
+
+/// GPU buffer of the food within view of the player, refreshed whenever food is eaten, the player changes level, the
+/// view distance changes or the dollhouse view stacks the levels apart.
+/// Food being eaten and the few keys, power-ups, batteries, fruit, teleporter pads and the exit portal are rebuilt every
+/// frame, since they move
+pub struct ObjectsRenderer {
+    food_buffer: gfx::Buffer<InstanceModel>,
+    pickup_buffer_pool: BufferPool<InstanceModel>,
+    buffer_len: u32,
+    drawn_for: Option<(usize, i32, i32, ViewDistance, bool)> // Food count, player z, player w, view distance and dollhouse view the buffer was last filled for
+}
+
+impl ObjectsRenderer {
+    pub fn new(queue: Arc<Queue>, world: &World, objects: &Objects) -> ObjectsRenderer {
+        let food_buffer = gfx::cpu_buffer(
+            queue.device().clone(),
+            objects.food().map(|food| food_model(world, *food, 0.0)).collect::<Vec<_>>(),
+            BufferUsage::VERTEX_BUFFER | BufferUsage::TRANSFER_DST);
+        ObjectsRenderer {
+            food_buffer,
+            pickup_buffer_pool: gfx::buffer_pool(queue.device().clone(), BufferUsage::VERTEX_BUFFER),
+            buffer_len: 0,
+            drawn_for: None
+        }
+    }
+
+    /// Fill the buffer with the food in view, lifted apart level by level if the dollhouse view is on
+    pub fn update(&mut self, world: &World, objects: &Objects, player: &Player, distance: ViewDistance, dollhouse: bool) {
+        let cell = player.cell();
+        let key = (objects.food().count(), cell[2], cell[3], distance, dollhouse);
+        if self.drawn_for == Some (key) {
+            return;
+        }
+        if let Ok (mut access) = self.food_buffer.write() {
+            self.drawn_for = Some (key);
+            let instances: Vec<InstanceModel> = objects.food()
+                .filter(|food| distance.contains(cell, **food))
+                .map(|food| food_model(world, *food, if dollhouse { crate::lift(food.2 as f32) } else { 0.0 }))
+                .collect();
+            self.buffer_len = instances.len() as u32;
+            for i in 0..instances.len() {
+                access[i] = instances[i];
+            }
+        }
+    }
+}
+
+impl Renderable for ObjectsRenderer {
+    // Food bobs up and down in the slices next to the player
+    fn draws(&self, view: &View, assets: &Assets, scene: &mut Scene) {
+        // TODO use own shader pipeline for customizability
+        let x_offset = (-view.between()) * ((view.world.width + 1) as f32);
+        let z_offset = (view.time * 2.0).sin() / 5.0;
+        scene.add(Draw {
+            mesh: assets.model("ceiling").vertices.clone(),
+            instances: self.food_buffer.clone(),
+            instance_count: self.buffer_len,
+            color: view.palette.color(2),
+            transform: linalg::translate([x_offset, 0.0, z_offset]),
+            lights: None
+        });
+
+        let near = |cell: &(usize, usize, usize, usize)| view.distance.contains(view.player.cell(), *cell);
+
+        // Eaten food spins as it shrinks away, while the particle system throws off its sparks
+        for (cell, progress) in view.objects.eaten_food(view.now).filter(|(cell, _)| near(cell)) {
+            scene.add(Draw {
+                mesh: assets.model("ceiling").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk([eaten_model(view.world, *cell, progress)]).unwrap(),
+                instance_count: 1,
+                color: view.palette.color(2),
+                transform: linalg::translate([x_offset, 0.0, z_offset + view.lift(cell.2 as f32)]),
+                lights: None
+            });
+        }
+
+        // Keys spin in place, colored to match their doors
+        for (cell, key) in view.objects.keys().filter(|(cell, _)| near(cell)) {
+            let instance = key_model(view.world, *cell, view.time);
+            scene.add(Draw {
+                mesh: assets.model("corner").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk([instance]).unwrap(),
+                instance_count: 1,
+                color: KEY_COLORS[key % KEY_COLORS.len()],
+                transform: linalg::translate([x_offset, 0.0, view.lift(cell.2 as f32)]),
+                lights: None
+            });
+        }
+
+        // Power-ups tumble, colored by what they do
+        for (cell, power_up) in view.objects.power_ups().filter(|(cell, _)| near(cell)) {
+            let instance = power_up_model(view.world, *cell, view.time);
+            scene.add(Draw {
+                mesh: assets.model("ceiling").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk([instance]).unwrap(),
+                instance_count: 1,
+                color: match power_up {
+                    PowerUp::Boots => view.palette.color(4),
+                    PowerUp::Clock => view.palette.color(5)
+                },
+                transform: linalg::translate([x_offset, 0.0, z_offset + view.lift(cell.2 as f32)]),
+                lights: None
+            });
+        }
+
+        // Bonus fruit is bigger than food, and bobs faster
+        if let Some (cell) = view.objects.fruit().filter(near) {
+            let instance = fruit_model(view.world, cell);
+            scene.add(Draw {
+                mesh: assets.model("ceiling").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk([instance]).unwrap(),
+                instance_count: 1,
+                color: view.palette.color(0),
+                transform: linalg::translate([x_offset, 0.0, (view.time * 5.0).sin() / 5.0 + view.lift(cell.2 as f32)]),
+                lights: None
+            });
+        }
+
+        // Teleporter pads turn on the floor, each pair pulsing in its own color
+        for (pair, (a, b)) in view.world.teleporters.iter().enumerate() {
+            let glow = 0.75 + 0.25 * (view.time * 4.0 + pair as f32).sin();
+            let instances: Vec<InstanceModel> = [a, b].into_iter().filter(|cell| near(cell)).map(|cell| pad_model(view.world, *cell, view.time, view.lift(cell.2 as f32))).collect();
+            if instances.is_empty() {
+                continue;
+            }
+            scene.add(Draw {
+                mesh: assets.model("floor").vertices.clone(),
+                instance_count: instances.len() as u32,
+                instances: self.pickup_buffer_pool.chunk(instances).unwrap(),
+                color: view.palette.color(pair + 4).map(|f| f * glow),
+                transform: linalg::translate([x_offset, 0.0, 0.0]),
+                lights: None
+            });
+        }
+
+        // Batteries stand upright, turning slowly
+        for cell in view.objects.batteries().filter(|cell| near(cell)) {
+            let instance = battery_model(view.world, *cell, view.time);
+            scene.add(Draw {
+                mesh: assets.model("corner").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk([instance]).unwrap(),
+                instance_count: 1,
+                color: view.palette.color(3),
+                transform: linalg::translate([x_offset, 0.0, view.lift(cell.2 as f32)]),
+                lights: None
+            });
+        }
+
+        // The exit is ringed by a pair of portals turning opposite ways, dim until it opens, then pulsing
+        if near(&view.world.exit) {
+            let (glow, pulse) = if view.world.exit_open {
+                (0.8 + 0.2 * (view.time * 6.0).sin(), 1.0 + 0.08 * (view.time * 3.0).sin())
+            } else {
+                (EXIT_SHUT_GLOW, 1.0)
+            };
+            let instances = [(pulse, view.time), (pulse * 0.7, -view.time * 1.5)]
+                .map(|(size, spin)| portal_model(view.world, size, spin));
+            scene.add(Draw {
+                mesh: assets.model("portal").vertices.clone(),
+                instances: self.pickup_buffer_pool.chunk(instances).unwrap(),
+                instance_count: 2,
+                color: EXIT_COLOR.map(|f| f * glow),
+                transform: linalg::translate([x_offset, 0.0, view.lift(view.world.exit.2 as f32)]),
+                lights: None
+            });
+        }
+    }
+}
+
+// Ring standing across the way out of the exit cell, or lying on its floor when the way out is through w
+fn portal_model(world: &World, size: f32, spin: f32) -> InstanceModel {
+    let (x, y, z, w) = world.exit;
+    let [dx, dy, dz, dw] = world.exit_delta;
+    let half = |d: i32| d as f32 / 2.0;
+    let (facing, position) = if dw != 0 {
+        ([90f32.to_radians(), 0.0, 0.0], [x as f32, y as f32, z as f32 + 0.05])
+    } else {
+        let facing = if dx != 0 { [0.0, 0.0, 90f32.to_radians()] } else if dz != 0 { [90f32.to_radians(), 0.0, 0.0] } else { [0.0; 3] };
+        (facing, [x as f32 + half(dx), y as f32 + half(dy), z as f32 + 0.5 + half(dz)])
+    };
+    let model = linalg::mul(linalg::model(facing, [size; 3], position), linalg::rotate([0.0, spin, 0.0]));
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+fn fruit_model(world: &World, (x, y, z, w): (usize, usize, usize, usize)) -> InstanceModel {
+    let model = linalg::model(
+        [90f32.to_radians(), 0.0, 45f32.to_radians()],
+        [0.9, 0.9, 1.0],
+        [x as f32, y as f32, z as f32 + 0.6]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+// Pads of a pair can be on different levels, so each is lifted apart for the dollhouse view by itself
+fn pad_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), time: f32, lift: f32) -> InstanceModel {
+    let model = linalg::model(
+        [90f32.to_radians(), 0.0, time],
+        [0.6, 1.0, 0.6],
+        [x as f32, y as f32, z as f32 + 0.02 + lift]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+fn battery_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), time: f32) -> InstanceModel {
+    let model = linalg::model(
+        [0.0, 0.0, time],
+        [1.2, 1.2, 0.4],
+        [x as f32, y as f32, z as f32 + 0.2]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+fn power_up_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), time: f32) -> InstanceModel {
+    let model = linalg::model(
+        [time * 1.5, 0.0, time * 2.0],
+        [0.7, 0.7, 0.7],
+        [x as f32, y as f32, z as f32 + 0.5]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+fn key_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), time: f32) -> InstanceModel {
+    let model = linalg::model(
+        [90f32.to_radians(), 0.0, time * 2.0],
+        [0.6, 0.6, 0.6],
+        [x as f32, y as f32, z as f32 + 0.3]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+// Food partway through being eaten, shrinking to nothing while it spins and lifts
+fn eaten_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), progress: f32) -> InstanceModel {
+    let size = 1.0 - progress;
+    let model = linalg::model(
+        [90f32.to_radians(), 0.0, 45f32.to_radians() + progress * 720f32.to_radians()],
+        [0.5 * size, 0.5 * size, size],
+        [x as f32, y as f32, z as f32 + 0.6 + progress * 0.3]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
+
+/// Model of a piece of food in the given cell. All the food in view shares one buffer, so each is lifted apart for the
+/// dollhouse view by itself
+pub fn food_model(world: &World, (x, y, z, w): (usize, usize, usize, usize), lift: f32) -> InstanceModel {
+    let model = linalg::model(
+        [90f32.to_radians(), 0.0, 45f32.to_radians()],
+        [0.5, 0.5, 1.0],
+        [x as f32, y as f32, z as f32 + 0.6 + lift]);
+    InstanceModel::new(linalg::mul(world_transform(world, w, 0.0), model))
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Device;
+use vulkano::pipeline::Pipeline as _;
+
+use maze_core::linalg;
+
+use crate::View;
+use crate::assets::Assets;
+use crate::ghost;
+use crate::gfx::{self, BufferPool, Commands};
+use crate::objects::food_model;
+use crate::parameters::{SLICE_OVERLAY_ALPHA, SLICE_OVERLAY_SATURATION};
+use crate::pipeline::{InstanceModel, Pipeline, Vertex};
+use crate::pipeline::evs::EchoData;
+
+/// Draws faint copies of the food and ghosts in the w-slices either side of the player's over the player's own slice,
+/// where they'd be after going through a portal, rather than only off to the side with the rest of their slice
+pub struct SliceOverlayRenderer {
+    vertex_buffer_pool: BufferPool<Vertex>,
+    instance_buffer_pool: BufferPool<InstanceModel>
+}
+
+impl SliceOverlayRenderer {
+    pub fn new(device: Arc<Device>) -> SliceOverlayRenderer {
+        SliceOverlayRenderer {
+            vertex_buffer_pool: gfx::buffer_pool(device.clone(), BufferUsage::VERTEX_BUFFER),
+            instance_buffer_pool: gfx::buffer_pool(device, BufferUsage::VERTEX_BUFFER)
+        }
+    }
+
+    /// Draw the copies, washed out towards grey in the colors of the slices they're from. They blend over the scene
+    /// with the echo's pipeline, so need drawing after everything else. Ghosts are left out when they aren't drawn
+    pub fn render(&self, view: &View, assets: &Assets, ghosts: bool, builder: &mut Commands, pipeline: &Pipeline) {
+        let [_, _, level, own] = view.player.cell();
+        let (low, high) = view.distance.levels(level, view.world.depth);
+        // Everything is laid out as if in the player's slice, which is drawn this far along
+        let x_offset = (own as f32 - view.between()) * ((view.world.width + 1) as f32);
+        builder.bind_pipeline_graphics(pipeline.echo_pipeline.clone());
+        for w in [own - 1, own + 1].into_iter().filter(|w| *w >= 0 && *w < view.world.fourth as i32) {
+            let color = faded(view.palette.slice(w));
+            // Bobbing along with the food in view
+            let food: Vec<InstanceModel> = view.objects.food()
+                .filter(|&&(_, _, z, fourth)| fourth == w as usize && z >= low && z <= high)
+                .map(|&(x, y, z, _)| food_model(view.world, (x, y, z, 0), view.lift(z as f32)))
+                .collect();
+            if !food.is_empty() {
+                let mesh = assets.model("ceiling").vertices.clone();
+                let vp = linalg::mul(view.view_projection(), linalg::translate([x_offset, 0.0, (view.time * 2.0).sin() / 5.0]));
+                let instance_count = food.len() as u32;
+                builder
+                    .push_constants(pipeline.echo_pipeline.layout().clone(), 0, EchoData { vp, color })
+                    .bind_vertex_buffers(0, (mesh.clone(), self.instance_buffer_pool.chunk(food).unwrap()))
+                    .draw(mesh.len() as u32, instance_count, 0, 0).unwrap();
+            }
+            if !ghosts {
+                continue;
+            }
+            for ghost in view.ghosts.iter().filter(|ghost| ghost.cell()[3] == w as usize && (low..=high).contains(&ghost.cell()[2])) {
+                let [x, y, z, _] = ghost.position();
+                let z = z + (view.time * 3.0).sin() / 4.0;
+                let pose = self.vertex_buffer_pool.chunk(assets.animation(ghost::animation(ghost)).pose(view.time)).unwrap();
+                let vertex_count = pose.len() as u32;
+                let instance = self.instance_buffer_pool.chunk([
+                    InstanceModel::new(linalg::translate([x + x_offset, y, z + view.lift(z)]))
+                ]).unwrap();
+                builder
+                    .push_constants(pipeline.echo_pipeline.layout().clone(), 0, EchoData { vp: view.view_projection(), color })
+                    .bind_vertex_buffers(0, (pose, instance))
+                    .draw(vertex_count, 1, 0, 0).unwrap();
+            }
+        }
+    }
+}
+
+// The slice's color washed out towards grey, at the overlay's opacity
+fn faded(color: [f32; 3]) -> [f32; 4] {
+    let grey = (color[0] + color[1] + color[2]) / 3.0;
+    let [r, g, b] = color.map(|c| grey + (c - grey) * SLICE_OVERLAY_SATURATION);
+    [r, g, b, SLICE_OVERLAY_ALPHA]
+}
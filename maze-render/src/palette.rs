@@ -0,0 +1,50 @@
+use maze_core::config::{self, Config};
+
+use crate::parameters::{COLORBLIND, HIGH_CONTRAST, RAINBOW};
+
+/// Colors the w-slices, and everything colored after them, are drawn in, and whether slices are also marked in ways
+/// that don't rely on color
+#[derive(Clone, Copy)]
+pub struct Palette {
+    colors: [[f32; 3]; 6],
+    pub marks: bool // Floors striped in a pattern of their own in each slice, and the player's slice numbered on screen
+}
+
+impl Palette {
+    /// Palette chosen in the config
+    pub fn new(config: &Config) -> Palette {
+        let colors = match config.palette {
+            config::Palette::Rainbow => RAINBOW,
+            config::Palette::Colorblind => COLORBLIND,
+            config::Palette::HighContrast => HIGH_CONTRAST
+        };
+        Palette { colors, marks: config.slice_marks }
+    }
+
+    /// Color of w-slice w, cycling through the palette past its last color
+    pub fn slice(&self, w: i32) -> [f32; 3] {
+        self.colors[w.rem_euclid(self.colors.len() as i32) as usize]
+    }
+
+    /// Color i of the palette, for things colored after the slices without belonging to any one of them
+    pub fn color(&self, i: usize) -> [f32; 3] {
+        self.colors[i % self.colors.len()]
+    }
+
+    /// Color part way between two w-slices, blending smoothly from one to the next
+    pub fn between(&self, w: f32) -> [f32; 3] {
+        let (from, to) = (self.slice(w.floor() as i32), self.slice(w.ceil() as i32));
+        [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * w.fract())
+    }
+
+    /// Every color in the palette, in order
+    pub fn colors(&self) -> [[f32; 3]; 6] {
+        self.colors
+    }
+
+    /// Stripes the shader marks floors in w-slice w with, or 0 for none. Each of the first four slices has its stripes
+    /// at a different angle, and the next four repeat those angles more finely
+    pub fn pattern(&self, w: usize) -> u32 {
+        if self.marks { w as u32 + 1 } else { 0 }
+    }
+}
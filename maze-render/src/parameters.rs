@@ -0,0 +1,125 @@
+use crate::particles::Emitter;
+
+pub const RAINBOW: [[f32; 3]; 6] = [
+    [ 1.000, 0.427, 0.416 ],
+    [ 0.937, 0.745, 0.490 ],
+    [ 0.914, 0.925, 0.420 ],
+    [ 0.467, 0.867, 0.467 ],
+    [ 0.545, 0.827, 0.902 ],
+    [ 0.694, 0.635, 0.792 ]
+];
+
+// Okabe-Ito colors, alternating warm and cool so neighbouring slices differ in lightness as well as hue
+pub const COLORBLIND: [[f32; 3]; 6] = [
+    [ 0.902, 0.624, 0.000 ],
+    [ 0.337, 0.706, 0.914 ],
+    [ 0.941, 0.894, 0.259 ],
+    [ 0.000, 0.447, 0.698 ],
+    [ 0.835, 0.369, 0.000 ],
+    [ 0.800, 0.475, 0.655 ]
+];
+
+pub const HIGH_CONTRAST: [[f32; 3]; 6] = [
+    [ 1.000, 1.000, 1.000 ],
+    [ 1.000, 0.100, 0.300 ],
+    [ 1.000, 0.900, 0.000 ],
+    [ 0.100, 0.400, 1.000 ],
+    [ 0.000, 1.000, 0.600 ],
+    [ 0.800, 0.200, 1.000 ]
+];
+
+// Doors and the keys opening them share a color, cycling through these
+pub const KEY_COLORS: [[f32; 3]; 4] = [
+    [ 0.95, 0.75, 0.15 ],
+    [ 0.75, 0.75, 0.80 ],
+    [ 0.70, 0.40, 0.20 ],
+    [ 0.30, 0.90, 0.85 ]
+];
+
+// Radius and intensity of the light around the player, with the flashlight off and on
+pub const PLAYER_LIGHT: [f32; 2] = [1.0, 0.65];
+pub const FLASHLIGHT: [f32; 2] = [2.5, 0.9];
+
+// Extra radius and intensity at the peak of the flash after teleporting
+pub const TELEPORT_FLASH: [f32; 2] = [3.0, 1.0];
+
+// Strongest wash towards the next slice's color, halfway through a portal
+pub const PORTAL_FADE: f32 = 0.35;
+
+// Degrees the field of view widens by while moving between cells
+pub const FOV_WIDEN: f32 = 8.0;
+
+// Degrees the field of view narrows by with the ghost right on top of the player, and the distance in cells it starts from
+pub const FOV_NARROW: [f32; 2] = [12.0, 4.0];
+
+// How hard the camera shakes, from 0 to 1, with the ghost within a cell and on losing a life
+pub const GHOST_SHAKE: f32 = 0.4;
+pub const HIT_SHAKE: f32 = 1.0;
+
+// On being caught for the last time: real seconds of slow motion before the defeat screen, the speed the game runs at
+// meanwhile, how far the camera drifts from the player towards the ghost, and degrees the field of view narrows by
+pub const DEATH_TIME: f32 = 1.0;
+pub const DEATH_SPEED: f32 = 0.25;
+pub const DEATH_ZOOM: [f32; 2] = [0.6, 20.0];
+
+// Radius and intensity of the light glowing from food, the portals between w-slices and the open exit
+pub const FOOD_GLOW: [f32; 2] = [0.5, 0.6];
+pub const PORTAL_GLOW: [f32; 2] = [0.7, 0.8];
+pub const EXIT_GLOW: [f32; 2] = [1.2, 1.0];
+
+// How far around the player walls in front of them fade out, in half screen heights
+pub const SEE_THROUGH_RADIUS: f32 = 0.35;
+
+// Light filling every level before its tint, where nothing else reaches
+pub const AMBIENT: f32 = 0.02;
+
+// Color and opacity of the echo retracing the fastest escape
+pub const ECHO_COLOR: [f32; 4] = [0.6, 0.8, 1.0, 0.35];
+
+// How much of their slice's color the copies of neighbouring slices' food and ghosts keep, and their opacity
+pub const SLICE_OVERLAY_SATURATION: f32 = 0.3;
+pub const SLICE_OVERLAY_ALPHA: f32 = 0.2;
+
+// Ring round the exit, and how bright it glows while the exit is still shut
+pub const EXIT_COLOR: [f32; 3] = [0.55, 1.0, 0.75];
+pub const EXIT_SHUT_GLOW: f32 = 0.25;
+
+// The other player in a split-screen race, so they stand out from the rainbow of w-slices
+pub const RIVAL_COLOR: [f32; 3] = [0.95, 0.95, 0.95];
+
+// Spikes, snares and pits
+pub const TRAP_COLORS: [[f32; 3]; 3] = [
+    [ 0.85, 0.15, 0.15 ],
+    [ 0.95, 0.55, 0.10 ],
+    [ 0.02, 0.02, 0.02 ]
+];
+
+// Props decorating the maze: cobwebs, pillars, and runes near the exit, which glow the exit's color and pulse
+// this much brighter and dimmer
+pub const COBWEB_COLOR: [f32; 3] = [0.85, 0.85, 0.8];
+pub const PILLAR_COLOR: [f32; 3] = [0.45, 0.42, 0.4];
+pub const RUNE_PULSE: f32 = 0.3;
+
+// Sparks thrown off by food as it's eaten
+pub const FOOD_BURST: Emitter = Emitter {
+    rate: 16.0, radius: 0.05, speed: [0.8, 1.6], spread: 1.2, gravity: 4.9, drag: 0.5, life: [0.3, 0.5], size: 0.08,
+    color: [0.914, 0.925, 0.420, 1.0], twinkle: 0.0
+};
+
+// Glitter drifting up off the ring round the open exit
+pub const PORTAL_SPARKLE: Emitter = Emitter {
+    rate: 30.0, radius: 0.4, speed: [0.1, 0.4], spread: std::f32::consts::PI, gravity: -0.3, drag: 1.0, life: [0.6, 1.2], size: 0.05,
+    color: [0.55, 1.0, 0.75, 0.9], twinkle: 20.0
+};
+
+// Wisps the ghosts leave behind as they float around
+pub const GHOST_TRAIL: Emitter = Emitter {
+    rate: 20.0, radius: 0.15, speed: [0.0, 0.1], spread: std::f32::consts::PI, gravity: -0.2, drag: 2.0, life: [0.4, 0.8], size: 0.1,
+    color: [0.9, 0.9, 1.0, 0.4], twinkle: 0.0
+};
+
+// Fireworks going off at the exit as the player escapes, once in each color of the rainbow
+pub const WIN_CELEBRATION: Emitter = Emitter {
+    rate: 40.0, radius: 0.1, speed: [1.5, 3.5], spread: 0.9, gravity: 3.0, drag: 0.8, life: [0.8, 1.6], size: 0.07,
+    color: [1.0, 1.0, 1.0, 1.0], twinkle: 15.0
+};
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use vulkano::ValidationError;
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Device;
+use vulkano::pipeline::{Pipeline as _, PipelineBindPoint};
+
+use maze_core::config::Config;
+use maze_core::game::Game;
+use maze_core::linalg;
+use maze_core::objects::EAT_TIME;
+
+use crate::View;
+use crate::gfx::{self, Buffer, BufferPool, Commands, FrameCommands};
+use crate::palette::Palette;
+use crate::parameters::{FOOD_BURST, GHOST_TRAIL, PORTAL_SPARKLE, WIN_CELEBRATION};
+use crate::pipeline::{Particle, Pipeline};
+use crate::pipeline::partcs::ParticleStep;
+use crate::pipeline::partvs::BillboardData;
+
+const CAPACITY: u32 = 4096; // Particles alive at once, past which the oldest make way for new ones
+const GROUP_SIZE: u32 = 256; // Slots each compute work group steps, matching the shader's local size
+
+/// How an emitter throws out its particles
+#[derive(Debug, Clone, Copy)]
+pub struct Emitter {
+    pub rate: f32, // Particles in a burst, or each second in a stream
+    pub radius: f32, // Cells from the emitter each can start out
+    pub speed: [f32; 2], // Slowest and fastest one is thrown, in cells a second
+    pub spread: f32, // Radians off straight up one can be thrown, up to PI for every way
+    pub gravity: f32, // Cells a second squared pulling them down, or up when negative
+    pub drag: f32, // Fraction of their speed lost each second
+    pub life: [f32; 2], // Shortest and longest seconds one lasts
+    pub size: f32, // Width in cells, shrinking to nothing over its life
+    pub color: [f32; 4],
+    pub twinkle: f32 // How fast it flickers, or 0 to glow steadily
+}
+
+/// Particles thrown off by things happening in the game: food being eaten, the open exit, the ghosts and the player
+/// escaping. New particles are made on the CPU, then a compute shader moves them all on every frame, and they're
+/// drawn as billboards straight from the same buffer.
+///
+/// Positions are laid out with every w-slice side by side from w = 0, the way the world is, and shifted over to the
+/// slice being looked at when drawn
+pub struct ParticleSystem {
+    particles: Buffer<Particle>,
+    spawn_pool: BufferPool<Particle>,
+    spawns: Vec<Particle>, // Made since the last step, waiting to go into the buffer
+    head: u32, // Slot the next new particle goes in, going round the buffer
+    seed: u32, // State of the random numbers spreading particles out
+    escaping: bool // Whether the player was on their way out last update
+}
+
+impl ParticleSystem {
+    pub fn new(device: Arc<Device>) -> ParticleSystem {
+        let usage = BufferUsage::STORAGE_BUFFER | BufferUsage::VERTEX_BUFFER;
+        ParticleSystem {
+            particles: gfx::cpu_buffer(device.clone(), (0..CAPACITY).map(|_| Particle::default()), usage),
+            spawn_pool: gfx::buffer_pool(device, BufferUsage::STORAGE_BUFFER),
+            spawns: Vec::new(),
+            head: 0,
+            seed: 0x9e37_79b9,
+            escaping: false
+        }
+    }
+
+    /// Throw out a burst of particles all at once
+    pub fn burst(&mut self, emitter: &Emitter, at: [f32; 3]) {
+        for _ in 0..emitter.rate.round() as usize {
+            self.spawn(emitter, at);
+        }
+    }
+
+    /// Keep particles coming for the given number of seconds, on average as many as the emitter's rate each second
+    pub fn stream(&mut self, emitter: &Emitter, at: [f32; 3], seconds: f32) {
+        let count = (emitter.rate * seconds + self.random()).floor() as usize;
+        for _ in 0..count {
+            self.spawn(emitter, at);
+        }
+    }
+
+    /// Set off the emitters for whatever happened in the game over the last few seconds
+    pub fn update(&mut self, game: &Game, config: &Config, now: Instant, seconds: f32) {
+        let world = &game.world;
+        let spacing = (world.width + 1) as f32;
+        let place = |[x, y, z, w]: [f32; 4]| [x + w * spacing, y, z];
+        for (&(x, y, z, w), progress) in game.objects.eaten_food(now) {
+            if progress * EAT_TIME < seconds {
+                self.burst(&FOOD_BURST, place([x as f32, y as f32, z as f32 + 0.6, w as f32]));
+            }
+        }
+        let (x, y, z, w) = world.exit;
+        let [dx, dy, dz, _] = world.exit_delta.map(|i| i as f32 / 2.0);
+        let exit = place([x as f32 + dx, y as f32 + dy, z as f32 + 0.5 + dz, w as f32]);
+        if world.exit_open {
+            self.stream(&PORTAL_SPARKLE, exit, seconds);
+        }
+        if config.mode.has_ghost() {
+            for ghost in &game.ghosts {
+                let [x, y, z, w] = ghost.position();
+                self.stream(&GHOST_TRAIL, place([x, y, z + 0.6, w]), seconds);
+            }
+        }
+        // Fireworks in every color of the palette as the player flies out
+        let escaping = game.player.escaping();
+        if escaping && !self.escaping {
+            for color in Palette::new(config).colors() {
+                let [r, g, b] = color;
+                self.burst(&Emitter { color: [r, g, b, 1.0], ..WIN_CELEBRATION }, exit);
+            }
+        }
+        self.escaping = escaping;
+    }
+
+    /// Move every particle on by the given number of seconds, taking in the new ones. Recorded outside the render pass
+    pub fn step(&mut self, seconds: f32, builder: &mut FrameCommands, pipeline: &Pipeline) -> Result<(), Box<ValidationError>> {
+        let spawned = self.spawns.len().min(CAPACITY as usize) as u32;
+        let spawns: Vec<Particle> = self.spawns.drain(..).take(CAPACITY as usize).collect();
+        let spawn_buffer = self.spawn_pool.chunk(spawns).unwrap();
+        let compute = &pipeline.particle_compute_pipeline;
+        let descriptor_set = gfx::storage_set(compute, [self.particles.clone(), spawn_buffer]);
+        builder
+            .bind_pipeline_compute(compute.clone())
+            .bind_descriptor_sets(PipelineBindPoint::Compute, compute.layout().clone(), 0, descriptor_set)
+            .push_constants(compute.layout().clone(), 0, ParticleStep { dt: seconds, head: self.head, spawned, capacity: CAPACITY })
+            .dispatch([CAPACITY.div_ceil(GROUP_SIZE), 1, 1])?;
+        self.head = (self.head + spawned) % CAPACITY;
+        Ok (())
+    }
+
+    /// Draw every particle as a quad turned to face the camera
+    pub fn render(&self, view: &View, builder: &mut Commands, pipeline: &Pipeline) {
+        // Particles are thrown off around the player, so they're lifted along with the player's level
+        let shift = linalg::translate([-view.between() * (view.world.width + 1) as f32, 0.0, view.lift(view.player.cell()[2] as f32)]);
+        let camera = view.camera.view();
+        let axis = |row: usize| {
+            let [x, y, z] = [camera[0][row], camera[1][row], camera[2][row]];
+            let length = (x * x + y * y + z * z).sqrt().max(f32::EPSILON);
+            [x / length, y / length, z / length, 0.0]
+        };
+        let billboard = BillboardData { vp: linalg::mul(view.view_projection(), shift), right: axis(0), up: axis(1), time: view.time };
+        builder
+            .bind_pipeline_graphics(pipeline.particle_pipeline.clone())
+            .push_constants(pipeline.particle_pipeline.layout().clone(), 0, billboard)
+            .bind_vertex_buffers(0, self.particles.clone())
+            .draw(6, CAPACITY, 0, 0).unwrap();
+    }
+
+    // Queue one particle thrown out somewhere in the emitter's radius, in a random direction within its spread
+    fn spawn(&mut self, emitter: &Emitter, at: [f32; 3]) {
+        let mut around = || {
+            let (tilt, turn) = (self.random() * emitter.spread, self.random() * std::f32::consts::TAU);
+            [tilt.sin() * turn.cos(), tilt.sin() * turn.sin(), tilt.cos()]
+        };
+        let offset = around().map(|f| f * emitter.radius);
+        let direction = around();
+        let speed = emitter.speed[0] + (emitter.speed[1] - emitter.speed[0]) * self.random();
+        let life = emitter.life[0] + (emitter.life[1] - emitter.life[0]) * self.random();
+        self.spawns.push(Particle {
+            position: linalg::add(at, offset),
+            age: 0.0,
+            velocity: direction.map(|f| f * speed),
+            life,
+            color: emitter.color,
+            size: emitter.size,
+            gravity: emitter.gravity,
+            drag: emitter.drag,
+            twinkle: emitter.twinkle
+        });
+    }
+
+    // Next random number from 0 up to 1, from a xorshift generator since nothing needs to be repeatable
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+}
@@ -0,0 +1,685 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferContents;
+use vulkano::device::Device;
+use vulkano::swapchain::Swapchain;
+use vulkano::pipeline::{ComputePipeline, GraphicsPipeline};
+use vulkano::pipeline::cache::PipelineCache;
+use vulkano::pipeline::graphics::vertex_input;
+use vulkano::render_pass::RenderPass;
+
+use maze_core::linalg;
+
+use crate::gfx::{self, load_shader, PipelineOptions, VertexInput};
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in vec3 color;
+        layout(location = 2) in vec3 normal;
+        layout(location = 3) in mat4 m;
+        layout(location = 7) in mat3 n; // Normal matrix of m
+        layout(push_constant) uniform ViewProjectionData {
+            mat4 vp;
+            vec3 pushColor;
+        } vpd;
+        layout(set = 0, binding = 0) uniform PlayerPositionData {
+            vec3 player_pos;
+            vec3 ghost_pos;
+            float light_radius;
+            float light_intensity;
+            vec4 portal; // Color of the slice being moved into, and how far to fade towards it
+            vec4 theme; // Tint of the level being drawn, and its ambient light
+            vec4 glows[8]; // Position of each glowing object nearby, and how far its light reaches
+            vec4 glow_colors[8]; // Color of each, and how bright
+            uint glow_count;
+            vec4 xray; // Where the player is in normalized device coordinates, and how far around them walls fade, in half screen heights
+            float aspect;
+            uint pattern; // Stripes marking the w-slice on floors: 0 for none, or the slice plus 1
+        } ppd;
+        layout(location = 0) out vec3 passPosition;
+        layout(location = 1) out vec3 passColor;
+        layout(location = 2) out vec3 passNormal;
+        layout(location = 3) out vec3 playerVec;
+        layout(location = 4) out vec3 ghostVec;
+        layout(location = 5) flat out float lightRadius;
+        layout(location = 6) flat out float lightIntensity;
+        layout(location = 7) flat out vec4 portal;
+        layout(location = 8) flat out vec4 theme;
+        layout(location = 9) out vec4 clip;
+        invariant gl_Position; // Matches the depth pre-pass exactly
+        void main() {
+            vec4 worldPosition = m * vec4(position, 1.0);
+            gl_Position = vpd.vp * worldPosition;
+            clip = gl_Position;
+            passPosition = worldPosition.xyz;
+            passColor = vpd.pushColor;
+            passNormal = normalize(n * normal);
+            playerVec = ppd.player_pos - worldPosition.xyz;
+            ghostVec = ppd.ghost_pos - worldPosition.xyz;
+            lightRadius = ppd.light_radius;
+            lightIntensity = ppd.light_intensity;
+            portal = ppd.portal;
+            theme = ppd.theme;
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in vec3 color;
+        layout(location = 2) in vec3 normal;
+        layout(location = 3) in vec3 playerVec;
+        layout(location = 4) in vec3 ghostVec;
+        layout(location = 5) flat in float lightRadius;
+        layout(location = 6) flat in float lightIntensity;
+        layout(location = 7) flat in vec4 portal;
+        layout(location = 8) flat in vec4 theme;
+        layout(set = 0, binding = 0) uniform PlayerPositionData {
+            vec3 player_pos;
+            vec3 ghost_pos;
+            float light_radius;
+            float light_intensity;
+            vec4 portal;
+            vec4 theme;
+            vec4 glows[8];
+            vec4 glow_colors[8];
+            uint glow_count;
+            vec4 xray;
+            float aspect;
+            uint pattern;
+        } ppd;
+        layout(location = 9) in vec4 clip;
+        layout(location = 0) out vec4 f_color;
+
+        // Falls off with distance, reaching full brightness within the radius
+        float point_light(vec3 light_pos, float radius) {
+            float distance2 = length(light_pos);
+            distance2 *= distance2;
+            return clamp((radius * radius / distance2) * clamp(dot(normal, normalize(light_pos)), 0.0, 1.0), 0.0, 1.0);
+        }
+
+        // Darkens alternate stripes across anything facing up, turned a different way in each w-slice and finer past
+        // the fourth, so slices can be told apart without their colors
+        float stripes() {
+            if (ppd.pattern == 0 || normal.z < 0.7) {
+                return 1.0;
+            }
+            uint slice = ppd.pattern - 1;
+            float angle = float(slice % 4) * 0.785398;
+            float along = dot(position.xy, vec2(cos(angle), sin(angle))) * float(2 + 2 * (slice / 4));
+            return fract(along) < 0.5 ? 0.6 : 1.0;
+        }
+
+        void main() {
+            vec3 directional_light = normalize(vec3(1, -2, 3));
+            float directional = 0.33 * clamp(dot(normal, -directional_light), 0.0, 1.0);
+            float point = clamp(lightIntensity * point_light(playerVec, lightRadius) + 0.65 * point_light(ghostVec, 1.0), 0.0, 1.0);
+            // Portals and food nearby cast their own colors
+            vec3 glow = vec3(0.0);
+            for (uint i = 0; i < ppd.glow_count; i++) {
+                glow += ppd.glow_colors[i].rgb * ppd.glow_colors[i].a * point_light(ppd.glows[i].xyz - position, ppd.glows[i].w);
+            }
+            // The level's tint colors the light filling it, but not the light glowing from the player, ghost and objects
+            vec3 light = theme.rgb * (theme.a + directional) + point + glow;
+            float brightness = dot(light, vec3(0.299, 0.587, 0.114));
+            // Wash towards the next slice's color while passing through a portal, splitting the channels slightly apart
+            vec3 wash = portal.rgb * vec3(1.0 + 0.3 * portal.a, 1.0, 1.0 - 0.3 * portal.a);
+            // Fade out anything in front of the player within a circle around them on screen, when blended
+            vec3 ndc = clip.xyz / clip.w;
+            float from_player = length((ndc.xy - ppd.xray.xy) * vec2(ppd.aspect, 1.0)) / max(ppd.xray.w, 0.0001);
+            float alpha = ndc.z < ppd.xray.z ? mix(0.2, 1.0, smoothstep(0.6, 1.0, from_player)) : 1.0;
+            f_color = vec4(mix(color * stripes() * light, wash * (0.4 + 0.6 * brightness), portal.a), alpha);
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        src: "
+        #version 450
+        layout(local_size_x = 256) in;
+        struct Rectangle {
+            vec3 position;
+            vec3 color;
+            float width;
+            float height;
+            float depth;
+        };
+        struct Vertex {
+            vec3 position;
+            vec3 color;
+            vec3 normal;
+        };
+        layout(push_constant) uniform SourceLength {
+            uint len;
+        } sl;
+        layout(set = 0, binding = 0) readonly buffer SourceBuffer {
+            Rectangle data[];
+        } src;
+        layout(set = 0, binding = 1) buffer DestBuffer {
+            Vertex data[];
+        } dst;
+        void main() {
+            // Called once per rectangular prism
+            uint i = gl_GlobalInvocationID.x;
+            Rectangle wall = src.data[i];
+            uint per = 36;
+            if (i < sl.len) {
+                // Bottom
+                dst.data[i * per +  0].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per +  1].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per +  2].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per +  3].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per +  4].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per +  5].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, 0.0);
+                
+                // Top
+                dst.data[i * per +  6].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per +  7].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per +  8].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per +  9].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per + 10].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per + 11].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, wall.depth);
+
+                // Front
+                dst.data[i * per + 12].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per + 13].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 14].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 15].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 16].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per + 17].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, wall.depth);
+
+                // Back
+                dst.data[i * per + 18].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per + 19].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per + 20].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per + 21].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per + 22].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per + 23].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, wall.depth);
+
+                // Right
+                dst.data[i * per + 24].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per + 25].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per + 26].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 27].position = wall.position + vec3(wall.width / 2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 28].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per + 29].position = wall.position + vec3(wall.width / 2.0, wall.height / 2.0, wall.depth);
+
+                // Left
+                dst.data[i * per + 30].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, wall.depth);
+                dst.data[i * per + 31].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, wall.depth);
+                dst.data[i * per + 32].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 33].position = wall.position + vec3(wall.width / -2.0, wall.height / -2.0, 0.0);
+                dst.data[i * per + 34].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, 0.0);
+                dst.data[i * per + 35].position = wall.position + vec3(wall.width / -2.0, wall.height / 2.0, wall.depth);
+            }
+            for (int j = 0; j < 12; j++) {
+                dst.data[i * per + j].color = wall.color;
+                dst.data[i * per + j].normal = vec3(0.0, 0.0, 1.0);;
+            }
+            for (int j = 12; j < 18; j++) { // -y
+                dst.data[i * per + j].color = wall.color;
+                dst.data[i * per + j].normal = vec3(0.0, -1.0, 0.0);;
+            }
+            for (int j = 18; j < 24; j++) { // +y
+                dst.data[i * per + j].color = wall.color;
+                dst.data[i * per + j].normal = vec3(0.0, 1.0, 0.0);;
+            }
+            for (int j = 24; j < 30; j++) { // +x
+                dst.data[i * per + j].color = wall.color;
+                dst.data[i * per + j].normal = vec3(1.0, 0.0, 0.0);;
+            }
+            for (int j = 30; j < 36; j++) { // -x
+                dst.data[i * per + j].color = wall.color;
+                dst.data[i * per + j].normal = vec3(-1.0, 0.0, 0.0);;
+            }
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod lvs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in vec3 color;
+        layout(push_constant) uniform LineData {
+            mat4 vp;
+        } ld;
+        layout(location = 0) out vec3 passColor;
+        void main() {
+            gl_Position = ld.vp * vec4(position, 1.0);
+            passColor = color;
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+// Depth-only pre-pass over the same meshes and instances as the main vertex shader, placing each vertex the same way
+pub mod dvs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 3) in mat4 m;
+        layout(push_constant) uniform ViewProjectionData {
+            mat4 vp;
+            vec3 pushColor;
+        } vpd;
+        invariant gl_Position;
+        void main() {
+            gl_Position = vpd.vp * (m * vec4(position, 1.0));
+        }
+        "
+    }
+}
+
+pub mod dfs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        void main() {
+        }
+        "
+    }
+}
+
+pub mod lfs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 passColor;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            f_color = vec4(passColor, 1.0);
+        }
+        "
+    }
+}
+
+pub mod pvs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in vec3 color;
+        layout(location = 2) in vec3 normal;
+        layout(location = 3) in mat4 m;
+        layout(push_constant) uniform PortalData {
+            mat4 vp;
+            vec3 color;
+            float time;
+        } pd;
+        layout(location = 0) out vec2 passLocal;
+        void main() {
+            gl_Position = pd.vp * m * vec4(position, 1.0);
+            passLocal = position.xz / 0.25; // Portal quad spans -0.25 to 0.25
+        }
+        "
+    }
+}
+
+pub mod pfs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec2 passLocal;
+        layout(push_constant) uniform PortalData {
+            mat4 vp;
+            vec3 color;
+            float time;
+        } pd;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            float radius = length(passLocal);
+            float angle = atan(passLocal.y, passLocal.x);
+            // Spiral arms turning over time, with a ripple travelling inwards
+            float swirl = 0.5 + 0.5 * sin(3.0 * angle + 8.0 * radius - 2.5 * pd.time);
+            float ripple = 0.5 + 0.5 * sin(20.0 * radius + 4.0 * pd.time);
+            float glow = mix(0.4, 1.3, swirl) * mix(0.85, 1.15, ripple);
+            vec3 rim = pd.color * smoothstep(0.7, 1.0, radius) * 0.6;
+            f_color = vec4(clamp(pd.color * glow + rim, 0.0, 1.0), 1.0);
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod evs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in vec3 color;
+        layout(location = 2) in vec3 normal;
+        layout(location = 3) in mat4 m;
+        layout(push_constant) uniform EchoData {
+            mat4 vp;
+            vec4 color;
+        } ed;
+        layout(location = 0) out vec4 passColor;
+        void main() {
+            gl_Position = ed.vp * m * vec4(position, 1.0);
+            passColor = ed.color;
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod efs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec4 passColor;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            f_color = passColor;
+        }
+        "
+    }
+}
+
+pub mod skyvs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) out vec2 passScreen;
+        void main() {
+            // Single triangle covering the whole screen
+            vec2 screen = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+            gl_Position = vec4(screen, 0.0, 1.0);
+            passScreen = screen;
+        }
+        "
+    }
+}
+
+pub mod skyfs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec2 passScreen;
+        layout(push_constant) uniform SkyData {
+            mat4 ray;
+            vec3 horizon;
+        } sd;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            vec3 direction = normalize((sd.ray * vec4(passScreen, -1.0, 0.0)).xyz);
+            // Bright band around the horizon, fading to a dim tint above and darkness below
+            float height = direction.z;
+            vec3 above = mix(sd.horizon * 0.5, sd.horizon * 0.2, sqrt(max(height, 0.0)));
+            vec3 below = mix(sd.horizon * 0.5, sd.horizon * 0.02, sqrt(max(-height, 0.0)));
+            f_color = vec4(height > 0.0 ? above : below, 1.0);
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod partcs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        src: "
+        #version 450
+        layout(local_size_x = 256) in;
+        struct Particle {
+            vec3 position;
+            float age;
+            vec3 velocity;
+            float life;
+            vec4 color;
+            float size;
+            float gravity;
+            float drag;
+            float twinkle;
+        };
+        layout(push_constant) uniform ParticleStep {
+            float dt;
+            uint head; // Slot the first particle spawned this frame goes in
+            uint spawned;
+            uint capacity;
+        } ps;
+        layout(set = 0, binding = 0) buffer Particles {
+            Particle data[];
+        } particles;
+        layout(set = 0, binding = 1) readonly buffer Spawns {
+            Particle data[];
+        } spawns;
+        void main() {
+            // Called once per slot, which either takes a new particle or moves its old one on
+            uint i = gl_GlobalInvocationID.x;
+            if (i >= ps.capacity) {
+                return;
+            }
+            uint fresh = (i + ps.capacity - ps.head) % ps.capacity;
+            if (fresh < ps.spawned) {
+                particles.data[i] = spawns.data[fresh];
+                return;
+            }
+            Particle p = particles.data[i];
+            if (p.age >= p.life) {
+                return;
+            }
+            p.age += ps.dt;
+            p.velocity.z -= p.gravity * ps.dt;
+            p.velocity *= max(1.0 - p.drag * ps.dt, 0.0);
+            p.position += p.velocity * ps.dt;
+            particles.data[i] = p;
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod partvs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec3 position;
+        layout(location = 1) in float age;
+        layout(location = 2) in float life;
+        layout(location = 3) in vec4 color;
+        layout(location = 4) in float size;
+        layout(location = 5) in float twinkle;
+        layout(push_constant) uniform BillboardData {
+            mat4 vp;
+            vec4 right; // Camera's right and up in the world, which each quad is laid along to face it
+            vec4 up;
+            float time;
+        } bd;
+        layout(location = 0) out vec2 passCorner;
+        layout(location = 1) out vec4 passColor;
+        const vec2 corners[6] = vec2[](vec2(-1, -1), vec2(1, -1), vec2(1, 1), vec2(1, 1), vec2(-1, 1), vec2(-1, -1));
+        void main() {
+            // Two triangles per particle, shrinking to nothing over its life so dead ones vanish
+            vec2 corner = corners[gl_VertexIndex];
+            float left = clamp(1.0 - age / max(life, 0.0001), 0.0, 1.0);
+            vec3 world = position + (bd.right.xyz * corner.x + bd.up.xyz * corner.y) * size * left / 2.0;
+            gl_Position = bd.vp * vec4(world, 1.0);
+            passCorner = corner;
+            float sparkle = twinkle > 0.0 ? 0.7 + 0.3 * sin(bd.time * twinkle + float(gl_InstanceIndex)) : 1.0;
+            passColor = vec4(color.rgb * sparkle, color.a * left);
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod partfs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec2 passCorner;
+        layout(location = 1) in vec4 passColor;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            // Soft round dot rather than a square
+            float fade = 1.0 - smoothstep(0.4, 1.0, length(passCorner));
+            if (fade <= 0.0) {
+                discard;
+            }
+            f_color = vec4(passColor.rgb, passColor.a * fade);
+        }
+        "
+    }
+}
+
+/// Vertex of a model, laid out like the compute shader's
+#[derive(BufferContents, vertex_input::Vertex, Default, Clone, Copy, PartialEq, Debug)]
+#[repr(C)]
+pub struct Vertex {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub color: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub normal: [f32; 3]
+}
+
+/// Particle as the particle compute shader moves it and the particle vertex shader draws it, one per instance
+#[derive(BufferContents, vertex_input::Vertex, Default, Clone, Copy, PartialEq, Debug)]
+#[repr(C)]
+pub struct Particle {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32_SFLOAT)]
+    pub age: f32,
+    #[format(R32G32B32_SFLOAT)]
+    pub velocity: [f32; 3],
+    #[format(R32_SFLOAT)]
+    pub life: f32,
+    #[format(R32G32B32A32_SFLOAT)]
+    pub color: [f32; 4],
+    #[format(R32_SFLOAT)]
+    pub size: f32,
+    #[format(R32_SFLOAT)]
+    pub gravity: f32,
+    #[format(R32_SFLOAT)]
+    pub drag: f32,
+    #[format(R32_SFLOAT)]
+    pub twinkle: f32
+}
+
+#[derive(BufferContents, vertex_input::Vertex, Default, Clone, Copy)]
+#[repr(C)]
+pub struct InstanceModel {
+    #[format(R32G32B32A32_SFLOAT)]
+    pub m: [[f32; 4]; 4],
+    #[format(R32G32B32_SFLOAT)]
+    pub n: [[f32; 3]; 3] // Turns the model's normals, even when m scales it unevenly
+}
+
+impl InstanceModel {
+    pub fn new(m: [[f32; 4]; 4]) -> InstanceModel {
+        InstanceModel { m, n: linalg::normal_matrix(m) }
+    }
+}
+
+// Unlit colored vertex for debug/overlay lines
+#[derive(BufferContents, vertex_input::Vertex, Default, Clone, Copy)]
+#[repr(C)]
+pub struct LineVertex {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub color: [f32; 3]
+}
+
+pub struct Pipeline {
+    pub render_pass: Arc<RenderPass>,
+    pub cache: Arc<PipelineCache>, // Kept for pipelines built later, like the UI's
+    pub graphics_pipeline: Arc<GraphicsPipeline>,
+    pub depth_pipeline: Option<Arc<GraphicsPipeline>>, // Lays down depth for the graphics pipeline, if there's a pre-pass
+    pub see_through_pipeline: Arc<GraphicsPipeline>,
+    pub line_pipeline: Arc<GraphicsPipeline>,
+    pub sky_pipeline: Arc<GraphicsPipeline>,
+    pub portal_pipeline: Arc<GraphicsPipeline>,
+    pub echo_pipeline: Arc<GraphicsPipeline>,
+    pub particle_pipeline: Arc<GraphicsPipeline>,
+    pub compute_pipeline: Arc<ComputePipeline>,
+    pub particle_compute_pipeline: Arc<ComputePipeline>
+}
+
+pub fn compile_shaders<T: vertex_input::Vertex>(
+        device: Arc<Device>,
+        cache: Arc<PipelineCache>,
+        swapchain: &Swapchain,
+        samples: u32,
+        depth_prepass: bool) -> Pipeline {
+    let vertex_shader = load_shader!(vs, device.clone(), "vertex");
+    let fragment_shader = load_shader!(fs, device.clone(), "fragment");
+    let compute_shader = load_shader!(cs, device.clone(), "compute");
+    let line_vertex_shader = load_shader!(lvs, device.clone(), "line vertex");
+    let line_fragment_shader = load_shader!(lfs, device.clone(), "line fragment");
+    let portal_vertex_shader = load_shader!(pvs, device.clone(), "portal vertex");
+    let portal_fragment_shader = load_shader!(pfs, device.clone(), "portal fragment");
+    let echo_vertex_shader = load_shader!(evs, device.clone(), "echo vertex");
+    let echo_fragment_shader = load_shader!(efs, device.clone(), "echo fragment");
+    let sky_vertex_shader = load_shader!(skyvs, device.clone(), "sky vertex");
+    let sky_fragment_shader = load_shader!(skyfs, device.clone(), "sky fragment");
+    let depth_vertex_shader = load_shader!(dvs, device.clone(), "depth vertex");
+    let depth_fragment_shader = load_shader!(dfs, device.clone(), "depth fragment");
+    let particle_compute_shader = load_shader!(partcs, device.clone(), "particle compute");
+    let particle_vertex_shader = load_shader!(partvs, device.clone(), "particle vertex");
+    let particle_fragment_shader = load_shader!(partfs, device.clone(), "particle fragment");
+
+    let render_pass = gfx::render_pass(device.clone(), swapchain.image_format(), samples);
+
+    let instanced = || VertexInput::new().vertex::<Vertex>().instance::<InstanceModel>();
+    let graphics_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), instanced(),
+        vertex_shader.main_entry_point(), fragment_shader.main_entry_point(), PipelineOptions { prepassed: depth_prepass, ..Default::default() });
+    // Writes only depth, ahead of the graphics pipeline, so each pixel it covers is shaded once
+    let depth_pipeline = depth_prepass.then(|| gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), instanced(),
+        depth_vertex_shader.main_entry_point(), depth_fragment_shader.main_entry_point(), PipelineOptions { color: false, ..Default::default() }));
+    // The same, but blended over the rest so walls can fade out in front of the player
+    let see_through_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), instanced(),
+        vertex_shader.main_entry_point(), fragment_shader.main_entry_point(), PipelineOptions { alpha_blending: true, ..Default::default() });
+    let line_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), VertexInput::new().vertex::<LineVertex>(),
+        line_vertex_shader.main_entry_point(), line_fragment_shader.main_entry_point(), PipelineOptions { lines: true, ..Default::default() });
+    let portal_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), instanced(),
+        portal_vertex_shader.main_entry_point(), portal_fragment_shader.main_entry_point(), PipelineOptions::default());
+    // Blended over everything else, so it's drawn last
+    let echo_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), instanced(),
+        echo_vertex_shader.main_entry_point(), echo_fragment_shader.main_entry_point(), PipelineOptions { alpha_blending: true, ..Default::default() });
+    // Billboards blended over the scene, with every vertex made up from the particle it belongs to
+    let particle_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), VertexInput::new().instance::<Particle>(),
+        particle_vertex_shader.main_entry_point(), particle_fragment_shader.main_entry_point(), PipelineOptions { alpha_blending: true, ..Default::default() });
+    // Drawn before anything else, so it neither tests nor writes depth
+    let sky_pipeline = gfx::graphics_pipeline(device.clone(), render_pass.clone(), cache.clone(), VertexInput::new(),
+        sky_vertex_shader.main_entry_point(), sky_fragment_shader.main_entry_point(), PipelineOptions { depth: false, ..Default::default() });
+
+    let compute_pipeline = gfx::compute_pipeline(device.clone(), cache.clone(), compute_shader.main_entry_point());
+    let particle_compute_pipeline = gfx::compute_pipeline(device.clone(), cache.clone(), particle_compute_shader.main_entry_point());
+
+    Pipeline {render_pass, cache, graphics_pipeline, depth_pipeline, see_through_pipeline, line_pipeline, sky_pipeline, portal_pipeline, echo_pipeline, particle_pipeline, compute_pipeline, particle_compute_pipeline}
+}
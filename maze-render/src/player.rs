@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Queue;
+use vulkano::pipeline::Pipeline as _;
+use vulkano::sync::GpuFuture;
+
+use maze_core::linalg;
+use maze_core::replay::Replay;
+
+use crate::View;
+use crate::assets::Assets;
+use crate::gfx::{self, BufferPool, Commands};
+use crate::ghost;
+use crate::parameters::{ECHO_COLOR, RIVAL_COLOR};
+use crate::pipeline::{InstanceModel, Pipeline, Vertex};
+use crate::pipeline::evs::EchoData;
+use crate::scene::{self, Draw, Renderable, Scene};
+
+/// GPU buffers for drawing the player
+pub struct PlayerRenderer {
+    vertex_buffer: gfx::Buffer<Vertex>,
+    instance_buffer_pool: BufferPool<InstanceModel>
+}
+
+impl PlayerRenderer {
+    pub fn new(queue: Arc<Queue>) -> (PlayerRenderer, Box<dyn GpuFuture>) {
+        let device = queue.device().clone();
+        let (vertex_buffer, future) = gfx::immutable_buffer(queue, player_buffer(), BufferUsage::VERTEX_BUFFER);
+        let renderer = PlayerRenderer {
+            vertex_buffer,
+            instance_buffer_pool: gfx::buffer_pool(device, BufferUsage::VERTEX_BUFFER)
+        };
+        (renderer, future)
+    }
+}
+
+impl Renderable for PlayerRenderer {
+    fn draws(&self, view: &View, _assets: &Assets, scene: &mut Scene) {
+        let position: [f32; 3] = view.player.get_position()[0..3].try_into().unwrap();
+        let instance_buffer = self.instance_buffer_pool.chunk([
+            InstanceModel::new(linalg::model([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], position))
+        ]).unwrap();
+        scene.add(Draw {
+            mesh: self.vertex_buffer.clone(),
+            instances: instance_buffer,
+            instance_count: 1,
+            color: view.palette.slice(view.player.cell()[3]),
+            transform: linalg::translate([0.0, 0.0, view.lift(position[2])]),
+            lights: Some (scene::lights(view, view.player.cell()[3].max(0) as usize, linalg::add(position, [0.0, 0.0, 0.8]), ghost::world_position(view, view.nearest_ghost())))
+        });
+        // The other racer, laid out beside the player's w-slice like the ghost
+        if let Some (position) = view.rival {
+            let x = position[0] + (position[3] - view.between()) * ((view.world.width + 1) as f32);
+            let instance_buffer = self.instance_buffer_pool.chunk([
+                InstanceModel::new(linalg::translate([x, position[1], position[2]]))
+            ]).unwrap();
+            scene.add(Draw {
+                mesh: self.vertex_buffer.clone(),
+                instances: instance_buffer,
+                instance_count: 1,
+                color: RIVAL_COLOR,
+                transform: linalg::translate([0.0, 0.0, view.lift(position[2])]),
+                lights: None
+            });
+        }
+    }
+}
+
+/// GPU buffers for drawing a see-through echo of an earlier run, retracing it alongside the player
+pub struct EchoRenderer {
+    vertex_buffer: gfx::Buffer<Vertex>,
+    instance_buffer_pool: BufferPool<InstanceModel>
+}
+
+impl EchoRenderer {
+    pub fn new(queue: Arc<Queue>) -> (EchoRenderer, Box<dyn GpuFuture>) {
+        let device = queue.device().clone();
+        let (vertex_buffer, future) = gfx::immutable_buffer(queue, player_buffer(), BufferUsage::VERTEX_BUFFER);
+        let renderer = EchoRenderer {
+            vertex_buffer,
+            instance_buffer_pool: gfx::buffer_pool(device, BufferUsage::VERTEX_BUFFER)
+        };
+        (renderer, future)
+    }
+
+    /// Draw the echo where the replay had got to this many seconds in. It blends over the scene with its own
+    /// pipeline, so needs drawing after everything else
+    pub fn render(&self, replay: &Replay, seconds: f32, view: &View, builder: &mut Commands, pipeline: &Pipeline) {
+        let position = replay.position(seconds);
+        // Laid out beside the player's w-slice like the ghost
+        let x = position[0] + (position[3] - view.between()) * ((view.world.width + 1) as f32);
+        let instance_buffer = self.instance_buffer_pool.chunk([
+            InstanceModel::new(linalg::translate([x, position[1], position[2] + view.lift(position[2])]))
+        ]).unwrap();
+        builder
+            .bind_pipeline_graphics(pipeline.echo_pipeline.clone())
+            .push_constants(pipeline.echo_pipeline.layout().clone(), 0, EchoData { vp: view.view_projection(), color: ECHO_COLOR })
+            .bind_vertex_buffers(0, (self.vertex_buffer.clone(), instance_buffer))
+            .draw(self.vertex_buffer.len() as u32, 1, 0, 0).unwrap();
+    }
+}
+
+fn player_buffer() -> Vec<Vertex> {
+    const PLAYER_COLOR: [f32; 3] = [ 0.2, 0.2, 0.8 ];
+    const HALF_SIZE: f32 = 0.2;
+    let (x, y) = (0.0, 0.0);
+    [
+        Vertex { position: [ x + HALF_SIZE, y + HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] },
+        Vertex { position: [ x + HALF_SIZE, y - HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] },
+        Vertex { position: [ x - HALF_SIZE, y - HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] },
+        Vertex { position: [ x - HALF_SIZE, y - HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] },
+        Vertex { position: [ x - HALF_SIZE, y + HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] },
+        Vertex { position: [ x + HALF_SIZE, y + HALF_SIZE, 0.5 ], color: PLAYER_COLOR, normal: [0.0, 0.0, 1.0] }
+    ].to_vec()
+}
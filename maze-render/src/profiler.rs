@@ -0,0 +1,200 @@
+//! GPU time spent on each part of a frame, and counts of the work its draws did, read back from Vulkan queries.
+//!
+//! Each frame starts with [`GpuProfiler::begin`] before anything else is recorded, and each part of it ends with
+//! [`GpuProfiler::mark`], which charges that part with the GPU time since the last mark. A part marked more than once
+//! in a frame, like the world on each half of a split-screen race, adds up. Results are read back a few frames later,
+//! once the GPU is done with them, so the CPU never waits on a query.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::info;
+use vulkano::device::{Device, Queue};
+use vulkano::query::{QueryPipelineStatisticFlags, QueryPool};
+
+use crate::gfx::{self, Commands, FrameCommands};
+
+/// Parts of a frame timed separately, by name
+pub const STAGES: [&str; 3] = ["particles", "world", "ui"];
+pub const PARTICLES: usize = 0;
+pub const WORLD: usize = 1;
+pub const UI: usize = 2;
+
+// Pipeline statistics counted over the render pass, by name
+const STATISTICS: [&str; 4] = ["primitives", "vertex shader runs", "primitives after clipping", "fragment shader runs"];
+const FRAMES: usize = 4; // Frames queried at once, more than are ever in flight, so results are in when a frame's queries come round again
+const MARKS: u32 = 16; // Most timestamps written in a frame, past which marks are ignored
+const REPORT_TIME: f32 = 5.0; // Seconds between logging the averages
+
+/// Queries timing the parts of each frame, and averages of their results
+pub struct GpuProfiler {
+    frames: Vec<FrameQueries>,
+    current: usize, // Frame being recorded
+    period: f64, // Nanoseconds each timestamp tick lasts
+    mask: u64, // Bits of a timestamp the card fills in
+    recent: Totals, // Since averages were last logged
+    overall: Totals, // Since the start, for the report at the end
+    logged: Instant
+}
+
+// Queries written during one frame
+struct FrameQueries {
+    timestamps: Arc<QueryPool>,
+    statistics: Option<Arc<QueryPool>>, // If the card counts them
+    stages: Vec<usize>, // Part each timestamp after the first ends, in order
+    recorded: bool // Whether the queries were written since their results were last read
+}
+
+// Times and counts added up over a number of frames
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    frames: u64,
+    nanoseconds: [f64; STAGES.len()],
+    counted: u64, // Frames with statistics
+    statistics: [u64; STATISTICS.len()]
+}
+
+impl GpuProfiler {
+    /// Profiler for frames drawn on the queue, or None if its queue family can't write timestamps. Pipeline
+    /// statistics are counted too if the device was created with the feature for them
+    pub fn new(device: Arc<Device>, queue: &Queue) -> Option<GpuProfiler> {
+        let bits = gfx::timestamp_bits(queue)?;
+        // Draws are recorded in secondary command buffers, which can only be counted if they inherit the query
+        let counting = device.enabled_features().pipeline_statistics_query && device.enabled_features().inherited_queries;
+        let flags = counted();
+        let frames = (0..FRAMES).map(|_| FrameQueries {
+            timestamps: gfx::timestamp_queries(device.clone(), MARKS),
+            statistics: counting.then(|| gfx::statistics_query(device.clone(), flags)),
+            stages: Vec::new(),
+            recorded: false
+        }).collect();
+        Some (GpuProfiler {
+            frames,
+            current: 0,
+            period: device.physical_device().properties().timestamp_period as f64,
+            mask: if bits >= 64 { u64::MAX } else { (1 << bits) - 1 },
+            recent: Totals::default(),
+            overall: Totals::default(),
+            logged: Instant::now()
+        })
+    }
+
+    /// Start timing a new frame, outside any render pass, first reading back the results of the last frame that used
+    /// the same queries
+    pub fn begin(&mut self, builder: &mut FrameCommands) {
+        self.current = (self.current + 1) % FRAMES;
+        self.collect();
+        let frame = &mut self.frames[self.current];
+        builder.reset_queries(frame.timestamps.clone(), 0..MARKS);
+        if let Some (statistics) = &frame.statistics {
+            builder.reset_queries(statistics.clone(), 0..1);
+        }
+        builder.write_timestamp(frame.timestamps.clone(), 0);
+        frame.stages.clear();
+        frame.recorded = true;
+    }
+
+    /// Charge the part of the frame, one of the stage constants, with the GPU time since the last mark. Marks can be
+    /// made in the primary command buffer or any secondary one executed from it, as long as they're made in order
+    pub fn mark<L>(&mut self, builder: &mut Commands<L>, stage: usize) {
+        let frame = &mut self.frames[self.current];
+        let query = frame.stages.len() as u32 + 1;
+        if !frame.recorded || query >= MARKS {
+            return;
+        }
+        builder.write_timestamp(frame.timestamps.clone(), query);
+        frame.stages.push(stage);
+    }
+
+    /// Start counting the work of the draws recorded from here on, just before the render pass
+    pub fn start_counting(&mut self, builder: &mut FrameCommands) {
+        if let Some (statistics) = &self.frames[self.current].statistics {
+            builder.begin_query(statistics.clone(), 0);
+        }
+    }
+
+    /// Stop counting, once the render pass ends
+    pub fn stop_counting(&mut self, builder: &mut FrameCommands) {
+        if let Some (statistics) = &self.frames[self.current].statistics {
+            builder.end_query(statistics.clone(), 0);
+        }
+    }
+
+    /// Statistics the secondary command buffers drawing inside the render pass have to inherit, to be counted
+    pub fn inherited(&self) -> QueryPipelineStatisticFlags {
+        if self.frames[0].statistics.is_some() { counted() } else { QueryPipelineStatisticFlags::empty() }
+    }
+
+    /// Averages per frame over the whole run so far
+    pub fn report(&self) -> String {
+        summary(&self.overall)
+    }
+
+    /// Average milliseconds of GPU time per frame on each of the stages, over the whole run so far
+    pub fn averages(&self) -> [f64; STAGES.len()] {
+        self.overall.nanoseconds.map(|nanoseconds| nanoseconds / self.overall.frames.max(1) as f64 / 1e6)
+    }
+
+    // Add the results of the current frame's queries from the last time they were used, if the GPU is done with them,
+    // and log the averages every so often
+    fn collect(&mut self) {
+        let frame = &mut self.frames[self.current];
+        if !frame.recorded {
+            return;
+        }
+        frame.recorded = false;
+        let mut ticks = vec![0u64; frame.stages.len() + 1];
+        let ready = gfx::query_results(&frame.timestamps, 0..ticks.len() as u32, &mut ticks);
+        if !ready {
+            return;
+        }
+        let mut nanoseconds = [0.0; STAGES.len()];
+        for (i, stage) in frame.stages.iter().enumerate() {
+            nanoseconds[*stage] += (ticks[i + 1].wrapping_sub(ticks[i]) & self.mask) as f64 * self.period;
+        }
+        let mut counts = [0u64; STATISTICS.len()];
+        let counted = frame.statistics.as_ref().is_some_and(|statistics| gfx::query_results(statistics, 0..1, &mut counts));
+        for totals in [&mut self.recent, &mut self.overall] {
+            totals.frames += 1;
+            for (total, time) in totals.nanoseconds.iter_mut().zip(nanoseconds) {
+                *total += time;
+            }
+            if counted {
+                totals.counted += 1;
+                for (total, count) in totals.statistics.iter_mut().zip(counts) {
+                    *total += count;
+                }
+            }
+        }
+        if self.logged.elapsed().as_secs_f32() >= REPORT_TIME {
+            info!(target: "render", "{}", summary(&self.recent).replace('\n', "; "));
+            self.recent = Totals::default();
+            self.logged = Instant::now();
+        }
+    }
+}
+
+// Pipeline statistics counted, in the order of STATISTICS
+fn counted() -> QueryPipelineStatisticFlags {
+    QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES
+        | QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+        | QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+        | QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS
+}
+
+// Average GPU time per frame on each part, and the average counts of work done, one line each
+fn summary(totals: &Totals) -> String {
+    let frames = totals.frames.max(1) as f64;
+    let times: Vec<String> = STAGES.iter().zip(totals.nanoseconds)
+        .map(|(stage, nanoseconds)| format!("{} {:.3}ms", stage, nanoseconds / frames / 1e6))
+        .collect();
+    let total: f64 = totals.nanoseconds.iter().sum();
+    let mut summary = format!("GPU time per frame over {} frames: {}, {:.3}ms in all", totals.frames, times.join(", "), total / frames / 1e6);
+    if totals.counted > 0 {
+        let counts: Vec<String> = STATISTICS.iter().zip(totals.statistics)
+            .map(|(name, count)| format!("{} {}", count / totals.counted, name))
+            .collect();
+        summary += &format!("\nWork per frame: {}", counts.join(", "));
+    }
+    summary
+}
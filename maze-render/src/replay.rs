@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Device;
+use vulkano::pipeline::Pipeline as _;
+
+use maze_core::replay::Flythrough;
+
+use crate::View;
+use crate::gfx::{self, BufferPool, Commands};
+use crate::pipeline::{LineVertex, Pipeline};
+use crate::pipeline::lvs::LineData;
+
+const ACTUAL_COLOR: [f32; 3] = [0.2, 0.4, 1.0];
+const OPTIMAL_COLOR: [f32; 3] = [1.0, 0.85, 0.2];
+
+/// Draws the route a flythrough follows next to the shortest route through the maze
+pub struct RouteRenderer {
+    vertex_buffer_pool: BufferPool<LineVertex>
+}
+
+impl RouteRenderer {
+    pub fn new(device: Arc<Device>) -> RouteRenderer {
+        RouteRenderer { vertex_buffer_pool: gfx::buffer_pool(device, BufferUsage::VERTEX_BUFFER) }
+    }
+
+    pub fn render(&self, flythrough: &Flythrough, view: &View, builder: &mut Commands, pipeline: &Pipeline) {
+        let between = view.between();
+        let spacing = (view.world.width + 1) as f32;
+        // Lay 4D cells out the same way the world lays out its w-slices
+        let project = |cell: &[i32; 4], offset: f32| -> [f32; 3] {
+            [cell[0] as f32 + (cell[3] as f32 - between) * spacing + offset, cell[1] as f32 + offset, cell[2] as f32 + 0.3 + view.lift(cell[2] as f32)]
+        };
+        let segments = |path: &[[i32; 4]], offset: f32, color: [f32; 3]| -> Vec<LineVertex> {
+            path.windows(2).flat_map(|pair| [
+                LineVertex { position: project(&pair[0], offset), color },
+                LineVertex { position: project(&pair[1], offset), color }
+            ]).collect()
+        };
+        let vertices: Vec<LineVertex> = segments(flythrough.path(), -0.1, ACTUAL_COLOR).into_iter()
+            .chain(segments(flythrough.optimal(), 0.1, OPTIMAL_COLOR))
+            .collect();
+        if vertices.is_empty() {
+            return;
+        }
+        let vertex_count = vertices.len() as u32;
+        let vertex_buffer = self.vertex_buffer_pool.chunk(vertices).unwrap();
+        builder
+            .bind_pipeline_graphics(pipeline.line_pipeline.clone())
+            .push_constants(pipeline.line_pipeline.layout().clone(), 0, LineData { vp: view.view_projection() })
+            .bind_vertex_buffers(0, vertex_buffer)
+            .draw(vertex_count, 1, 0, 0).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use std::cmp::Ordering;
+use std::mem;
+use std::sync::Arc;
+use std::thread;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::command_buffer::SecondaryAutoCommandBuffer;
+use vulkano::descriptor_set::DescriptorSet;
+use vulkano::device::Device;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline as _, PipelineBindPoint};
+
+use maze_core::linalg;
+use maze_core::world::{Axis, Wall};
+
+use crate::View;
+use crate::assets::Assets;
+use crate::gfx::{self, BufferPool, Commands, Recorder, UniformSets};
+use crate::parameters::{AMBIENT, EXIT_COLOR, EXIT_GLOW, FLASHLIGHT, FOOD_GLOW, PLAYER_LIGHT, PORTAL_FADE, PORTAL_GLOW, SEE_THROUGH_RADIUS, TELEPORT_FLASH};
+use crate::pipeline::{InstanceModel, Pipeline, Vertex};
+use crate::pipeline::vs::{ViewProjectionData, PlayerPositionData};
+
+const MAX_GLOWS: usize = 8; // Glowing objects lighting each draw, as many as the shader has room for
+const GLOW_LEVELS: i32 = 2; // Levels below the player's that glowing objects light from
+const MIN_BATCH: usize = 32; // Fewest draws recorded on a thread of their own, below which a thread costs more than it saves
+
+type LightSet = Arc<DescriptorSet>;
+
+// Draws recorded on one thread with the pipeline they're drawn with, each with the lights it binds first if any
+type Job<'a> = (Vec<(&'a Draw, Option<LightSet>)>, Arc<GraphicsPipeline>);
+
+/// Anything drawn with the main graphics pipeline
+pub trait Renderable {
+    /// Queue this frame's draws into the scene
+    fn draws(&self, view: &View, assets: &Assets, scene: &mut Scene);
+}
+
+/// Instances of one mesh, all drawn in the same color
+pub struct Draw {
+    pub mesh: gfx::Buffer<Vertex>, // Loaded once, or posed fresh each frame
+    pub instances: gfx::Buffer<InstanceModel>,
+    pub instance_count: u32,
+    pub color: [f32; 3],
+    pub transform: [[f32; 4]; 4], // Applied before the camera, eg. to lay out w-slices side by side
+    pub lights: Option<PlayerPositionData> // Where the player and ghost glow from; None keeps the previous draw's
+}
+
+/// Lights glowing from the player and ghost at the given positions, reaching further while the flashlight is lit
+/// and flaring up just after the player teleports, in the theme of the player's level, along with whatever glows
+/// nearest the player in w-slice w. Everything washes towards the color of the next w-slice while the player moves
+/// through a portal
+pub fn lights(view: &View, w: usize, player_pos: [f32; 3], ghost_pos: [f32; 3]) -> PlayerPositionData {
+    let [radius, intensity] = if view.player.flashlight.lit() { FLASHLIGHT } else { PLAYER_LIGHT };
+    let flash = view.player.teleport_flash();
+    let [r, g, b] = view.palette.slice(view.player.cell()[3].max(0));
+    let nearest = glows(view, w);
+    let mut glows = [[0.0; 4]; MAX_GLOWS];
+    let mut glow_colors = [[0.0; 4]; MAX_GLOWS];
+    for (i, ([x, y, z], [radius, intensity], [r, g, b])) in nearest.iter().copied().enumerate() {
+        glows[i] = [x, y, z, radius];
+        glow_colors[i] = [r, g, b, intensity];
+    }
+    PlayerPositionData {
+        player_pos: player_pos.into(),
+        ghost_pos,
+        light_radius: radius + TELEPORT_FLASH[0] * flash,
+        light_intensity: (intensity + TELEPORT_FLASH[1] * flash).into(),
+        portal: [r, g, b, PORTAL_FADE * view.portal_fade()],
+        theme: theme(view, view.player.cell()[2].max(0) as usize),
+        glows,
+        glow_colors,
+        glow_count: (nearest.len() as u32).into(),
+        xray: [0.0; 4], // Filled in as the draw is recorded
+        aspect: 0.0,
+        pattern: 0 // Set per slice by the world renderer
+    }
+}
+
+// Food, portals and the open exit in w-slice w on the player's level or just below, nearest the player first and no
+// more than the shader can take, as their position within the slice, radius and intensity, and color
+fn glows(view: &View, w: usize) -> Vec<([f32; 3], [f32; 2], [f32; 3])> {
+    let (world, [px, py, pz, _]) = (view.world, view.player.get_position());
+    let level = view.player.cell()[2];
+    let near = |z: usize| (level - GLOW_LEVELS..=level).contains(&(z as i32));
+    let mut glows: Vec<([f32; 3], [f32; 2], [f32; 3])> = view.objects.food()
+        .filter(|&&(_, _, z, fourth)| fourth == w && near(z))
+        .map(|&(x, y, z, _)| ([x as f32, y as f32, z as f32 + 0.6], FOOD_GLOW, view.palette.color(2)))
+        .collect();
+    for z in (0..world.depth).filter(|z| near(*z)) {
+        for y in 0..world.height {
+            for x in 0..world.width {
+                // Each portal glows the color of the slice it leads to, like its swirl
+                if w > 0 && world.walls.get(Axis::W, (x, y, z, w)) == Wall::NoWall {
+                    glows.push(([x as f32 - 0.3, y as f32, z as f32 + 0.4], PORTAL_GLOW, view.palette.slice(w as i32 - 1)));
+                }
+                if w + 1 < world.fourth && world.walls.get(Axis::W, (x, y, z, w + 1)) == Wall::NoWall {
+                    glows.push(([x as f32 + 0.3, y as f32, z as f32 + 0.4], PORTAL_GLOW, view.palette.slice(w as i32 + 1)));
+                }
+            }
+        }
+    }
+    let (x, y, z, fourth) = world.exit;
+    if world.exit_open && fourth == w && near(z) {
+        let [dx, dy, dz, _] = world.exit_delta.map(|i| i as f32 / 2.0);
+        glows.push(([x as f32 + dx, y as f32 + dy, z as f32 + 0.5 + dz], EXIT_GLOW, EXIT_COLOR));
+    }
+    let distance = |[x, y, z]: [f32; 3]| (x - px).powi(2) + (y - py).powi(2) + (z - pz).powi(2);
+    glows.sort_by(|a, b| distance(a.0).partial_cmp(&distance(b.0)).unwrap_or(Ordering::Equal));
+    glows.truncate(MAX_GLOWS);
+    glows
+}
+
+/// Tint and ambient light of level z, darker and cooler further down by default
+pub fn theme(view: &View, z: usize) -> [f32; 4] {
+    let [r, g, b] = view.theme.tint(z, view.world.depth);
+    [r, g, b, AMBIENT]
+}
+
+/// Collects the draws for a frame and records them all at once, spread over as many threads as there are draws to go
+/// round. Draws that could hide the player, like walls, can be queued to fade out in front of them, and are drawn
+/// blended over everything else
+pub struct Scene {
+    light_buffer_pool: BufferPool<PlayerPositionData>,
+    light_sets: UniformSets,
+    draws: Vec<Draw>,
+    see_through: Option<Vec<Draw>>, // Drawn after the rest, if walls fade out in front of the player at all
+    lights: Option<PlayerPositionData>, // Given with the last draw queued that had any
+    bound: [Option<PlayerPositionData>; 2] // Lights the last of the ordinary and the fading draws will have bound
+}
+
+impl Scene {
+    pub fn new(device: Arc<Device>, pipeline: &Pipeline, see_through: bool) -> Scene {
+        Scene {
+            light_buffer_pool: gfx::buffer_pool(device, BufferUsage::UNIFORM_BUFFER),
+            light_sets: UniformSets::new(&pipeline.graphics_pipeline),
+            draws: Vec::new(),
+            see_through: if see_through { Some (Vec::new()) } else { None },
+            lights: None,
+            bound: [None, None]
+        }
+    }
+
+    pub fn add(&mut self, mut draw: Draw) {
+        self.light(&mut draw, 0);
+        self.draws.push(draw);
+    }
+
+    /// Queue a draw that fades out where it stands in front of the player, or an ordinary one if nothing does
+    pub fn add_see_through(&mut self, mut draw: Draw) {
+        if self.see_through.is_none() {
+            return self.add(draw);
+        }
+        self.light(&mut draw, 1);
+        self.see_through.as_mut().unwrap().push(draw);
+    }
+
+    // Draws without lights of their own take those given last, but the ordinary and fading draws are recorded apart,
+    // so each only binds them when they differ from the last in its own list
+    fn light(&mut self, draw: &mut Draw, list: usize) {
+        let lights = draw.lights.or(self.lights);
+        self.lights = lights;
+        draw.lights = if lights == self.bound[list] { None } else { lights };
+        self.bound[list] = lights;
+    }
+
+    /// Record every queued draw in order, then those fading out in front of the player, leaving the scene empty for the
+    /// next frame. The draws are split into batches recorded on their own threads, each into a command buffer of its own
+    /// to execute in the order returned. If the pipeline has a depth pre-pass, the ordinary draws' depth is recorded
+    /// ahead of them all
+    pub fn submit(&mut self, view: &View, recorder: &Recorder, pipeline: &Pipeline) -> Vec<Arc<SecondaryAutoCommandBuffer>> {
+        let view_projection = view.view_projection();
+        // Where the player shows on screen, for walls in front of them to fade around
+        let [x, y, z, _] = view.player.get_position();
+        let [cx, cy, cz, cw] = linalg::transform(view_projection, [x, y, z + 0.4 + view.lift(z), 1.0]);
+        let projection = view.camera.projection();
+        let xray = [cx / cw, cy / cw, cz / cw, if self.see_through.is_some() && cw > 0.0 { SEE_THROUGH_RADIUS } else { 0.0 }];
+        let aspect = projection[1][1] / projection[0][0];
+        let see_through = self.see_through.as_mut().map(mem::take).unwrap_or_default();
+        let lists = [(mem::take(&mut self.draws), &pipeline.graphics_pipeline), (see_through, &pipeline.see_through_pipeline)];
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let size = (lists[0].0.len() + lists[1].0.len()).div_ceil(threads).max(MIN_BATCH);
+        // Descriptor sets for the lights all come from the one pool, so they're made here before the batches are
+        // recorded. The first draw of each batch binds whatever lights it's drawn with, since no batch sees the last's
+        let mut batches = Vec::new();
+        for (draws, graphics_pipeline) in lists {
+            let mut batch: Vec<(Draw, Option<LightSet>)> = Vec::new();
+            let mut lights: Option<LightSet> = None;
+            for draw in draws {
+                if let Some (data) = draw.lights {
+                    let light_buffer = self.light_buffer_pool.next(PlayerPositionData { xray, aspect, ..data }).unwrap();
+                    lights = Some (self.light_sets.next(light_buffer));
+                }
+                let set = if draw.lights.is_some() || batch.is_empty() { lights.clone() } else { None };
+                batch.push((draw, set));
+                if batch.len() == size {
+                    batches.push((mem::take(&mut batch), graphics_pipeline.clone()));
+                }
+            }
+            if !batch.is_empty() {
+                batches.push((batch, graphics_pipeline.clone()));
+            }
+        }
+        self.lights = None;
+        self.bound = [None, None];
+        // With a depth pre-pass, the ordinary batches are recorded first with the depth pipeline too, which needs no
+        // lights, then every batch as usual
+        let mut jobs: Vec<Job> = Vec::new();
+        if let Some (depth_pipeline) = &pipeline.depth_pipeline {
+            for (batch, graphics_pipeline) in &batches {
+                if Arc::ptr_eq(graphics_pipeline, &pipeline.graphics_pipeline) {
+                    jobs.push((batch.iter().map(|(draw, _)| (draw, None)).collect(), depth_pipeline.clone()));
+                }
+            }
+        }
+        for (batch, graphics_pipeline) in &batches {
+            jobs.push((batch.iter().map(|(draw, set)| (draw, set.clone())).collect(), graphics_pipeline.clone()));
+        }
+        thread::scope(|scope| {
+            let recording: Vec<_> = jobs.into_iter().map(|(batch, graphics_pipeline)| scope.spawn(move || {
+                let mut builder = recorder.begin();
+                builder.bind_pipeline_graphics(graphics_pipeline.clone());
+                for (draw, set) in batch {
+                    record(draw, set, view_projection, &mut builder, &graphics_pipeline);
+                }
+                builder.build().unwrap()
+            })).collect();
+            recording.into_iter().map(|batch| batch.join().expect("Failed to record draws")).collect()
+        })
+    }
+}
+
+// Record one draw, binding its lights if it has any
+fn record(draw: &Draw, lights: Option<LightSet>, view_projection: [[f32; 4]; 4], builder: &mut Commands, graphics_pipeline: &Arc<GraphicsPipeline>) {
+    if let Some (descriptor_set) = lights {
+        builder.bind_descriptor_sets(
+            PipelineBindPoint::Graphics,
+            graphics_pipeline.layout().clone(),
+            0,
+            descriptor_set);
+    }
+    builder
+        .push_constants(graphics_pipeline.layout().clone(), 0, ViewProjectionData {
+            vp: linalg::mul(view_projection, draw.transform),
+            pushColor: draw.color })
+        .bind_vertex_buffers(0, (draw.mesh.clone(), draw.instances.clone()))
+        .draw(
+            draw.mesh.len() as u32,
+            draw.instance_count,
+            0,
+            0).unwrap();
+}
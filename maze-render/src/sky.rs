@@ -0,0 +1,15 @@
+use vulkano::pipeline::Pipeline as _;
+
+use crate::gfx::Commands;
+use crate::pipeline::Pipeline;
+use crate::pipeline::skyfs::SkyData;
+use crate::View;
+
+// Background dome tinted by the fourth dimension color, blending smoothly while moving between w slices
+pub fn render(view: &View, builder: &mut Commands, pipeline: &Pipeline) {
+    let horizon = view.palette.between(view.between().max(0.0));
+    builder
+        .bind_pipeline_graphics(pipeline.sky_pipeline.clone())
+        .push_constants(pipeline.sky_pipeline.layout().clone(), 0, SkyData { ray: view.camera.ray(), horizon })
+        .draw(3, 1, 0, 0).unwrap();
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use log::{info, warn};
+use png::{Decoder, Transformations};
+use vulkano::image::view::ImageView;
+use vulkano::image::Image;
+use vulkano::image::sampler::Sampler;
+use vulkano::sync::GpuFuture;
+use vulkano::format::Format;
+use vulkano::device::{Device, Queue};
+
+use maze_core::config::TextureQuality;
+use maze_core::error::MazeError;
+use crate::assets::Assets;
+use crate::gfx::{self, MipmapMode};
+use crate::ktx2::{Encoding, Ktx2};
+
+// Location of a single image within the atlas
+#[derive(Clone, Copy, Debug)]
+pub struct AtlasRegion {
+    pub layer: u32,
+    pub uv: [f32; 4] // Start u, start v, finish u, finish v
+}
+
+// Several images packed into the layers of one array texture, so they can all be bound with a single descriptor set
+pub struct TextureAtlas {
+    pub image: Arc<Image>,
+    regions: HashMap<String, AtlasRegion>
+}
+
+// Pixel data for every layer of the atlas, before uploading
+struct AtlasData {
+    format: Format,
+    width: u32,
+    height: u32,
+    image_sizes: Vec<[u32; 2]>,
+    levels: Vec<Vec<u8>> // Largest level first, each containing every layer in order
+}
+
+impl TextureAtlas {
+    // Load the named images from PNG files, or from KTX2 files of the same name when they're present and usable on this device.
+    // Basis Universal KTX2 files are transcoded to whichever BCn format the device supports
+    pub fn new(queue: Arc<Queue>, assets: &Assets, names: &[&str]) -> Result<(TextureAtlas, Box<dyn GpuFuture>), MazeError> {
+        let ktx2_files: Vec<String> = names.iter().map(|name| assets.resolve(&format!("{}.ktx2", name)).to_string_lossy().to_string()).collect();
+        let data = if ktx2_files.iter().any(|file| Path::new(file).exists()) {
+            load_ktx2(queue.device(), &ktx2_files).or_else(|reason| {
+                warn!(target: "render", "Falling back to PNG textures: {}", reason);
+                load_png(assets, names)
+            })?
+        } else {
+            load_png(assets, names)?
+        };
+        for name in names {
+            info!(target: "render", "Loaded texture {}", name);
+        }
+
+        let regions = names.iter().map(|name| name.to_string()).zip(data.image_sizes.iter()).enumerate().map(|(i, (name, [w, h]))| {
+            (name, AtlasRegion { layer: i as u32, uv: [0.0, 0.0, *w as f32 / data.width as f32, *h as f32 / data.height as f32] })
+        }).collect();
+
+        let (image, future) = gfx::array_texture(queue, data.format, [data.width, data.height], names.len() as u32, data.levels);
+        Ok ((TextureAtlas { image, regions }, future))
+    }
+
+    pub fn region(&self, name: &str) -> AtlasRegion {
+        *self.regions.get(name).unwrap_or_else(|| panic!("No texture `{}' in atlas", name))
+    }
+
+    // Region covering part of an image, given in that image's own texture coordinates
+    pub fn sub_region(&self, name: &str, rect: [f32; 4]) -> AtlasRegion {
+        let region = self.region(name);
+        let [u0, v0, u1, v1] = region.uv;
+        let (du, dv) = (u1 - u0, v1 - v0);
+        AtlasRegion { layer: region.layer, uv: [u0 + rect[0] * du, v0 + rect[1] * dv, u0 + rect[2] * du, v0 + rect[3] * dv] }
+    }
+
+    pub fn access(&self) -> Arc<ImageView> {
+        gfx::image_view(self.image.clone())
+    }
+}
+
+// Pre-compressed textures are uploaded directly and Basis Universal ones transcoded first, so every layer must share one
+// encoding, size and mip chain
+fn load_ktx2(device: &Arc<Device>, files: &[String]) -> Result<AtlasData, String> {
+    let textures = files.iter().map(|file| Ktx2::load(file)).collect::<Result<Vec<_>, _>>()?;
+    let first = &textures[0];
+    if textures.iter().any(|t| t.encoding != first.encoding || t.width != first.width || t.height != first.height) {
+        return Err("KTX2 textures must all have the same format, size and mip levels".to_string());
+    }
+    let format = match first.encoding {
+        Encoding::Raw (format) if !gfx::sampleable(device, format) => {
+            return Err(format!("{:?} uses a texture compression this device doesn't support, or can't be sampled", format))
+        },
+        Encoding::Raw (format) => format,
+        Encoding::Basis { uastc, alpha, srgb } => transcode_target(device, uastc, alpha, srgb)
+            .ok_or("no format Basis Universal textures can be transcoded to is supported on this device")?
+    };
+    let textures = textures.iter().map(|t| t.transcode(format)).collect::<Result<Vec<_>, _>>()?;
+    if textures.iter().any(|t| t.len() != textures[0].len()) {
+        return Err("KTX2 textures must all have the same format, size and mip levels".to_string());
+    }
+    let levels = (0..textures[0].len()).map(|level| textures.iter().flat_map(|t| t[level].iter().cloned()).collect()).collect();
+    Ok(AtlasData {
+        format,
+        width: first.width,
+        height: first.height,
+        image_sizes: vec![[first.width, first.height]; textures.len()],
+        levels
+    })
+}
+
+// Format to transcode Basis Universal textures to, the first this device can sample of: BC7 to keep UASTC's quality,
+// or BC1 (BC3 with alpha) for ETC1S, which looks no better in BC7, then uncompressed RGBA
+fn transcode_target(device: &Arc<Device>, uastc: bool, alpha: bool, srgb: bool) -> Option<Format> {
+    let bc1_or_bc3 = match (alpha, srgb) {
+        (false, false) => Format::BC1_RGB_UNORM_BLOCK,
+        (false, true) => Format::BC1_RGB_SRGB_BLOCK,
+        (true, false) => Format::BC3_UNORM_BLOCK,
+        (true, true) => Format::BC3_SRGB_BLOCK
+    };
+    let bc7 = if srgb { Format::BC7_SRGB_BLOCK } else { Format::BC7_UNORM_BLOCK };
+    let rgba = if srgb { Format::R8G8B8A8_SRGB } else { Format::R8G8B8A8_UNORM };
+    let candidates = if uastc { [bc7, bc1_or_bc3, rgba] } else { [bc1_or_bc3, bc7, rgba] };
+    candidates.into_iter().find(|format| gfx::sampleable(device, *format))
+}
+
+// Every layer is as large as the largest image, smaller images sit in the top-left corner.
+// Mip levels are generated on the CPU, since vulkano only blits mips for the first layer of an array
+fn load_png(assets: &Assets, names: &[&str]) -> Result<AtlasData, MazeError> {
+    let images: Vec<(u32, u32, Vec<u8>)> = names.iter().map(|name| {
+        let file = format!("{}.png", name);
+        decode_png(&file, &assets.read(&file)?)
+    }).collect::<Result<_, _>>()?;
+    let width = images.iter().map(|(w, _, _)| *w).max().unwrap();
+    let height = images.iter().map(|(_, h, _)| *h).max().unwrap();
+    let mut layers: Vec<Vec<u8>> = images.iter().map(|(w, h, pixels)| {
+        let mut layer = vec![0; (width * height * 4) as usize];
+        for row in 0..*h as usize {
+            let (src, dst) = (row * *w as usize * 4, row * width as usize * 4);
+            layer[dst..dst + *w as usize * 4].copy_from_slice(&pixels[src..src + *w as usize * 4]);
+        }
+        layer
+    }).collect();
+    let level_count = 32 - width.max(height).leading_zeros();
+    let mut levels = vec![layers.concat()];
+    let (mut level_width, mut level_height) = (width, height);
+    for _ in 1..level_count {
+        layers = layers.iter().map(|layer| downscale(layer, level_width, level_height)).collect();
+        level_width = (level_width / 2).max(1);
+        level_height = (level_height / 2).max(1);
+        levels.push(layers.concat());
+    }
+    Ok (AtlasData {
+        format: Format::R8G8B8A8_SRGB,
+        width,
+        height,
+        image_sizes: images.iter().map(|(w, h, _)| [*w, *h]).collect(),
+        levels
+    })
+}
+
+/// Width, height and RGBA pixels of the game's icon, built into the binary, for the window
+pub fn window_icon() -> Result<(u32, u32, Vec<u8>), MazeError> {
+    decode_png("icon.png", include_bytes!("../../res/icon.png"))
+}
+
+fn decode_png(file: &str, bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), MazeError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(|e| MazeError::resource(file, e))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(|e| MazeError::resource(file, e))?;
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => pixels,
+        png::ColorType::Rgb => pixels.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        png::ColorType::Indexed => unreachable!("Palette is expanded while decoding")
+    };
+    Ok ((info.width, info.height, pixels))
+}
+
+// Halve an RGBA image with a box filter, averaging colors in linear space
+fn downscale(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let to_linear = |c: u8| (c as f32 / 255.0).powf(2.2);
+    let to_srgb = |c: f32| (c.powf(1.0 / 2.2) * 255.0).round() as u8;
+    let (new_width, new_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut scaled = Vec::with_capacity((new_width * new_height * 4) as usize);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let samples: Vec<usize> = [(0, 0), (1, 0), (0, 1), (1, 1)].iter().map(|(dx, dy)| {
+                let sx = (x * 2 + dx).min(width - 1);
+                let sy = (y * 2 + dy).min(height - 1);
+                ((sy * width + sx) * 4) as usize
+            }).collect();
+            for channel in 0..3 {
+                let sum: f32 = samples.iter().map(|i| to_linear(pixels[i + channel])).sum();
+                scaled.push(to_srgb(sum / 4.0));
+            }
+            let alpha: u32 = samples.iter().map(|i| pixels[i + 3] as u32).sum();
+            scaled.push((alpha / 4) as u8);
+        }
+    }
+    scaled
+}
+
+// Build a sampler matching the configured texture quality, within the limits of the device
+pub fn sampler(device: Arc<Device>, quality: &TextureQuality) -> Arc<Sampler> {
+    let (mipmap_mode, max_lod, anisotropy) = match quality {
+        TextureQuality::Low => (MipmapMode::Nearest, 0.0, 1.0),
+        TextureQuality::Trilinear => (MipmapMode::Linear, 1_000.0, 1.0),
+        TextureQuality::Anisotropic (level) => {
+            let anisotropy = if device.enabled_features().sampler_anisotropy {
+                (*level as f32).clamp(1.0, device.physical_device().properties().max_sampler_anisotropy)
+            } else {
+                1.0 // Not supported by the device, fall back to trilinear
+            };
+            (MipmapMode::Linear, 1_000.0, anisotropy)
+        }
+    };
+    gfx::sampler(device, mipmap_mode, max_lod, anisotropy)
+}
@@ -0,0 +1,713 @@
+use std::iter::empty;
+use std::sync::Arc;
+use std::time::Instant;
+
+use vulkano::buffer::{BufferContents, BufferUsage};
+use vulkano::descriptor_set::DescriptorSet;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline as _, PipelineBindPoint};
+use vulkano::pipeline::graphics::vertex_input::Vertex;
+use vulkano::pipeline::cache::PipelineCache;
+use vulkano::render_pass::RenderPass;
+use vulkano::device::{Queue, Device};
+
+use maze_core::config::{Config, DisplayClock, Mode, HUD_WIDGETS};
+use maze_core::objects::PowerUp;
+use maze_core::player::{GameState, Player};
+use maze_core::stats::Stats;
+use crate::assets::Assets;
+use crate::gfx::{self, load_shader, BufferPool, Commands, PipelineOptions, VertexInput};
+use crate::pipeline::Pipeline;
+use crate::texture::{self, AtlasRegion};
+use maze_core::world::World;
+use maze_core::world::analysis::Analysis;
+
+const DIGIT_WIDTH: f32 = 1.0 / 10.0;
+const DIGIT_HEIGHT: f32 = 100.0 / 512.0;
+const CONTROL_WIDTH: f32 = 0.093;
+const CONTROL_HEIGHT: f32 = 100.0 / 512.0;
+const ICON_SIZE: f32 = 100.0 / 512.0;
+const HOVER_GROWTH: f32 = 1.15; // Size of a control under the cursor, relative to the usual
+const PRESS_SHRINK: f32 = 0.9; // Size of a control while it's clicked on
+const GLYPH_WIDTH: f32 = 32.0 / 512.0;
+const GLYPH_HEIGHT: f32 = 64.0 / 512.0;
+const GLYPH_COLUMNS: usize = 16;
+// Indices into HUD_WIDGETS
+const CONTROLS: usize = 0;
+const CLOCK: usize = 1;
+const SCORE: usize = 2;
+const LIVES: usize = 3;
+// Letters the font only has without their accents
+const ACCENTED: [(&str, char); 9] = [
+    ("ÀÁÂÃÄÅàáâãäå", 'A'), ("Çç", 'C'), ("ÈÉÊËèéêë", 'E'), ("ÌÍÎÏìíîï", 'I'), ("Ññ", 'N'),
+    ("ÒÓÔÕÖØòóôõöø", 'O'), ("ß", 'S'), ("ÙÚÛÜùúûü", 'U'), ("Ýýÿ", 'Y')
+];
+
+pub struct UserInterface {
+    graphics_pipeline: Arc<GraphicsPipeline>,
+    rect_buffer: gfx::Buffer<UIVertex>,
+    instance_buffer_pool: BufferPool<UIElement>,
+    texture_descriptor: Arc<DescriptorSet>,
+    scale_x: f32,
+    scale_y: f32,
+    scale: f32, // UI scale setting, times the display's scale factor
+    controls: Vec<([i32; 4], UIElement, UIElement)>,
+    compass: Vec<([i32; 4], UIElement)>,
+    digits: Vec<UIElement>,
+    glyphs: Vec<UIElement>, // Printable ASCII, from space on
+    slash: UIElement,
+    colon: UIElement,
+    minus: UIElement,
+    icons: Vec<(PowerUp, UIElement)>,
+    battery: UIElement,
+    battery_bar: UIElement,
+    fruit: UIElement,
+    warning: UIElement,
+    multiplier: UIElement,
+    tally: Vec<UIElement>, // Icons for each line of the final score
+    win: UIElement,
+    lose: UIElement,
+    stats: Option<StatsScreen>, // Shown over the win and lose screens instead of the final score
+    campaign: Option<CampaignProgress>, // Shown above the final score between campaign levels
+    analysis: Option<String>, // Line about the maze just escaped, shown above the final score
+    widgets: [UIElement; 4], // Area taken up by each of HUD_WIDGETS in its usual place, to pick it up by
+    cursor: Option<[f32; 2]>, // Where the mouse is over the UI, in normalized device coordinates
+    pressed: bool, // Whether the mouse button is held down
+    drag: Option<(usize, [f32; 2], [f32; 2])> // Widget being dragged, where the mouse picked it up, and how far it had already moved
+}
+
+/// What pressing or letting go of the mouse button on the UI did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Click {
+    Move ([i32; 4]), // Pressed on a control, to move the player the same way as its key
+    Place (usize, [f32; 2]) // Dropped a HUD widget, by its index into HUD_WIDGETS, this far from its usual place
+}
+
+// Campaign level just played out of how many there are, and the points from every level up to it
+#[derive(Clone, Copy)]
+struct CampaignProgress {
+    level: usize,
+    levels: usize,
+    score: u32
+}
+
+// Numbers shown on the statistics screen
+#[derive(Clone, Copy)]
+struct StatsScreen {
+    best: Option<u32>, // Fastest escape from this size of maze
+    solved: usize,
+    played: usize
+}
+
+// Index into the font of the glyph standing in for the character, or of a question mark for one it has nothing like
+fn glyph_index(c: char) -> usize {
+    let c = ACCENTED.iter().find(|(accented, _)| accented.contains(c)).map_or(c, |(_, plain)| *plain);
+    let c = if (' '..='~').contains(&c) { c } else { '?' };
+    c as usize - ' ' as usize
+}
+
+// Whether the maze is more than one cell across along the move's axis
+fn along_maze(world: &World, delta: &[i32; 4]) -> bool {
+    let sizes = [world.width, world.height, world.depth, world.fourth];
+    (0..4).all(|i| delta[i] == 0 || sizes[i] > 1)
+}
+
+// One textured quad, drawn as an instance of the rect buffer
+#[derive(BufferContents, Vertex, Default, Clone, Copy)]
+#[repr(C)]
+struct UIElement {
+    #[format(R32G32B32A32_SFLOAT)]
+    texture_region: [f32; 4],
+    #[format(R32G32_SFLOAT)]
+    size: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    offset: [f32; 2],
+    #[format(R32_UINT)]
+    layer: u32
+}
+
+impl UIElement {
+    fn new(region: AtlasRegion, size: [f32; 2], offset: [f32; 2]) -> UIElement {
+        UIElement { texture_region: region.uv, size, offset, layer: region.layer }
+    }
+}
+
+impl UserInterface {
+    /// Lay out the UI for a window of this resolution, scaled up for high DPI displays by their scale factor
+    pub fn new(queue: Arc<Queue>, pipeline: &Pipeline, assets: &Assets, resolution: [u32; 2], scale_factor: f64, config: &Config) -> UserInterface {
+        let atlas = assets.atlas();
+        // Initialize pipeline for displaying UI
+        let graphics_pipeline = graphics_pipeline(queue.device().clone(), pipeline.render_pass.clone(), pipeline.cache.clone());
+
+        // Initialize texture samplers
+        let sampler = texture::sampler(queue.device().clone(), &config.texture_quality);
+        let texture_descriptor = gfx::texture_set(&graphics_pipeline, atlas.access(), sampler);
+
+        // Build rect buffer
+        let rect_buffer = gfx::cpu_buffer(
+            queue.device().clone(),
+            [
+                [0.0, 0.0],
+                [0.0, 1.0],
+                [1.0, 0.0],
+                [1.0, 0.0],
+                [0.0, 1.0],
+                [1.0, 1.0]
+            ].map(|xy| UIVertex { position: xy, uv: xy.map(|f| f.clamp(0.0, 1.0)) }),
+            BufferUsage::VERTEX_BUFFER);
+
+        // Use UI scaling
+        let scale = config.ui_scale * scale_factor as f32;
+        let [digit_ui_width, digit_ui_height] =
+            [DIGIT_WIDTH, DIGIT_HEIGHT].map(|f| f * scale);
+
+        // Build UI elements
+        let control_ui_width = 0.1 * scale;
+        let control_ui_height = 0.16 * scale;
+        let [mut control_w, mut control_a, mut control_s, mut control_d,
+            mut control_q, mut control_e, mut control_space, mut control_lctrl] =
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0].map(|i| {
+                UIElement::new(
+                    atlas.sub_region("controls", [i * CONTROL_WIDTH, 0.0, (i + 1.0) * CONTROL_WIDTH, CONTROL_HEIGHT]),
+                    [control_ui_width, control_ui_height], [0.0, 0.0]) });
+        let (control_x_pos, control_y_pos) = (-0.84, -0.92);
+        control_w.offset = [control_x_pos, control_y_pos];
+        control_a.offset = [control_x_pos - 0.66 * control_ui_width, control_y_pos + control_ui_height];
+        control_s.offset = [control_x_pos + 0.33 * control_ui_width, control_y_pos + control_ui_height];
+        control_d.offset = [control_x_pos + 1.33 * control_ui_width, control_y_pos + control_ui_height];
+        control_q.offset = [control_x_pos - control_ui_width, control_y_pos];
+        control_e.offset = [control_x_pos + control_ui_width, control_y_pos];
+        control_space.offset = [control_x_pos + control_ui_width * 2.5, control_y_pos];
+        control_lctrl.offset = [control_x_pos + control_ui_width * 2.5, control_y_pos + control_ui_height];
+        let controls = [
+            ([0, -1, 0, 0], control_w),
+            ([-1, 0, 0, 0], control_a),
+            ([0, 1, 0, 0], control_s),
+            ([1, 0, 0, 0], control_d),
+            ([0, 0, 0, -1], control_q),
+            ([0, 0, 0, 1], control_e),
+            ([0, 0, 1, 0], control_space),
+            ([0, 0, -1, 0], control_lctrl)].map(|(delta, control)| {
+                let mut dim = control;
+                dim.layer = atlas.region("controls_dim").layer;
+                (delta, control, dim)
+            }).to_vec();
+
+        // Compass reuses the control glyphs, centered at the top of the screen
+        let compass = controls.iter().map(|(delta, control, _dim)| {
+            let mut control = *control;
+            control.offset = [-control_ui_width / 2.0, -1.0];
+            (*delta, control)
+        }).collect();
+
+        let digits: Vec<UIElement> = (0..=9).map(|i| {
+            UIElement::new(
+                atlas.sub_region("digits", [DIGIT_WIDTH * i as f32, 0.0, DIGIT_WIDTH * (i + 1) as f32, DIGIT_HEIGHT]),
+                [digit_ui_width, digit_ui_height],
+                [0.0, 0.0]) // Will be set later, when needed
+            }).collect();
+        let slash = UIElement::new(
+            atlas.sub_region("digits", [0.0, DIGIT_HEIGHT, DIGIT_WIDTH, 2.0 * DIGIT_HEIGHT]),
+            [digit_ui_width, digit_ui_height],
+            [1.0 - 3.0 * digit_ui_width, 1.0 - digit_ui_height]);
+        let colon = UIElement::new(
+            atlas.sub_region("digits", [DIGIT_WIDTH, DIGIT_HEIGHT, 2.0 * DIGIT_WIDTH, 2.0 * DIGIT_HEIGHT]),
+            [digit_ui_width, digit_ui_height],
+            [1.0 - 3.0 * digit_ui_width, -1.0]);
+        let minus = UIElement::new(
+            atlas.sub_region("digits", [2.0 * DIGIT_WIDTH, DIGIT_HEIGHT, 3.0 * DIGIT_WIDTH, 2.0 * DIGIT_HEIGHT]),
+            [digit_ui_width, digit_ui_height],
+            [1.0 - 6.0 * digit_ui_width, -1.0]);
+
+        // Power-up icons, shown with the seconds left on them
+        let icons = [PowerUp::Boots, PowerUp::Clock].into_iter().enumerate().map(|(i, power_up)| {
+            (power_up, UIElement::new(
+                atlas.sub_region("icons", [ICON_SIZE * i as f32, 0.0, ICON_SIZE * (i + 1) as f32, ICON_SIZE]),
+                [2.0 * digit_ui_width, digit_ui_height],
+                [0.0, 0.0])) // Will be set later, when needed
+        }).collect();
+
+        // Flashlight battery, with a bar stretched across it by how much charge is left
+        let battery = UIElement::new(
+            atlas.sub_region("icons", [2.0 * ICON_SIZE, 0.0, 3.0 * ICON_SIZE, ICON_SIZE]),
+            [4.0 * digit_ui_width, 2.0 * digit_ui_height],
+            [-2.0 * digit_ui_width, 1.0 - 2.0 * digit_ui_height]);
+        let battery_bar = UIElement::new(
+            atlas.sub_region("icons", [3.0 * ICON_SIZE, 0.0, 4.0 * ICON_SIZE, ICON_SIZE]),
+            [2.2 * digit_ui_width, 0.5 * digit_ui_height],
+            [-1.2 * digit_ui_width, 1.0 - 1.25 * digit_ui_height]);
+
+        let fruit = UIElement::new(
+            atlas.sub_region("icons", [4.0 * ICON_SIZE, 0.0, 5.0 * ICON_SIZE, ICON_SIZE]),
+            [2.0 * digit_ui_width, digit_ui_height],
+            [1.0 - 5.0 * digit_ui_width, 1.0 - 2.0 * digit_ui_height]);
+
+        // Warning shown over a control that would walk into a trap
+        let warning = UIElement::new(
+            atlas.sub_region("icons", [0.0, ICON_SIZE, ICON_SIZE, 2.0 * ICON_SIZE]),
+            [0.0, 0.0],
+            [0.0, 0.0]); // Will be set later, when needed
+
+        // Combo multiplier, shown beside the score
+        let multiplier = UIElement::new(
+            atlas.sub_region("icons", [ICON_SIZE, ICON_SIZE, 2.0 * ICON_SIZE, 2.0 * ICON_SIZE]),
+            [2.0 * digit_ui_width, digit_ui_height],
+            [1.0 - 9.0 * digit_ui_width, 1.0 - digit_ui_height]);
+
+        // Food, fruit, combo, time, no-hint and total lines of the final score
+        let tally = [[2.0, 1.0], [4.0, 0.0], [1.0, 1.0], [1.0, 0.0], [3.0, 1.0], [4.0, 1.0]].into_iter().enumerate().map(|(i, [u, v])| {
+            UIElement::new(
+                atlas.sub_region("icons", [u * ICON_SIZE, v * ICON_SIZE, (u + 1.0) * ICON_SIZE, (v + 1.0) * ICON_SIZE]),
+                [2.0 * digit_ui_width, digit_ui_height],
+                [-2.5 * digit_ui_width, 1.0 - (6.5 - i as f32) * digit_ui_height])
+        }).collect();
+
+        // Text of the event feed, half as wide as it is tall
+        let glyph_height = 0.5 * digit_ui_height * config.event_feed_size;
+        let glyphs = (0..96).map(|i| {
+            let [u, v] = [(i % GLYPH_COLUMNS) as f32 * GLYPH_WIDTH, (i / GLYPH_COLUMNS) as f32 * GLYPH_HEIGHT];
+            UIElement::new(
+                atlas.sub_region("font", [u, v, u + GLYPH_WIDTH, v + GLYPH_HEIGHT]),
+                [0.5 * glyph_height, glyph_height],
+                [0.0, 0.0]) // Will be set later, when needed
+        }).collect();
+
+        // Where each widget sits when it hasn't been moved, covering every element that goes with it
+        let widgets = [
+            [control_x_pos - control_ui_width, control_y_pos, 4.5 * control_ui_width, 2.0 * control_ui_height],
+            [1.0 - 6.0 * digit_ui_width, -1.0, 6.0 * digit_ui_width, digit_ui_height],
+            [1.0 - 9.0 * digit_ui_width, 1.0 - 2.0 * digit_ui_height, 9.0 * digit_ui_width, 2.0 * digit_ui_height],
+            [-1.0, 1.0 - digit_ui_height, 2.0 * digit_ui_width, digit_ui_height]
+        ].map(|[x, y, width, height]| UIElement { size: [width, height], offset: [x, y], ..UIElement::default() });
+
+        let win = UIElement::new(atlas.region("win"), [2.0, 2.0], [-1.0, -1.0]);
+        let lose = UIElement::new(atlas.region("lose"), [2.0, 2.0], [-1.0, -1.0]);
+
+        // Compensate for aspect ratio
+        let [x, y] = resolution;
+        let ratio = x as f32 / y as f32;
+        let (scale_x, scale_y) = if ratio >= 1.0 { (ratio, 1.0) } else { (1.0, 1.0 / ratio) };
+
+        let instance_buffer_pool = gfx::buffer_pool(queue.device().clone(), BufferUsage::VERTEX_BUFFER);
+
+        UserInterface { graphics_pipeline, rect_buffer, instance_buffer_pool, texture_descriptor, scale_x, scale_y, scale, controls, compass, digits, glyphs, slash, colon, minus, icons, battery, battery_bar, fruit, warning, multiplier, tally, win, lose, stats: None, campaign: None, analysis: None, widgets, cursor: None, pressed: false, drag: None }
+    }
+
+    /// Show which campaign level was just played, counting from 1, and the campaign's points so far on the win and
+    /// lose screens, or None outside of a campaign
+    pub fn show_campaign(&mut self, progress: Option<(usize, usize, u32)>) {
+        self.campaign = progress.map(|(level, levels, score)| CampaignProgress { level, levels, score });
+    }
+
+    /// Show how hard the maze just escaped was on the win screen, or nothing with None
+    pub fn show_analysis(&mut self, analysis: Option<&Analysis>) {
+        self.analysis = analysis.map(|analysis| format!("difficulty {} - {} moves, {} dead ends", analysis.difficulty, analysis.solution_length, analysis.dead_ends));
+    }
+
+    /// Show the best time for this size of maze and how many runs were escaped from in place of the final score,
+    /// or go back to the final score with None
+    pub fn show_stats(&mut self, stats: Option<&Stats>, size: &str) {
+        self.stats = stats.map(|stats| StatsScreen { best: stats.best_times().get(size).copied(), solved: stats.solved(), played: stats.runs.len() });
+    }
+
+    pub fn showing_stats(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Follow the mouse, at a position in normalized device coordinates of the UI's screen, or None once it's left
+    pub fn point(&mut self, cursor: Option<[f32; 2]>) {
+        self.cursor = cursor;
+    }
+
+    /// Press or release the mouse button. Pressing on a control moves the player, unless the HUD is being edited, when
+    /// pressing picks up the widget under the mouse and releasing drops it wherever the mouse has taken it
+    pub fn click(&mut self, pressed: bool, world: &World, config: &Config) -> Option<Click> {
+        self.pressed = pressed;
+        if config.edit_hud {
+            if pressed {
+                let widget = (0..HUD_WIDGETS.len()).find(|i| self.hovered(&self.placed(*i, self.widgets[*i], config)));
+                self.drag = widget.zip(self.cursor).map(|(widget, cursor)| (widget, cursor, config.hud_layout[widget]));
+                return None;
+            }
+            return self.drag.take().map(|(widget, _, _)| Click::Place (widget, self.widget_offset(widget, config)));
+        }
+        self.drag = None;
+        if !pressed || !config.display_controls {
+            return None;
+        }
+        // Only the controls drawn can be clicked
+        self.controls.iter()
+            .filter(|(delta, _, _)| along_maze(world, delta))
+            .find(|(_, control, _)| self.hovered(&self.placed(CONTROLS, *control, config)))
+            .map(|(delta, _, _)| Click::Move (*delta))
+    }
+
+    pub fn render(&self, now: Instant, player: &Player, world: &World, config: &Config, feed: &[&str], builder: &mut Commands) {
+        // Display valid controls, growing under the mouse and shrinking back while clicked on. Controls along an axis the
+        // maze is only one cell across, eg. up and down in a maze one level deep, are left out altogether
+        let controls: Vec<UIElement> = self.controls.iter().filter(|(delta, _, _)| along_maze(world, delta)).map(|(delta, control, dim)| {
+            let control = self.placed(CONTROLS, *control, config);
+            let mut element = if world.check_move(player.cell(), *delta, &player.inventory) { control } else { self.placed(CONTROLS, *dim, config) };
+            if self.hovered(&control) && !config.edit_hud {
+                let scale = if self.pressed { PRESS_SHRINK } else { HOVER_GROWTH };
+                element.offset = [0, 1].map(|i| element.offset[i] - element.size[i] * (scale - 1.0) / 2.0);
+                element.size = element.size.map(|f| f * scale);
+            }
+            element
+        }).collect();
+
+        // Warn about traps one move away
+        let warnings: Vec<UIElement> = self.controls.iter().filter_map(|(delta, control, _dim)| {
+            let target = [0, 1, 2, 3].map(|i| player.cell()[i] + delta[i]);
+            world.trap(target).filter(|_| world.check_move(player.cell(), *delta, &player.inventory)).map(|_| {
+                let mut warning = self.warning;
+                warning.size = control.size;
+                warning.offset = self.placed(CONTROLS, *control, config).offset;
+                warning
+            })
+        }).collect();
+
+        let [digit_ui_width, digit_ui_height] = [DIGIT_WIDTH, DIGIT_HEIGHT].map(|f| f * self.scale);
+
+        // Display stopwatch
+        let stopwatch_secs = player.stopwatch as usize % 60;
+        let stopwatch_mins = player.stopwatch as usize / 60 % 60;
+        let mut stopwatch_secs_ones = self.digits[stopwatch_secs % 10];
+        stopwatch_secs_ones.offset = [1.0 - 1.0 * digit_ui_width, -1.0];
+        let mut stopwatch_secs_tens = self.digits[stopwatch_secs / 10 % 10];
+        stopwatch_secs_tens.offset = [1.0 - 2.0 * digit_ui_width, -1.0];
+        let mut stopwatch_mins_ones = self.digits[stopwatch_mins % 10];
+        stopwatch_mins_ones.offset = [1.0 - 4.0 * digit_ui_width, -1.0];
+        let mut stopwatch_mins_tens = self.digits[stopwatch_mins / 10 % 10];
+        stopwatch_mins_tens.offset = [1.0 - 5.0 * digit_ui_width, -1.0];
+        let stopwatch = [stopwatch_mins_tens, stopwatch_mins_ones, self.colon, stopwatch_secs_tens, stopwatch_secs_ones].map(|e| self.placed(CLOCK, e, config));
+        let minus = [self.placed(CLOCK, self.minus, config)];
+
+        // Display player's score
+        let mut score_ones = self.digits[player.score as usize % 10];
+        score_ones.offset = [1.0 - 4.0 * digit_ui_width, 1.0 - digit_ui_height];
+        let mut score_tens = self.digits[player.score as usize / 10 % 10];
+        score_tens.offset = [1.0 - 5.0 * digit_ui_width, 1.0 - digit_ui_height];
+        let mut max_ones = self.digits[config.food_count % 10];
+        max_ones.offset = [1.0 - 1.0 * digit_ui_width, 1.0 - digit_ui_height];
+        let mut max_tens = self.digits[config.food_count / 10 % 10];
+        max_tens.offset = [1.0 - 2.0 * digit_ui_width, 1.0 - digit_ui_height];
+        let score = [score_tens, score_ones, self.slash, max_tens, max_ones].map(|e| self.placed(SCORE, e, config));
+
+        // Display bonus points from fruit above the score
+        let bonus = [100, 10, 1].into_iter().enumerate().map(|(i, place)| {
+            let mut digit = self.digits[player.bonus as usize / place % 10];
+            digit.offset = [1.0 - (3 - i) as f32 * digit_ui_width, 1.0 - 2.0 * digit_ui_height];
+            digit
+        });
+        let bonus: Vec<UIElement> = [self.fruit].into_iter().chain(bonus).map(|e| self.placed(SCORE, e, config)).collect();
+
+        // Display the combo multiplier while it lasts
+        let mut combo_digit = self.digits[player.combo as usize % 10];
+        combo_digit.offset = [1.0 - 7.0 * digit_ui_width, 1.0 - digit_ui_height];
+        let combo = [self.multiplier, combo_digit].map(|e| self.placed(SCORE, e, config));
+
+        // Display remaining lives in the opposite corner
+        let mut lives_ones = self.digits[player.lives as usize % 10];
+        lives_ones.offset = [-1.0 + digit_ui_width, 1.0 - digit_ui_height];
+        let mut lives_tens = self.digits[player.lives as usize / 10 % 10];
+        lives_tens.offset = [-1.0, 1.0 - digit_ui_height];
+        let lives = [lives_tens, lives_ones].map(|e| self.placed(LIVES, e, config));
+
+        // Stack active power-ups above the lives
+        let effects: Vec<UIElement> = player.effects(now).enumerate().flat_map(|(i, (power_up, remaining))| {
+            let y = 1.0 - (i + 2) as f32 * digit_ui_height;
+            let seconds = remaining.ceil() as usize;
+            let mut icon = self.icons.iter().find(|(icon, _)| *icon == power_up).unwrap().1;
+            icon.offset = [-1.0, y];
+            let mut tens = self.digits[seconds / 10 % 10];
+            tens.offset = [-1.0 + 2.0 * digit_ui_width, y];
+            let mut ones = self.digits[seconds % 10];
+            ones.offset = [-1.0 + 3.0 * digit_ui_width, y];
+            [icon, tens, ones].map(|e| self.placed(LIVES, e, config))
+        }).collect();
+
+        // Display flashlight charge
+        let mut battery_bar = self.battery_bar;
+        battery_bar.size[0] *= player.flashlight.battery();
+        let battery = [self.battery, battery_bar];
+
+        // Point towards the exit
+        let compass_direction = world.exit_direction(player.cell());
+        let compass = self.compass.iter().filter(|(delta, _)| {
+            config.display_compass && player.game_state == GameState::Playing && *delta == compass_direction
+        }).map(|(_, element)| element);
+
+        // Number the player's w-slice under the compass, for telling slices apart without their colors
+        let slice = player.cell()[3].max(0) as usize + 1;
+        let compass_height = self.compass.first().map_or(0.0, |(_, element)| element.size[1]);
+        let slice_number: Vec<UIElement> = [10, 1].into_iter().enumerate().map(|(i, place)| {
+            let mut digit = self.digits[slice / place % 10];
+            digit.offset = [(i as f32 - 1.0) * digit_ui_width, -1.0 + compass_height];
+            digit
+        }).collect();
+
+        // Recent lines of the event feed centred above the battery, the newest at the bottom
+        let feed: Vec<UIElement> = feed.iter().rev().enumerate().flat_map(|(row, line)| {
+            let [width, height] = self.glyphs[0].size;
+            let y = 1.0 - 2.0 * digit_ui_height - (row + 1) as f32 * height;
+            let left = -(line.chars().count() as f32) * width / 2.0;
+            line.chars().enumerate().map(move |(i, c)| {
+                let mut glyph = self.glyphs[glyph_index(c)];
+                glyph.offset = [left + i as f32 * width, y];
+                glyph
+            })
+        }).collect();
+
+        // Break down the final score once the player escapes
+        let final_score = player.final_score(config);
+        let lines = [final_score.food, final_score.fruit, final_score.combo, final_score.time, final_score.no_hint, final_score.total()];
+        let tally: Vec<UIElement> = self.tally.iter().zip(lines).flat_map(|(icon, points)| {
+            let y = icon.offset[1];
+            let digits = [100, 10, 1].into_iter().enumerate().map(move |(i, place)| {
+                let mut digit = self.digits[points as usize / place % 10];
+                digit.offset = [(i as f32 - 0.5) * digit_ui_width, y];
+                digit
+            });
+            [*icon].into_iter().chain(digits)
+        }).collect();
+
+        // How hard the maze was, centred above the final score
+        let analysis: Vec<UIElement> = self.analysis.iter().flat_map(|line| {
+            let [width, _] = self.glyphs[0].size;
+            let y = self.tally[0].offset[1] - 1.5 * digit_ui_height;
+            let left = -(line.chars().count() as f32) * width / 2.0;
+            line.chars().enumerate().map(move |(i, c)| {
+                let mut glyph = self.glyphs[glyph_index(c)];
+                glyph.offset = [left + i as f32 * width, y];
+                glyph
+            })
+        }).collect();
+
+        // Or the best time and mazes escaped out of those played, on the same lines as the final score
+        let stats: Vec<UIElement> = self.stats.iter().flat_map(|stats| {
+            let digit = |value: usize, x: f32, y: f32| {
+                let mut digit = self.digits[value % 10];
+                digit.offset = [x * digit_ui_width, y];
+                digit
+            };
+            let mut best = self.tally[3];
+            best.offset[1] = 1.0 - 4.5 * digit_ui_height;
+            let y = best.offset[1];
+            let best_time = stats.best.map(|seconds| {
+                let (mins, secs) = (seconds as usize / 60 % 100, seconds as usize % 60);
+                let mut colon = self.colon;
+                colon.offset = [1.5 * digit_ui_width, y];
+                vec![digit(mins / 10, -0.5, y), digit(mins, 0.5, y), colon, digit(secs / 10, 2.5, y), digit(secs, 3.5, y)]
+            }).unwrap_or_default();
+            let mut solved = self.tally[5];
+            solved.offset[1] = 1.0 - 3.5 * digit_ui_height;
+            let y = solved.offset[1];
+            let mut slash = self.slash;
+            slash.offset = [2.5 * digit_ui_width, y];
+            let fraction = [100, 10, 1].into_iter().enumerate().flat_map(move |(i, place)| {
+                [digit(stats.solved / place, i as f32 - 0.5, y), digit(stats.played / place, i as f32 + 3.5, y)]
+            });
+            [best].into_iter().chain(best_time).chain([solved, slash]).chain(fraction).collect::<Vec<_>>()
+        }).collect();
+
+        // Between campaign levels, the level played and the campaign's points so far
+        let campaign: Vec<UIElement> = self.campaign.iter().flat_map(|campaign| {
+            let digit = |value: usize, x: f32, y: f32| {
+                let mut digit = self.digits[value % 10];
+                digit.offset = [x * digit_ui_width, y];
+                digit
+            };
+            let mut total = self.tally[5];
+            total.offset[1] = 1.0 - 7.5 * digit_ui_height;
+            let y = total.offset[1];
+            let score = [1000, 100, 10, 1].into_iter().enumerate().map(move |(i, place)| digit(campaign.score as usize / place, i as f32 - 0.5, y));
+            let y = y - digit_ui_height;
+            let mut slash = self.slash;
+            slash.offset = [1.5 * digit_ui_width, y];
+            let levels = [10, 1].into_iter().enumerate().flat_map(move |(i, place)| {
+                [digit(campaign.level / place, i as f32 - 0.5, y), digit(campaign.levels / place, i as f32 + 2.5, y)]
+            });
+            [total].into_iter().chain(score).chain([slash]).chain(levels).collect::<Vec<_>>()
+        }).collect();
+
+        // Display win/lose screens
+        let screens = [self.lose, self.win];
+        let game_state_elements = match player.game_state {
+            GameState::Playing | GameState::Respawning | GameState::Reviewing => &screens[0..0],
+            GameState::Lost => &screens[0..1],
+            GameState::Won => &screens[1..2]
+        }.iter();
+
+        // Every widget is shown while the HUD is being edited, so even those usually hidden can be moved
+        let editing = config.edit_hud;
+        let mut elements = Box::new(empty()) as Box<dyn Iterator<Item = &UIElement>>;
+        if config.display_controls || editing {
+            elements = Box::new(elements.chain(controls.iter()));
+            if player.game_state == GameState::Playing {
+                elements = Box::new(elements.chain(warnings.iter()));
+            }
+        }
+        // A time attack always shows its clock counting down, and zen never shows one
+        if player.time_limit().is_some() && player.game_state != GameState::Won {
+            elements = Box::new(elements.chain(minus.iter()));
+        }
+        if (config.mode != Mode::Zen && (config.display_clock != DisplayClock::None || player.time_limit().is_some())) || editing {
+            elements = Box::new(elements.chain(stopwatch.iter()));
+        }
+        elements = Box::new(elements.chain(score.iter()));
+        if config.fruit_interval > 0.0 {
+            elements = Box::new(elements.chain(bonus.iter()));
+        }
+        if config.combo_time > 0.0 && player.combo > 1 && player.game_state == GameState::Playing {
+            elements = Box::new(elements.chain(combo.iter()));
+        }
+        if config.lives > 1 || editing {
+            elements = Box::new(elements.chain(lives.iter()));
+        }
+        if player.game_state == GameState::Playing {
+            elements = Box::new(elements.chain(effects.iter()));
+        }
+
+        // TODO do this ahead of time!
+        // Anchor to edges and compensate for aspect ratio
+        let mut elements = Box::new(elements.map(|e| self.anchored(*e))) as Box<dyn Iterator<Item = UIElement>>;
+
+        // Centered elements only compensate for aspect ratio
+        let battery = battery.iter().filter(|_| config.flashlight_time > 0.0 && player.game_state == GameState::Playing);
+        let game_over = player.game_state == GameState::Won || player.game_state == GameState::Lost;
+        // Survival has no way out, so its score is shown once the ghost catches the player
+        let scored = player.game_state == GameState::Won || (player.game_state == GameState::Lost && config.mode == Mode::Survival);
+        let tally = tally.iter().filter(|_| scored && self.stats.is_none());
+        let analysis = analysis.iter().filter(|_| player.game_state == GameState::Won && self.stats.is_none());
+        let stats = stats.iter().filter(|_| game_over);
+        let campaign = campaign.iter().filter(|_| game_over);
+        let slice_number = slice_number.iter().filter(|_| config.slice_marks && player.game_state == GameState::Playing);
+        let game_state_elements = compass.chain(slice_number).chain(feed.iter()).chain(battery).chain(game_state_elements).chain(tally).chain(analysis).chain(stats).chain(campaign).map(|e| {
+            let mut e = *e;
+            e.size[0] /= self.scale_x;
+            e.size[1] /= self.scale_y;
+            e.offset[0] /= self.scale_x;
+            e.offset[1] /= self.scale_y;
+            e
+        });
+        elements = Box::new(elements.chain(game_state_elements));
+
+        // Render every UI element in one instanced draw
+        let instances: Vec<UIElement> = elements.collect();
+        if instances.is_empty() {
+            return;
+        }
+        let instance_count = instances.len() as u32;
+        let instance_buffer = self.instance_buffer_pool.chunk(instances).unwrap();
+        builder
+            .bind_pipeline_graphics(self.graphics_pipeline.clone())
+            .bind_descriptor_sets(PipelineBindPoint::Graphics,
+                self.graphics_pipeline.layout().clone(),
+                0,
+                self.texture_descriptor.clone())
+            .bind_vertex_buffers(0, (self.rect_buffer.clone(), instance_buffer))
+            .draw(6, instance_count, 0, 0).unwrap();
+    }
+
+    // Where an element placed relative to the screen's edges ends up on this screen, anchored to the nearest edges and
+    // compensating for the aspect ratio
+    fn anchored(&self, mut e: UIElement) -> UIElement {
+        e.size[0] /= self.scale_x;
+        e.size[1] /= self.scale_y;
+        e.offset[0] /= self.scale_x;
+        e.offset[1] /= self.scale_y;
+        e.offset[0] += e.offset[0].signum() * (self.scale_x - 1.0) / 2.0;
+        e.offset[1] += e.offset[0].signum() * (self.scale_y - 1.0) / 2.0;
+        e
+    }
+
+    // The element moved along with the widget it's part of, by its index into HUD_WIDGETS
+    fn placed(&self, widget: usize, mut e: UIElement, config: &Config) -> UIElement {
+        let offset = self.widget_offset(widget, config);
+        e.offset = [0, 1].map(|i| e.offset[i] + offset[i]);
+        e
+    }
+
+    // How far the widget is from its usual place, following the mouse while it's being dragged
+    fn widget_offset(&self, widget: usize, config: &Config) -> [f32; 2] {
+        match (self.drag, self.cursor) {
+            (Some ((dragged, from, start)), Some (cursor)) if dragged == widget => {
+                // The mouse moves over the screen, so undo the aspect ratio compensation to get back to the layout
+                let scale = [self.scale_x, self.scale_y];
+                [0, 1].map(|i| (start[i] + (cursor[i] - from[i]) * scale[i]).clamp(-2.0, 2.0))
+            },
+            _ => config.hud_layout[widget]
+        }
+    }
+
+    // Whether the mouse is over the element, as anchored to the screen's edges
+    fn hovered(&self, element: &UIElement) -> bool {
+        let e = self.anchored(*element);
+        self.cursor.is_some_and(|cursor| (0..2).all(|i| (e.offset[i]..e.offset[i] + e.size[i]).contains(&cursor[i])))
+    }
+}
+
+fn graphics_pipeline(device: Arc<Device>, render_pass: Arc<RenderPass>, cache: Arc<PipelineCache>) -> Arc<GraphicsPipeline> {
+    let vertex_shader = load_shader!(vs, device.clone(), "UI vertex");
+    let fragment_shader = load_shader!(fs, device.clone(), "UI fragment");
+
+    gfx::graphics_pipeline(
+        device,
+        render_pass,
+        cache,
+        VertexInput::new().vertex::<UIVertex>().instance::<UIElement>(),
+        vertex_shader.main_entry_point(),
+        fragment_shader.main_entry_point(),
+        // Ignore depth testing for overlaying UI images
+        PipelineOptions { depth: false, alpha_blending: true, ..Default::default() })
+}
+
+#[derive(BufferContents, Vertex, Default, Clone, Copy)]
+#[repr(C)]
+struct UIVertex {
+    #[format(R32G32_SFLOAT)]
+    position: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    uv: [f32; 2]
+}
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+        #version 450
+        layout(location = 0) in vec2 position;
+        layout(location = 1) in vec2 uv;
+        layout(location = 2) in vec4 texture_region;
+        layout(location = 3) in vec2 size;
+        layout(location = 4) in vec2 offset;
+        layout(location = 5) in uint layer;
+        layout(location = 0) out vec2 passUv;
+        layout(location = 1) flat out uint passLayer;
+        void main() {
+            vec2 tex_start = texture_region.xy;
+            vec2 tex_finish = texture_region.zw;
+            gl_Position = vec4(position * size + offset, 0.0, 1.0);
+            passUv = vec2(uv.x * (tex_finish.x - tex_start.x) + tex_start.x, uv.y * (tex_finish.y - tex_start.y) + tex_start.y);
+            passLayer = layer;
+        }
+        ",
+        custom_derives: [Clone, Copy, PartialEq, Debug, Default]
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+        #version 450
+        layout(location = 0) in vec2 passUv;
+        layout(location = 1) flat in uint passLayer;
+        layout(set = 0, binding = 0) uniform sampler2DArray tex;
+        layout(location = 0) out vec4 f_color;
+        void main() {
+            f_color = texture(tex, vec3(passUv, passLayer));
+        }
+        "
+    }
+}
@@ -0,0 +1,561 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use log::info;
+use vulkano::buffer::BufferUsage;
+use vulkano::device::Queue;
+use vulkano::sync::{now, GpuFuture};
+use vulkano::pipeline::Pipeline as _;
+
+use maze_core::config::ViewDistance;
+use maze_core::decor::{self, Prop};
+use maze_core::linalg;
+use maze_core::timing::Span;
+use maze_core::world::{Axis, Cell, Trap, Wall, World};
+
+use crate::View;
+use crate::gfx::{self, BufferPool, Commands};
+use crate::pipeline::Pipeline;
+use crate::pipeline::InstanceModel;
+use crate::assets::Assets;
+use crate::scene::{self, Draw, Renderable, Scene};
+use crate::pipeline::pfs::PortalData;
+use crate::pipeline::vs::PlayerPositionData;
+use crate::parameters::{COBWEB_COLOR, EXIT_COLOR, KEY_COLORS, PILLAR_COLOR, RUNE_PULSE, TRAP_COLORS};
+
+const CHUNK_CELLS: usize = 16; // Cells along each side of a chunk, the pieces levels are built and kept loaded in
+const CHUNK_REACH: usize = 2; // Chunks either side of a player's own kept loaded, on every level and slice in view
+const NEAR_REACH: usize = 1; // Chunks either side of a player's own on their own level built straight away if missing
+const MAX_CHUNKS: usize = 768; // Chunks kept loaded before those needed least recently are dropped
+const SIGHT_REACH: usize = (CHUNK_REACH + 1) * CHUNK_CELLS; // Cells along either axis lines of sight are followed, past any chunk loaded
+
+// Slice w, level z, and column and row of a chunk within the level
+type ChunkKey = (usize, usize, usize, usize);
+
+// Instances in a chunk, one list for each kind of thing in the order LevelInstances lists them
+type ChunkInstances = Vec<Vec<InstanceModel>>;
+
+// Column and row of each chunk of a level in sight
+type Sight = HashSet<(usize, usize)>;
+
+struct LevelInstances {
+    walls: Vec<InstanceModel>,
+    floors: Vec<InstanceModel>,
+    ceilings: Vec<InstanceModel>,
+    corners: Vec<InstanceModel>,
+    left_portals: Vec<InstanceModel>,
+    right_portals: Vec<InstanceModel>,
+    cobwebs: Vec<InstanceModel>,
+    pillars: Vec<InstanceModel>,
+    runes: Vec<InstanceModel>,
+    traps: [Vec<InstanceModel>; 3], // Spikes, snares and pits
+    doors: Vec<Vec<InstanceModel>> // Indexed by key number
+}
+
+impl LevelInstances {
+    fn into_iter(self) -> std::vec::IntoIter<Vec<InstanceModel>> {
+        let mut list = vec![self.walls, self.floors, self.ceilings, self.corners, self.left_portals, self.right_portals, self.cobwebs, self.pillars, self.runes];
+        list.extend(self.traps);
+        list.extend(self.doors);
+        list.into_iter()
+    }
+}
+
+struct LevelBuffers {
+    walls: gfx::Buffer<InstanceModel>,
+    floors: gfx::Buffer<InstanceModel>,
+    ceilings: gfx::Buffer<InstanceModel>,
+    corners: gfx::Buffer<InstanceModel>,
+    left_portals: gfx::Buffer<InstanceModel>,
+    right_portals: gfx::Buffer<InstanceModel>,
+    cobwebs: gfx::Buffer<InstanceModel>,
+    pillars: gfx::Buffer<InstanceModel>,
+    runes: gfx::Buffer<InstanceModel>,
+    traps: [gfx::Buffer<InstanceModel>; 3],
+    doors: Vec<gfx::Buffer<InstanceModel>>
+}
+
+impl From<Vec<gfx::Buffer<InstanceModel>>> for LevelBuffers {
+    fn from(list: Vec<gfx::Buffer<InstanceModel>>) -> Self {
+        LevelBuffers {
+            walls: list[0].clone(),
+            floors: list[1].clone(),
+            ceilings: list[2].clone(),
+            corners: list[3].clone(),
+            left_portals: list[4].clone(),
+            right_portals: list[5].clone(),
+            cobwebs: list[6].clone(),
+            pillars: list[7].clone(),
+            runes: list[8].clone(),
+            traps: [list[9].clone(), list[10].clone(), list[11].clone()],
+            doors: list[12..].to_vec()
+        }
+    }
+}
+
+// GPU buffers for one chunk of a level
+struct Chunk {
+    buffers: LevelBuffers,
+    used: u64 // Last frame a player was near enough to need it
+}
+
+/// GPU buffers for the walls, floors, portals and props of one generated world. Each level is split into square
+/// chunks, which are only built around the players and dropped again once they've been out of the way for a while,
+/// so even huge mazes start quickly and fit in memory
+pub struct WorldRenderer {
+    chunks: HashMap<ChunkKey, Chunk>,
+    pending: HashSet<ChunkKey>, // Being built in the background
+    world: Arc<World>, // As chunks are built from, replaced whenever walls move
+    version: u64, // How many times walls have moved, so chunks built from an older world are thrown away
+    requests: Sender<(Arc<World>, u64, ChunkKey)>,
+    built: Receiver<(u64, ChunkKey, ChunkInstances)>,
+    frame: u64,
+    staging: BufferPool<InstanceModel>, // Instances on their way into the buffers, reused for every chunk built
+    decorations: f32 // Density props are scattered at
+}
+
+impl WorldRenderer {
+    /// Get ready to build the world's chunks, decorated with props at the given density, once there are players to
+    /// build them around. The returned future signals straight away, since nothing is uploaded yet
+    pub fn new(world: &World, decorations: f32, queue: Arc<Queue>) -> (WorldRenderer, Box<dyn GpuFuture>) {
+        let (requests, jobs) = channel::<(Arc<World>, u64, ChunkKey)>();
+        let (finished, built) = channel();
+        // Build chunks in the background until the renderer is dropped
+        thread::spawn(move || {
+            for (world, version, key) in jobs {
+                let instances = chunk_instances(&world, decorations, key);
+                if finished.send((version, key, instances)).is_err() {
+                    return;
+                }
+            }
+        });
+        let staging = gfx::buffer_pool(queue.device().clone(), BufferUsage::TRANSFER_SRC);
+        let renderer = WorldRenderer {
+            chunks: HashMap::new(),
+            pending: HashSet::new(),
+            world: Arc::new(world.clone()),
+            version: 0,
+            requests,
+            built,
+            frame: 0,
+            staging,
+            decorations
+        };
+        info!(target: "render", "Initialized world");
+        (renderer, now(queue.device().clone()).boxed())
+    }
+
+    /// Keep the chunks around each player, given by the cell they're in, loaded on every level and slice within the
+    /// view distance. Missing chunks right around a player on their own level are built straight away, eg. at the start
+    /// or after a teleport, and the rest in the background to be drawn once they're ready. The returned future signals
+    /// when the new buffers are ready
+    pub fn stream(&mut self, players: &[[i32; 4]], distance: &ViewDistance, queue: Arc<Queue>) -> Box<dyn GpuFuture> {
+        self.frame += 1;
+        let mut future = now(queue.device().clone()).boxed();
+        let built: Vec<_> = self.built.try_iter().collect();
+        for (version, key, instances) in built {
+            self.pending.remove(&key);
+            if version == self.version && !self.chunks.contains_key(&key) {
+                future = self.load(key, instances, queue.clone(), future);
+            }
+        }
+        let world = self.world.clone();
+        for cell in players {
+            let [x, y] = [cell[0].clamp(0, world.width as i32 - 1), cell[1].clamp(0, world.height as i32 - 1)].map(|i| i as usize / CHUNK_CELLS);
+            let own = (cell[3].clamp(0, world.fourth as i32 - 1) as usize, cell[2].clamp(0, world.depth as i32 - 1) as usize);
+            let (low, high) = distance.levels(cell[2], world.depth);
+            for w in distance.slices(cell[3], world.fourth) {
+                for z in low..=high {
+                    for (cx, cy) in chunks_around(&world, [x, y], CHUNK_REACH) {
+                        let key = (w, z, cx, cy);
+                        if let Some (chunk) = self.chunks.get_mut(&key) {
+                            chunk.used = self.frame;
+                        } else if (w, z) == own && cx.abs_diff(x) <= NEAR_REACH && cy.abs_diff(y) <= NEAR_REACH {
+                            let instances = chunk_instances(&world, self.decorations, key);
+                            future = self.load(key, instances, queue.clone(), future);
+                        } else if self.pending.insert(key) {
+                            let _ = self.requests.send((world.clone(), self.version, key));
+                        }
+                    }
+                }
+            }
+        }
+        // Drop the chunks needed least recently once there are too many, keeping any needed this frame
+        let excess = self.chunks.len().saturating_sub(MAX_CHUNKS);
+        if excess > 0 {
+            let mut unused: Vec<(u64, ChunkKey)> = self.chunks.iter().filter(|(_, chunk)| chunk.used < self.frame).map(|(key, chunk)| (chunk.used, *key)).collect();
+            unused.sort_unstable();
+            for (_, key) in unused.into_iter().take(excess) {
+                self.chunks.remove(&key);
+            }
+        }
+        future
+    }
+
+    /// Rebuild the loaded chunks of levels (w, z) whose walls have moved, leaving the rest untouched.
+    /// The returned future signals when the new buffers are ready
+    pub fn update(&mut self, world: &World, levels: &[(usize, usize)], queue: Arc<Queue>) -> Box<dyn GpuFuture> {
+        let _span = Span::new("render", "Level buffer update");
+        self.world = Arc::new(world.clone());
+        self.version += 1;
+        self.pending.clear();
+        let mut future = now(queue.device().clone()).boxed();
+        for &(w, z) in levels {
+            let mut split = split_level(world, self.decorations, w, z);
+            let keys: Vec<ChunkKey> = self.chunks.keys().filter(|key| (key.0, key.1) == (w, z)).copied().collect();
+            for key in keys {
+                let instances = split.remove(&(key.2, key.3)).unwrap_or_default();
+                future = self.load(key, instances, queue.clone(), future);
+            }
+        }
+        future
+    }
+
+    // Upload a chunk's instances, copying them all in through the staging pool at once, and join the upload onto the future
+    fn load(&mut self, key: ChunkKey, instances: ChunkInstances, queue: Arc<Queue>, future: Box<dyn GpuFuture>) -> Box<dyn GpuFuture> {
+        let (buffers, upload) = gfx::device_local_buffers(queue, &self.staging, instances, BufferUsage::VERTEX_BUFFER);
+        self.chunks.insert(key, Chunk { buffers: LevelBuffers::from(buffers), used: self.frame });
+        future.join(upload).boxed()
+    }
+
+    // Chunks of level (w, z) that are loaded, only those in sight if given
+    fn level_chunks<'a>(&'a self, world: &World, w: usize, z: usize, sight: Option<&'a Sight>) -> impl Iterator<Item = &'a LevelBuffers> {
+        let [columns, rows] = chunk_counts(world);
+        (0..rows).flat_map(move |cy| (0..columns).map(move |cx| (cx, cy)))
+            .filter(move |chunk| sight.is_none_or(|sight| sight.contains(chunk)))
+            .filter_map(move |(cx, cy)| self.chunks.get(&(w, z, cx, cy)))
+            .map(|chunk| &chunk.buffers)
+    }
+
+    /// Draw the portals between the w-slices around the player, which use their own pipeline
+    pub fn render_portals(&self, view: &View, assets: &Assets, builder: &mut Commands, pipeline: &Pipeline) {
+        let view_projection = view.view_projection();
+        let between = view.between();
+        let model = assets.model("ceiling");
+        let (min_level, max_level) = visible_levels(view);
+        let (own, sight) = chunks_in_sight(view);
+        builder.bind_pipeline_graphics(pipeline.portal_pipeline.clone());
+        for w in visible_fourths(view) {
+            // Each portal swirls with the color of the slice it leads to
+            let left_color = view.palette.slice(w as i32 - 1);
+            let right_color = view.palette.slice(w as i32 + 1);
+            for level in min_level..=max_level {
+                let wvp = linalg::mul(view_projection, level_transform(view, w, level, between));
+                for level_buffers in self.level_chunks(view.world, w, level, sight.as_ref().filter(|_| (w, level) == own)) {
+                    for (color, instances) in [(left_color, level_buffers.left_portals.clone()), (right_color, level_buffers.right_portals.clone())] {
+                        builder
+                            .push_constants(
+                                pipeline.portal_pipeline.layout().clone(),
+                                0,
+                                PortalData { vp: wvp, color, time: view.time })
+                            .bind_vertex_buffers(0, (model.vertices.clone(), instances.clone()))
+                            .draw(
+                                model.vertices.len() as u32,
+                                instances.len() as u32,
+                                0,
+                                0)
+                            .unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Renderable for WorldRenderer {
+    // Walls, floors and corners of the w-slices around the player, each level lit in its own theme
+    fn draws(&self, view: &View, assets: &Assets, scene: &mut Scene) {
+        let (world, player, ghost) = (view.world, view.player, view.nearest_ghost());
+        let between = view.between();
+        let (min_level, max_level) = visible_levels(view);
+        let (own, sight) = chunks_in_sight(view);
+        for w in visible_fourths(view) {
+            // Light each slice as though the player and ghost were standing in it
+            let slice_lights = scene::lights(view, w,
+                {
+                    let diff = w as f32 - between;
+                    let mut arr: [f32; 3] = player.get_position()[0..3].try_into().unwrap();
+                    arr[0] -= diff * (1 + world.width) as f32;
+                    arr
+                },
+                {
+                    let diff = w as f32 - ghost.position()[3];
+                    let mut arr: [f32; 3] = ghost.position()[0..3].try_into().unwrap();
+                    arr[0] -= diff * (1 + world.width) as f32;
+                    arr
+                });
+            let fourth_color = view.palette.slice(w as i32);
+            let corner_color = fourth_color.map(|f| (f * 1.2).clamp(0.0, 1.0));
+            let floor_color = fourth_color.map(|f| f * 0.1);
+            let ascend_color = [1.0, 1.0, 1.0];
+            let rune_color = EXIT_COLOR.map(|f| f * (1.0 + RUNE_PULSE * (view.time * 2.0).sin()));
+            for level in min_level..=max_level {
+                let transform = level_transform(view, w, level, between);
+                // Only the first draw of each level sets its lights, for the rest to share
+                let mut lights = Some (PlayerPositionData { theme: scene::theme(view, level), pattern: view.palette.pattern(w), ..slice_lights });
+                for level_buffers in self.level_chunks(world, w, level, sight.as_ref().filter(|_| (w, level) == own)) {
+                    // Anything standing tall enough to hide the player fades out in front of them
+                    let draws = [
+                        (fourth_color, assets.model("wall"), level_buffers.walls.clone(), true),
+                        (floor_color, assets.model("floor"), level_buffers.floors.clone(), false),
+                        (corner_color, assets.model("corner"), level_buffers.corners.clone(), true),
+                        (ascend_color, assets.model("ceiling"), level_buffers.ceilings.clone(), false),
+                        (COBWEB_COLOR, assets.model("cobweb"), level_buffers.cobwebs.clone(), true),
+                        (PILLAR_COLOR, assets.model("pillar"), level_buffers.pillars.clone(), true),
+                        (rune_color, assets.model("rune"), level_buffers.runes.clone(), false),
+                        (TRAP_COLORS[0], assets.model("corner"), level_buffers.traps[0].clone(), false),
+                        (TRAP_COLORS[1], assets.model("ceiling"), level_buffers.traps[1].clone(), false),
+                        (TRAP_COLORS[2], assets.model("floor"), level_buffers.traps[2].clone(), false),
+                    ];
+                    // Doors stay drawn until the player holds their key
+                    let doors = level_buffers.doors.iter().enumerate()
+                        .filter(|(key, _)| !player.inventory.has_key(*key))
+                        .map(|(key, instances)| (KEY_COLORS[key % KEY_COLORS.len()], assets.model("wall"), instances.clone(), true));
+                    for (color, model, instances, tall) in draws.into_iter().chain(doors) {
+                        let draw = Draw {
+                            mesh: model.vertices.clone(),
+                            instance_count: instances.len() as u32,
+                            instances,
+                            color,
+                            transform,
+                            lights: lights.take()
+                        };
+                        if tall { scene.add_see_through(draw) } else { scene.add(draw) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Columns and rows of chunks each level is split into
+fn chunk_counts(world: &World) -> [usize; 2] {
+    [world.width, world.height].map(|cells| cells.div_ceil(CHUNK_CELLS))
+}
+
+// Column and row of each chunk of a level within the reach of the given one, which are counted the same way
+fn chunks_around(world: &World, [x, y]: [usize; 2], reach: usize) -> impl Iterator<Item = (usize, usize)> {
+    let [columns, rows] = chunk_counts(world);
+    let (xs, ys) = (x.saturating_sub(reach)..(x + reach + 1).min(columns), y.saturating_sub(reach)..(y + reach + 1).min(rows));
+    ys.flat_map(move |cy| xs.clone().map(move |cx| (cx, cy)))
+}
+
+// Instances of one chunk of a level
+fn chunk_instances(world: &World, decorations: f32, (w, z, cx, cy): ChunkKey) -> ChunkInstances {
+    split_level(world, decorations, w, z).remove(&(cx, cy)).unwrap_or_default()
+}
+
+// Instances of a level split up by the column and row of the chunk they stand in, with every chunk of the level there
+// even if it's empty. Anything on a chunk's boundary, like the walls down its left side, goes in the chunk to its right
+fn split_level(world: &World, decorations: f32, w: usize, z: usize) -> HashMap<(usize, usize), ChunkInstances> {
+    let [columns, rows] = chunk_counts(world);
+    let lists: Vec<Vec<InstanceModel>> = level_instances(world, decorations, w, z).into_iter().collect();
+    let count = lists.len();
+    let mut chunks: HashMap<(usize, usize), ChunkInstances> = (0..rows)
+        .flat_map(|cy| (0..columns).map(move |cx| ((cx, cy), vec![Vec::new(); count])))
+        .collect();
+    for (i, list) in lists.into_iter().enumerate() {
+        for instance in list {
+            let [x, y, _, _] = linalg::transform(instance.m, [0.0, 0.0, 0.0, 1.0]);
+            let [cx, cy] = [(x, columns), (y, rows)].map(|(f, count)| (((f + 0.5).max(0.0) as usize) / CHUNK_CELLS).min(count - 1));
+            chunks.get_mut(&(cx, cy)).unwrap()[i].push(instance);
+        }
+    }
+    chunks
+}
+
+// The player's own level (w, z), and the chunks of it that could be seen from the camera, or None to draw all of it.
+// With the camera down among the walls most of a corridor maze is hidden behind the nearest few, so only chunks with a
+// cell in sight of the player are drawn, along with the chunks to their right and below holding their far walls. From
+// above the walls any of the level may be in view
+fn chunks_in_sight(view: &View) -> ((usize, usize), Option<Sight>) {
+    let (world, camera) = (view.world, view.camera);
+    let cell = view.player.cell();
+    let own = (cell[3].clamp(0, world.fourth as i32 - 1) as usize, cell[2].clamp(0, world.depth as i32 - 1) as usize);
+    if camera.is_free() || camera.is_overhead() || camera.is_dollhouse() || camera.position()[2] >= own.1 as f32 + 1.0 {
+        return (own, None);
+    }
+    // Between cells the player could be seen out of either
+    let [x, y, _, _] = view.player.get_position();
+    let clamp = |f: f32, len: usize| (f.max(0.0) as usize).min(len - 1);
+    let [columns, rows] = chunk_counts(world);
+    let mut sight = HashSet::new();
+    for from_x in [x.floor(), x.ceil()].map(|f| clamp(f, world.width)) {
+        for from_y in [y.floor(), y.ceil()].map(|f| clamp(f, world.height)) {
+            for [cx, cy] in world.in_sight(own.0, own.1, [from_x, from_y], SIGHT_REACH) {
+                for [dx, dy] in [[0, 0], [1, 0], [0, 1], [1, 1]] {
+                    sight.insert((((cx + dx) / CHUNK_CELLS).min(columns - 1), ((cy + dy) / CHUNK_CELLS).min(rows - 1)));
+                }
+            }
+        }
+    }
+    (own, Some (sight))
+}
+
+// W-slices close enough to the player to be drawn
+fn visible_fourths(view: &View) -> impl Iterator<Item = usize> {
+    view.distance.slices(view.player.cell()[3], view.world.fourth)
+}
+
+// Levels within the view distance of the player's, or just their own when seen flat from above
+fn visible_levels(view: &View) -> (usize, usize) {
+    let level = view.player.cell()[2];
+    if view.camera.is_overhead() {
+        let level = level.clamp(0, view.world.depth as i32 - 1) as usize;
+        return (level, level);
+    }
+    view.distance.levels(level, view.world.depth)
+}
+
+/// Offset of the given w-slice from the one the player is in; slices are laid out side by side along x
+pub fn world_transform(world: &World, fourth: usize, between: f32) -> [[f32; 4]; 4] {
+    let spacing = (world.width + 1) as f32;
+    linalg::translate([(fourth as f32 - between) * spacing, 0.0, 0.0])
+}
+
+// Offset of level z of the given w-slice, lifted apart from the others in the dollhouse view
+fn level_transform(view: &View, fourth: usize, z: usize, between: f32) -> [[f32; 4]; 4] {
+    linalg::mul(linalg::translate([0.0, 0.0, view.lift(z as f32)]), world_transform(view.world, fourth, between))
+}
+
+// Given fixed w and z coordinates, generate a list of instances of each type of object within the level
+fn level_instances(world: &World, decorations: f32, w: usize, z: usize) -> LevelInstances {
+    // Mark fourth-dimensional portals i guess
+    let left_portals: Vec<InstanceModel> = world.walls.level(Axis::W, w, z).filter_map(|([x, y], wall)| {
+        // Check "left" fourth dimension adjacent cell
+        match wall {
+            Wall::SolidWall | Wall::Door (_) => None,
+            Wall::NoWall => {
+                let (x, y, z) = (x as f32 - 0.3, y as f32, z as f32 + 0.4);
+                Some (InstanceModel::new(linalg::model([90f32.to_radians(), 90f32.to_radians(), 0.0], [0.5, 1.0, 1.0], [x, y, z])))
+            }
+        }
+    }).collect();
+    let right_portals: Vec<InstanceModel> = world.walls.level(Axis::W, w + 1, z).filter_map(|([x, y], wall)| {
+        // Check "right" fourth dimension adjacent cell
+        match wall {
+            Wall::SolidWall | Wall::Door (_) => None,
+            Wall::NoWall => {
+                let (x, y, z) = (x as f32 + 0.3, y as f32, z as f32 + 0.4);
+                Some (InstanceModel::new(linalg::model([90f32.to_radians(), 270f32.to_radians(), 0.0], [0.5, 1.0, 1.0], [x, y, z])))
+            }
+        }
+    }).collect();
+
+    // Map horizontal walls
+    let top_to_down = world.walls.level(Axis::X, w, z).filter_map(|([x, y], wall)| {
+        // Draw a wall between cells (x - 1, y, z) and (x, y, z)
+        let (x, y, z) = (x as f32 - 0.5, y as f32, z as f32);
+        match wall {
+            Wall::SolidWall => Some (
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 90f32.to_radians()], [1.0, 1.0, 1.0], [x, y, z]))
+                ),
+            Wall::NoWall | Wall::Door (_) => None
+        }
+    });
+    let left_to_right = world.walls.level(Axis::Y, w, z).filter_map(|([x, y], wall)| {
+        // Draw a wall between cells (x, y - 1, z) and (x, y, z)
+        let (x, y, z) = (x as f32, y as f32 - 0.5, z as f32);
+        match wall {
+            Wall::SolidWall => Some (
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [x, y, z]))
+                ),
+            Wall::NoWall | Wall::Door (_) => None
+        }
+    });
+    let walls: Vec<InstanceModel> = top_to_down.chain(left_to_right).collect();
+
+    // Map floors to rectangles
+    let floors: Vec<InstanceModel> = world.walls.level(Axis::Z, w, z).filter_map(|([x, y], wall)| {
+        // Draw a floor between cells (x, y, z - 1) and (x, y, z)
+        let (x, y, z) = (x as f32, y as f32, z as f32 - 0.05);
+        match wall {
+            Wall::SolidWall => Some (
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [x, y, z]))
+                ),
+            Wall::NoWall | Wall::Door (_) => None
+        }
+    }).collect();
+
+    // Mark cells with open ceilings
+    let ceilings: Vec<InstanceModel> = world.walls.level(Axis::Z, w, z + 1).filter_map(|([x, y], wall)| {
+        match wall {
+            Wall::SolidWall | Wall::Door (_) => None,
+            Wall::NoWall => {
+                let (x, y, z) = (x as f32, y as f32, z as f32 + 0.8);
+                Some (InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [x, y, z])))
+            }
+        }
+    }).collect();
+
+    // Generate wall corners
+    let mut corners: Vec<InstanceModel> = Vec::new();
+    let walled = |axis: Axis, x: usize, y: usize| world.walls.get(axis, (x, y, z, w)) != Wall::NoWall;
+    for x in 0..world.width + 1 {
+        for y in 0..world.height + 1 {
+            // Only add corner if at least 1 horizontal wall is touching
+            if (y < world.height && walled(Axis::X, x, y))
+            || (x < world.width && walled(Axis::Y, x, y))
+            || (y > 0 && walled(Axis::X, x, y - 1))
+            || (x > 0 && walled(Axis::Y, x - 1, y)) {
+                // Draw a wall corner between cells (x - 1, y - 1, z) and (x, y, z)
+                let (x, y, z) = (x as f32 - 0.5, y as f32 - 0.5, z as f32);
+                corners.push(InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [x, y, z])));
+            }
+        }
+    }
+
+    // Spikes are a cluster of short posts, snares a diamond on the floor, and pits a dark hole
+    let mut traps = [Vec::new(), Vec::new(), Vec::new()];
+    for (y, row) in world.cells[w][z].iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let (x, y, z) = (x as f32, y as f32, z as f32);
+            match cell {
+                Cell::Trap (Trap::Spikes) => traps[0].extend([[-0.2, -0.2], [0.2, -0.2], [-0.2, 0.2], [0.2, 0.2], [0.0, 0.0]].map(|[dx, dy]| {
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [0.6, 0.6, 0.3], [x + dx, y + dy, z]))
+                })),
+                Cell::Trap (Trap::Snare) => traps[1].push(
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 45f32.to_radians()], [0.6, 0.6, 0.6], [x, y, z + 0.03]))),
+                Cell::Trap (Trap::Pit) => traps[2].push(
+                    InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [0.8, 1.0, 0.8], [x, y, z + 0.01]))),
+                _ => {}
+            }
+        }
+    }
+
+    // Cobwebs hang in a top corner of the wall they're strung across, pillars stand over the wall corners and runes
+    // lie on the floor
+    let (mut cobwebs, mut pillars, mut runes) = (Vec::new(), Vec::new(), Vec::new());
+    for prop in decor::decorate(world, w, z, decorations) {
+        match prop {
+            Prop::Cobweb { cell: (x, y, z, _), wall: [wx, wy], side } => {
+                // Webs fan out from the corner towards the middle of the wall
+                let (turn, [ax, ay]) = if wx == 0 { (0.0, [1.0, 0.0]) } else { (90f32.to_radians(), [0.0, 1.0]) };
+                let side = side as f32;
+                let (x, y, z) = (x as f32 + (wx as f32 + ax * side) * 0.38, y as f32 + (wy as f32 + ay * side) * 0.38, z as f32 + 0.9);
+                cobwebs.push(InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, turn], [-side, 1.0, 1.0], [x, y, z])));
+            },
+            Prop::Pillar { corner: (x, y, z, _) } => pillars.push(
+                InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [x as f32 - 0.5, y as f32 - 0.5, z as f32]))),
+            Prop::Rune { cell: (x, y, z, _), turn } => runes.push(
+                InstanceModel::new(linalg::model([90f32.to_radians(), 0.0, turn], [1.0, 1.0, 1.0], [x as f32, y as f32, z as f32 + 0.05])))
+        }
+    }
+
+    // Doors sit where a wall would, one list per key
+    let doors = world.doors.iter().map(|&(a, b)| {
+        if (a.2, a.3) != (z, w) {
+            return Vec::new();
+        }
+        let m = if a.0 != b.0 {
+            linalg::model([90f32.to_radians(), 0.0, 90f32.to_radians()], [1.0, 1.0, 1.0], [b.0 as f32 - 0.5, b.1 as f32, z as f32])
+        } else {
+            linalg::model([90f32.to_radians(), 0.0, 0.0], [1.0, 1.0, 1.0], [b.0 as f32, b.1 as f32 - 0.5, z as f32])
+        };
+        vec![InstanceModel::new(m)]
+    }).collect();
+
+    LevelInstances { walls, floors, corners, ceilings, left_portals, right_portals, cobwebs, pillars, runes, traps, doors }
+}
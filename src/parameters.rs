@@ -1,8 +0,0 @@
-pub const RAINBOW: [[f32; 3]; 6] = [
-    [ 1.000, 0.427, 0.416 ],
-    [ 0.937, 0.745, 0.490 ],
-    [ 0.914, 0.925, 0.420 ],
-    [ 0.467, 0.867, 0.467 ],
-    [ 0.545, 0.827, 0.902 ],
-    [ 0.694, 0.635, 0.792 ]
-];